@@ -0,0 +1,42 @@
+//! 协作式取消
+//!
+//! 长时间运行的打包/解压操作会在条目之间（以及大文件的分块之间）
+//! 检查取消标志，一旦被设置就返回 `ZipError::Cancelled`。
+
+use crate::error::{Result, ZipError};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// 取消令牌
+///
+/// 克隆出的令牌共享同一个标志，可以在另一个线程（如 GUI）中调用 `cancel()`。
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken {
+    flag: Arc<AtomicBool>,
+}
+
+impl CancelToken {
+    /// 创建新的（未取消的）令牌
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 请求取消
+    pub fn cancel(&self) {
+        self.flag.store(true, Ordering::SeqCst);
+    }
+
+    /// 是否已请求取消
+    pub fn is_cancelled(&self) -> bool {
+        self.flag.load(Ordering::SeqCst)
+    }
+
+    /// 已取消时返回 `ZipError::Cancelled`
+    pub(crate) fn check(&self) -> Result<()> {
+        if self.is_cancelled() {
+            Err(ZipError::Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+}
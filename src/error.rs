@@ -118,6 +118,19 @@ pub enum ZipError {
         reason: String,
     },
 
+    /// Entry data could not be inflated; `source` tells truncated input from corrupt data
+    #[error("corrupt ZIP entry '{name}' in archive '{archive}': decompression failed: {source}")]
+    InflateFailed {
+        name: String,
+        archive: PathBuf,
+        #[source]
+        source: crate::miniz::inflate::InflateError,
+    },
+
+    /// Corrupt or truncated gzip stream
+    #[error("corrupt gzip stream: {reason}")]
+    GzipCorrupt { reason: String },
+
     /// Overwrite conflict
     #[error("not overwriting '{path}' when extracting '{archive}'")]
     OverwriteConflict { path: PathBuf, archive: PathBuf },
@@ -187,7 +200,7 @@ pub enum ZipError {
     FileSizeFailed { path: PathBuf },
 
     /// Unsupported compression method
-    #[error("unsupported compression method: {method}")]
+    #[error("unsupported compression method: {method} ({})", compression_method_name(*.method))]
     UnsupportedCompression { method: u16 },
 
     /// CRC32 mismatch
@@ -207,6 +220,22 @@ pub enum ZipError {
         source: io::Error,
     },
 
+    /// Entry is encrypted and no password was supplied
+    #[error("entry '{name}' in archive '{archive}' is encrypted; a password is required")]
+    PasswordRequired { name: String, archive: PathBuf },
+
+    /// Supplied password does not match the entry's encryption header
+    #[error("incorrect password for entry '{name}' in archive '{archive}'")]
+    InvalidPassword { name: String, archive: PathBuf },
+
+    /// Archive changed on disk since it was opened with an integrity anchor
+    #[error("archive '{archive}' was modified after it was opened: {reason}")]
+    ArchiveModified { archive: PathBuf, reason: String },
+
+    /// Operation cancelled through a CancelToken
+    #[error("operation cancelled")]
+    Cancelled,
+
     /// IO error with context
     #[error("IO error: {0}")]
     Io(#[from] io::Error),
@@ -302,6 +331,8 @@ impl ZipError {
             ZipError::EntryNotFound { .. } => ZipErrorCode::NoEntry,
             ZipError::CorruptArchive { .. } => ZipErrorCode::Broken,
             ZipError::CorruptEntry { .. } => ZipErrorCode::BrokenEntry,
+            ZipError::InflateFailed { .. } => ZipErrorCode::BrokenEntry,
+            ZipError::GzipCorrupt { .. } => ZipErrorCode::BrokenEntry,
             ZipError::OverwriteConflict { .. } => ZipErrorCode::Overwrite,
             ZipError::CreateDirFailed { .. } => ZipErrorCode::CreateDir,
             ZipError::SetPermFailed { .. } => ZipErrorCode::SetPerm,
@@ -318,6 +349,10 @@ impl ZipError {
             ZipError::Crc32Mismatch { .. } => ZipErrorCode::BrokenEntry,
             ZipError::InvalidPath { .. } => ZipErrorCode::OpenX,
             ZipError::CreateSymlinkFailed { .. } => ZipErrorCode::CreateLink,
+            ZipError::PasswordRequired { .. } => ZipErrorCode::OpenX,
+            ZipError::InvalidPassword { .. } => ZipErrorCode::OpenX,
+            ZipError::ArchiveModified { .. } => ZipErrorCode::Broken,
+            ZipError::Cancelled => ZipErrorCode::Create,
             ZipError::Io(_) => ZipErrorCode::Open,
             ZipError::Generic(_) => ZipErrorCode::Create,
         }
@@ -416,6 +451,116 @@ pub enum ZipMode {
     CherryPick,
 }
 
+/// Encoding used for entry names written to the archive
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Encoding {
+    /// UTF-8; general purpose bit 11 is set for non-ASCII names
+    #[default]
+    Utf8,
+    /// IBM Code Page 437 for legacy DOS consumers; bit 11 is always clear
+    Cp437,
+}
+
+/// Header conventions of a reference ZIP tool, used to mimic its output
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Compat {
+    /// Native output (miniz conventions): made by Unix 2.3, version needed 2.0
+    #[default]
+    Miniz,
+    /// Info-ZIP `zip` 3.0 on Unix: made by Unix 3.0, version needed 1.0 for
+    /// stored entries and directories and 2.0 for deflate, `UT` then `ux` extra fields
+    InfoZip,
+    /// PKZIP 2.04g: made by MS-DOS 2.0, DOS attributes only, no extra fields
+    Pkzip,
+}
+
+/// What `ZipBuilder` does when an appended entry has the same name as an existing one
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DuplicatePolicy {
+    /// Add the new entry alongside the old one (ZIP allows duplicate names)
+    #[default]
+    Allow,
+    /// Add the new entry and report `ZipWarning::DuplicateEntry`
+    Warn,
+    /// Fail the build before anything is written
+    Error,
+    /// Drop the old entry and rewrite the archive without its data
+    Replace,
+}
+
+/// Set of file metadata stored when writing (`ZipBuilder::preserve`) or
+/// applied when extracting (`Extractor::restore`)
+///
+/// Combine flags with `|`, e.g. `PreserveSet::PERMISSIONS | PreserveSet::MTIME`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct PreserveSet(u8);
+
+impl PreserveSet {
+    /// Unix permission bits, stored in the upper half of the external attributes
+    pub const PERMISSIONS: Self = Self(1 << 0);
+    /// Modification time: the DOS time fields plus the `UT` extended timestamp
+    pub const MTIME: Self = Self(1 << 1);
+    /// Unix UID/GID in the `ux` extra field
+    pub const OWNERSHIP: Self = Self(1 << 2);
+    /// Symbolic links stored as links (mode `S_IFLNK`, target as content) instead of followed
+    pub const SYMLINKS: Self = Self(1 << 3);
+    /// Extended attributes, stored in the non-standard `0x5a52` extra field
+    ///
+    /// Only has an effect with the `xattr` feature on Linux and macOS.
+    pub const XATTR: Self = Self(1 << 4);
+
+    /// No metadata
+    pub const fn empty() -> Self {
+        Self(0)
+    }
+
+    /// Every kind of metadata
+    pub const fn all() -> Self {
+        Self(0b1_1111)
+    }
+
+    /// Whether every flag in `other` is set
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl std::ops::BitOr for PreserveSet {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl std::ops::BitOrAssign for PreserveSet {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+/// Encryption applied to file entries when writing
+///
+/// The central directory (names, sizes, CRCs) is never encrypted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Encryption {
+    /// Traditional PKWARE encryption; weak, but readable by virtually every tool
+    ZipCrypto { password: String },
+    /// WinZip AES (AE-2) with the given key strength
+    #[cfg(feature = "aes")]
+    Aes { password: String, strength: AesStrength },
+}
+
+/// AES key size used by `Encryption::Aes`
+#[cfg(feature = "aes")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AesStrength {
+    Aes128,
+    Aes192,
+    #[default]
+    Aes256,
+}
+
 /// File type in ZIP archive
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum FileType {
@@ -456,6 +601,110 @@ impl FileType {
     }
 }
 
+/// Host system that created a ZIP entry (high byte of `version_made_by`, APPNOTE 4.4.2)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HostOs {
+    /// MS-DOS and OS/2 (FAT / VFAT / FAT32)
+    Fat,
+    Amiga,
+    OpenVms,
+    Unix,
+    VmCms,
+    AtariSt,
+    Hpfs,
+    Macintosh,
+    ZSystem,
+    CpM,
+    Ntfs,
+    Mvs,
+    Vse,
+    AcornRisc,
+    Vfat,
+    AlternateMvs,
+    BeOs,
+    Tandem,
+    Os400,
+    /// OS X (Darwin)
+    Darwin,
+    /// Unassigned value
+    Other(u8),
+}
+
+impl HostOs {
+    pub fn from_u8(host: u8) -> Self {
+        match host {
+            0 => HostOs::Fat,
+            1 => HostOs::Amiga,
+            2 => HostOs::OpenVms,
+            3 => HostOs::Unix,
+            4 => HostOs::VmCms,
+            5 => HostOs::AtariSt,
+            6 => HostOs::Hpfs,
+            7 => HostOs::Macintosh,
+            8 => HostOs::ZSystem,
+            9 => HostOs::CpM,
+            10 => HostOs::Ntfs,
+            11 => HostOs::Mvs,
+            12 => HostOs::Vse,
+            13 => HostOs::AcornRisc,
+            14 => HostOs::Vfat,
+            15 => HostOs::AlternateMvs,
+            16 => HostOs::BeOs,
+            17 => HostOs::Tandem,
+            18 => HostOs::Os400,
+            19 => HostOs::Darwin,
+            other => HostOs::Other(other),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HostOs::Fat => "MS-DOS",
+            HostOs::Amiga => "Amiga",
+            HostOs::OpenVms => "OpenVMS",
+            HostOs::Unix => "Unix",
+            HostOs::VmCms => "VM/CMS",
+            HostOs::AtariSt => "Atari ST",
+            HostOs::Hpfs => "OS/2 HPFS",
+            HostOs::Macintosh => "Macintosh",
+            HostOs::ZSystem => "Z-System",
+            HostOs::CpM => "CP/M",
+            HostOs::Ntfs => "Windows NTFS",
+            HostOs::Mvs => "MVS",
+            HostOs::Vse => "VSE",
+            HostOs::AcornRisc => "Acorn Risc",
+            HostOs::Vfat => "VFAT",
+            HostOs::AlternateMvs => "alternate MVS",
+            HostOs::BeOs => "BeOS",
+            HostOs::Tandem => "Tandem",
+            HostOs::Os400 => "OS/400",
+            HostOs::Darwin => "OS X",
+            HostOs::Other(_) => "unknown",
+        }
+    }
+}
+
+/// Human-readable name of a ZIP compression method (APPNOTE 4.4.5)
+pub fn compression_method_name(method: u16) -> &'static str {
+    match method {
+        0 => "store",
+        1 => "shrink",
+        2..=5 => "reduce",
+        6 => "implode",
+        7 => "tokenize",
+        8 => "deflate",
+        9 => "deflate64",
+        10 => "PKWARE DCL implode",
+        12 => "bzip2",
+        14 => "LZMA",
+        93 => "zstd",
+        95 => "xz",
+        98 => "PPMd",
+        99 => "AES",
+        _ => "unknown",
+    }
+}
+
 /// ZIP entry metadata
 #[derive(Debug, Clone)]
 pub struct ZipEntry {
@@ -465,7 +714,9 @@ pub struct ZipEntry {
     pub compressed_size: u64,
     /// Uncompressed size in bytes
     pub uncompressed_size: u64,
-    /// Modification time
+    /// Modification time from the DOS date/time fields, as the C version's `zip_list` reports it
+    ///
+    /// The more precise time from the extra fields, if any, is in `times`.
     pub timestamp: std::time::SystemTime,
     /// Unix permissions (mode)
     pub permissions: u32,
@@ -479,25 +730,115 @@ pub struct ZipEntry {
     pub file_type: FileType,
     /// Is symlink
     pub is_symlink: bool,
+    /// Is encrypted (general purpose flag bit 0)
+    pub is_encrypted: bool,
+    /// Raw `version made by` field from the central directory
+    pub version_made_by: u16,
+    /// Raw DOS modification date from the central directory
+    pub dos_date: u16,
+    /// Raw DOS modification time from the central directory
+    pub dos_time: u16,
+    /// Raw external file attributes from the central directory
+    pub external_attr: u32,
+    /// Timestamps gathered from the DOS fields and the time extra fields
+    pub times: EntryTimes,
+    /// File name stored in the local header
+    ///
+    /// `None` unless the local headers were read, see `ZipArchive::entries_with_local_names`.
+    pub local_name: Option<String>,
+    /// Unix owner `(uid, gid)` from the `0x7875` or legacy `0x5855` extra field
+    pub owner: Option<(u32, u32)>,
+    /// Extended attributes `(name, value)` from the non-standard `0x5a52` extra field
+    pub xattrs: Vec<(String, Vec<u8>)>,
+}
+
+/// Entry timestamps, each taken from the most precise source available
+///
+/// Sources in order of preference: `0x000a` NTFS (100 ns), `0x5455` extended
+/// timestamp (1 s), then the DOS date/time (2 s, `mtime` only).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EntryTimes {
+    /// Last modification time
+    pub mtime: std::time::SystemTime,
+    /// Last access time, if recorded
+    pub atime: Option<std::time::SystemTime>,
+    /// Creation time, if recorded
+    pub ctime: Option<std::time::SystemTime>,
 }
 
 impl ZipEntry {
     /// Create a new ZipEntry
     pub fn new(filename: String) -> Self {
+        let now = std::time::SystemTime::now();
         ZipEntry {
             filename,
             compressed_size: 0,
             uncompressed_size: 0,
-            timestamp: std::time::SystemTime::now(),
+            timestamp: now,
             permissions: 0o644,
             crc32: 0,
             offset: 0,
             is_directory: false,
             file_type: FileType::File,
             is_symlink: false,
+            is_encrypted: false,
+            version_made_by: 0,
+            dos_date: 0,
+            dos_time: 0,
+            external_attr: 0,
+            times: EntryTimes {
+                mtime: now,
+                atime: None,
+                ctime: None,
+            },
+            local_name: None,
+            owner: None,
+            xattrs: Vec::new(),
         }
     }
 
+    /// Host system that created this entry (high byte of `version_made_by`)
+    pub fn created_by_host(&self) -> HostOs {
+        HostOs::from_u8((self.version_made_by >> 8) as u8)
+    }
+
+    /// ZIP specification version used by the creator, as (major, minor)
+    ///
+    /// The low byte of `version_made_by` stores `major * 10 + minor`, e.g. 23 -> (2, 3).
+    pub fn spec_version(&self) -> (u8, u8) {
+        let spec = (self.version_made_by & 0xFF) as u8;
+        (spec / 10, spec % 10)
+    }
+
+    /// Raw DOS modification (date, time) as stored in the central directory
+    pub fn dos_datetime(&self) -> (u16, u16) {
+        (self.dos_date, self.dos_time)
+    }
+
+    /// Modification time decoded from the DOS fields as a civil date and time
+    ///
+    /// DOS timestamps carry no time zone, so no offset is applied.
+    /// Returns `None` when the fields do not form a valid date or time.
+    pub fn local_datetime(&self) -> Option<time::PrimitiveDateTime> {
+        let year = ((self.dos_date >> 9) & 0x7F) as i32 + 1980;
+        let month = time::Month::try_from(((self.dos_date >> 5) & 0x0F) as u8).ok()?;
+        let day = (self.dos_date & 0x1F) as u8;
+        let hour = ((self.dos_time >> 11) & 0x1F) as u8;
+        let minute = ((self.dos_time >> 5) & 0x3F) as u8;
+        let second = ((self.dos_time & 0x1F) * 2) as u8;
+
+        let date = time::Date::from_calendar_date(year, month, day).ok()?;
+        let time = time::Time::from_hms(hour, minute, second).ok()?;
+        Some(time::PrimitiveDateTime::new(date, time))
+    }
+
+    /// Bytes saved by compression (`uncompressed_size - compressed_size`)
+    ///
+    /// Negative when the stored data is larger than the original content.
+    pub fn space_saved(&self) -> i64 {
+        self.uncompressed_size as i64 - self.compressed_size as i64
+    }
+
     /// Set the directory flag
     pub fn with_directory(mut self, is_dir: bool) -> Self {
         self.is_directory = is_dir;
@@ -517,6 +858,7 @@ impl ZipEntry {
     /// Set the timestamp
     pub fn with_timestamp(mut self, ts: std::time::SystemTime) -> Self {
         self.timestamp = ts;
+        self.times.mtime = ts;
         self
     }
 
@@ -545,4 +887,29 @@ mod tests {
         assert_eq!(FileType::from_u32(0o40755), FileType::Directory);
         assert_eq!(FileType::from_u32(0o120755), FileType::Symlink);
     }
+
+    #[test]
+    fn test_local_datetime() {
+        let mut entry = ZipEntry::new("a.txt".to_string());
+        // 2024-03-15 13:45:30
+        entry.dos_date = ((2024 - 1980) << 9) | (3 << 5) | 15;
+        entry.dos_time = (13 << 11) | (45 << 5) | (30 / 2);
+
+        assert_eq!(entry.dos_datetime(), (entry.dos_date, entry.dos_time));
+        let dt = entry.local_datetime().unwrap();
+        assert_eq!((dt.year(), dt.month(), dt.day()), (2024, time::Month::March, 15));
+        assert_eq!((dt.hour(), dt.minute(), dt.second()), (13, 45, 30));
+
+        entry.dos_date = 0;
+        assert_eq!(entry.local_datetime(), None);
+    }
+
+    #[test]
+    fn test_space_saved() {
+        let mut entry = ZipEntry::new("a.txt".to_string()).with_size(1000);
+        entry.compressed_size = 300;
+        assert_eq!(entry.space_saved(), 700);
+        entry.compressed_size = 1005;
+        assert_eq!(entry.space_saved(), -5);
+    }
 }
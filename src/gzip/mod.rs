@@ -3,9 +3,21 @@
 //! Note: The underlying C implementation uses zlib streams (miniz),
 //! and we mirror its behavior for byte counts and sizing.
 
-use crate::error::{Result, ZipError};
-use crate::miniz::deflate::compress_to_buffer;
+use crate::error::{CompressionLevel, Result, ZipError};
+use crate::miniz::deflate::{compress_raw, compress_to_buffer};
 use crate::miniz::inflate::decompress_to_buffer;
+use crate::miniz::{crc32, Crc32, InflateReader};
+use crate::unzip::ZipArchive;
+use crate::zip::ZipWriter;
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::path::Path;
+
+const FTEXT: u8 = 0x01;
+const FHCRC: u8 = 0x02;
+const FEXTRA: u8 = 0x04;
+const FNAME: u8 = 0x08;
+const FCOMMENT: u8 = 0x10;
 
 #[derive(Debug, Clone)]
 pub struct GzipOutput {
@@ -76,6 +88,202 @@ pub fn inflate_with_stats(
     })
 }
 
+/// Decompress the single gzip member starting at `pos`, with stats.
+///
+/// `bytes_read` is the exact length of the member (header, deflate data and
+/// the 8-byte footer), so data after it — another member or unrelated
+/// trailing bytes — starts at `pos + bytes_read`. The CRC32 and ISIZE footers
+/// are checked. `bytes_written` is the decompressed length.
+pub fn inflate_member_with_stats(
+    buffer: &[u8],
+    pos: usize,
+    size: Option<usize>,
+) -> Result<GzipOutput> {
+    if pos == 0 || pos > buffer.len() + 1 {
+        return Err(ZipError::generic("pos is out of range"));
+    }
+
+    let data = &buffer[pos - 1..];
+    let mut output = Vec::with_capacity(size.unwrap_or(data.len().saturating_mul(2)).max(10));
+    let mut reader = InflateReader::new(data);
+    decode_member(&mut reader, &mut vec![0u8; 32 * 1024], |chunk| output.extend_from_slice(chunk))?;
+
+    Ok(GzipOutput {
+        bytes_read: reader.total_in() as usize,
+        bytes_written: output.len(),
+        output,
+    })
+}
+
+/// Stream a `.gz` file through the inflater and check its CRC32 and ISIZE footers.
+///
+/// Output is hashed and discarded, so memory use stays bounded regardless of
+/// the file size. Multi-member files are verified member by member. Returns
+/// the total decompressed length; corruption and truncation are reported as
+/// `ZipError::GzipCorrupt`.
+pub fn verify<R: Read>(src: R) -> Result<u64> {
+    decode_members(src, |_| {})
+}
+
+/// Decompress a `.gz` file into a ZIP archive holding a single deflate entry.
+///
+/// The gzip CRC32 and ISIZE footers are checked while decompressing; all
+/// members of a multi-member file end up in the one entry. `zip_path` is
+/// overwritten if it exists.
+pub fn gzip_to_zip(gz_path: impl AsRef<Path>, zip_path: impl AsRef<Path>, entry_name: &str) -> Result<()> {
+    let gz_path = gz_path.as_ref();
+    let file = File::open(gz_path).map_err(|e| ZipError::FileOpen {
+        path: gz_path.to_path_buf(),
+        source: e,
+    })?;
+    let mut data = Vec::new();
+    decode_members(BufReader::new(file), |chunk| data.extend_from_slice(chunk))?;
+
+    let mut writer = ZipWriter::new(zip_path, CompressionLevel::Level6)?;
+    writer.add_reader(entry_name, &mut data.as_slice())?;
+    writer.finalize()
+}
+
+/// Write one ZIP entry out as a single-member `.gz` file.
+///
+/// The entry is decompressed with its CRC32 checked, then recompressed at
+/// level 6. The member header records the entry's file name (without its
+/// directory part) and no modification time.
+pub fn zip_entry_to_gzip(zip_path: impl AsRef<Path>, entry_name: &str, gz_path: impl AsRef<Path>) -> Result<()> {
+    let data = ZipArchive::open(zip_path)?.read_entry(entry_name)?;
+    let compressed = compress_raw(&data, 6).map_err(|e| ZipError::generic(format!("deflate failed: {e}")))?;
+
+    let base_name = entry_name.rsplit('/').next().unwrap_or(entry_name);
+    let mut output = Vec::with_capacity(10 + base_name.len() + 1 + compressed.len() + 8);
+    // magic, CM = deflate, FLG, MTIME = 0, XFL = 0, OS = unknown
+    output.extend_from_slice(&[0x1f, 0x8b, 8, 0, 0, 0, 0, 0, 0, 255]);
+    if !base_name.is_empty() && !base_name.contains('\0') {
+        output[3] |= FNAME;
+        output.extend_from_slice(base_name.as_bytes());
+        output.push(0);
+    }
+    output.extend_from_slice(&compressed);
+    output.extend_from_slice(&crc32(0, &data).to_le_bytes());
+    output.extend_from_slice(&(data.len() as u32).to_le_bytes());
+
+    let gz_path = gz_path.as_ref();
+    std::fs::write(gz_path, output).map_err(|e| ZipError::OpenWriteFailed {
+        path: gz_path.to_path_buf(),
+        source: e,
+    })
+}
+
+/// Inflate every member of a gzip stream, passing the output to `on_data`
+/// chunk by chunk and checking each member's footers. Returns the total
+/// decompressed length.
+fn decode_members<R: Read>(src: R, mut on_data: impl FnMut(&[u8])) -> Result<u64> {
+    let mut reader = InflateReader::new(src);
+    let mut buffer = vec![0u8; 32 * 1024];
+    let mut total = 0u64;
+
+    loop {
+        total += decode_member(&mut reader, &mut buffer, &mut on_data)?;
+
+        if !reader.has_more_input().map_err(gzip_io_error)? {
+            return Ok(total);
+        }
+        reader.reset();
+    }
+}
+
+/// Inflate one member — header, deflate data and footer — passing the output
+/// to `on_data`. Returns the member's decompressed length; the reader is left
+/// just after the footer.
+fn decode_member<R: Read>(
+    reader: &mut InflateReader<R>,
+    buffer: &mut [u8],
+    mut on_data: impl FnMut(&[u8]),
+) -> Result<u64> {
+    read_member_header(reader)?;
+
+    let mut crc = Crc32::new();
+    let mut length = 0u64;
+    loop {
+        let n = reader.read(buffer).map_err(gzip_io_error)?;
+        if n == 0 {
+            break;
+        }
+        crc.update(&buffer[..n]);
+        on_data(&buffer[..n]);
+        length += n as u64;
+    }
+
+    let mut footer = [0u8; 8];
+    reader.read_trailer(&mut footer).map_err(gzip_io_error)?;
+    let expected_crc = u32::from_le_bytes([footer[0], footer[1], footer[2], footer[3]]);
+    let expected_len = u32::from_le_bytes([footer[4], footer[5], footer[6], footer[7]]);
+    if crc.value() != expected_crc {
+        return Err(gzip_corrupt(format!(
+            "CRC32 mismatch: expected {:08x}, got {:08x}",
+            expected_crc,
+            crc.value()
+        )));
+    }
+    if length as u32 != expected_len {
+        return Err(gzip_corrupt(format!(
+            "length mismatch: expected {} (mod 2^32), got {}",
+            expected_len, length
+        )));
+    }
+    Ok(length)
+}
+
+/// Read and validate one member header, leaving the reader at the deflate data.
+fn read_member_header<R: Read>(reader: &mut InflateReader<R>) -> Result<()> {
+    let mut header = [0u8; 10];
+    reader.read_trailer(&mut header).map_err(gzip_io_error)?;
+    if header[0] != 0x1f || header[1] != 0x8b {
+        return Err(gzip_corrupt("not a gzip stream (bad magic)".to_string()));
+    }
+    if header[2] != 8 {
+        return Err(gzip_corrupt(format!("unsupported compression method {}", header[2])));
+    }
+    let flags = header[3];
+    if flags & !(FTEXT | FHCRC | FEXTRA | FNAME | FCOMMENT) != 0 {
+        return Err(gzip_corrupt(format!("reserved header flags set ({:#04x})", flags)));
+    }
+
+    if flags & FEXTRA != 0 {
+        let mut xlen = [0u8; 2];
+        reader.read_trailer(&mut xlen).map_err(gzip_io_error)?;
+        let mut extra = vec![0u8; u16::from_le_bytes(xlen) as usize];
+        reader.read_trailer(&mut extra).map_err(gzip_io_error)?;
+    }
+    for flag in [FNAME, FCOMMENT] {
+        if flags & flag != 0 {
+            let mut byte = [0u8; 1];
+            loop {
+                reader.read_trailer(&mut byte).map_err(gzip_io_error)?;
+                if byte[0] == 0 {
+                    break;
+                }
+            }
+        }
+    }
+    if flags & FHCRC != 0 {
+        let mut header_crc = [0u8; 2];
+        reader.read_trailer(&mut header_crc).map_err(gzip_io_error)?;
+    }
+    Ok(())
+}
+
+fn gzip_corrupt(reason: String) -> ZipError {
+    ZipError::GzipCorrupt { reason }
+}
+
+fn gzip_io_error(e: io::Error) -> ZipError {
+    match e.kind() {
+        io::ErrorKind::UnexpectedEof => gzip_corrupt("unexpected end of data".to_string()),
+        io::ErrorKind::InvalidData => gzip_corrupt(format!("decompression failed: {}", e)),
+        _ => ZipError::Io(e),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -97,4 +305,101 @@ mod tests {
         let inflated = inflate_with_stats(&compressed.output, 1, None).unwrap();
         assert_eq!(inflated.output, data);
     }
+
+    #[test]
+    fn test_inflate_rejects_bad_adler32() {
+        let data = b"Hello world! Hello world! Hello world!";
+        let mut compressed = deflate_with_stats(data, 6, 1, None).unwrap().output;
+        let last = compressed.len() - 1;
+        compressed[last] ^= 0xff;
+        let err = inflate(&compressed).unwrap_err();
+        assert!(err.to_string().contains("inflate failed"), "{}", err);
+    }
+
+    fn gzip_member(data: &[u8]) -> Vec<u8> {
+        let mut out = vec![0x1f, 0x8b, 8, FNAME, 0, 0, 0, 0, 0, 3];
+        out.extend_from_slice(b"data.bin\0");
+        out.extend_from_slice(&crate::miniz::deflate::compress_raw(data, 6).unwrap());
+        out.extend_from_slice(&crate::miniz::crc32(0, data).to_le_bytes());
+        out.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        out
+    }
+
+    #[test]
+    fn test_member_stats_stop_at_member_end() {
+        let data = b"Hello world! Hello world! Hello world!";
+        let member = gzip_member(data);
+        let mut buffer = b"xyz".to_vec();
+        buffer.extend_from_slice(&member);
+        buffer.extend_from_slice(b"trailing bytes");
+
+        let inflated = inflate_member_with_stats(&buffer, 4, None).unwrap();
+        assert_eq!(inflated.output, data);
+        assert_eq!(inflated.bytes_read, member.len());
+        assert_eq!(inflated.bytes_written, data.len());
+        assert_eq!(&buffer[3 + inflated.bytes_read..], b"trailing bytes");
+
+        let mut bad_crc = member.clone();
+        let footer = bad_crc.len() - 8;
+        bad_crc[footer] ^= 0xff;
+        let err = inflate_member_with_stats(&bad_crc, 1, None).unwrap_err();
+        assert!(matches!(err, ZipError::GzipCorrupt { .. }), "{}", err);
+    }
+
+    #[test]
+    fn test_verify() {
+        let data: Vec<u8> = (0..100_000u32).map(|i| (i % 251) as u8 ^ (i / 997) as u8).collect();
+        let good = gzip_member(&data);
+        assert_eq!(verify(good.as_slice()).unwrap(), data.len() as u64);
+
+        let mut two = good.clone();
+        two.extend_from_slice(&gzip_member(b"second member"));
+        assert_eq!(verify(two.as_slice()).unwrap(), data.len() as u64 + 13);
+
+        let footer = good.len() - 8;
+        let mut bad_crc = good.clone();
+        bad_crc[footer] ^= 0xff;
+        let err = verify(bad_crc.as_slice()).unwrap_err();
+        assert!(err.to_string().contains("CRC32 mismatch"), "{}", err);
+
+        let mut bad_len = good.clone();
+        bad_len[footer + 4] ^= 0xff;
+        let err = verify(bad_len.as_slice()).unwrap_err();
+        assert!(err.to_string().contains("length mismatch"), "{}", err);
+
+        let err = verify(&good[..good.len() - 3]).unwrap_err();
+        assert!(matches!(err, ZipError::GzipCorrupt { .. }), "{}", err);
+    }
+
+    #[test]
+    fn test_gzip_zip_round_trip() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let data: Vec<u8> = (0..50_000u32).map(|i| (i % 13) as u8 + b'a').collect();
+        let gz_path = temp.path().join("data.gz");
+        let mut gz = gzip_member(&data[..20_000]);
+        gz.extend_from_slice(&gzip_member(&data[20_000..]));
+        std::fs::write(&gz_path, gz).unwrap();
+
+        let zip_path = temp.path().join("data.zip");
+        gzip_to_zip(&gz_path, &zip_path, "dir/data.txt").unwrap();
+        let archive = ZipArchive::open(&zip_path).unwrap();
+        let entries = archive.entries().unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].filename, "dir/data.txt");
+        assert!(entries[0].compressed_size < data.len() as u64);
+
+        let back = temp.path().join("back.gz");
+        zip_entry_to_gzip(&zip_path, "dir/data.txt", &back).unwrap();
+        let bytes = std::fs::read(&back).unwrap();
+        assert_eq!(&bytes[..4], &[0x1f, 0x8b, 8, FNAME]);
+        assert_eq!(&bytes[10..19], b"data.txt\0");
+
+        let mut round_trip = Vec::new();
+        let len = decode_members(bytes.as_slice(), |chunk| round_trip.extend_from_slice(chunk)).unwrap();
+        assert_eq!(len, data.len() as u64);
+        assert_eq!(round_trip, data);
+
+        let err = zip_entry_to_gzip(&zip_path, "missing", &back).unwrap_err();
+        assert!(matches!(err, ZipError::EntryNotFound { .. }), "{:?}", err);
+    }
 }
@@ -26,34 +26,90 @@
 //!     .unwrap();
 //! ```
 
+#[cfg(feature = "aes")]
+mod aes_ctr;
+pub mod cancel;
 pub mod error;
 pub mod gzip;
 pub mod miniz;
 pub mod platform;
 pub mod process;
+#[cfg(feature = "aes")]
+mod sha1;
+mod sha256;
 pub mod unzip;
 pub mod zip;
 
 // 重导出常用类型
+pub use cancel::CancelToken;
 pub use error::{
-    CompressionLevel, FileType, Result, ZipEntry, ZipError, ZipErrorCode, ZipMode,
+    compression_method_name, Compat, CompressionLevel, DuplicatePolicy, Encoding, Encryption, EntryTimes, FileType, HostOs, PreserveSet, Result, ZipEntry, ZipError, ZipErrorCode, ZipMode,
 };
-pub use gzip::{deflate as gzip_deflate, inflate as gzip_inflate};
-pub use miniz::{adler32, crc32};
+#[cfg(feature = "aes")]
+pub use error::AesStrength;
+pub use gzip::{deflate as gzip_deflate, gzip_to_zip, inflate as gzip_inflate, zip_entry_to_gzip};
+pub use miniz::{adler32, crc32, DeflateImpl};
 pub use process::{UnzipProcess, ZipProcess};
 pub use zip::append;
-pub use zip::{ZipBuildOutput, ZipBuilder};
+pub use zip::{concat, concat_with, CollisionPolicy};
+pub use zip::{zip_directory, ZipDirectoryOptions, ZipDirectorySummary};
+pub use zip::{BuildItem, ZipBuildOutput, ZipBuilder};
 pub use zip::data::ZipWarning;
 
 // 纯 Rust unzip 模块
-pub use unzip::{Extractor, ZipArchive};
+pub use unzip::{classify_entry_name, external_attr_to_mode, is_safe_entry_name, normalize_entry_name, EntryNameClass};
+pub use unzip::{ArchiveOverhead, ArchiveStats, CompatibilityReport, EntryReader, ManifestMismatch, ExtractOutput, ExtractWarning, Extractor, Layout, StreamingZipReader, SymlinkPolicy, ZipArchive};
+#[cfg(feature = "async")]
+pub use unzip::AsyncExtractor;
 
 // 纯 Rust ZIP writer
-pub use zip::writer::ZipWriter;
+pub use zip::writer::{mode_to_external_attr, EntrySummary, PrecompressedEntry, ZipWriter};
 
 /// 库版本
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
 
+/// 编译进来的可选功能，见 [`features`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Features {
+    /// WinZip AES 加密（`aes` feature）
+    pub aes: bool,
+    /// bzip2 解压；本 crate 尚未实现，始终为 false
+    pub bzip2: bool,
+    /// zstd 解压；本 crate 尚未实现，始终为 false
+    pub zstd: bool,
+    /// 内存映射读取归档（`mmap` feature）
+    pub mmap: bool,
+    /// 基于 tokio 的 `AsyncExtractor`（`async` feature）
+    pub async_io: bool,
+    /// 基于 rayon 的并行解压 `ZipArchive::par_read_all`（`rayon` feature）
+    pub rayon: bool,
+    /// 扩展属性的保存与还原（`xattr` feature）
+    pub xattr: bool,
+}
+
+/// 返回当前构建编译进来的可选功能
+pub fn features() -> Features {
+    Features {
+        aes: cfg!(feature = "aes"),
+        bzip2: false,
+        zstd: false,
+        mmap: cfg!(feature = "mmap"),
+        async_io: cfg!(feature = "async"),
+        rayon: cfg!(feature = "rayon"),
+        xattr: cfg!(feature = "xattr"),
+    }
+}
+
+/// 可以解压的压缩方法，按方法号升序：0（存储）、8（DEFLATE），
+/// 启用 `aes` feature 时还有 99（WinZip AES，内部数据为存储或 DEFLATE）
+pub fn supported_methods() -> &'static [u16] {
+    if cfg!(feature = "aes") {
+        &[0, 8, 99]
+    } else {
+        &[0, 8]
+    }
+}
+
 /// 创建 ZIP 文件（便捷函数）
 ///
 /// # 参数
@@ -148,6 +204,19 @@ mod tests {
         assert!(!VERSION.is_empty());
     }
 
+    #[cfg(not(any(feature = "aes", feature = "mmap", feature = "async", feature = "rayon", feature = "xattr")))]
+    #[test]
+    fn test_core_features() {
+        assert_eq!(features(), Features::default());
+        assert_eq!(supported_methods(), &[0, 8]);
+    }
+
+    #[test]
+    fn test_supported_methods_follow_features() {
+        assert!(supported_methods().starts_with(&[0, 8]));
+        assert_eq!(supported_methods().contains(&99), features().aes);
+    }
+
     #[test]
     fn test_current_platform() {
         let _platform = current_platform();
@@ -216,7 +216,7 @@ impl BitReader {
         // 跳过剩余的位以到达字节边界
         let num_bits = self.num_bits % 8;
         if num_bits > 0 {
-            println!("DEBUG: read_byte skipping {} bits at position {}", num_bits, self.pos);
+            log::trace!("read_byte skipping {} bits at position {}", num_bits, self.pos);
             self.bit_buf >>= num_bits;
             self.num_bits -= num_bits;
         }
@@ -224,11 +224,11 @@ impl BitReader {
         // 现在我们在字节边界，可以读取下一个字节
         if self.pos < self.data.len() {
             let result = self.data[self.pos];
-            println!("DEBUG: read_byte reading byte {} from position {}", result, self.pos);
+            log::trace!("read_byte reading byte {} from position {}", result, self.pos);
             self.pos += 1;
             Some(result)
         } else {
-            println!("DEBUG: read_byte no more data at position {}", self.pos);
+            log::trace!("read_byte no more data at position {}", self.pos);
             None
         }
     }
@@ -1,6 +1,7 @@
 //! CRC32 校验和计算
 //!
-//! 使用标准多项式 0xEDB88320
+//! 默认使用 ZIP/gzip 的标准多项式 0xEDB88320（CRC-32/ISO-HDLC），
+//! [`Crc32::with_polynomial`] 可选 CRC-32C（Castagnoli，0x82F63B78）。
 
 /// CRC32 查找表（预计算）
 const CRC32_TABLE: [u32; 256] = [
@@ -43,9 +44,38 @@ const CRC32_TABLE: [u32; 256] = [
     0xB40BBE37, 0xC30C8EA1, 0x5A05DF1B, 0x2D02EF8D,
 ];
 
+/// CRC-32C 查找表（反射多项式 0x82F63B78），编译期生成
+const CRC32C_TABLE: [u32; 256] = make_table(0x82F63B78);
+
+const fn make_table(poly: u32) -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ poly } else { crc >> 1 };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
 /// CRC32 初始值
 pub const CRC32_INIT: u32 = 0;
 
+/// CRC32 多项式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Crc32Variant {
+    /// CRC-32/ISO-HDLC（0xEDB88320），ZIP 和 gzip 使用的标准 CRC32
+    #[default]
+    IsoHdlc,
+    /// CRC-32C / Castagnoli（0x82F63B78），iSCSI、ext4 等使用
+    Crc32C,
+}
+
 /// 计算 CRC32 校验和
 pub fn crc32(crc: u32, data: &[u8]) -> u32 {
     let mut crc32 = crc ^ 0xFFFFFFFF;
@@ -79,16 +109,39 @@ pub fn crc32(crc: u32, data: &[u8]) -> u32 {
     crc32 ^ 0xFFFFFFFF
 }
 
+/// 计算 CRC-32C 校验和
+pub fn crc32c(crc: u32, data: &[u8]) -> u32 {
+    let mut crc = crc ^ 0xFFFFFFFF;
+    for &byte in data {
+        crc = (crc >> 8) ^ CRC32C_TABLE[((crc ^ byte as u32) & 0xFF) as usize];
+    }
+    crc ^ 0xFFFFFFFF
+}
+
 /// CRC32 计算器（流式）
 #[derive(Debug, Clone, Default)]
 pub struct Crc32 {
     crc: u32,
+    variant: Crc32Variant,
 }
 
 impl Crc32 {
-    /// 创建新的 CRC32 计算器
+    /// 创建新的 CRC32 计算器（CRC-32/ISO-HDLC）
     pub fn new() -> Self {
-        Self { crc: CRC32_INIT }
+        Self::with_polynomial(Crc32Variant::IsoHdlc)
+    }
+
+    /// 创建使用指定多项式的计算器
+    pub fn with_polynomial(variant: Crc32Variant) -> Self {
+        Self {
+            crc: CRC32_INIT,
+            variant,
+        }
+    }
+
+    /// 当前使用的多项式
+    pub fn variant(&self) -> Crc32Variant {
+        self.variant
     }
 
     /// 重置计算器
@@ -98,7 +151,10 @@ impl Crc32 {
 
     /// 更新数据
     pub fn update(&mut self, data: &[u8]) -> u32 {
-        self.crc = crc32(self.crc, data);
+        self.crc = match self.variant {
+            Crc32Variant::IsoHdlc => crc32(self.crc, data),
+            Crc32Variant::Crc32C => crc32c(self.crc, data),
+        };
         self.crc
     }
 
@@ -107,7 +163,7 @@ impl Crc32 {
         self.crc
     }
 
-    /// 计算数据的 CRC32
+    /// 计算数据的 CRC32（CRC-32/ISO-HDLC）
     pub fn compute(data: &[u8]) -> u32 {
         crc32(CRC32_INIT, data)
     }
@@ -132,4 +188,19 @@ mod tests {
         hasher.update(b"world!");
         assert_eq!(hasher.value(), Crc32::compute(b"Hello world!"));
     }
+
+    #[test]
+    fn test_crc32_variants() {
+        let mut castagnoli = Crc32::with_polynomial(Crc32Variant::Crc32C);
+        castagnoli.update(b"1234");
+        castagnoli.update(b"56789");
+        assert_eq!(castagnoli.value(), 0xE3069283);
+        assert_eq!(crc32c(CRC32_INIT, b""), 0);
+
+        let mut zip = Crc32::default();
+        assert_eq!(zip.variant(), Crc32Variant::IsoHdlc);
+        zip.update(b"123456789");
+        assert_eq!(zip.value(), 0xCBF43926);
+        assert_eq!(zip.value(), Crc32::compute(b"123456789"));
+    }
 }
@@ -8,10 +8,18 @@
 //! 参考：`/home/putao/code/c-cpp/zip/src/miniz.c`
 
 use crate::miniz::bitstream::BitWriter;
+use crate::miniz::deflate_fast::DeflateFast;
+use crate::miniz::deflate_good::DeflateGood;
 use crate::miniz::huffman::HuffmanTable;
 use crate::miniz;
 use std::mem;
 
+/// 空输入的 DEFLATE 数据：一个只含块结束符的最终静态 Huffman 块
+const EMPTY_FINAL_BLOCK: [u8; 2] = [0x03, 0x00];
+
+/// 空数据的 Adler32
+const ADLER32_EMPTY: u32 = 1;
+
 /// DEFLATE 压缩级别
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum CompressionLevel {
@@ -23,13 +31,32 @@ pub enum CompressionLevel {
     Max = 9,
 }
 
+/// DEFLATE 压缩实现
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DeflateImpl {
+    /// 单次探测的 LZ77 + 静态 Huffman（对应 C 版本 tdefl_compress_fast()），速度优先
+    #[default]
+    Fast,
+    /// 哈希链 LZ77 + 动态 Huffman（对应 C 版本 tdefl_compress_normal()），压缩率优先
+    Good,
+}
+
 /// DEFLATE 压缩选项
 #[derive(Debug, Clone)]
 pub struct DeflateOptions {
     pub level: CompressionLevel,
+    /// 窗口大小的以 2 为底的对数（8-15），限制 LZ77 的最大匹配距离并写入 ZLIB 头部的 CINFO
     pub window_bits: i32,
     pub mem_level: i32,
     pub strategy: Strategy,
+    pub algorithm: DeflateImpl,
+    /// 哈希链上每个位置最多探测的候选匹配数（对应 zlib 的 max_chain），`None` 按级别取值；
+    /// 以下三项只作用于 `DeflateImpl::Good`
+    pub max_chain_length: Option<usize>,
+    /// 惰性匹配时上一个匹配达到该长度就只探测四分之一的链（对应 zlib 的 good_length），`None` 按级别取值
+    pub good_match: Option<usize>,
+    /// 找到该长度的匹配即停止探测（对应 zlib 的 nice_length，3-258），`None` 按级别取值
+    pub nice_match: Option<usize>,
 }
 
 impl Default for DeflateOptions {
@@ -39,6 +66,10 @@ impl Default for DeflateOptions {
             window_bits: 15,
             mem_level: 8,
             strategy: Strategy::Default,
+            algorithm: DeflateImpl::Fast,
+            max_chain_length: None,
+            good_match: None,
+            nice_match: None,
         }
     }
 }
@@ -76,6 +107,12 @@ pub struct DeflateEncoder {
     state: DeflateState,
     input_buffer: Vec<u8>,
     output_buffer: Vec<u8>,
+    /// LZ77 快速压缩器（字典 + 哈希表），在多次压缩之间复用
+    fast: DeflateFast,
+    /// 哈希链压缩器，仅在选择 `DeflateImpl::Good` 时创建
+    good: Option<DeflateGood>,
+    /// 已输入的未压缩数据的 Adler32，写入 ZLIB 尾部
+    adler32: u32,
 }
 
 impl DeflateEncoder {
@@ -107,12 +144,53 @@ impl DeflateEncoder {
             },
             input_buffer: Vec::new(),
             output_buffer: Vec::new(),
+            fast: DeflateFast::new().max_distance(window_size(options.window_bits)),
+            good: match options.algorithm {
+                DeflateImpl::Fast => None,
+                DeflateImpl::Good => Some(Self::good_encoder(&options)),
+            },
+            adler32: miniz::ADLER32_INIT,
         })
     }
 
+    /// 按级别创建哈希链压缩器，再应用选项中显式设置的调优参数
+    fn good_encoder(options: &DeflateOptions) -> DeflateGood {
+        let mut good = DeflateGood::for_level(options.level as i32).max_distance(window_size(options.window_bits));
+        if let Some(max_chain) = options.max_chain_length {
+            good = good.max_chain(max_chain);
+        }
+        if let Some(good_match) = options.good_match {
+            good = good.good_match(good_match);
+        }
+        if let Some(nice_match) = options.nice_match {
+            good = good.nice_match(nice_match);
+        }
+        good
+    }
+
+    /// 重置压缩状态，保留已分配的字典、哈希表和缓冲区，
+    /// 以便同一个压缩器连续处理多个条目
+    /// 对应 C 版本的 tdefl_init()
+    pub fn reset(&mut self) {
+        self.state.block_start = 0;
+        self.state.next_out = 0;
+        self.state.avail_out = 0;
+        self.state.total_out = 0;
+        self.state.hash.fill(0);
+        self.state.prev.fill(0);
+        self.input_buffer.clear();
+        self.output_buffer.clear();
+        self.fast.reset();
+        if let Some(good) = &mut self.good {
+            good.reset();
+        }
+        self.adler32 = miniz::ADLER32_INIT;
+    }
+
     /// 压缩数据
     pub fn compress(&mut self, data: &[u8], flush: FlushMode) -> Result<usize, DeflateError> {
         let compressed_data = self.deflate_compress(data, flush)?;
+        self.adler32 = miniz::adler32(self.adler32, data);
 
         self.output_buffer.extend_from_slice(&compressed_data);
 
@@ -125,11 +203,14 @@ impl DeflateEncoder {
         let mut output = mem::take(&mut self.output_buffer);
 
         // 如果需要 ZLIB header（window_bits > 8）
-        if self.state.options.window_bits > 8 {
+        if self.has_zlib_header() {
             // 对于空数据，使用正确的格式
             if output.is_empty() {
-                // 直接返回 C 版本和 Python zlib 使用的格式
-                return Ok(vec![0x78, 0x9C, 0x03, 0x00, 0x00, 0x00, 0x00, 0x01]);
+                // 与 C 版本和 Python zlib 相同的格式（window_bits 为 15 时即 78 9C 03 00 00 00 00 01）
+                let mut empty = self.create_zlib_header();
+                empty.extend_from_slice(&EMPTY_FINAL_BLOCK);
+                empty.extend_from_slice(&ADLER32_EMPTY.to_be_bytes());
+                return Ok(empty);
             }
 
             // 非空数据，添加 ZLIB header
@@ -138,16 +219,15 @@ impl DeflateEncoder {
         } else {
             // 对于空数据但没有 ZLIB header，也返回正确格式
             if output.is_empty() {
-                return Ok(vec![0x03, 0x00, 0x00, 0x00, 0x00, 0x01]);
+                let mut empty = EMPTY_FINAL_BLOCK.to_vec();
+                empty.extend_from_slice(&ADLER32_EMPTY.to_be_bytes());
+                return Ok(empty);
             }
         }
 
-        // 添加 Adler32 校验和（紧跟在 ZLIB header 或压缩数据之后）
-        let adler32_val = miniz::adler32(0, &output);
-        output.push((adler32_val >> 24) as u8);
-        output.push((adler32_val >> 16) as u8);
-        output.push((adler32_val >> 8) as u8);
-        output.push(adler32_val as u8);
+        // 添加未压缩数据的 Adler32 校验和（大端，紧跟在压缩数据之后）
+        // 对应 C 版本 tdefl_compress() 在 TDEFL_FINISH 时写入的 m_adler32
+        output.extend_from_slice(&self.adler32.to_be_bytes());
 
         Ok(output)
     }
@@ -195,41 +275,30 @@ pub struct DeflateOutput {
 /// - `pos`: 起始位置（1-based，与 C 版本一致）
 /// - `_size`: 缓冲区大小估计，None 表示自动分配（当前未使用）
 pub fn compress(data: &[u8], level: i32, pos: i32, _size: Option<i32>) -> Result<DeflateOutput, DeflateError> {
-    // 特殊处理空数据
-    if data.is_empty() {
-        // 返回标准的空压缩数据（ZLIB 格式）
-        return Ok(DeflateOutput {
-            output: vec![0x78, 0x9C, 0x03, 0x00, 0x00, 0x00, 0x00, 0x01],
-            bytes_read: 0,
-            bytes_written: 8,
-        });
-    }
+    let level = level_from_i32(level)?;
 
-    // 验证 pos 范围（1-based 索引）
-    if pos < 1 {
-        return Err(DeflateError::BadParam);
-    }
+    // 空数据没有可以指向的起始位置，不检查 pos，照常输出一个空的 ZLIB 流
+    let input_data = if data.is_empty() {
+        data
+    } else {
+        // 验证 pos 范围（1-based 索引）
+        if pos < 1 {
+            return Err(DeflateError::BadParam);
+        }
 
-    let pos_index = (pos - 1) as usize;
+        let pos_index = (pos - 1) as usize;
 
-    // 验证 pos 不超出范围
-    if pos_index >= data.len() {
-        return Err(DeflateError::BadParam);
-    }
+        // 验证 pos 不超出范围
+        if pos_index >= data.len() {
+            return Err(DeflateError::BadParam);
+        }
 
-    // 从 pos 位置开始读取数据
-    let input_data = &data[pos_index..];
+        // 从 pos 位置开始读取数据
+        &data[pos_index..]
+    };
 
     let options = DeflateOptions {
-        level: match level {
-            0 => CompressionLevel::NoCompression,
-            1 => CompressionLevel::Fastest,
-            2 => CompressionLevel::Fast,
-            6 => CompressionLevel::Default,
-            7 => CompressionLevel::High,
-            9 => CompressionLevel::Max,
-            _ => return Err(DeflateError::InvalidLevel),
-        },
+        level,
         window_bits: 15,  // ZLIB format with header
         ..Default::default()
     };
@@ -247,6 +316,31 @@ pub fn compress(data: &[u8], level: i32, pos: i32, _size: Option<i32>) -> Result
     })
 }
 
+/// 按选项压缩为 ZLIB 格式，`window_bits` 决定窗口大小和头部的 CINFO
+pub fn compress_with_options(data: &[u8], options: DeflateOptions) -> Result<Vec<u8>, DeflateError> {
+    let mut encoder = DeflateEncoder::new(options)?;
+    encoder.compress(data, FlushMode::Finish)?;
+    encoder.get_compressed()
+}
+
+/// `window_bits` 对应的窗口字节数，超出 8-15 的值按边界处理
+fn window_size(window_bits: i32) -> usize {
+    1 << window_bits.clamp(8, 15)
+}
+
+/// 将整数压缩级别转换为 `CompressionLevel`
+pub fn level_from_i32(level: i32) -> Result<CompressionLevel, DeflateError> {
+    match level {
+        0 => Ok(CompressionLevel::NoCompression),
+        1 => Ok(CompressionLevel::Fastest),
+        2 => Ok(CompressionLevel::Fast),
+        6 => Ok(CompressionLevel::Default),
+        7 => Ok(CompressionLevel::High),
+        9 => Ok(CompressionLevel::Max),
+        _ => Err(DeflateError::InvalidLevel),
+    }
+}
+
 /// 压缩结果（带统计信息）
 pub struct CompressResult {
     pub output: Vec<u8>,
@@ -268,52 +362,93 @@ pub fn compress_to_buffer(data: &[u8], level: i32, capacity: usize) -> Result<Co
 /// 原始 DEFLATE 压缩（不带 ZLIB 头部）
 pub fn compress_raw(data: &[u8], level: i32) -> Result<Vec<u8>, DeflateError> {
     let result = compress(data, level, 1, None)?;
+    strip_zlib_framing(&result.output, true)
+}
+
+/// 使用已有的压缩器做原始 DEFLATE 压缩（不带 ZLIB 头部）
+///
+/// 与 `compress_raw` 输出相同，但复用 `encoder` 的工作内存；
+/// 调用前会先 `reset()`，因此同一个压缩器可以依次处理多个条目。
+pub fn compress_raw_with(encoder: &mut DeflateEncoder, data: &[u8]) -> Result<Vec<u8>, DeflateError> {
+    encoder.reset();
+    encoder.compress(data, FlushMode::Finish)?;
+    let output = encoder.get_compressed()?;
+    strip_zlib_framing(&output, encoder.has_zlib_header())
+}
+
+/// 把一段数据压缩为原始 DEFLATE 片段，按顺序拼接各片段即得到一个完整的 DEFLATE 流
+///
+/// `finish` 为 false 的片段不设置 BFINAL 并按字节对齐；最后一段必须以 `finish = true` 调用，
+/// 即使 `data` 为空也会写出结束块。每段都先 `reset()`，片段之间不跨段引用，
+/// 因此压缩率略低于一次性压缩，但内存占用只与单段大小有关。
+pub fn compress_raw_segment_with(encoder: &mut DeflateEncoder, data: &[u8], finish: bool) -> Result<Vec<u8>, DeflateError> {
+    encoder.reset();
+    let flush = if finish { FlushMode::Finish } else { FlushMode::Sync };
+    let output = encoder.deflate_compress(data, flush)?;
+    if finish && output.is_empty() {
+        return Ok(EMPTY_FINAL_BLOCK.to_vec());
+    }
+    Ok(output)
+}
+
+/// 把数据写成存储块（BTYPE=00），每块最多 65535 字节；`is_final` 时最后一块设置 BFINAL
+pub fn stored_blocks(data: &[u8], is_final: bool) -> Vec<u8> {
+    let mut output = Vec::with_capacity(data.len() + data.len() / 65535 * 5 + 5);
+    let mut chunks = data.chunks(u16::MAX as usize).peekable();
+    if data.is_empty() && is_final {
+        // 空的最终存储块
+        output.extend_from_slice(&[0x01, 0x00, 0x00, 0xff, 0xff]);
+    }
+    while let Some(chunk) = chunks.next() {
+        // 块头 (BFINAL, BTYPE=00 无压缩)，其余位为对齐填充
+        output.push((is_final && chunks.peek().is_none()) as u8);
 
-    // 移除 ZLIB 头部和尾部
-    if result.output.len() >= 6 {
-        // 跳过 ZLIB 头部 (2字节) 和 CRC32 (4字节)
-        Ok(result.output[2..result.output.len() - 4].to_vec())
+        // 长度及其补码 (小端)
+        let len = chunk.len() as u16;
+        output.extend_from_slice(&len.to_le_bytes());
+        output.extend_from_slice(&(!len).to_le_bytes());
+
+        // 数据
+        output.extend_from_slice(chunk);
+    }
+    output
+}
+
+/// 去掉 ZLIB 头部（`header` 为 true 时，2 字节）和 Adler32 尾部（4 字节），只留下 DEFLATE 数据
+fn strip_zlib_framing(output: &[u8], header: bool) -> Result<Vec<u8>, DeflateError> {
+    let start = if header { 2 } else { 0 };
+    if output.len() >= start + 4 {
+        Ok(output[start..output.len() - 4].to_vec())
     } else {
         Err(DeflateError::CompressionError("Compressed data too short".to_string()))
     }
 }
 
 impl DeflateEncoder {
-    fn deflate_compress(&mut self, data: &[u8], _flush: FlushMode) -> Result<Vec<u8>, DeflateError> {
+    /// `flush` 不是 `Finish` 时输出不设置 BFINAL：未压缩模式下存储块本身按字节对齐，
+    /// 其余模式以同步标记结尾，下一次调用的输出可以直接接在后面
+    fn deflate_compress(&mut self, data: &[u8], flush: FlushMode) -> Result<Vec<u8>, DeflateError> {
+        let is_final = flush == FlushMode::Finish;
         if self.state.options.level == CompressionLevel::NoCompression {
-            // 不压缩，直接存储
-            let mut output = Vec::new();
-
-            if !data.is_empty() {
-                // 块头 (BFINAL=0, BTYPE=00 无压缩)
-                output.push(0x00);
-
-                // 长度 (小端)
-                let len = data.len() as u16;
-                output.push(len as u8);
-                output.push((len >> 8) as u8);
-
-                // 255的补码
-                output.push(!len as u8);
-                output.push((!len >> 8) as u8);
-
-                // 数据
-                output.extend_from_slice(data);
-            } else {
-                // 对于 NoCompression 级别的空数据，也使用静态 Huffman 块
-                // 块头 (BFINAL=0, BTYPE=10 静态Huffman)
-                output.push(0x01);  // BFINAL=0, BTYPE=10
-
-                // 块结束标记（静态 Huffman 编码的 256）
-                output.push(0x00);
-                output.push(0x2d);
+            // 对于空数据，让 get_compressed 处理特殊的 ZLIB 格式
+            if data.is_empty() {
+                return Ok(Vec::new());
             }
 
-            return Ok(output);
+            // 不压缩，直接存储
+            return Ok(stored_blocks(data, is_final));
         }
 
-        // 使用快速压缩实现
-        self.compress_fast(data)
+        log::trace!(
+            "deflate {} bytes with {:?} at {:?}",
+            data.len(),
+            self.state.options.algorithm,
+            self.state.options.level
+        );
+        match self.state.options.algorithm {
+            DeflateImpl::Fast => self.compress_fast(data, is_final),
+            DeflateImpl::Good => self.compress_good(data, is_final),
+        }
     }
 
     /// 位反转函数 - 将 MSB 优先的码转换为 LSB 优先
@@ -326,7 +461,7 @@ impl DeflateEncoder {
     }
 
     /// 使用快速压缩实现
-    fn compress_fast(&self, data: &[u8]) -> Result<Vec<u8>, DeflateError> {
+    fn compress_fast(&mut self, data: &[u8], is_final: bool) -> Result<Vec<u8>, DeflateError> {
         use crate::miniz::deflate_fast;
 
         // 对于空数据，让 get_compressed 处理特殊的 ZLIB 格式
@@ -334,12 +469,29 @@ impl DeflateEncoder {
             return Ok(Vec::new());
         }
 
-        // 使用快速压缩（LZ77 + 静态Huffman）
-        deflate_fast::deflate_compress_fast(data).map_err(|e| DeflateError::CompressionError(e))
+        // 使用快速压缩（LZ77 + 静态Huffman），复用压缩器内部的字典和哈希表
+        deflate_fast::deflate_compress_fast_segment(&mut self.fast, data, is_final)
+            .map_err(|e| DeflateError::CompressionError(e))
+    }
+
+    /// 使用哈希链 + 动态 Huffman 实现
+    fn compress_good(&mut self, data: &[u8], is_final: bool) -> Result<Vec<u8>, DeflateError> {
+        use crate::miniz::deflate_good;
+
+        if data.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let level = self.state.options.level as i32;
+        let window = window_size(self.state.options.window_bits);
+        let good = self
+            .good
+            .get_or_insert_with(|| DeflateGood::for_level(level).max_distance(window));
+        deflate_good::deflate_compress_good_segment(good, data, is_final).map_err(DeflateError::CompressionError)
     }
 
     /// ZLIB 格式的压缩实现（带头部和尾部）
-    fn compress_zlib(&self, data: &[u8]) -> Result<Vec<u8>, DeflateError> {
+    fn compress_zlib(&mut self, data: &[u8]) -> Result<Vec<u8>, DeflateError> {
         let mut output = Vec::new();
 
         // 添加 ZLIB 头部
@@ -358,7 +510,7 @@ impl DeflateEncoder {
             output.push(0xFF);
         } else {
             // 对于非空数据，使用 DEFLATE 压缩
-            let deflate_data = self.compress_fast(data)?;
+            let deflate_data = self.compress_fast(data, true)?;
             output.extend_from_slice(&deflate_data);
         }
 
@@ -366,15 +518,21 @@ impl DeflateEncoder {
         Ok(output)
     }
 
+    /// 输出是否带 ZLIB 头部（`window_bits` 大于 8 时）；Adler32 尾部总是存在
+    fn has_zlib_header(&self) -> bool {
+        self.state.options.window_bits > 8
+    }
+
     /// 创建 ZLIB 头部
     fn create_zlib_header(&self) -> Vec<u8> {
-        // CMF (Compression Method and flags): method=8 (deflate), info=7 (level 6)
-        // FLG (Flags): check_bits=156 (makes CMF*256+FLG divisible by 31)
-        // 0x78 = 0b01111000 (method=8, info=7)
-        // 0x9C = 0b10011100 (check_bits=156) - standard zlib header
-        let cmf = 0x78; // method=8, info=7
-        let flg = 0x9C; // standard zlib header
-        vec![cmf, flg]
+        // CMF: method=8 (deflate)，CINFO = window_bits - 8
+        // FLG: FLEVEL=2（默认级别），FCHECK 使 CMF*256+FLG 能被 31 整除
+        // window_bits 为 15 时即标准的 0x78 0x9C
+        let cinfo = (self.state.options.window_bits.clamp(8, 15) - 8) as u8;
+        let cmf = 0x08 | (cinfo << 4);
+        let flg = 0x80u8;
+        let fcheck = (31 - (cmf as u16 * 256 + flg as u16) % 31) % 31;
+        vec![cmf, flg | fcheck as u8]
     }
 
     fn get_static_huffman_code(&self, byte: u8) -> u16 {
@@ -428,4 +586,209 @@ mod tests {
         let with_header = compress(data, 6, 1, None).unwrap();
         assert!(compressed.len() < with_header.output.len());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_window_bits_limits_distance() {
+        use crate::miniz::deflate_fast::LZSymbol;
+        use crate::miniz::inflate;
+
+        // 2000 字节的伪随机块重复三次：32 KB 窗口下可以整块回引，1 KB 窗口下不行
+        let mut seed = 7u32;
+        let block: Vec<u8> = (0..2000)
+            .map(|_| {
+                seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                (seed >> 16) as u8
+            })
+            .collect();
+        let data = block.repeat(3);
+
+        let max_distance = |symbols: Vec<LZSymbol>| {
+            symbols
+                .iter()
+                .filter_map(|s| match s {
+                    LZSymbol::Match { distance, .. } => Some(*distance as usize),
+                    LZSymbol::Literal(_) => None,
+                })
+                .max()
+                .unwrap_or(0)
+        };
+        assert!(max_distance(DeflateFast::new().compress(&data)) > 1024);
+        assert!(max_distance(DeflateFast::new().max_distance(1024).compress(&data)) <= 1024);
+        assert!(max_distance(DeflateGood::for_level(9).compress(&data)) > 1024);
+        assert!(max_distance(DeflateGood::for_level(9).max_distance(1024).compress(&data)) <= 1024);
+
+        for algorithm in [DeflateImpl::Fast, DeflateImpl::Good] {
+            let options = DeflateOptions {
+                window_bits: 10,
+                algorithm,
+                ..Default::default()
+            };
+            let compressed = compress_with_options(&data, options).unwrap();
+            assert_eq!(compressed[0], 0x28, "CINFO should declare a 1 KB window");
+            assert_eq!((compressed[0] as u32 * 256 + compressed[1] as u32) % 31, 0);
+            assert_eq!(inflate::decompress(&compressed, 1, None).unwrap().output, data);
+        }
+
+        // 默认窗口仍然输出标准头部
+        let compressed = compress_with_options(b"", DeflateOptions::default()).unwrap();
+        assert_eq!(compressed, [0x78, 0x9C, 0x03, 0x00, 0x00, 0x00, 0x00, 0x01]);
+    }
+
+    #[test]
+    fn test_good_tuning_options() {
+        use crate::miniz::inflate;
+
+        // 从 400 个伪随机 6 字节片段中随机拼接：同一哈希下有大量候选，链越长越容易找到更长的匹配
+        let mut seed = 11u32;
+        let mut next = || {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            seed >> 16
+        };
+        let pieces: Vec<Vec<u8>> = (0..400).map(|_| (0..6).map(|_| b'a' + (next() % 4) as u8).collect()).collect();
+        let data: Vec<u8> = (0..8_000).flat_map(|_| pieces[next() as usize % pieces.len()].clone()).collect();
+
+        let compress_with = |max_chain_length, good_match, nice_match| {
+            let mut encoder = DeflateEncoder::new(DeflateOptions {
+                level: CompressionLevel::Max,
+                algorithm: DeflateImpl::Good,
+                max_chain_length,
+                good_match,
+                nice_match,
+                ..Default::default()
+            })
+            .unwrap();
+            let raw = compress_raw_with(&mut encoder, &data).unwrap();
+            assert_eq!(inflate::decompress_raw(&raw).unwrap(), data);
+            raw.len()
+        };
+
+        let short_chain = compress_with(Some(1), None, None);
+        let default_chain = compress_with(None, None, None);
+        let long_chain = compress_with(Some(8192), Some(258), Some(258));
+        assert!(long_chain < short_chain, "{} vs {}", long_chain, short_chain);
+        assert!(default_chain < short_chain, "{} vs {}", default_chain, short_chain);
+        assert!(long_chain <= default_chain, "{} vs {}", long_chain, default_chain);
+
+        // 较小的 nice_match 提前结束探测，输出仍然有效
+        assert!(compress_with(None, None, Some(3)) >= default_chain);
+    }
+
+    #[test]
+    fn test_zlib_trailer_is_adler32_of_input() {
+        // 与 Python zlib.compress(b"Hello world!", 6) 逐字节一致
+        let zlib = compress(b"Hello world!", 6, 1, None).unwrap().output;
+        assert_eq!(
+            zlib,
+            [
+                0x78, 0x9c, 0xf3, 0x48, 0xcd, 0xc9, 0xc9, 0x57, 0x28, 0xcf, 0x2f, 0xca, 0x49, 0x51, 0x04, 0x00,
+                0x1d, 0x09, 0x04, 0x5e
+            ]
+        );
+
+        // 复用的压缩器在 reset() 后重新计算
+        let data: Vec<u8> = (0..100_000u32).map(|i| (i % 251) as u8 ^ (i / 997) as u8).collect();
+        let mut encoder = DeflateEncoder::new(DeflateOptions {
+            algorithm: DeflateImpl::Good,
+            ..Default::default()
+        })
+        .unwrap();
+        for input in [&data[..], &b"abc"[..]] {
+            encoder.reset();
+            encoder.compress(input, FlushMode::Finish).unwrap();
+            let output = encoder.get_compressed().unwrap();
+            assert_eq!(output[output.len() - 4..], miniz::adler32(miniz::ADLER32_INIT, input).to_be_bytes());
+        }
+    }
+
+    #[test]
+    fn test_empty_input_round_trips() {
+        use crate::miniz::inflate;
+
+        // ZLIB 格式与原始 DEFLATE
+        for level in [0, 1, 2, 6, 7, 9] {
+            let zlib = compress(b"", level, 1, None).unwrap();
+            assert_eq!(zlib.output, [0x78, 0x9C, 0x03, 0x00, 0x00, 0x00, 0x00, 0x01]);
+            assert_eq!((zlib.bytes_read, zlib.bytes_written), (0, 8));
+            assert!(inflate::decompress(&zlib.output, 1, None).unwrap().output.is_empty());
+
+            let raw = compress_raw(b"", level).unwrap();
+            assert_eq!(raw, EMPTY_FINAL_BLOCK);
+            assert!(inflate::decompress_raw(&raw).unwrap().is_empty());
+        }
+        // 空输入同样校验压缩级别
+        assert!(matches!(compress(b"", 42, 1, None), Err(DeflateError::InvalidLevel)));
+
+        // 存储级别跨越多个存储块时最后一块才是最终块
+        let data: Vec<u8> = (0..150_000u32).map(|i| (i % 251) as u8).collect();
+        let stored = compress_raw(&data, 0).unwrap();
+        assert_eq!(stored.len(), data.len() + 3 * 5);
+        assert_eq!(inflate::decompress_raw(&stored).unwrap(), data);
+
+        // 复用的压缩器，包括不带 ZLIB 头部的小窗口
+        for window_bits in [8, 15] {
+            for algorithm in [DeflateImpl::Fast, DeflateImpl::Good] {
+                let mut encoder = DeflateEncoder::new(DeflateOptions {
+                    window_bits,
+                    algorithm,
+                    ..Default::default()
+                })
+                .unwrap();
+                for data in [&b""[..], b"abc", b""] {
+                    let raw = compress_raw_with(&mut encoder, data).unwrap();
+                    assert_eq!(inflate::decompress_raw(&raw).unwrap(), data, "window_bits {}", window_bits);
+                }
+            }
+        }
+
+        // gzip 模块
+        let gz = crate::gzip::deflate(b"").unwrap();
+        let inflated = crate::gzip::inflate_with_stats(&gz, 1, None).unwrap();
+        assert!(inflated.output.is_empty());
+        assert_eq!(inflated.bytes_read, gz.len());
+
+        // ZIP 中的空条目
+        let temp = tempfile::TempDir::new().unwrap();
+        let zip_path = temp.path().join("empty.zip");
+        let mut writer =
+            crate::zip::ZipWriter::new(&zip_path, crate::error::CompressionLevel::Level6).unwrap();
+        writer.add_reader("empty.txt", &mut &b""[..]).unwrap();
+        writer.finalize().unwrap();
+        let archive = crate::unzip::ZipArchive::open(&zip_path).unwrap();
+        assert!(archive.read_entry("empty.txt").unwrap().is_empty());
+        let mut streamed = Vec::new();
+        std::io::Read::read_to_end(&mut archive.entry_reader("empty.txt").unwrap(), &mut streamed).unwrap();
+        assert!(streamed.is_empty());
+    }
+
+    #[test]
+    fn test_segments_concatenate_into_one_stream() {
+        use crate::miniz::inflate;
+
+        let data: Vec<u8> = (0..200_000u32).map(|i| (i % 251) as u8 ^ (i / 997) as u8).collect();
+        for level in [CompressionLevel::NoCompression, CompressionLevel::Fastest, CompressionLevel::Max] {
+            for algorithm in [DeflateImpl::Fast, DeflateImpl::Good] {
+                let mut encoder = DeflateEncoder::new(DeflateOptions {
+                    level,
+                    algorithm,
+                    ..Default::default()
+                })
+                .unwrap();
+                // 段长不是 8 的倍数，非最终段的结束位置落在字节中间
+                let mut stream = Vec::new();
+                for chunk in data.chunks(70_001) {
+                    let segment = compress_raw_segment_with(&mut encoder, chunk, false).unwrap();
+                    stream.extend_from_slice(&segment);
+                }
+                stream.extend_from_slice(&compress_raw_segment_with(&mut encoder, b"", true).unwrap());
+                assert_eq!(inflate::decompress_raw(&stream).unwrap(), data, "{:?} {:?}", level, algorithm);
+
+                // 存储块与压缩片段可以混用
+                let mut stream = compress_raw_segment_with(&mut encoder, b"abcabcabc", false).unwrap();
+                stream.extend_from_slice(&stored_blocks(b"xyz", false));
+                stream.extend_from_slice(&compress_raw_segment_with(&mut encoder, b"abc", true).unwrap());
+                assert_eq!(inflate::decompress_raw(&stream).unwrap(), b"abcabcabcxyzabc");
+            }
+        }
+        assert_eq!(inflate::decompress_raw(&stored_blocks(b"", true)).unwrap(), b"");
+    }
+}
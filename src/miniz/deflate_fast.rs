@@ -3,6 +3,7 @@
 //! 复刻 /home/putao/code/c-cpp/zip/src/miniz.c 的 LZ77 压缩算法
 
 use crate::miniz::bitstream::BitWriter;
+use crate::miniz::deflate_good::write_sync_flush;
 
 // 常量定义（完全对应 C 版本）
 const TDEFL_LZ_DICT_SIZE: usize = 32768;
@@ -19,6 +20,8 @@ pub struct DeflateFast {
     dict: Vec<u8>,
     /// 哈希表（3字节trigram -> 位置）
     hash: Vec<u16>,
+    /// 允许的最大匹配距离（窗口大小），不超过 TDEFL_LZ_DICT_SIZE
+    max_distance: usize,
 }
 
 impl DeflateFast {
@@ -28,9 +31,21 @@ impl DeflateFast {
             // 字典大小 = TDEFL_LZ_DICT_SIZE + TDEFL_MAX_MATCH_LEN - 1
             dict: vec![0; TDEFL_LZ_DICT_SIZE + TDEFL_MAX_MATCH_LEN - 1],
             hash: vec![0; 4096], // TDEFL_LEVEL1_HASH_SIZE_MASK + 1
+            max_distance: TDEFL_LZ_DICT_SIZE,
         }
     }
 
+    /// 限制最大匹配距离，对应 zlib 的 windowBits（窗口为 `1 << windowBits` 字节）
+    pub fn max_distance(mut self, max_distance: usize) -> Self {
+        self.max_distance = max_distance.clamp(1, TDEFL_LZ_DICT_SIZE);
+        self
+    }
+
+    /// 重置哈希表，保留字典和哈希表的分配
+    pub fn reset(&mut self) {
+        self.hash.fill(0);
+    }
+
     /// 读取3字节trigram（小端）
     #[inline(always)]
     fn read_trigram(dict: &[u8], pos: usize) -> u32 {
@@ -119,10 +134,10 @@ impl DeflateFast {
                 let probe_pos = self.hash[hash as usize] as usize;
                 self.hash[hash as usize] = lookahead_pos as u16;
 
-                // 检查是否找到匹配
-                cur_match_dist = lookahead_pos - probe_pos;
+                // 检查是否找到匹配（16 位回绕相减，对应 C 版本的 (mz_uint16)(lookahead_pos - probe_pos)）
+                cur_match_dist = (lookahead_pos as u16).wrapping_sub(probe_pos as u16) as usize;
 
-                if cur_match_dist <= dict_size
+                if cur_match_dist <= dict_size.min(self.max_distance)
                     && cur_match_dist > 0
                     && (Self::read_trigram(&self.dict, probe_pos & TDEFL_LZ_DICT_SIZE_MASK) & 0xFFFFFF)
                         == first_trigram
@@ -132,22 +147,14 @@ impl DeflateFast {
                     let p = &self.dict[cur_pos..];
                     let q = &self.dict[probe_pos..];
 
-                    // 快速比较（每次比较2字节）
-                    let mut probe_len = 32u32;
+                    // 逐字节扩展匹配，最长 TDEFL_MAX_MATCH_LEN（C 版本 32 轮、每轮 8 字节，覆盖完整的 258 字节）
                     let mut match_len = 0;
 
                     while match_len < lookahead_size
                         && match_len < TDEFL_MAX_MATCH_LEN
-                        && probe_len > 0
+                        && p[match_len] == q[match_len]
                     {
-                        if p[match_len] == q[match_len] {
-                            match_len += 1;
-                            if match_len % 2 == 0 {
-                                probe_len -= 1;
-                            }
-                        } else {
-                            break;
-                        }
+                        match_len += 1;
                     }
 
                     cur_match_len = match_len;
@@ -164,7 +171,7 @@ impl DeflateFast {
                         cur_match_len = cur_match_len.min(lookahead_size);
                         symbols.push(LZSymbol::Match {
                             length: cur_match_len as u16,
-                            distance: (cur_match_dist - 1) as u16,
+                            distance: cur_match_dist as u16,
                         });
                     }
                 } else {
@@ -215,14 +222,28 @@ pub fn deflate_compress_fast(data: &[u8]) -> Result<Vec<u8>, String> {
         return Ok(Vec::new());
     }
 
-    let mut encoder = DeflateFast::new();
+    deflate_compress_fast_with(&mut DeflateFast::new(), data)
+}
+
+/// 使用调用方提供的压缩器执行 LZ77 + 静态Huffman编码，复用其字典和哈希表
+pub fn deflate_compress_fast_with(encoder: &mut DeflateFast, data: &[u8]) -> Result<Vec<u8>, String> {
+    deflate_compress_fast_segment(encoder, data, true)
+}
+
+/// 压缩一段数据；`is_final` 为 false 时不设置 BFINAL，并以空存储块（同步标记）结尾，
+/// 使输出按字节对齐，可以直接接上下一段
+pub fn deflate_compress_fast_segment(encoder: &mut DeflateFast, data: &[u8], is_final: bool) -> Result<Vec<u8>, String> {
+    if data.is_empty() {
+        return Ok(Vec::new());
+    }
+
     let symbols = encoder.compress(data);
 
     // 使用BitWriter写入DEFLATE格式
     let mut bit_writer = BitWriter::new();
 
-    // 块头 (BFINAL=1, BTYPE=01 静态Huffman)
-    bit_writer.write_bits(0x03, 3);
+    // 块头 (BFINAL, BTYPE=01 静态Huffman)
+    bit_writer.write_bits(0x02 | is_final as u32, 3);
 
     // 对每个符号进行Huffman编码
     for symbol in symbols {
@@ -257,9 +278,9 @@ pub fn deflate_compress_fast(data: &[u8]) -> Result<Vec<u8>, String> {
                 }
 
                 // 编码长度
-                let len_huffman = LENGTH_HUFFMAN[len_code as usize - 257];
-                let reversed_code = reverse_bits(len_huffman.0 as u32, len_huffman.1);
-                bit_writer.write_bits(reversed_code, len_huffman.1);
+                let (code, code_len) = static_length_code(len_code);
+                let reversed_code = reverse_bits(code, code_len);
+                bit_writer.write_bits(reversed_code, code_len);
                 if len_extra_bits > 0 {
                     bit_writer.write_bits(len_extra_val, len_extra_bits as u8);
                 }
@@ -293,6 +314,10 @@ pub fn deflate_compress_fast(data: &[u8]) -> Result<Vec<u8>, String> {
     // 块结束标记（符号 256）
     bit_writer.write_bits(0x0000000, 7);
 
+    if !is_final {
+        write_sync_flush(&mut bit_writer);
+    }
+
     // 对齐到字节边界
     bit_writer.align_to_byte();
 
@@ -328,39 +353,17 @@ const DIST_EXTRA_TABLE: [u8; 30] = [
     13, 13,
 ];
 
-// 静态Huffman码表（MSB）
-const LENGTH_HUFFMAN: [(u16, u8); 29] = [
-    (0b0010000, 5),   // 257
-    (0b11100000, 5),  // 258
-    (0b0100000, 5),   // 259
-    (0b11000000, 5),  // 260
-    (0b1010000, 5),   // 261
-    (0b0110000, 5),   // 262
-    (0b0001000, 5),   // 263
-    (0b1001000, 5),   // 264
-    (0b0001010, 5),   // 265
-    (0b1001010, 5),   // 266
-    (0b1001100, 5),   // 267
-    (0b1001110, 5),   // 268
-    (0b1110100, 6),   // 269
-    (0b1111100, 6),   // 270
-    (0b1111101, 6),   // 271
-    (0b11100100, 7),  // 272
-    (0b11101100, 7),  // 273
-    (0b11101101, 7),  // 274
-    (0b11110100, 7),  // 275
-    (0b11110101, 7),  // 276
-    (0b11110110, 7),  // 277
-    (0b11110111, 7),  // 278
-    (0b111110010, 8), // 279
-    (0b111110011, 8), // 280
-    (0b111110100, 8), // 281
-    (0b111110101, 8), // 282
-    (0b111110110, 8), // 283
-    (0b111110111, 8), // 284
-    (0b111111000, 8), // 285
-];
+/// 长度符号（257-285）的静态 Huffman 码（MSB 优先）
+/// 256-279 为 7 位码 0000000-0010111，280-287 为 8 位码 11000000-11000111（RFC 1951 3.2.6）
+fn static_length_code(symbol: u32) -> (u32, u8) {
+    if symbol <= 279 {
+        (symbol - 256, 7)
+    } else {
+        (0xC0 + (symbol - 280), 8)
+    }
+}
 
+// 静态距离码：5 位定长码，码值即距离码（MSB）
 const DIST_HUFFMAN: [(u16, u8); 30] = [
     (0b00000, 5),     // 0
     (0b00001, 5),     // 1
@@ -413,4 +416,33 @@ mod tests {
         // 应该能够显著压缩重复模式
         assert!(compressed.len() < data.len() / 2, "Should compress repeated pattern");
     }
+
+    #[test]
+    fn test_max_match_length() {
+        use crate::miniz::deflate_good::deflate_compress_good;
+        use crate::miniz::inflate::decompress_raw;
+
+        // 以固定距离 100 重复，匹配长度跨越 258 上限前后
+        let block: Vec<u8> = (0..100u32).map(|i| (i * 7 + 3) as u8).collect();
+        for repeat_len in [255, 256, 257, 258, 259, 260, 516, 1000] {
+            let mut data = block.clone();
+            data.extend(block.iter().cycle().take(repeat_len));
+
+            let symbols = DeflateFast::new().compress(&data);
+            assert!(symbols.iter().all(|s| match s {
+                LZSymbol::Match { length, .. } => (3..=258).contains(length),
+                LZSymbol::Literal(_) => true,
+            }));
+            if repeat_len >= 258 {
+                assert!(symbols
+                    .iter()
+                    .any(|s| matches!(s, LZSymbol::Match { length: 258, distance: 100 })));
+            }
+
+            let fast = deflate_compress_fast(&data).unwrap();
+            assert_eq!(decompress_raw(&fast).unwrap(), data, "fast, repeat {}", repeat_len);
+            let good = deflate_compress_good(&data, 9).unwrap();
+            assert_eq!(decompress_raw(&good).unwrap(), data, "good, repeat {}", repeat_len);
+        }
+    }
 }
@@ -0,0 +1,703 @@
+//! DEFLATE 高压缩比实现 - 哈希链 LZ77 + 动态 Huffman
+//!
+//! 对应 C 版本 tdefl_compress_normal() 与 tdefl_start_dynamic_block()：
+//! - LZ77 沿哈希链查找最长匹配，而不是只探测一次
+//! - 较高级别使用惰性匹配：下一个位置有更长的匹配时先输出一个字面量
+//! - 每个块根据符号频率构造 Huffman 码，并在动态 / 静态 / 存储块之间取最短的一种
+
+use crate::miniz::bitstream::BitWriter;
+use crate::miniz::deflate_fast::LZSymbol;
+use crate::miniz::huffman::{
+    DIST_BASE, DIST_EXTRA, FIXED_DISTANCE_CODE_LENGTHS, FIXED_LITLEN_CODE_LENGTHS, LENGTH_BASE,
+    LENGTH_DEZIGZAG, LENGTH_EXTRA,
+};
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+const WINDOW_SIZE: usize = 32768;
+const WINDOW_MASK: usize = WINDOW_SIZE - 1;
+const MIN_MATCH_LEN: usize = 3;
+const MAX_MATCH_LEN: usize = 258;
+const HASH_BITS: usize = 15;
+const HASH_SIZE: usize = 1 << HASH_BITS;
+/// 距离超过该值的 3 字节匹配不划算（对应 zlib 的 TOO_FAR）
+const TOO_FAR: usize = 4096;
+/// 每个块最多包含的 LZ 符号数（对应 C 版本 TDEFL_LZ_CODE_BUF_SIZE 的量级）
+const BLOCK_SYMBOLS: usize = 16384;
+/// 存储块的最大长度
+const MAX_STORED_LEN: usize = 65535;
+
+/// 字面量/长度码表大小（0-285）
+const LITLEN_CODES: usize = 286;
+/// 距离码表大小（0-29）
+const DIST_CODES: usize = 30;
+/// 码长码表大小（0-18）
+const CODE_LENGTH_CODES: usize = 19;
+
+/// 哈希链 LZ77 压缩器
+pub struct DeflateGood {
+    /// 哈希表：3 字节哈希 -> 最近出现位置 + 1（0 表示空）
+    head: Vec<usize>,
+    /// 哈希链：位置 & WINDOW_MASK -> 同一哈希的上一个位置 + 1
+    prev: Vec<usize>,
+    /// 每个位置最多探测的链长度
+    max_chain: usize,
+    /// 上一个位置的匹配达到该长度时，探测链缩短为四分之一（对应 zlib 的 good_length）
+    good_match: usize,
+    /// 找到该长度的匹配即停止探测（对应 zlib 的 nice_length）
+    nice_match: usize,
+    /// 是否使用惰性匹配（对应 zlib 的 deflate_slow）
+    lazy: bool,
+    /// 允许的最大匹配距离（窗口大小），不超过 WINDOW_SIZE
+    max_distance: usize,
+}
+
+impl DeflateGood {
+    /// 创建新的贪心匹配压缩器，`max_chain` 为每个位置最多探测的候选匹配数
+    pub fn new(max_chain: usize) -> Self {
+        Self {
+            head: vec![0; HASH_SIZE],
+            prev: vec![0; WINDOW_SIZE],
+            max_chain: max_chain.max(1),
+            good_match: MAX_MATCH_LEN,
+            nice_match: MAX_MATCH_LEN,
+            lazy: false,
+            max_distance: WINDOW_SIZE,
+        }
+    }
+
+    /// 根据压缩级别创建压缩器，级别越高哈希链越长；与 zlib 一样从级别 4 起使用惰性匹配，
+    /// good / nice 长度取 zlib 配置表中相近级别的值
+    pub fn for_level(level: i32) -> Self {
+        let (max_chain, good_match, nice_match) = match level {
+            ..=2 => (8, 4, 16),
+            3..=5 => (32, 8, 32),
+            6 => (128, 8, 128),
+            7 | 8 => (1024, 32, MAX_MATCH_LEN),
+            _ => (4096, 32, MAX_MATCH_LEN),
+        };
+        Self::new(max_chain)
+            .good_match(good_match)
+            .nice_match(nice_match)
+            .lazy(level >= 4)
+    }
+
+    /// 设置每个位置最多探测的候选匹配数
+    pub fn max_chain(mut self, max_chain: usize) -> Self {
+        self.max_chain = max_chain.max(1);
+        self
+    }
+
+    /// 设置缩短探测链的匹配长度：惰性匹配时上一个位置的匹配不短于该值，就只探测四分之一的链
+    pub fn good_match(mut self, good_match: usize) -> Self {
+        self.good_match = good_match.max(MIN_MATCH_LEN);
+        self
+    }
+
+    /// 设置足够好的匹配长度：找到不短于该值的匹配即停止探测
+    pub fn nice_match(mut self, nice_match: usize) -> Self {
+        self.nice_match = nice_match.clamp(MIN_MATCH_LEN, MAX_MATCH_LEN);
+        self
+    }
+
+    /// 设置是否使用惰性匹配
+    pub fn lazy(mut self, lazy: bool) -> Self {
+        self.lazy = lazy;
+        self
+    }
+
+    /// 限制最大匹配距离，对应 zlib 的 windowBits（窗口为 `1 << windowBits` 字节）
+    pub fn max_distance(mut self, max_distance: usize) -> Self {
+        self.max_distance = max_distance.clamp(1, WINDOW_SIZE);
+        self
+    }
+
+    /// 重置哈希表，保留分配
+    pub fn reset(&mut self) {
+        self.head.fill(0);
+        self.prev.fill(0);
+    }
+
+    #[inline(always)]
+    fn hash(data: &[u8], pos: usize) -> usize {
+        let v = (data[pos] as usize) | ((data[pos + 1] as usize) << 8) | ((data[pos + 2] as usize) << 16);
+        (v.wrapping_mul(0x9E37_79B1) >> (32 - HASH_BITS)) & (HASH_SIZE - 1)
+    }
+
+    /// 把位置 `pos` 插入哈希链
+    #[inline(always)]
+    fn insert(&mut self, data: &[u8], pos: usize) {
+        if pos + MIN_MATCH_LEN <= data.len() {
+            let h = Self::hash(data, pos);
+            self.prev[pos & WINDOW_MASK] = self.head[h];
+            self.head[h] = pos + 1;
+        }
+    }
+
+    /// 沿哈希链查找最长匹配，返回 (长度, 距离)；长度小于 3 表示没有可用匹配
+    ///
+    /// `prev_len` 为上一个位置尚未输出的匹配长度，达到 `good_match` 时只探测四分之一的链。
+    fn longest_match(&self, data: &[u8], pos: usize, prev_len: usize) -> (usize, usize) {
+        if pos + MIN_MATCH_LEN > data.len() {
+            return (0, 0);
+        }
+
+        let max_len = MAX_MATCH_LEN.min(data.len() - pos);
+        let mut best_len = MIN_MATCH_LEN - 1;
+        let mut best_dist = 0;
+        let mut candidate = self.head[Self::hash(data, pos)];
+        let nice_len = self.nice_match.min(max_len);
+        let mut chain = if prev_len >= self.good_match {
+            (self.max_chain >> 2).max(1)
+        } else {
+            self.max_chain
+        };
+
+        while candidate != 0 && chain > 0 {
+            let cand_pos = candidate - 1;
+            let dist = pos - cand_pos;
+            if dist == 0 || dist > self.max_distance {
+                break;
+            }
+
+            // 先比较当前最优长度处的字节，快速排除不可能更长的候选
+            if data[cand_pos + best_len] == data[pos + best_len] {
+                let len = data[cand_pos..cand_pos + max_len]
+                    .iter()
+                    .zip(&data[pos..pos + max_len])
+                    .take_while(|(a, b)| a == b)
+                    .count();
+                if len > best_len {
+                    best_len = len;
+                    best_dist = dist;
+                    if len >= nice_len {
+                        break;
+                    }
+                }
+            }
+
+            // 链上的位置必须严格递减，否则说明槽位已被更新的位置覆盖
+            let next = self.prev[cand_pos & WINDOW_MASK];
+            if next >= candidate {
+                break;
+            }
+            candidate = next;
+            chain -= 1;
+        }
+
+        if best_len == MIN_MATCH_LEN && best_dist > TOO_FAR {
+            return (0, 0);
+        }
+        (best_len, best_dist)
+    }
+
+    /// 压缩数据，返回 LZ 编码序列（距离为实际距离 1-32768）
+    pub fn compress(&mut self, data: &[u8]) -> Vec<LZSymbol> {
+        self.reset();
+        if self.lazy {
+            return self.compress_lazy(data);
+        }
+
+        let mut symbols = Vec::new();
+        let mut pos = 0;
+        while pos < data.len() {
+            let (len, dist) = self.longest_match(data, pos, 0);
+            if len >= MIN_MATCH_LEN {
+                symbols.push(LZSymbol::Match {
+                    length: len as u16,
+                    distance: dist as u16,
+                });
+                for p in pos..pos + len {
+                    self.insert(data, p);
+                }
+                pos += len;
+            } else {
+                symbols.push(LZSymbol::Literal(data[pos]));
+                self.insert(data, pos);
+                pos += 1;
+            }
+        }
+        symbols
+    }
+
+    /// 惰性匹配：在位置 p 找到匹配后先不输出，看 p + 1 是否有更长的匹配；
+    /// 有则把 p 处的字节作为字面量输出，改用新的匹配
+    fn compress_lazy(&mut self, data: &[u8]) -> Vec<LZSymbol> {
+        let mut symbols = Vec::new();
+        // 在 pos - 1 处找到、尚未输出的匹配
+        let mut pending: Option<(usize, usize)> = None;
+        let mut pos = 0;
+        while pos < data.len() {
+            let prev_len = pending.map_or(0, |(prev_len, _)| prev_len);
+            let (len, dist) = self.longest_match(data, pos, prev_len);
+
+            if let Some((prev_len, prev_dist)) = pending.take() {
+                if len <= prev_len {
+                    // 当前位置没有更好的匹配：输出上一个位置的匹配（pos - 1 已插入哈希链）
+                    symbols.push(LZSymbol::Match {
+                        length: prev_len as u16,
+                        distance: prev_dist as u16,
+                    });
+                    let end = pos - 1 + prev_len;
+                    for p in pos..end {
+                        self.insert(data, p);
+                    }
+                    pos = end;
+                    continue;
+                }
+                symbols.push(LZSymbol::Literal(data[pos - 1]));
+            }
+
+            self.insert(data, pos);
+            if len >= MIN_MATCH_LEN {
+                pending = Some((len, dist));
+            } else {
+                symbols.push(LZSymbol::Literal(data[pos]));
+            }
+            pos += 1;
+        }
+        symbols
+    }
+}
+
+/// 使用哈希链 LZ77 + 动态 Huffman 压缩数据（原始 DEFLATE 流，不含 zlib 头）
+pub fn deflate_compress_good_with(encoder: &mut DeflateGood, data: &[u8]) -> Result<Vec<u8>, String> {
+    deflate_compress_good_segment(encoder, data, true)
+}
+
+/// 压缩一段数据；`is_final` 为 false 时最后一块不设置 BFINAL，并以同步标记结尾
+pub fn deflate_compress_good_segment(encoder: &mut DeflateGood, data: &[u8], is_final: bool) -> Result<Vec<u8>, String> {
+    if data.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let symbols = encoder.compress(data);
+    let mut writer = BitWriter::new();
+
+    let mut block_start = 0; // 当前块在原始数据中的起始偏移
+    let num_blocks = symbols.len().div_ceil(BLOCK_SYMBOLS);
+    for (i, block) in symbols.chunks(BLOCK_SYMBOLS).enumerate() {
+        let block_len: usize = block.iter().map(symbol_len).sum();
+        let raw = &data[block_start..block_start + block_len];
+        write_block(&mut writer, block, raw, is_final && i + 1 == num_blocks);
+        block_start += block_len;
+    }
+    if !is_final {
+        write_sync_flush(&mut writer);
+    }
+
+    Ok(writer.into_bytes())
+}
+
+/// 使用哈希链 LZ77 + 动态 Huffman 压缩数据
+pub fn deflate_compress_good(data: &[u8], level: i32) -> Result<Vec<u8>, String> {
+    deflate_compress_good_with(&mut DeflateGood::for_level(level), data)
+}
+
+/// 符号覆盖的原始字节数
+fn symbol_len(symbol: &LZSymbol) -> usize {
+    match symbol {
+        LZSymbol::Literal(_) => 1,
+        LZSymbol::Match { length, .. } => *length as usize,
+    }
+}
+
+/// 长度 -> (长度码下标 0-28, 额外位数, 额外位值)
+fn length_code(length: u16) -> (usize, u8, u32) {
+    let idx = match LENGTH_BASE[..29].binary_search(&length) {
+        Ok(i) => i,
+        Err(i) => i - 1,
+    };
+    (idx, LENGTH_EXTRA[idx], (length - LENGTH_BASE[idx]) as u32)
+}
+
+/// 距离 -> (距离码 0-29, 额外位数, 额外位值)
+fn dist_code(distance: u16) -> (usize, u8, u32) {
+    let idx = match DIST_BASE.binary_search(&distance) {
+        Ok(i) => i,
+        Err(i) => i - 1,
+    };
+    (idx, DIST_EXTRA[idx], (distance - DIST_BASE[idx]) as u32)
+}
+
+/// 由频率构造码长不超过 `max_bits` 的 Huffman 码长
+///
+/// 超长时把频率减半后重建，直到满足限制。
+/// 至少有两个符号获得码长，保证码是完整的。
+fn build_code_lengths(freqs: &[u32], max_bits: u8) -> Vec<u8> {
+    let mut freqs = freqs.to_vec();
+    let used = freqs.iter().filter(|&&f| f > 0).count();
+    if used < 2 {
+        // 补齐到两个符号：单个符号的码长为 1
+        for f in freqs.iter_mut().take(2) {
+            if *f == 0 {
+                *f = 1;
+            }
+        }
+    }
+
+    loop {
+        let lengths = huffman_lengths(&freqs);
+        if lengths.iter().all(|&l| l <= max_bits) {
+            return lengths;
+        }
+        for f in freqs.iter_mut().filter(|f| **f > 0) {
+            *f = (*f >> 1) | 1;
+        }
+    }
+}
+
+/// 标准 Huffman 构造，返回各符号的码长（频率为 0 的符号码长为 0）
+fn huffman_lengths(freqs: &[u32]) -> Vec<u8> {
+    // 节点：叶子为 0..n，内部节点依次追加；parent 记录父节点下标
+    let n = freqs.len();
+    let mut parent = vec![usize::MAX; n];
+    let mut heap: BinaryHeap<Reverse<(u64, usize)>> = freqs
+        .iter()
+        .enumerate()
+        .filter(|(_, &f)| f > 0)
+        .map(|(i, &f)| Reverse((f as u64, i)))
+        .collect();
+
+    while heap.len() > 1 {
+        let Reverse((fa, a)) = heap.pop().unwrap();
+        let Reverse((fb, b)) = heap.pop().unwrap();
+        let node = parent.len();
+        parent.push(usize::MAX);
+        parent[a] = node;
+        parent[b] = node;
+        heap.push(Reverse((fa + fb, node)));
+    }
+
+    // 内部节点总是在子节点之后创建，从后往前即可算出深度
+    let mut depth = vec![0u8; parent.len()];
+    for i in (0..parent.len()).rev() {
+        if parent[i] != usize::MAX {
+            depth[i] = depth[parent[i]].saturating_add(1);
+        }
+    }
+    depth.truncate(n);
+    depth
+}
+
+/// 由码长生成规范 Huffman 码（MSB 优先），对应 RFC 1951 3.2.2
+fn canonical_codes(lengths: &[u8]) -> Vec<u16> {
+    let mut bl_count = [0u16; 16];
+    for &l in lengths {
+        if l > 0 {
+            bl_count[l as usize] += 1;
+        }
+    }
+    let mut next_code = [0u16; 16];
+    let mut code = 0u16;
+    for bits in 1..16 {
+        code = (code + bl_count[bits - 1]) << 1;
+        next_code[bits] = code;
+    }
+    lengths
+        .iter()
+        .map(|&l| {
+            if l == 0 {
+                0
+            } else {
+                let c = next_code[l as usize];
+                next_code[l as usize] += 1;
+                c
+            }
+        })
+        .collect()
+}
+
+/// 写入一个 Huffman 码（DEFLATE 按 LSB 优先写出，需反转位序）
+#[inline]
+fn write_code(writer: &mut BitWriter, code: u16, len: u8) {
+    let reversed = code.reverse_bits() >> (16 - len as u32);
+    writer.write_bits(reversed as u32, len);
+}
+
+/// 码长序列的游程编码：(符号 0-18, 额外位值)
+fn run_length_encode(lengths: &[u8]) -> Vec<(u8, u8)> {
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < lengths.len() {
+        let len = lengths[i];
+        let run = lengths[i..].iter().take_while(|&&l| l == len).count();
+
+        if len == 0 && run >= 3 {
+            let mut left = run;
+            while left >= 11 {
+                let n = left.min(138);
+                out.push((18, (n - 11) as u8));
+                left -= n;
+            }
+            if left >= 3 {
+                out.push((17, (left - 3) as u8));
+                left = 0;
+            }
+            out.extend(std::iter::repeat_n((0, 0), left));
+        } else if len != 0 && run >= 4 {
+            // 先输出一次码长本身，其后的重复用 16 表示
+            out.push((len, 0));
+            let mut left = run - 1;
+            while left >= 3 {
+                let n = left.min(6);
+                out.push((16, (n - 3) as u8));
+                left -= n;
+            }
+            out.extend(std::iter::repeat_n((len, 0), left));
+        } else {
+            out.extend(std::iter::repeat_n((len, 0), run));
+        }
+        i += run;
+    }
+    out
+}
+
+/// 统计块内字面量/长度码和距离码的频率（包含块结束符号 256）
+fn symbol_frequencies(block: &[LZSymbol]) -> (Vec<u32>, Vec<u32>) {
+    let mut lit_freq = vec![0u32; LITLEN_CODES];
+    let mut dist_freq = vec![0u32; DIST_CODES];
+    for symbol in block {
+        match *symbol {
+            LZSymbol::Literal(byte) => lit_freq[byte as usize] += 1,
+            LZSymbol::Match { length, distance } => {
+                lit_freq[257 + length_code(length).0] += 1;
+                dist_freq[dist_code(distance).0] += 1;
+            }
+        }
+    }
+    lit_freq[256] = 1;
+    (lit_freq, dist_freq)
+}
+
+/// 用给定码长编码块数据所需的位数（不含块头）
+fn data_cost(lit_freq: &[u32], dist_freq: &[u32], lit_lengths: &[u8], dist_lengths: &[u8]) -> u64 {
+    let mut bits = 0u64;
+    for (i, &f) in lit_freq.iter().enumerate() {
+        let extra = if i >= 257 { LENGTH_EXTRA[i - 257] as u64 } else { 0 };
+        bits += f as u64 * (lit_lengths[i] as u64 + extra);
+    }
+    for (i, &f) in dist_freq.iter().enumerate() {
+        bits += f as u64 * (dist_lengths[i] as u64 + DIST_EXTRA[i] as u64);
+    }
+    bits
+}
+
+/// 动态块头（HLIT/HDIST/HCLEN 与码长序列）
+struct DynamicHeader {
+    hlit: usize,
+    hdist: usize,
+    hclen: usize,
+    cl_lengths: Vec<u8>,
+    rle: Vec<(u8, u8)>,
+}
+
+impl DynamicHeader {
+    fn new(lit_lengths: &[u8], dist_lengths: &[u8]) -> Self {
+        let hlit = 257.max(lit_lengths.iter().rposition(|&l| l > 0).map_or(0, |i| i + 1));
+        let hdist = 1.max(dist_lengths.iter().rposition(|&l| l > 0).map_or(0, |i| i + 1));
+
+        let mut all_lengths = lit_lengths[..hlit].to_vec();
+        all_lengths.extend_from_slice(&dist_lengths[..hdist]);
+        let rle = run_length_encode(&all_lengths);
+
+        let mut cl_freq = vec![0u32; CODE_LENGTH_CODES];
+        for &(sym, _) in &rle {
+            cl_freq[sym as usize] += 1;
+        }
+        let cl_lengths = build_code_lengths(&cl_freq, 7);
+        let hclen = 4.max(
+            LENGTH_DEZIGZAG
+                .iter()
+                .rposition(|&s| cl_lengths[s] > 0)
+                .map_or(0, |i| i + 1),
+        );
+
+        Self { hlit, hdist, hclen, cl_lengths, rle }
+    }
+
+    /// 块头（不含 3 位 BFINAL/BTYPE）占用的位数
+    fn cost(&self) -> u64 {
+        let mut bits = 5 + 5 + 4 + 3 * self.hclen as u64;
+        for &(sym, _) in &self.rle {
+            bits += self.cl_lengths[sym as usize] as u64;
+            bits += match sym {
+                16 => 2,
+                17 => 3,
+                18 => 7,
+                _ => 0,
+            };
+        }
+        bits
+    }
+
+    fn write(&self, writer: &mut BitWriter) {
+        writer.write_bits((self.hlit - 257) as u32, 5);
+        writer.write_bits((self.hdist - 1) as u32, 5);
+        writer.write_bits((self.hclen - 4) as u32, 4);
+        for &sym in &LENGTH_DEZIGZAG[..self.hclen] {
+            writer.write_bits(self.cl_lengths[sym] as u32, 3);
+        }
+
+        let cl_codes = canonical_codes(&self.cl_lengths);
+        for &(sym, extra) in &self.rle {
+            write_code(writer, cl_codes[sym as usize], self.cl_lengths[sym as usize]);
+            match sym {
+                16 => writer.write_bits(extra as u32, 2),
+                17 => writer.write_bits(extra as u32, 3),
+                18 => writer.write_bits(extra as u32, 7),
+                _ => {}
+            }
+        }
+    }
+}
+
+/// 写出一个块，在动态 Huffman、静态 Huffman 和存储块中选择最短的一种
+fn write_block(writer: &mut BitWriter, block: &[LZSymbol], raw: &[u8], is_final: bool) {
+    let (lit_freq, dist_freq) = symbol_frequencies(block);
+
+    let lit_lengths = build_code_lengths(&lit_freq, 15);
+    let dist_lengths = build_code_lengths(&dist_freq, 15);
+    let header = DynamicHeader::new(&lit_lengths, &dist_lengths);
+    let dynamic_cost = header.cost() + data_cost(&lit_freq, &dist_freq, &lit_lengths, &dist_lengths);
+
+    // 规范码需要按完整的 288 个码长生成，否则 9 位码的起点会错
+    let fixed_lit = &FIXED_LITLEN_CODE_LENGTHS[..];
+    let fixed_dist = &FIXED_DISTANCE_CODE_LENGTHS[..];
+    let static_cost = data_cost(&lit_freq, &dist_freq, fixed_lit, fixed_dist);
+
+    // 存储块：对齐填充最多 7 位，每 65535 字节一个 LEN/NLEN（32 位）加块头
+    let stored_chunks = raw.len().div_ceil(MAX_STORED_LEN).max(1) as u64;
+    let stored_cost = 7 + stored_chunks * (32 + 3) + raw.len() as u64 * 8;
+
+    if stored_cost < dynamic_cost.min(static_cost) {
+        write_stored(writer, raw, is_final);
+    } else if static_cost <= dynamic_cost {
+        writer.write_bits(is_final as u32, 1);
+        writer.write_bits(1, 2); // BTYPE=01 静态 Huffman
+        write_symbols(writer, block, fixed_lit, fixed_dist);
+    } else {
+        writer.write_bits(is_final as u32, 1);
+        writer.write_bits(2, 2); // BTYPE=10 动态 Huffman
+        header.write(writer);
+        write_symbols(writer, block, &lit_lengths, &dist_lengths);
+    }
+}
+
+/// 用给定码长编码块内的符号，最后写入块结束符号 256
+fn write_symbols(writer: &mut BitWriter, block: &[LZSymbol], lit_lengths: &[u8], dist_lengths: &[u8]) {
+    let lit_codes = canonical_codes(lit_lengths);
+    let dist_codes = canonical_codes(dist_lengths);
+
+    for symbol in block {
+        match *symbol {
+            LZSymbol::Literal(byte) => {
+                write_code(writer, lit_codes[byte as usize], lit_lengths[byte as usize]);
+            }
+            LZSymbol::Match { length, distance } => {
+                let (idx, extra_bits, extra) = length_code(length);
+                write_code(writer, lit_codes[257 + idx], lit_lengths[257 + idx]);
+                writer.write_bits(extra, extra_bits);
+
+                let (idx, extra_bits, extra) = dist_code(distance);
+                write_code(writer, dist_codes[idx], dist_lengths[idx]);
+                writer.write_bits(extra, extra_bits);
+            }
+        }
+    }
+    write_code(writer, lit_codes[256], lit_lengths[256]);
+}
+
+/// 写出同步标记：一个非最终的空存储块（对应 zlib 的 Z_SYNC_FLUSH），之后输出按字节对齐
+pub(crate) fn write_sync_flush(writer: &mut BitWriter) {
+    write_stored(writer, &[], false);
+}
+
+/// 写出存储块，超过 65535 字节时拆成多个
+fn write_stored(writer: &mut BitWriter, raw: &[u8], is_final: bool) {
+    let chunks: Vec<&[u8]> = if raw.is_empty() {
+        vec![raw]
+    } else {
+        raw.chunks(MAX_STORED_LEN).collect()
+    };
+    let last = chunks.len() - 1;
+    for (i, chunk) in chunks.into_iter().enumerate() {
+        writer.write_bits((is_final && i == last) as u32, 1);
+        writer.write_bits(0, 2); // BTYPE=00 存储块
+        writer.align_to_byte();
+        let len = chunk.len() as u16;
+        writer.write_bits(len as u32, 16);
+        writer.write_bits(!len as u32, 16);
+        writer.write_bytes(chunk);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::miniz::deflate_fast::deflate_compress_fast;
+    use crate::miniz::inflate::decompress_raw;
+
+    fn text_corpus() -> Vec<u8> {
+        let words = [
+            "archive", "central", "directory", "deflate", "entry", "header", "local", "offset",
+            "compressed", "stored", "signature", "extra", "field", "comment", "method",
+        ];
+        let mut seed = 12345u32;
+        let mut out = Vec::new();
+        while out.len() < 100_000 {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            out.extend_from_slice(words[(seed >> 16) as usize % words.len()].as_bytes());
+            out.push(if (seed >> 8) & 7 == 0 { b'\n' } else { b' ' });
+        }
+        out
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let mut seed = 1u32;
+        let noise: Vec<u8> = (0..70_000)
+            .map(|_| {
+                seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                (seed >> 16) as u8
+            })
+            .collect();
+        let inputs: [Vec<u8>; 5] = [
+            b"a".to_vec(),
+            vec![0u8; 1000],
+            (0..=255u8).cycle().take(5000).collect(),
+            text_corpus(),
+            noise,
+        ];
+
+        for data in &inputs {
+            let compressed = deflate_compress_good(data, 9).unwrap();
+            assert_eq!(&decompress_raw(&compressed).unwrap(), data);
+        }
+    }
+
+    #[test]
+    fn test_smaller_than_fast() {
+        let data = text_corpus();
+        let good = deflate_compress_good(&data, 9).unwrap();
+        let fast = deflate_compress_fast(&data).unwrap();
+        assert!(good.len() < fast.len(), "good {} >= fast {}", good.len(), fast.len());
+    }
+
+    #[test]
+    fn test_lazy_smaller_than_greedy() {
+        let data = text_corpus();
+        let greedy = deflate_compress_good_with(&mut DeflateGood::new(4096), &data).unwrap();
+        let lazy = deflate_compress_good_with(&mut DeflateGood::new(4096).lazy(true), &data).unwrap();
+        assert!(lazy.len() < greedy.len(), "lazy {} >= greedy {}", lazy.len(), greedy.len());
+        assert_eq!(decompress_raw(&lazy).unwrap(), data);
+        assert_eq!(decompress_raw(&greedy).unwrap(), data);
+    }
+
+    #[test]
+    fn test_run_length_encode() {
+        let lengths = [0u8; 20].iter().chain(&[5u8; 8]).chain(&[3, 3]).copied().collect::<Vec<_>>();
+        let rle = run_length_encode(&lengths);
+        assert_eq!(rle, [(18, 9), (5, 0), (16, 3), (5, 0), (3, 0), (3, 0)]);
+    }
+}
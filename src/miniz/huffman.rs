@@ -175,8 +175,8 @@ impl HuffmanTable {
                     idx += stride;
                 }
             } else {
-                // 长代码使用树结构
-                let mut idx = result.lookup[(rev_code & 0xFF) as usize];
+                // 长代码使用树结构：低 9 位索引快速查找表，其余位沿树向下
+                let mut idx = result.lookup[(rev_code & 0x1FF) as usize];
                 if idx == -1 || idx == 0 {
                     // 需要在树中创建新节点
                     if result.tree.is_empty() {
@@ -184,15 +184,15 @@ impl HuffmanTable {
                         result.tree.push(-1);
                     }
                     let new_idx = result.tree.len() as i16;
-                    result.lookup[(rev_code & 0xFF) as usize] = -new_idx;
+                    result.lookup[(rev_code & 0x1FF) as usize] = -new_idx;
                     result.tree.push(-1);
                     result.tree.push(-1);
                     idx = -new_idx;
                 }
 
                 // 遍历树，添加剩余位
-                let mut mask = 1 << 8;
-                for _ in 9..len {
+                let mut mask = 1 << 9;
+                for _ in 10..len {
                     let bit = if (rev_code / mask) & 1 != 0 { 1 } else { 0 };
                     mask <<= 1;
 
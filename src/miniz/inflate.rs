@@ -2,13 +2,18 @@
 //!
 //! 对应 C 版本 miniz.c 的 `tinfl_*` 函数系列：
 //! - `decompress()` 对应 `tinfl_decompress()`
-//! - `InflateDecoder` 对应 `tinfl_decompressor`
+//! - `InflateDecoder` 对应 `tinfl_decompressor`（非回绕输出缓冲区模式）
+//!
+//! `mz_inflate()` 的 32 KB 回绕字典模式见 [`inflate_stream`](crate::miniz::inflate_stream)。
 //!
 //! 参考：`/home/putao/code/c-cpp/zip/src/miniz.c`
 
 use crate::miniz::bitstream::BitReader;
 use crate::miniz::huffman::HuffmanTable;
 
+/// DEFLATE 的最大窗口（32 KB），也是原始流允许的最大回引距离
+const MAX_WINDOW_SIZE: u32 = 32768;
+
 /// INFLATE 解压状态标志
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum InflateStatus {
@@ -28,6 +33,8 @@ pub enum InflateStatus {
     BadParam,
     /// 无法继续处理
     CannotMakeProgress,
+    /// 回引距离超出 ZLIB 头部声明的窗口，距离保存在 `InflateState::dist`
+    DistanceTooFar,
 }
 
 /// 解压标志
@@ -63,6 +70,8 @@ pub struct InflateState {
     block_type: u8,
     /// 距离
     dist: u32,
+    /// 允许的最大回引距离，由 ZLIB 头部的 CINFO 决定，原始流为 32768
+    window_size: u32,
     /// 计数器
     counter: u32,
     /// 额外位数
@@ -83,6 +92,8 @@ pub struct InflateState {
     tables: [HuffmanTable; 3],
     /// 表大小
     table_sizes: [usize; 3],
+    /// 块解码返回 `InflateStatus::Failed` 时的具体原因
+    error: Option<InflateError>,
 }
 
 /// INFLATE 解码器
@@ -100,6 +111,7 @@ impl InflateDecoder {
             final_block: false,
             block_type: 0,
             dist: 0,
+            window_size: MAX_WINDOW_SIZE,
             counter: 0,
             num_extra: 0,
             output_buffer: Vec::new(),
@@ -110,6 +122,7 @@ impl InflateDecoder {
             check_adler32: 0,
             tables: [HuffmanTable::new(), HuffmanTable::new(), HuffmanTable::new()],
             table_sizes: [0, 0, 0],
+            error: None,
         };
 
         // 初始化静态 Huffman 表
@@ -118,25 +131,16 @@ impl InflateDecoder {
         Self { state }
     }
 
-    /// 解压数据
-    pub fn decompress(
-        &mut self,
-        input: &[u8],
-        output: &mut [u8],
-        flags: InflateFlags,
-    ) -> Result<(InflateStatus, usize, usize), InflateError> {
-        // Debug: print input data
-        println!("DEBUG: Input data ({} bytes): {:?}", input.len(), input);
-
-        // 设置输入数据
-        self.state.bit_reader = BitReader::from_slice(input);
+    /// 重置解码状态，保留已分配的输出缓冲区和 Huffman 表，
+    /// 以便同一个解码器连续处理多个条目
+    /// 对应 C 版本的 tinfl_init()
+    pub fn reset(&mut self) {
         self.state.bit_buf = 0;
         self.state.num_bits = 0;
-
-        // 重置状态
         self.state.final_block = false;
         self.state.block_type = 0;
         self.state.dist = 0;
+        self.state.window_size = MAX_WINDOW_SIZE;
         self.state.counter = 0;
         self.state.num_extra = 0;
         self.state.output_pos = 0;
@@ -145,6 +149,24 @@ impl InflateDecoder {
         self.state.zhdr1 = 0;
         self.state.z_adler32 = 1;
         self.state.check_adler32 = 1;
+        self.state.error = None;
+    }
+
+    /// 解压数据
+    pub fn decompress(
+        &mut self,
+        input: &[u8],
+        output: &mut [u8],
+        flags: InflateFlags,
+    ) -> Result<(InflateStatus, usize, usize), InflateError> {
+        // Debug: print input data
+        log::trace!("Input data ({} bytes): {:?}", input.len(), input);
+
+        // 设置输入数据
+        self.state.bit_reader = BitReader::from_slice(input);
+
+        // 重置状态
+        self.reset();
 
         // 解压 ZLIB 头部（如果需要）
         if flags.parse_zlib_header {
@@ -152,19 +174,19 @@ impl InflateDecoder {
         }
 
         // Debug output
-        println!("DEBUG: Starting decompress, input len: {}, flags: {:?}", input.len(), flags);
+        log::trace!("Starting decompress, input len: {}, flags: {:?}", input.len(), flags);
 
         // 解压主循环
         let result = loop {
             // 如果上一个块已经是最终块，返回 Done
             if self.state.final_block {
-                println!("DEBUG: Final block was processed, returning Done");
+                log::trace!("Final block was processed, returning Done");
                 break (InflateStatus::Done, self.state.output_pos);
             }
 
             match self.decompress_block(flags) {
                 InflateStatus::Done => {
-                    println!("DEBUG: Decompression completed");
+                    log::trace!("Decompression completed");
                     break (InflateStatus::Done, self.state.output_pos);
                 }
                 InflateStatus::HasMoreOutput => {
@@ -177,8 +199,8 @@ impl InflateDecoder {
                     break (InflateStatus::NeedsMoreInput, self.state.output_pos);
                 }
                 InflateStatus::Failed => {
-                    println!("DEBUG: Decompression failed");
-                    return Err(InflateError::DecompressionFailed);
+                    log::debug!("inflate failed");
+                    return Err(self.state.error.take().unwrap_or(InflateError::DecompressionFailed));
                 }
                 InflateStatus::Adler32Mismatch => {
                     return Err(InflateError::Adler32Mismatch);
@@ -189,17 +211,34 @@ impl InflateDecoder {
                 InflateStatus::CannotMakeProgress => {
                     return Err(InflateError::CannotMakeProgress);
                 }
+                InflateStatus::DistanceTooFar => {
+                    return Err(InflateError::DistanceTooFar {
+                        distance: self.state.dist,
+                        window: self.state.window_size,
+                    });
+                }
                 InflateStatus::Ok => {
                     continue;
                 }
             }
         };
 
+        // ZLIB 流在最终块之后是大端 Adler-32
+        // 对应 miniz.c tinfl_decompress() 中 `TINFL_SKIP_BITS(32, num_bits & 7)` 之后的校验
+        if flags.parse_zlib_header && result.0 == InflateStatus::Done {
+            self.read_zlib_trailer()?;
+            self.state.check_adler32 = crate::miniz::adler32(
+                crate::miniz::ADLER32_INIT,
+                &self.state.output_buffer[..self.state.output_pos],
+            );
+            self.check_adler32_checksum()?;
+        }
+
         // 将内部 output_buffer 复制到外部输出缓冲区
         let copy_len = self.state.output_pos.min(output.len());
         output[..copy_len].copy_from_slice(&self.state.output_buffer[..copy_len]);
 
-        println!("DEBUG: Copied {} bytes to external output buffer", copy_len);
+        log::trace!("Copied {} bytes to external output buffer", copy_len);
 
         Ok((result.0, result.1, 0))
     }
@@ -307,24 +346,34 @@ pub fn decompress_to_buffer(data: &[u8], capacity: usize) -> Result<DecompressRe
 
 /// 原始 INFLATE 解压（不带 ZLIB 头部）
 pub fn decompress_raw(data: &[u8]) -> Result<Vec<u8>, InflateError> {
+    decompress_raw_with_capacity(data, 0)
+}
+
+/// 原始 INFLATE 解压，输出缓冲区预先分配 `capacity` 字节
+///
+/// 已知解压后大小（如 ZIP 中央目录记录的 uncompressed_size）时传入该值，
+/// 输出恰好填满时不会发生重新分配；大小不符时缓冲区照常增长，结果不受影响。
+pub fn decompress_raw_with_capacity(data: &[u8], capacity: usize) -> Result<Vec<u8>, InflateError> {
     let mut decoder = InflateDecoder::new();
-    let mut output = vec![0; data.len() * 2]; // 预分配较大的输出缓冲区
+    decoder.state.output_buffer = Vec::with_capacity(capacity);
 
-    let (status, bytes_read, _) = decoder.decompress(
+    // 解码器内部的输出缓冲区会按需增长，这里不预先猜测输出大小，
+    // 直接取回完整输出，避免高压缩比数据被截断
+    let (status, _, _) = decoder.decompress(
         data,
-        &mut output,
+        &mut [],
         InflateFlags {
             parse_zlib_header: false,
             ..Default::default()
         },
     )?;
 
-    if status != InflateStatus::Done {
-        return Err(InflateError::DecompressionFailed);
+    match status {
+        InflateStatus::Done => Ok(decoder.get_output()),
+        // 输入在最终块结束之前耗尽
+        InflateStatus::NeedsMoreInput => Err(InflateError::NeedMoreInput),
+        _ => Err(InflateError::DecompressionFailed),
     }
-
-    output.truncate(bytes_read);
-    Ok(output)
 }
 
 impl InflateDecoder {
@@ -422,6 +471,9 @@ impl InflateDecoder {
             return Err(InflateError::BadZlibHeader);
         }
 
+        // CINFO 声明窗口大小为 2^(CINFO+8) 字节，流中的回引距离不得超过它
+        self.state.window_size = 1 << (cinfo + 8);
+
         // 重置 Adler32 校验和
         self.state.z_adler32 = 1;
         self.state.check_adler32 = 1;
@@ -435,24 +487,24 @@ impl InflateDecoder {
 
         // 不需要手动设置位置，因为 read_byte() 已经正确移动了 pos 到 2
 
-        println!("DEBUG: ZLIB header parsed - cm: {}, cinfo: {}, fcheck: {}, fdict: {}", cm, cinfo, _fcheck, fdict);
+        log::trace!("ZLIB header parsed - cm: {}, cinfo: {}, fcheck: {}, fdict: {}", cm, cinfo, _fcheck, fdict);
 
         // Skip remaining bits to align to byte boundary (like C version's TINFL_SKIP_BITS(5, num_bits & 7))
         let remaining_bits = self.state.bit_reader.buffered_bits() % 8;
         if remaining_bits > 0 {
-            println!("DEBUG: Skipping {} bits after ZLIB header", remaining_bits);
+            log::trace!("Skipping {} bits after ZLIB header", remaining_bits);
             self.state.bit_reader.read_bits(remaining_bits);
         }
 
-        println!("DEBUG: After ZLIB header alignment - bit_pos: {}, num_bits: {}", self.state.bit_reader.get_pos(), self.state.bit_reader.buffered_bits());
+        log::trace!("After ZLIB header alignment - bit_pos: {}, num_bits: {}", self.state.bit_reader.get_pos(), self.state.bit_reader.buffered_bits());
 
         // 确保 ZLIB header 解析后在字节边界开始读取 deflate 块头
         if self.state.bit_reader.buffered_bits() != 0 {
-            println!("DEBUG: Warning: Not aligned to byte boundary after ZLIB header");
+            log::debug!("not aligned to byte boundary after zlib header");
             return Err(InflateError::BadZlibHeader);
         }
 
-        println!("DEBUG: After ZLIB header - bit_pos: {}, num_bits: {}", self.state.bit_reader.get_pos(), self.state.bit_reader.buffered_bits());
+        log::trace!("After ZLIB header - bit_pos: {}, num_bits: {}", self.state.bit_reader.get_pos(), self.state.bit_reader.buffered_bits());
 
         Ok(())
     }
@@ -470,7 +522,7 @@ impl InflateDecoder {
             }
         }
 
-        println!("DEBUG: Before reading block header - num_bits: {}, bit_buf: {:064b}",
+        log::trace!("Before reading block header - num_bits: {}, bit_buf: {:064b}",
                  self.state.num_bits, self.state.bit_buf);
 
         // 读取块头 (3 bits, like C version's TINFL_GET_BITS(3, r->m_final, 3))
@@ -480,8 +532,8 @@ impl InflateDecoder {
         self.state.final_block = (block_header & 1) == 1;
         self.state.block_type = (block_header >> 1) as u8;
 
-        println!("DEBUG: decompress_block - final_block: {} (bit0: {}), block_type: {} (header: {:03b})", self.state.final_block, block_header & 1, self.state.block_type, block_header);
-        println!("DEBUG: After reading block header - num_bits: {}, bit_buf: {:064b}",
+        log::trace!("decompress_block - final_block: {} (bit0: {}), block_type: {} (header: {:03b})", self.state.final_block, block_header & 1, self.state.block_type, block_header);
+        log::trace!("After reading block header - num_bits: {}, bit_buf: {:064b}",
                  self.state.num_bits, self.state.bit_buf);
 
         match self.state.block_type {
@@ -494,17 +546,17 @@ impl InflateDecoder {
 
     /// 解压无压缩块
     fn decompress_uncompressed_block(&mut self) -> InflateStatus {
-        println!("DEBUG: decompress_uncompressed_block - bit_buf: {}, num_bits: {}", self.state.bit_buf, self.state.num_bits);
+        log::trace!("decompress_uncompressed_block - bit_buf: {}, num_bits: {}", self.state.bit_buf, self.state.num_bits);
 
         // 跳过剩余的位以对齐到字节边界 (like C version's TINFL_SKIP_BITS(5, num_bits & 7))
         let num_bits = self.state.num_bits & 7;
         if num_bits != 0 {
-            println!("DEBUG: Skipping {} bits", num_bits);
+            log::trace!("Skipping {} bits", num_bits);
             self.state.bit_buf >>= num_bits;
             self.state.num_bits -= num_bits;
         }
 
-        println!("DEBUG: After alignment - num_bits: {}", self.state.num_bits);
+        log::trace!("After alignment - num_bits: {}", self.state.num_bits);
 
         // 读取长度和补码 (像 C 版本一样)
         let mut raw_header = [0u8; 4];
@@ -527,16 +579,16 @@ impl InflateDecoder {
         let len = raw_header[0] as u16 | ((raw_header[1] as u16) << 8);
         let nlen = raw_header[2] as u16 | ((raw_header[3] as u16) << 8);
 
-        println!("DEBUG: Uncompressed block header bytes - raw_header: {:?}, len: {}, nlen: {}", raw_header, len, nlen);
+        log::trace!("Uncompressed block header bytes - raw_header: {:?}, len: {}, nlen: {}", raw_header, len, nlen);
 
         // 验证补码
         if len != !nlen {
-            println!("DEBUG: Checksum failed: len ({}) != !nlen ({})", len, !nlen);
+            log::trace!("Checksum failed: len ({}) != !nlen ({})", len, !nlen);
             return InflateStatus::Failed;
         }
 
         // 读取数据 (像 C 版本一样)
-        println!("DEBUG: Reading {} bytes of data", len);
+        log::trace!("Reading {} bytes of data", len);
         for _ in 0..len {
             if self.state.num_bits >= 8 {
                 // 从 bit_buf 读取 8 位
@@ -557,24 +609,24 @@ impl InflateDecoder {
             }
         }
 
-        println!("DEBUG: Read {} bytes, output_pos: {}", len, self.state.output_pos);
+        log::trace!("Read {} bytes, output_pos: {}", len, self.state.output_pos);
 
         // 检查是否完成
         if self.state.final_block {
-            println!("DEBUG: Final block, checking Adler32");
+            log::trace!("Final block, checking Adler32");
             match self.check_adler32_checksum() {
                 Ok(_) => {
-                    println!("DEBUG: Adler32 check passed");
+                    log::trace!("Adler32 check passed");
                     return InflateStatus::Done;
                 }
                 Err(_) => {
-                    println!("DEBUG: Adler32 check failed");
+                    log::trace!("Adler32 check failed");
                     return InflateStatus::Adler32Mismatch;
                 }
             }
         }
 
-        println!("DEBUG: Block processed successfully");
+        log::trace!("Block processed successfully");
         InflateStatus::Ok
     }
 
@@ -595,7 +647,7 @@ impl InflateDecoder {
         // 读取 HCLEN (4 bits): 码长码的码长数减 4
         let hclen = self.get_bits(4)? as i32 + 4;
 
-        println!("DEBUG: Dynamic Huffman - hlit: {}, hdist: {}, hclen: {}", hlit, hdist, hclen);
+        log::trace!("Dynamic Huffman - hlit: {}, hdist: {}, hclen: {}", hlit, hdist, hclen);
 
         // 对应 C 版本: 读取码长码的码长 (line 2464)
         // 读取码长码的码长（按 LENGTH_DEZIGZAG 顺序）
@@ -606,11 +658,12 @@ impl InflateDecoder {
             codelens[idx] = self.get_bits(3)? as i32;
         }
 
-        println!("DEBUG: Code lengths: {:?}", &codelens[..19]);
+        log::trace!("Code lengths: {:?}", &codelens[..19]);
 
         // 构建码长码的 Huffman 表
+        // 码长超额分配（over-subscribed）的表无法构建，视为无效的 Huffman 码
         let codelen_table = HuffmanTable::build(&codelens.iter().map(|&x| x as u8).collect::<Vec<_>>(), 19)
-            .map_err(|_| InflateError::DecompressionFailed)?;
+            .map_err(|_| InflateError::InvalidCode)?;
 
         // 对应 C 版本: 读取长度和距离的码长 (line 2470-2547)
         let mut code_lengths = vec![0i32; (hlit + hdist) as usize];
@@ -684,7 +737,7 @@ impl InflateDecoder {
             }
         }
 
-        println!("DEBUG: Parsed {} code lengths", code_lengths.len());
+        log::trace!("Parsed {} code lengths", code_lengths.len());
 
         // 构建长度码表
         let mut litlen_code_lengths = vec![0u8; 288];
@@ -694,7 +747,7 @@ impl InflateDecoder {
             }
         }
         self.state.tables[0] = HuffmanTable::build(&litlen_code_lengths, 288)
-            .map_err(|_| InflateError::DecompressionFailed)?;
+            .map_err(|_| InflateError::InvalidCode)?;
         self.state.table_sizes[0] = 288;
 
         // 构建距离码表
@@ -705,7 +758,7 @@ impl InflateDecoder {
             }
         }
         self.state.tables[1] = HuffmanTable::build(&dist_code_lengths, 32)
-            .map_err(|_| InflateError::DecompressionFailed)?;
+            .map_err(|_| InflateError::InvalidCode)?;
         self.state.table_sizes[1] = 32;
 
         Ok(())
@@ -715,7 +768,7 @@ impl InflateDecoder {
     fn decompress_compressed_block(&mut self) -> InflateStatus {
         // 初始化 Huffman 表（根据块类型）
         if self.state.block_type == 1 {
-            println!("DEBUG: Building static Huffman tables");
+            log::trace!("Building static Huffman tables");
             // 静态 Huffman 表（RFC 1951）
             // 字面/长度码: 0-143(8位), 144-255(9位), 256-279(7位), 280-287(8位)
             let mut litlen_code_lengths = vec![0u8; 288];
@@ -736,10 +789,10 @@ impl InflateDecoder {
                 Ok(table) => {
                     self.state.tables[0] = table;
                     self.state.table_sizes[0] = 288;
-                    println!("DEBUG: Static lit/len table built successfully, size: 288");
+                    log::trace!("Static lit/len table built successfully, size: 288");
                 }
                 Err(e) => {
-                    println!("DEBUG: Failed to build lit/len table: {:?}", e);
+                    log::trace!("Failed to build lit/len table: {:?}", e);
                     return InflateStatus::Failed;
                 }
             }
@@ -750,22 +803,23 @@ impl InflateDecoder {
                 Ok(table) => {
                     self.state.tables[1] = table;
                     self.state.table_sizes[1] = 32;
-                    println!("DEBUG: Static distance table built successfully, size: 32");
+                    log::trace!("Static distance table built successfully, size: 32");
                 }
                 Err(e) => {
-                    println!("DEBUG: Failed to build distance table: {:?}", e);
+                    log::trace!("Failed to build distance table: {:?}", e);
                     return InflateStatus::Failed;
                 }
             }
         } else if self.state.block_type == 2 {
             // 解析动态 Huffman 表
-            if let Err(_) = self.parse_dynamic_huffman_tables() {
+            if let Err(e) = self.parse_dynamic_huffman_tables() {
+                self.state.error = Some(e);
                 return InflateStatus::Failed;
             }
         }
 
         // DEFLATE 压缩块的完整实现，参考 C 版本的 tinfl_decompress()
-        println!("DEBUG: decompress_compressed_block - block_type: {}", self.state.block_type);
+        log::trace!("decompress_compressed_block - block_type: {}", self.state.block_type);
 
         // Huffman 表长度和距离的基础值
         static LENGTH_BASE: [i32; 31] = [
@@ -796,7 +850,7 @@ impl InflateDecoder {
             match self.decode_huffman_symbol() {
                 Some(256) => {
                     // 块结束标记
-                    println!("DEBUG: End of block marker found, final_block: {}", self.state.final_block);
+                    log::trace!("End of block marker found, final_block: {}", self.state.final_block);
                     return InflateStatus::Ok;
                 }
                 Some(literal) if literal < 256 => {
@@ -825,6 +879,11 @@ impl InflateDecoder {
                                 distance += extra_bits as u32;
                             }
 
+                            if distance > self.state.window_size {
+                                self.state.dist = distance;
+                                return InflateStatus::DistanceTooFar;
+                            }
+
                             // LZ77 复制
                             self.lz77_copy(length, distance);
                         }
@@ -855,7 +914,7 @@ impl InflateDecoder {
         }
 
         // Debug output
-        println!("DEBUG: decode_huffman_symbol_with_table(table={}) - num_bits: {}, bit_buf: 0x{:x}",
+        log::trace!("decode_huffman_symbol_with_table(table={}) - num_bits: {}, bit_buf: 0x{:x}",
                  table_index, self.state.num_bits, self.state.bit_buf);
 
         // 检查表索引有效
@@ -870,7 +929,7 @@ impl InflateDecoder {
         let symbol = symbol_len.0;
         let code_len = symbol_len.1;
 
-        println!("DEBUG: Huffman decode - symbol: {}, code_len: {}, bit_buf_low9: 0x{:03x}",
+        log::trace!("Huffman decode - symbol: {}, code_len: {}, bit_buf_low9: 0x{:03x}",
                  symbol, code_len, self.state.bit_buf & 0x1FF);
 
         // 检查码长是否有效且我们有足够的位
@@ -951,6 +1010,19 @@ impl InflateDecoder {
         }
     }
 
+    /// 对齐到字节边界后读取 4 字节的 ZLIB 尾部到 `z_adler32`
+    fn read_zlib_trailer(&mut self) -> Result<(), InflateError> {
+        let skip = self.state.num_bits & 7;
+        self.state.bit_buf >>= skip;
+        self.state.num_bits -= skip;
+        self.state.z_adler32 = 0;
+        for _ in 0..4 {
+            let byte = self.get_bits(8)?;
+            self.state.z_adler32 = (self.state.z_adler32 << 8) | byte;
+        }
+        Ok(())
+    }
+
     /// 检查 Adler32 校验和
     fn check_adler32_checksum(&mut self) -> Result<(), InflateError> {
         if self.state.z_adler32 != self.state.check_adler32 {
@@ -977,6 +1049,8 @@ pub enum InflateError {
     BadZlibHeader,
     #[error("Invalid Huffman code")]
     InvalidCode,
+    #[error("Distance {distance} exceeds the {window}-byte window declared in the zlib header")]
+    DistanceTooFar { distance: u32, window: u32 },
 }
 
 #[cfg(test)]
@@ -1006,4 +1080,100 @@ mod tests {
         let result = decompress(&zlib_header, 1, None);
         assert!(result.is_err()); // 应该失败，因为没有压缩数据
     }
+
+    #[test]
+    fn test_empty_stored_blocks_between_blocks() {
+        use crate::miniz::bitstream::BitWriter;
+        use crate::miniz::InflateReader;
+        use std::io::Read;
+
+        fn put_code(writer: &mut BitWriter, code: u32, len: u8) {
+            for i in (0..len).rev() {
+                writer.write_bits((code >> i) & 1, 1);
+            }
+        }
+        fn put_literals(writer: &mut BitWriter, text: &[u8]) {
+            for &byte in text {
+                put_code(writer, 0x30 + byte as u32, 8);
+            }
+        }
+        // Z_SYNC_FLUSH 产生的空存储块：BTYPE=00，对齐后 LEN=0、NLEN=0xFFFF，没有数据
+        fn sync_flush(writer: &mut BitWriter, last: bool) {
+            writer.write_bits(last as u32, 1);
+            writer.write_bits(0, 2);
+            writer.align_to_byte();
+            writer.write_bytes(&[0x00, 0x00, 0xFF, 0xFF]);
+        }
+
+        let mut writer = BitWriter::new();
+        writer.write_bits(0, 1); // 非最终块
+        writer.write_bits(1, 2); // BTYPE=01
+        put_literals(&mut writer, b"hello ");
+        put_code(&mut writer, 0, 7); // 块结束
+        sync_flush(&mut writer, false);
+        sync_flush(&mut writer, false);
+        writer.write_bits(0, 1);
+        writer.write_bits(1, 2);
+        put_literals(&mut writer, b"world ");
+        // 跨过空块回引第一块的数据：长度码 260（长度 6），距离码 6（9 + 2 位额外值 3）
+        put_code(&mut writer, 4, 7);
+        put_code(&mut writer, 6, 5);
+        writer.write_bits(3, 2);
+        put_code(&mut writer, 0, 7);
+        sync_flush(&mut writer, true);
+        let deflated = writer.into_bytes();
+        let expected = b"hello world hello ".to_vec();
+
+        assert_eq!(decompress_raw(&deflated).unwrap(), expected);
+
+        let mut reader = InflateReader::new(&deflated[..]);
+        let mut output = Vec::new();
+        reader.read_to_end(&mut output).unwrap();
+        assert_eq!(output, expected);
+        assert_eq!(reader.total_in(), deflated.len() as u64);
+    }
+
+    #[test]
+    fn test_distance_beyond_declared_window_rejected() {
+        use crate::miniz::bitstream::BitWriter;
+
+        // 静态 Huffman 块：1000 个字面量 'a'，再以距离 1000 回引 3 字节
+        fn put_code(writer: &mut BitWriter, code: u32, len: u8) {
+            for i in (0..len).rev() {
+                writer.write_bits((code >> i) & 1, 1);
+            }
+        }
+        let mut writer = BitWriter::new();
+        writer.write_bits(1, 1); // BFINAL
+        writer.write_bits(1, 2); // BTYPE=01
+        for _ in 0..1000 {
+            put_code(&mut writer, 0x30 + b'a' as u32, 8);
+        }
+        put_code(&mut writer, 1, 7); // 长度码 257：长度 3
+        put_code(&mut writer, 19, 5); // 距离码 19：769 + 8 位额外值
+        writer.write_bits(1000 - 769, 8);
+        put_code(&mut writer, 0, 7); // 块结束
+        let deflated = writer.into_bytes();
+        let expected = vec![b'a'; 1003];
+
+        let zlib = |cmf: u8, flg: u8| {
+            let mut stream = vec![cmf, flg];
+            stream.extend_from_slice(&deflated);
+            stream.extend_from_slice(&crate::miniz::adler32(1, &expected).to_be_bytes());
+            stream
+        };
+
+        // 32 KB 窗口（CINFO=7）可以正常解压
+        assert_eq!(decompress(&zlib(0x78, 0x9C), 1, None).unwrap().output, expected);
+        assert_eq!(decompress_raw(&deflated).unwrap(), expected);
+
+        // CINFO=0 声明 256 字节窗口
+        match decompress(&zlib(0x08, 0x1D), 1, None) {
+            Err(InflateError::DistanceTooFar { distance, window }) => {
+                assert_eq!(distance, 1000);
+                assert_eq!(window, 256);
+            }
+            other => panic!("expected DistanceTooFar, got {:?}", other.map(|o| o.output.len())),
+        }
+    }
 }
\ No newline at end of file
@@ -0,0 +1,525 @@
+//! 流式 INFLATE
+//!
+//! [`InflateReader`] 从任意 `Read` 按需读取原始 DEFLATE 数据并增量解压，
+//! 只保留 32 KB 回引窗口和固定大小的输入缓冲区，内存占用与数据大小无关。
+//! 块类型、Huffman 表、最终块标志和窗口在多次 `read` 调用之间保持，
+//! 调用方可以用任意大小的缓冲区读取。
+//!
+//! # 为什么与 `inflate.rs` 并存
+//!
+//! C 版本的 `tinfl_decompress()` 有两种用法：`mz_uncompress()` / ZIP 解压使用
+//! 非回绕的整块输出缓冲区（`TINFL_FLAG_USING_NON_WRAPPING_OUTPUT_BUF`），
+//! `mz_inflate()` 则在 32 KB 回绕字典（`TINFL_LZ_DICT_SIZE`）上逐次恢复协程状态。
+//! [`InflateDecoder`](crate::miniz::inflate::InflateDecoder) 只移植了前一种：
+//! 它一次拿到全部输入，把输出缓冲区本身当作回引窗口，符号解码中途无法挂起，
+//! 也无法在不保留全部输出的情况下继续。这里对应后一种用法，状态划分与
+//! `tinfl_decompress()` 的块头 / 存储块 / Huffman 符号 / 回引复制各阶段一致。
+//!
+//! 两者解码同一格式，输出必须逐字节相同，由 `test_matches_inflate_decoder` 交叉验证；
+//! 需要 Adler-32 校验的 zlib 数据仍走 `inflate.rs`。
+
+use crate::miniz::huffman::{
+    HuffmanTable, DIST_BASE, DIST_EXTRA, FIXED_DISTANCE_CODE_LENGTHS, FIXED_LITLEN_CODE_LENGTHS,
+    LENGTH_BASE, LENGTH_DEZIGZAG, LENGTH_EXTRA,
+};
+use crate::miniz::inflate::InflateError;
+use std::io::{self, Read};
+
+/// 回引窗口大小（DEFLATE 允许的最大距离）
+const WINDOW_SIZE: usize = 32768;
+
+/// 输入缓冲区大小
+const INPUT_BUFFER_SIZE: usize = 16 * 1024;
+
+/// 解码到哪一步
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    /// 下一步读取块头
+    BlockHeader,
+    /// 存储块中还剩 `remaining` 字节
+    Stored { remaining: usize },
+    /// Huffman 块中，下一步解码一个符号
+    Huffman,
+    /// 正在输出一个回引
+    Copy { length: usize, distance: usize },
+    /// 最终块已结束
+    Done,
+}
+
+/// 带缓冲的 LSB 优先位读取
+struct Bits<R> {
+    inner: R,
+    input: Box<[u8]>,
+    in_pos: usize,
+    in_len: usize,
+    bit_buf: u64,
+    num_bits: u32,
+    /// 已移入位缓冲区或直接取走的字节数
+    taken: u64,
+}
+
+impl<R: Read> Bits<R> {
+    /// 从输入中取一个字节；输入结束时返回 `None`
+    fn next_byte(&mut self) -> io::Result<Option<u8>> {
+        if self.in_pos == self.in_len {
+            self.in_pos = 0;
+            self.in_len = loop {
+                match self.inner.read(&mut self.input) {
+                    Ok(n) => break n,
+                    Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                    Err(e) => return Err(e),
+                }
+            };
+            if self.in_len == 0 {
+                return Ok(None);
+            }
+        }
+        let byte = self.input[self.in_pos];
+        self.in_pos += 1;
+        self.taken += 1;
+        Ok(Some(byte))
+    }
+
+    /// 向位缓冲区补充一个字节；输入结束时返回 false
+    fn fill_byte(&mut self) -> io::Result<bool> {
+        match self.next_byte()? {
+            Some(byte) => {
+                self.bit_buf |= (byte as u64) << self.num_bits;
+                self.num_bits += 8;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// 读取 `n`（≤ 16）位
+    fn bits(&mut self, n: u32) -> io::Result<u32> {
+        while self.num_bits < n {
+            if !self.fill_byte()? {
+                return Err(truncated());
+            }
+        }
+        let value = (self.bit_buf & ((1u64 << n) - 1)) as u32;
+        self.bit_buf >>= n;
+        self.num_bits -= n;
+        Ok(value)
+    }
+
+    /// 用 `table` 解码一个符号
+    fn decode(&mut self, table: &HuffmanTable) -> io::Result<u16> {
+        // 码长最多 15 位；数据末尾可能不足 15 位，按实际码长判断是否截断
+        while self.num_bits < 15 && self.fill_byte()? {}
+        let (symbol, len) = table.decode(self.bit_buf as u32);
+        let len = len as u32;
+        if len == 0 {
+            return Err(invalid(InflateError::InvalidCode));
+        }
+        if len > self.num_bits {
+            return Err(truncated());
+        }
+        self.bit_buf >>= len;
+        self.num_bits -= len;
+        Ok(symbol)
+    }
+
+    /// 丢弃到下一个字节边界的剩余位
+    fn align(&mut self) {
+        let drop = self.num_bits % 8;
+        self.bit_buf >>= drop;
+        self.num_bits -= drop;
+    }
+
+    /// 按字节读取（须已对齐）：先取位缓冲区中的整字节，再直接复制输入缓冲区
+    fn read_aligned(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        let mut n = 0;
+        while n < out.len() && self.num_bits >= 8 {
+            out[n] = self.bit_buf as u8;
+            self.bit_buf >>= 8;
+            self.num_bits -= 8;
+            n += 1;
+        }
+        if n == out.len() {
+            return Ok(n);
+        }
+        if self.in_pos == self.in_len {
+            match self.next_byte()? {
+                Some(byte) => {
+                    out[n] = byte;
+                    n += 1;
+                }
+                None => return Ok(n),
+            }
+        }
+        let count = (self.in_len - self.in_pos).min(out.len() - n);
+        out[n..n + count].copy_from_slice(&self.input[self.in_pos..self.in_pos + count]);
+        self.in_pos += count;
+        self.taken += count as u64;
+        Ok(n + count)
+    }
+}
+
+/// 从 `Read` 增量解压原始 DEFLATE 流（无 zlib/gzip 头尾）的 reader
+///
+/// `read` 返回 0 表示最终块已结束；输入在此之前结束时返回 `UnexpectedEof`，
+/// 数据损坏时返回 `InvalidData`，内部错误为 [`InflateError`]。
+/// DEFLATE 流之后的数据（如 gzip 尾部）可以继续用 [`read_trailer`](Self::read_trailer) 读取。
+pub struct InflateReader<R> {
+    bits: Bits<R>,
+    window: Box<[u8]>,
+    window_pos: usize,
+    total_out: u64,
+    final_block: bool,
+    state: State,
+    litlen: HuffmanTable,
+    dist: HuffmanTable,
+}
+
+impl<R: Read> InflateReader<R> {
+    /// 创建 reader，从 `inner` 的当前位置开始解压
+    pub fn new(inner: R) -> Self {
+        Self {
+            bits: Bits {
+                inner,
+                input: vec![0u8; INPUT_BUFFER_SIZE].into_boxed_slice(),
+                in_pos: 0,
+                in_len: 0,
+                bit_buf: 0,
+                num_bits: 0,
+                taken: 0,
+            },
+            window: vec![0u8; WINDOW_SIZE].into_boxed_slice(),
+            window_pos: 0,
+            total_out: 0,
+            final_block: false,
+            state: State::BlockHeader,
+            litlen: HuffmanTable::new(),
+            dist: HuffmanTable::new(),
+        }
+    }
+
+    /// 已消耗的压缩数据字节数（最后一个字节只用到一部分时也计入）
+    ///
+    /// 解压结束后即 DEFLATE 流的精确长度，可用来定位流之后的数据。
+    pub fn total_in(&self) -> u64 {
+        self.bits.taken - (self.bits.num_bits / 8) as u64
+    }
+
+    /// 已输出的解压数据字节数
+    pub fn total_out(&self) -> u64 {
+        self.total_out
+    }
+
+    /// 最终块是否已经解压完毕
+    pub fn is_done(&self) -> bool {
+        self.state == State::Done
+    }
+
+    /// 从下一个字节边界起读出不解压的原始输入，返回读到的字节数，0 表示输入结束
+    pub fn read_raw(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.bits.align();
+        self.bits.read_aligned(buf)
+    }
+
+    /// 从下一个字节边界起读满 `buf`，用于读取 DEFLATE 流之前或之后的非压缩数据
+    ///
+    /// 不足 `buf.len()` 字节时返回 `UnexpectedEof`。
+    pub fn read_trailer(&mut self, buf: &mut [u8]) -> io::Result<()> {
+        let mut n = 0;
+        while n < buf.len() {
+            match self.read_raw(&mut buf[n..])? {
+                0 => return Err(truncated()),
+                count => n += count,
+            }
+        }
+        Ok(())
+    }
+
+    /// 下一个字节边界之后是否还有输入
+    pub fn has_more_input(&mut self) -> io::Result<bool> {
+        self.bits.align();
+        if self.bits.num_bits > 0 {
+            return Ok(true);
+        }
+        if self.bits.in_pos < self.bits.in_len {
+            return Ok(true);
+        }
+        // 预读一个字节放回位缓冲区，之后的读取照常取到它
+        self.bits.fill_byte()
+    }
+
+    /// 重新开始解压紧接着的下一个 DEFLATE 流，已缓冲的输入保留
+    ///
+    /// 窗口和计数清零；`total_in` 继续累计。
+    pub fn reset(&mut self) {
+        self.bits.align();
+        self.window_pos = 0;
+        self.total_out = 0;
+        self.final_block = false;
+        self.state = State::BlockHeader;
+    }
+
+    /// 读取块头并进入对应状态
+    fn read_block_header(&mut self) -> io::Result<()> {
+        self.final_block = self.bits.bits(1)? == 1;
+        match self.bits.bits(2)? {
+            0 => {
+                self.bits.align();
+                let len = self.bits.bits(16)?;
+                let nlen = self.bits.bits(16)?;
+                if len != !nlen & 0xFFFF {
+                    return Err(invalid(InflateError::DecompressionFailed));
+                }
+                self.state = State::Stored { remaining: len as usize };
+            }
+            1 => {
+                self.litlen = build_table(&FIXED_LITLEN_CODE_LENGTHS, 288)?;
+                self.dist = build_table(&FIXED_DISTANCE_CODE_LENGTHS, 30)?;
+                self.state = State::Huffman;
+            }
+            2 => {
+                self.read_dynamic_tables()?;
+                self.state = State::Huffman;
+            }
+            _ => return Err(invalid(InflateError::DecompressionFailed)),
+        }
+        Ok(())
+    }
+
+    /// 解析动态 Huffman 块的码表
+    fn read_dynamic_tables(&mut self) -> io::Result<()> {
+        let hlit = self.bits.bits(5)? as usize + 257;
+        let hdist = self.bits.bits(5)? as usize + 1;
+        let hclen = self.bits.bits(4)? as usize + 4;
+
+        let mut code_length_lengths = [0u8; 19];
+        for &index in &LENGTH_DEZIGZAG[..hclen] {
+            code_length_lengths[index] = self.bits.bits(3)? as u8;
+        }
+        let code_lengths_table = build_table(&code_length_lengths, 19)?;
+
+        let mut lengths = vec![0u8; hlit + hdist];
+        let mut i = 0;
+        while i < lengths.len() {
+            let symbol = self.bits.decode(&code_lengths_table)?;
+            let (value, repeat) = match symbol {
+                0..=15 => (symbol as u8, 1),
+                16 => {
+                    let previous = *i
+                        .checked_sub(1)
+                        .and_then(|p| lengths.get(p))
+                        .ok_or_else(|| invalid(InflateError::InvalidCode))?;
+                    (previous, 3 + self.bits.bits(2)? as usize)
+                }
+                17 => (0, 3 + self.bits.bits(3)? as usize),
+                18 => (0, 11 + self.bits.bits(7)? as usize),
+                _ => return Err(invalid(InflateError::InvalidCode)),
+            };
+            if i + repeat > lengths.len() {
+                return Err(invalid(InflateError::InvalidCode));
+            }
+            lengths[i..i + repeat].fill(value);
+            i += repeat;
+        }
+        if lengths[256] == 0 {
+            // 没有块结束符号的码表无法结束这个块
+            return Err(invalid(InflateError::InvalidCode));
+        }
+
+        self.litlen = build_table(&lengths[..hlit], hlit)?;
+        self.dist = build_table(&lengths[hlit..], hdist)?;
+        Ok(())
+    }
+
+    /// 当前块结束
+    fn end_block(&mut self) {
+        self.state = if self.final_block { State::Done } else { State::BlockHeader };
+    }
+
+    /// 写出一个字节并记入窗口
+    fn emit(&mut self, out: &mut [u8], n: &mut usize, byte: u8) {
+        out[*n] = byte;
+        *n += 1;
+        self.window[self.window_pos] = byte;
+        self.window_pos = (self.window_pos + 1) % WINDOW_SIZE;
+        self.total_out += 1;
+    }
+}
+
+impl<R: Read> Read for InflateReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut n = 0;
+        while n < buf.len() {
+            match self.state {
+                State::Done => break,
+                State::BlockHeader => self.read_block_header()?,
+                State::Stored { remaining: 0 } => self.end_block(),
+                State::Stored { remaining } => {
+                    let want = remaining.min(buf.len() - n);
+                    let count = self.bits.read_aligned(&mut buf[n..n + want])?;
+                    if count == 0 {
+                        return Err(truncated());
+                    }
+                    for &byte in &buf[n..n + count] {
+                        self.window[self.window_pos] = byte;
+                        self.window_pos = (self.window_pos + 1) % WINDOW_SIZE;
+                    }
+                    self.total_out += count as u64;
+                    n += count;
+                    self.state = State::Stored { remaining: remaining - count };
+                }
+                State::Huffman => {
+                    let symbol = self.bits.decode(&self.litlen)? as usize;
+                    if symbol < 256 {
+                        self.emit(buf, &mut n, symbol as u8);
+                        continue;
+                    }
+                    if symbol == 256 {
+                        self.end_block();
+                        continue;
+                    }
+                    let index = symbol - 257;
+                    if index >= 29 {
+                        return Err(invalid(InflateError::InvalidCode));
+                    }
+                    let length = LENGTH_BASE[index] as usize + self.bits.bits(LENGTH_EXTRA[index] as u32)? as usize;
+                    let dist_symbol = self.bits.decode(&self.dist)? as usize;
+                    if dist_symbol >= 30 {
+                        return Err(invalid(InflateError::InvalidCode));
+                    }
+                    let distance =
+                        DIST_BASE[dist_symbol] as usize + self.bits.bits(DIST_EXTRA[dist_symbol] as u32)? as usize;
+                    if distance as u64 > self.total_out {
+                        return Err(invalid(InflateError::DecompressionFailed));
+                    }
+                    self.state = State::Copy { length, distance };
+                }
+                State::Copy { length, distance } => {
+                    let count = length.min(buf.len() - n);
+                    for _ in 0..count {
+                        let byte = self.window[(self.window_pos + WINDOW_SIZE - distance) % WINDOW_SIZE];
+                        self.emit(buf, &mut n, byte);
+                    }
+                    self.state = if count == length {
+                        State::Huffman
+                    } else {
+                        State::Copy { length: length - count, distance }
+                    };
+                }
+            }
+        }
+        Ok(n)
+    }
+}
+
+/// 构建解码表，码长不合法时返回 `InvalidData`
+fn build_table(lengths: &[u8], num_symbols: usize) -> io::Result<HuffmanTable> {
+    HuffmanTable::build(lengths, num_symbols).map_err(|_| invalid(InflateError::InvalidCode))
+}
+
+fn invalid(error: InflateError) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
+
+fn truncated() -> io::Error {
+    io::Error::new(io::ErrorKind::UnexpectedEof, InflateError::NeedMoreInput)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::miniz::deflate::{compress_raw, compress_raw_with, DeflateEncoder, DeflateImpl, DeflateOptions};
+
+    /// 单个最终存储块
+    fn stored_block(data: &[u8]) -> Vec<u8> {
+        let len = data.len() as u16;
+        let mut out = vec![0x01];
+        out.extend_from_slice(&len.to_le_bytes());
+        out.extend_from_slice(&(!len).to_le_bytes());
+        out.extend_from_slice(data);
+        out
+    }
+
+    #[test]
+    fn test_small_reads_match_input() {
+        let data: Vec<u8> = (0..200_000u32).map(|i| (i % 251) as u8 ^ (i / 997) as u8).collect();
+        let mut good = DeflateEncoder::new(DeflateOptions {
+            algorithm: DeflateImpl::Good,
+            ..Default::default()
+        })
+        .unwrap();
+        // 存储块、静态 Huffman 块、动态 Huffman 块
+        for (data, compressed) in [
+            (&data[..3000], stored_block(&data[..3000])),
+            (&data[..], compress_raw(&data, 6).unwrap()),
+            (&data[..], compress_raw_with(&mut good, &data).unwrap()),
+        ] {
+            let mut reader = InflateReader::new(compressed.as_slice());
+            let mut output = Vec::new();
+            let mut chunk = [0u8; 7];
+            loop {
+                let n = reader.read(&mut chunk).unwrap();
+                if n == 0 {
+                    break;
+                }
+                output.extend_from_slice(&chunk[..n]);
+            }
+            assert_eq!(output, data);
+            assert!(reader.is_done());
+            assert_eq!(reader.total_in(), compressed.len() as u64);
+            assert_eq!(reader.total_out(), data.len() as u64);
+        }
+    }
+
+    #[test]
+    fn test_truncated_and_trailer() {
+        let data = b"hello hello hello hello".to_vec();
+        let mut stream = compress_raw(&data, 6).unwrap();
+        let deflate_len = stream.len();
+
+        let mut output = Vec::new();
+        let err = InflateReader::new(&stream[..deflate_len - 1]).read_to_end(&mut output).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+
+        stream.extend_from_slice(b"TAIL");
+        let mut reader = InflateReader::new(stream.as_slice());
+        output.clear();
+        reader.read_to_end(&mut output).unwrap();
+        assert_eq!(output, data);
+        assert_eq!(reader.total_in(), deflate_len as u64);
+        let mut tail = [0u8; 4];
+        reader.read_trailer(&mut tail).unwrap();
+        assert_eq!(&tail, b"TAIL");
+        assert!(!reader.has_more_input().unwrap());
+    }
+
+    #[test]
+    fn test_matches_inflate_decoder() {
+        use crate::miniz::inflate::decompress_raw;
+
+        let text = b"the quick brown fox jumps over the lazy dog\n".repeat(500);
+        let noise: Vec<u8> = (0..70_000u32).map(|i| (i.wrapping_mul(2_654_435_761) >> 13) as u8).collect();
+        let mut good = DeflateEncoder::new(DeflateOptions {
+            algorithm: DeflateImpl::Good,
+            ..Default::default()
+        })
+        .unwrap();
+        let mut streams = vec![stored_block(&text[..1000])];
+        for data in [&text[..], &noise[..], &b"a"[..]] {
+            for level in [1, 6, 9] {
+                streams.push(compress_raw(data, level).unwrap());
+            }
+            streams.push(compress_raw_with(&mut good, data).unwrap());
+        }
+
+        for stream in &streams {
+            let mut output = Vec::new();
+            InflateReader::new(stream.as_slice()).read_to_end(&mut output).unwrap();
+            assert_eq!(output, decompress_raw(stream).unwrap());
+
+            // 截断的流两者都拒绝
+            let truncated = &stream[..stream.len() - 1];
+            assert!(decompress_raw(truncated).is_err());
+            assert!(InflateReader::new(truncated).read_to_end(&mut Vec::new()).is_err());
+        }
+    }
+}
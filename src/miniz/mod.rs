@@ -5,14 +5,17 @@
 pub mod crc32;
 pub mod deflate;
 pub mod deflate_fast;
+pub mod deflate_good;
 pub mod inflate;
+pub mod inflate_stream;
 pub mod huffman;
 pub mod bitstream;
 pub mod lz77;
 
-pub use crc32::{crc32, Crc32};
-pub use deflate::{compress, compress_raw, compress_to_buffer, CompressResult, DeflateEncoder, DeflateOptions};
-pub use inflate::{decompress, decompress_to_buffer, decompress_raw, DecompressResult, InflateDecoder};
+pub use crc32::{crc32, crc32c, Crc32, Crc32Variant};
+pub use deflate::{compress, compress_raw, compress_to_buffer, CompressResult, DeflateEncoder, DeflateImpl, DeflateOptions};
+pub use inflate::{decompress, decompress_to_buffer, decompress_raw, DecompressResult, InflateDecoder, InflateError};
+pub use inflate_stream::InflateReader;
 pub use huffman::{
     HuffmanTable, LENGTH_BASE, LENGTH_EXTRA, DIST_BASE, DIST_EXTRA,
     FIXED_LITLEN_CODE_LENGTHS, FIXED_DISTANCE_CODE_LENGTHS,
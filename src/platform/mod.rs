@@ -292,6 +292,84 @@ pub fn utf16_to_utf8(input: &[u16]) -> std::io::Result<String> {
         .map_err(|_| std::io::Error::new(std::io::ErrorKind::InvalidData, "invalid UTF-16"))
 }
 
+/// Read all extended attributes of `path` as `(name, value)` pairs
+///
+/// Follows symlinks. Filesystems without xattr support yield an empty list.
+#[cfg(all(feature = "xattr", any(target_os = "linux", target_os = "android", target_os = "macos")))]
+pub fn read_xattrs(path: &Path) -> std::io::Result<Vec<(String, Vec<u8>)>> {
+    let c_path = xattr_c_string(path.as_os_str())?;
+
+    let names = xattr_buffer(|buf, size| unsafe {
+        #[cfg(target_os = "macos")]
+        return libc::listxattr(c_path.as_ptr(), buf as *mut libc::c_char, size, 0);
+        #[cfg(not(target_os = "macos"))]
+        return libc::listxattr(c_path.as_ptr(), buf as *mut libc::c_char, size);
+    });
+    let names = match names {
+        Ok(names) => names,
+        Err(e) if e.raw_os_error() == Some(libc::ENOTSUP) => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+
+    let mut attrs = Vec::new();
+    for name in names.split(|&b| b == 0).filter(|name| !name.is_empty()) {
+        let c_name = std::ffi::CString::new(name)
+            .map_err(|_| std::io::Error::new(std::io::ErrorKind::InvalidData, "xattr name contains NUL"))?;
+        let value = xattr_buffer(|buf, size| unsafe {
+            #[cfg(target_os = "macos")]
+            return libc::getxattr(c_path.as_ptr(), c_name.as_ptr(), buf, size, 0, 0);
+            #[cfg(not(target_os = "macos"))]
+            return libc::getxattr(c_path.as_ptr(), c_name.as_ptr(), buf, size);
+        })?;
+        attrs.push((String::from_utf8_lossy(name).into_owned(), value));
+    }
+    Ok(attrs)
+}
+
+/// Set the extended attribute `name` of `path` to `value`
+#[cfg(all(feature = "xattr", any(target_os = "linux", target_os = "android", target_os = "macos")))]
+pub fn write_xattr(path: &Path, name: &str, value: &[u8]) -> std::io::Result<()> {
+    let c_path = xattr_c_string(path.as_os_str())?;
+    let c_name = xattr_c_string(std::ffi::OsStr::new(name))?;
+    let value_ptr = value.as_ptr() as *const libc::c_void;
+    #[cfg(target_os = "macos")]
+    let ret = unsafe { libc::setxattr(c_path.as_ptr(), c_name.as_ptr(), value_ptr, value.len(), 0, 0) };
+    #[cfg(not(target_os = "macos"))]
+    let ret = unsafe { libc::setxattr(c_path.as_ptr(), c_name.as_ptr(), value_ptr, value.len(), 0) };
+    if ret != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(all(feature = "xattr", any(target_os = "linux", target_os = "android", target_os = "macos")))]
+fn xattr_c_string(s: &std::ffi::OsStr) -> std::io::Result<std::ffi::CString> {
+    use std::os::unix::ffi::OsStrExt;
+    std::ffi::CString::new(s.as_bytes())
+        .map_err(|_| std::io::Error::new(std::io::ErrorKind::InvalidInput, "path contains NUL"))
+}
+
+/// Run a size-query-then-fill xattr call, retrying if the value grew in between
+#[cfg(all(feature = "xattr", any(target_os = "linux", target_os = "android", target_os = "macos")))]
+fn xattr_buffer(mut call: impl FnMut(*mut libc::c_void, usize) -> libc::ssize_t) -> std::io::Result<Vec<u8>> {
+    loop {
+        let size = call(std::ptr::null_mut(), 0);
+        if size < 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        let mut buf = vec![0u8; size as usize];
+        let len = call(buf.as_mut_ptr() as *mut libc::c_void, buf.len());
+        if len >= 0 {
+            buf.truncate(len as usize);
+            return Ok(buf);
+        }
+        let err = std::io::Error::last_os_error();
+        if err.raw_os_error() != Some(libc::ERANGE) {
+            return Err(err);
+        }
+    }
+}
+
 /// Generic platform implementation for other OSes
 #[cfg(not(any(unix, windows)))]
 pub struct GenericPlatform;
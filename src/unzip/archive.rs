@@ -1,9 +1,23 @@
 //! 纯 Rust ZIP Archive 实现
 //! 完全复刻 C 版本 ZipArchive 的行为，不使用 FFI
 
-use crate::error::{FileType, Result, ZipEntry, ZipError};
+use crate::cancel::CancelToken;
+use crate::error::{CompressionLevel, FileType, Result, ZipEntry, ZipError};
+use crate::miniz::crc32::crc32;
 use crate::miniz::inflate;
+use crate::miniz::InflateReader;
+use crate::platform::{current_platform, Platform};
+use crate::sha256::sha256;
+use crate::unzip::entry_name::{classify_entry_name, ensure_no_symlink_escape, is_safe_entry_name};
+use crate::unzip::entry_reader::EntryReader;
+use crate::zip::cp437;
+use crate::zip::crypto;
+use crate::zip::extra;
 use crate::zip::reader::{ZipEntryInfo, ZipReader};
+use crate::zip::replace::replace_file;
+use crate::zip::ZipWriter;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
 use std::fs::File;
 use std::io::{BufReader, Read, Seek, SeekFrom, Write};
 use std::path::{Path, PathBuf};
@@ -18,7 +32,10 @@ use std::path::{Path, PathBuf};
 ///   } else {
 ///     *mode = (mode_t) external_attr & 0777;
 ///   }
-fn extract_permissions(external_attr: u32, version_made_by: u16, is_dir: bool) -> u32 {
+///
+/// 只返回 0777 权限位；不是 Unix 创建（`version_made_by` 高字节不为 3）或高 16 位为 0 时，
+/// 目录为 0700、文件为 0600。与 [`mode_to_external_attr`](crate::zip::writer::mode_to_external_attr) 互逆。
+pub fn external_attr_to_mode(external_attr: u32, version_made_by: u16, is_dir: bool) -> u32 {
     // 提取高16位（Unix 权限）
     let unix_attr = (external_attr >> 16) & 0xFFFF;
 
@@ -40,9 +57,14 @@ fn extract_permissions(external_attr: u32, version_made_by: u16, is_dir: bool) -
     }
 }
 
-/// DOS 时间转换为 SystemTime
+/// DOS 时间转换为 SystemTime，日期为 0 或无效时返回 `UNIX_EPOCH`
 /// 对应 C 版本的 mz_zip_dos_to_time_t()
 fn dos_to_system_time(dos_time: u16, dos_date: u16) -> std::time::SystemTime {
+    dos_to_time(dos_time, dos_date).unwrap_or(std::time::UNIX_EPOCH)
+}
+
+/// DOS 时间转换为 SystemTime，日期为 0（未设置）或无效时返回 `None`
+fn dos_to_time(dos_time: u16, dos_date: u16) -> Option<std::time::SystemTime> {
     use std::time::UNIX_EPOCH;
 
     // DOS 日期格式：bit 9-15=year, bit 5-8=month, bit 0-4=day
@@ -55,9 +77,9 @@ fn dos_to_system_time(dos_time: u16, dos_date: u16) -> std::time::SystemTime {
     let minute = ((dos_time >> 5) & 0x3F) as u8;
     let second = ((dos_time << 1) & 0x3E) as u8;
 
-    // 如果日期为 0（未设置或无效），返回 UNIX_EPOCH
+    // 日期为 0（未设置）或月、日为 0 时无效
     if dos_date == 0 || month == 0 || day == 0 {
-        return UNIX_EPOCH;
+        return None;
     }
 
     // 构造时间
@@ -71,17 +93,498 @@ fn dos_to_system_time(dos_time: u16, dos_date: u16) -> std::time::SystemTime {
         Err(_) => None,
     };
 
-    match datetime {
-        Some(dt) => {
-            // time crate 的 OffsetDateTime 转 SystemTime
-            let timestamp = dt.unix_timestamp();
-            if timestamp >= 0 {
-                UNIX_EPOCH + std::time::Duration::from_secs(timestamp as u64)
-            } else {
-                UNIX_EPOCH - std::time::Duration::from_secs((-timestamp) as u64)
+    datetime.map(|dt| {
+        // time crate 的 OffsetDateTime 转 SystemTime
+        let timestamp = dt.unix_timestamp();
+        if timestamp >= 0 {
+            UNIX_EPOCH + std::time::Duration::from_secs(timestamp as u64)
+        } else {
+            UNIX_EPOCH - std::time::Duration::from_secs((-timestamp) as u64)
+        }
+    })
+}
+
+/// 尽量填满 buf，直到读满或遇到真正的 EOF
+/// 返回实际读取的字节数；`Interrupted` 会被重试
+fn read_full<R: Read>(reader: &mut R, buf: &mut [u8]) -> std::io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
+
+/// 将中央目录记录转换为对外的 `ZipEntry`
+pub(crate) fn to_zip_entry(info: &ZipEntryInfo) -> ZipEntry {
+    // 对应 C 版本 zip.c:111-123 的 zip_get_permissions()
+    // 从 external_attr 提取 Unix 权限
+    let permissions = external_attr_to_mode(info.external_attr, info.version_made_by, info.is_dir);
+    // `timestamp` 与 C 版本 zip_list 一致，只取 DOS 时间（mz_zip_dos_to_time_t）；
+    // extra field 中更精确的时间只放在 `times` 里，解压时用它恢复
+    let timestamp = dos_to_system_time(info.mtime_dos, info.mdate_dos);
+    let times = extra::entry_times(&info.extra, timestamp);
+
+    ZipEntry {
+        filename: info.name.clone(),
+        compressed_size: info.compressed_size,
+        uncompressed_size: info.uncompressed_size,
+        crc32: info.crc32,
+        offset: info.local_header_offset,
+        is_directory: info.is_dir,
+        timestamp,
+        permissions,
+        file_type: if info.is_dir {
+            FileType::Directory
+        } else if info.compression_method == 8 {
+            FileType::File
+        } else {
+            FileType::File
+        },
+        is_symlink: false,
+        is_encrypted: info.flags & FLAG_ENCRYPTED != 0,
+        version_made_by: info.version_made_by,
+        dos_date: info.mdate_dos,
+        dos_time: info.mtime_dos,
+        external_attr: info.external_attr,
+        times,
+        local_name: None,
+        owner: extra::unix_owner(&info.extra),
+        xattrs: extra::xattrs(&info.extra),
+    }
+}
+
+/// 与 `to_zip_entry` 相同，但 DOS 日期为 0 或无效、中央目录又没有时间字段时，
+/// 改用本地文件头 extra field 中的时间（部分流式写入器只在本地头写 `0x5455`）
+///
+/// 读取本地文件头失败时保留 `to_zip_entry` 的结果，列出条目不因此失败。
+pub(crate) fn to_zip_entry_in(path: &Path, info: &ZipEntryInfo) -> ZipEntry {
+    let mut entry = to_zip_entry(info);
+    if dos_to_time(info.mtime_dos, info.mdate_dos).is_some() || extra::modified_time(&info.extra).is_some() {
+        return entry;
+    }
+    if let Ok(local_extra) = read_local_extra(path, info) {
+        entry.times = extra::entry_times(&local_extra, entry.times.mtime);
+        entry.owner = entry.owner.or_else(|| extra::unix_owner(&local_extra));
+    }
+    entry
+}
+
+/// 读取条目本地文件头中的 extra field
+fn read_local_extra(path: &Path, info: &ZipEntryInfo) -> Result<Vec<u8>> {
+    let mut file = File::open(path).map_err(|e| ZipError::file_open(path, e))?;
+    read_local_fields(path, &mut file, info).map(|(_, extra)| extra)
+}
+
+/// 读取条目本地文件头中的文件名和 extra field 原始字节
+fn read_local_fields(path: &Path, file: &mut File, info: &ZipEntryInfo) -> Result<(Vec<u8>, Vec<u8>)> {
+    file.seek(SeekFrom::Start(info.local_header_offset))
+        .map_err(|e| ZipError::file_read(path, e))?;
+    let mut buf = [0u8; 30];
+    file.read_exact(&mut buf).map_err(|e| ZipError::file_read(path, e))?;
+    let header = LocalHeader::parse(path, info, &buf)?;
+    let mut name = vec![0u8; header.name_len];
+    file.read_exact(&mut name).map_err(|e| ZipError::file_read(path, e))?;
+    let mut extra = vec![0u8; header.extra_len];
+    file.read_exact(&mut extra).map_err(|e| ZipError::file_read(path, e))?;
+    Ok((name, extra))
+}
+
+/// 按中央目录记录的标志位解码本地文件头中的文件名，规则与中央目录相同
+pub(crate) fn decode_local_name(bytes: &[u8], flags: u16) -> String {
+    match std::str::from_utf8(bytes) {
+        Ok(name) => name.to_string(),
+        Err(_) if flags & FLAG_UTF8 == 0 => cp437::decode(bytes),
+        Err(_) => String::from_utf8_lossy(bytes).to_string(),
+    }
+}
+
+#[cfg(test)]
+thread_local! {
+    /// 当前线程中 `sync_written` 的调用次数，供测试确认落盘选项生效
+    static SYNC_CALLS: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
+#[cfg(test)]
+pub(crate) fn sync_calls() -> usize {
+    SYNC_CALLS.with(|calls| calls.get())
+}
+
+/// 把刚写完的文件落盘；Unix 上还同步其所在目录，使新建的目录项在崩溃后仍然存在
+fn sync_written(file: &File, output: &Path) -> Result<()> {
+    file.sync_all()
+        .map_err(|e| ZipError::generic(&format!("Failed to sync output file: {:?}", e)))?;
+    #[cfg(unix)]
+    if let Some(parent) = output.parent() {
+        File::open(parent)
+            .and_then(|dir| dir.sync_all())
+            .map_err(|e| {
+                ZipError::generic(&format!("Failed to sync directory {}: {:?}", parent.display(), e))
+            })?;
+    }
+    #[cfg(test)]
+    SYNC_CALLS.with(|calls| calls.set(calls.get() + 1));
+    Ok(())
+}
+
+/// external_attr 高 16 位的 Unix 模式是否表示符号链接
+pub(crate) fn is_symlink_attr(external_attr: u32) -> bool {
+    const S_IFMT: u32 = 0o170000; // 文件类型掩码
+    const S_IFLNK: u32 = 0o120000; // 符号链接文件类型
+    ((external_attr >> 16) & S_IFMT) == S_IFLNK
+}
+
+/// 通用标志位 bit 0：条目已加密
+const FLAG_ENCRYPTED: u16 = 0x0001;
+/// WinZip AES 加密条目的压缩方法，实际方法记录在 `0x9901` extra field 中
+const METHOD_AES: u16 = 99;
+/// 通用标志位 bit 3：CRC 和大小写在数据之后的数据描述符中
+const FLAG_DATA_DESCRIPTOR: u16 = 0x0008;
+/// 通用标志位 bit 11：文件名为 UTF-8
+const FLAG_UTF8: u16 = 0x0800;
+/// 本地头中表示“压缩大小未知”的哨兵值
+const SIZE_UNKNOWN: u32 = 0xFFFF_FFFF;
+/// DEFLATE 的最大压缩比（每个 258 字节的匹配至少占 2 bit）
+const MAX_DEFLATE_RATIO: u64 = 1032;
+
+/// 解压时对每个文件条目数据的变换：参数为条目名和解压后的数据，返回要写出的数据
+pub(crate) type EntryTransform = dyn FnMut(&str, &[u8]) -> Vec<u8> + Send;
+
+/// 开启预分配时，不小于此大小的条目才预先扩展输出文件
+const PREALLOCATE_MIN_SIZE: u64 = 1024 * 1024;
+
+/// 可取消的解压每次读取的块大小，两块之间检查取消
+const CANCEL_CHECK_CHUNK: usize = 64 * 1024;
+
+/// 不带注释的 EOCD 记录长度
+const EOCD_LEN: u64 = 22;
+
+/// 本地文件头（30 字节固定部分）中解压需要的字段
+pub(crate) struct LocalHeader {
+    pub flags: u16,
+    pub compression_method: u16,
+    pub mod_time: u16,
+    pub crc32: u32,
+    pub compressed_size: u64,
+    pub name_len: usize,
+    pub extra_len: usize,
+    /// 数据已由 AE-2 认证码校验，CRC32 字段不使用
+    pub authenticated: bool,
+}
+
+impl LocalHeader {
+    /// 解析并校验本地文件头签名
+    pub(crate) fn parse(archive: &Path, entry: &ZipEntryInfo, buf: &[u8; 30]) -> Result<Self> {
+        let sig = u32::from_le_bytes(buf[0..4].try_into().unwrap());
+        if sig != 0x04034b50 {
+            return Err(ZipError::CorruptEntry {
+                name: entry.name.clone(),
+                archive: archive.to_path_buf(),
+                reason: format!("invalid local header signature: 0x{:08x}", sig),
+            });
+        }
+
+        let flags = u16::from_le_bytes(buf[6..8].try_into().unwrap());
+        let mut crc32 = u32::from_le_bytes(buf[14..18].try_into().unwrap());
+        let mut compressed_size = u32::from_le_bytes(buf[18..22].try_into().unwrap()) as u64;
+
+        // bit 3：CRC 和大小写在数据之后的数据描述符中，本地头里是 0；
+        // 流式写入器还可能把压缩大小写成 0xFFFFFFFF 而不带 ZIP64 字段。
+        // 这两种情况下本地头的值不可信，改用中央目录中的值，避免按 4 GB 去读
+        if flags & FLAG_DATA_DESCRIPTOR != 0 {
+            crc32 = entry.crc32;
+            compressed_size = entry.compressed_size;
+        } else if compressed_size == SIZE_UNKNOWN as u64 {
+            compressed_size = entry.compressed_size;
+        }
+
+        // STORE 条目的压缩大小必须等于解压大小，否则会读错字节数；加密条目带有加密头，不做此检查
+        let compression_method = u16::from_le_bytes(buf[8..10].try_into().unwrap());
+        if compression_method == 0
+            && flags & FLAG_ENCRYPTED == 0
+            && compressed_size != entry.uncompressed_size
+        {
+            return Err(ZipError::CorruptEntry {
+                name: entry.name.clone(),
+                archive: archive.to_path_buf(),
+                reason: format!(
+                    "stored entry size mismatch: compressed {} bytes, uncompressed {} bytes",
+                    compressed_size, entry.uncompressed_size
+                ),
+            });
+        }
+
+        Ok(Self {
+            flags,
+            compression_method,
+            mod_time: u16::from_le_bytes(buf[10..12].try_into().unwrap()),
+            crc32,
+            compressed_size,
+            name_len: u16::from_le_bytes(buf[26..28].try_into().unwrap()) as usize,
+            extra_len: u16::from_le_bytes(buf[28..30].try_into().unwrap()) as usize,
+            authenticated: false,
+        })
+    }
+
+    /// 解压后需要校验的 CRC32；AE-2 加密的条目没有 CRC，返回 `None`
+    pub(crate) fn expected_crc(&self) -> Option<u32> {
+        (!self.authenticated).then_some(self.crc32)
+    }
+}
+
+/// 解密加密条目的数据；未加密的条目原样返回
+///
+/// 支持传统 PKWARE 加密（ZipCrypto）和 WinZip AES（需要 `aes` feature）。
+/// 没有密码时返回 `PasswordRequired`，密码校验值不匹配时返回 `InvalidPassword`。
+/// AES 条目解密后 `header` 中的压缩方法被替换为实际方法。
+pub(crate) fn decrypt_entry_data<'a>(
+    archive: &Path,
+    entry: &ZipEntryInfo,
+    header: &mut LocalHeader,
+    password: Option<&[u8]>,
+    data: Cow<'a, [u8]>,
+) -> Result<Cow<'a, [u8]>> {
+    let name = entry.name.as_str();
+    if header.flags & FLAG_ENCRYPTED == 0 {
+        return Ok(data);
+    }
+    let password = password.ok_or_else(|| ZipError::PasswordRequired {
+        name: name.to_string(),
+        archive: archive.to_path_buf(),
+    })?;
+
+    if header.compression_method == METHOD_AES {
+        return decrypt_aes(archive, entry, header, password, &data).map(Cow::Owned);
+    }
+
+    // bit 3 时 CRC 在加密头生成时还未知，校验字节取自修改时间
+    let check_byte = if header.flags & FLAG_DATA_DESCRIPTOR != 0 {
+        (header.mod_time >> 8) as u8
+    } else {
+        (header.crc32 >> 24) as u8
+    };
+    crypto::decrypt_entry(password, check_byte, &data)
+        .map(Cow::Owned)
+        .ok_or_else(|| ZipError::InvalidPassword {
+            name: name.to_string(),
+            archive: archive.to_path_buf(),
+        })
+}
+
+/// 解密 WinZip AES 条目，实际压缩方法和 AE 版本取自 `0x9901` extra field
+#[cfg(feature = "aes")]
+fn decrypt_aes(
+    archive: &Path,
+    entry: &ZipEntryInfo,
+    header: &mut LocalHeader,
+    password: &[u8],
+    data: &[u8],
+) -> Result<Vec<u8>> {
+    use crate::error::AesStrength;
+    use crate::zip::aes::{self, DecryptError};
+
+    let corrupt = |reason: &str| ZipError::CorruptEntry {
+        name: entry.name.clone(),
+        archive: archive.to_path_buf(),
+        reason: reason.to_string(),
+    };
+    let (version, strength, method) =
+        extra::aes_field(&entry.extra).ok_or_else(|| corrupt("missing AES extra field"))?;
+    let strength =
+        AesStrength::from_u8(strength).ok_or_else(|| corrupt("invalid AES key strength"))?;
+
+    let plain = aes::decrypt_entry(password, strength, data).map_err(|e| match e {
+        DecryptError::WrongPassword => ZipError::InvalidPassword {
+            name: entry.name.clone(),
+            archive: archive.to_path_buf(),
+        },
+        DecryptError::Truncated => corrupt("truncated AES data"),
+        DecryptError::AuthenticationFailed => corrupt("AES authentication code mismatch"),
+    })?;
+
+    header.compression_method = method;
+    // AE-1 仍带有 CRC32，AE-2 只靠认证码
+    header.authenticated = version == 2;
+    Ok(plain)
+}
+
+#[cfg(not(feature = "aes"))]
+fn decrypt_aes(
+    _archive: &Path,
+    entry: &ZipEntryInfo,
+    _header: &mut LocalHeader,
+    _password: &[u8],
+    _data: &[u8],
+) -> Result<Vec<u8>> {
+    Err(ZipError::generic(&format!(
+        "entry '{}' is AES-encrypted; enable the `aes` feature to decrypt it",
+        entry.name
+    )))
+}
+
+/// 解压条目的原始数据并校验 CRC32
+/// 对应 C 版本 mz_zip_reader_extract_to_mem() 的解压部分
+pub(crate) fn decode_entry_data(
+    archive: &Path,
+    name: &str,
+    compression_method: u16,
+    uncompressed_size: u64,
+    crc32_expected: Option<u32>,
+    compressed_data: Cow<'_, [u8]>,
+) -> Result<Vec<u8>> {
+    // 解压数据
+    let decompressed_data = if compression_method == 8 {
+        // DEFLATE 压缩
+        // 注意：ZIP 格式的 DEFLATE 不包含 zlib 头尾
+        // 使用 parse_zlib_header=false 的 inflate 解码
+        // 按中央目录记录的大小一次分配输出缓冲区；DEFLATE 的压缩比不超过 1032:1，
+        // 声称更大的大小不可信，不按它分配
+        let max_size = (compressed_data.len() as u64).saturating_mul(MAX_DEFLATE_RATIO);
+        let capacity = uncompressed_size.min(max_size) as usize;
+        inflate::decompress_raw_with_capacity(&compressed_data, capacity).map_err(|source| {
+            ZipError::InflateFailed {
+                name: name.to_string(),
+                archive: archive.to_path_buf(),
+                source,
             }
+        })?
+    } else if compression_method == 0 {
+        // 无压缩（STORE）
+        compressed_data.into_owned()
+    } else {
+        // shrink / implode / bzip2 / LZMA 等其他方法一律报告为不支持，而不是当作损坏的数据
+        return Err(ZipError::UnsupportedCompression { method: compression_method });
+    };
+
+    // 验证 CRC32
+    if let Some(crc32_expected) = crc32_expected {
+        check_crc32(archive, name, crc32_expected, &decompressed_data)?;
+    }
+    Ok(decompressed_data)
+}
+
+/// 把 `InflateReader` / `EntryReader` 返回的 I/O 错误转换为 `ZipError`
+///
+/// 数据损坏或截断时为 `ZipError::InflateFailed`，CRC32 不一致时为 `EntryReader` 给出的
+/// `ZipError::CorruptEntry`，其余为读取归档本身失败。
+fn inflate_read_error(archive: &Path, name: &str, e: std::io::Error) -> ZipError {
+    let read_failed = ZipError::generic(&format!("Failed to read compressed data: {:?}", e));
+    match e.into_inner().map(|inner| inner.downcast::<inflate::InflateError>()) {
+        Some(Ok(source)) => ZipError::InflateFailed {
+            name: name.to_string(),
+            archive: archive.to_path_buf(),
+            source: *source,
+        },
+        Some(Err(inner)) => inner.downcast::<ZipError>().map_or(read_failed, |e| *e),
+        None => read_failed,
+    }
+}
+
+/// 校验解压后数据的 CRC32，不一致时返回 `ZipError::CorruptEntry`
+pub(crate) fn check_crc32(archive: &Path, name: &str, crc32_expected: u32, data: &[u8]) -> Result<()> {
+    let crc32_actual = crc32(0, data);
+    if crc32_actual != crc32_expected {
+        return Err(ZipError::CorruptEntry {
+            name: name.to_string(),
+            archive: archive.to_path_buf(),
+            reason: format!(
+                "CRC32 mismatch: expected 0x{:08x}, got 0x{:08x}",
+                crc32_expected, crc32_actual
+            ),
+        });
+    }
+    Ok(())
+}
+
+/// 归档用到的格式特性，由 `ZipArchive::compatibility_report` 根据中央目录生成
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CompatibilityReport {
+    /// 加密条目（ZipCrypto 或 WinZip AES）的名称
+    pub encrypted: Vec<String>,
+    /// AES 加密条目的名称；未启用 `aes` feature 时无法解密
+    pub aes_encrypted: Vec<String>,
+    /// 使用 ZIP64 字段（0x0001 extra field 或 0xFFFFFFFF 占位值）的条目名称
+    pub zip64: Vec<String>,
+    /// 使用不支持的压缩方法的条目：(名称, 方法)
+    pub unsupported_methods: Vec<(String, u16)>,
+    /// 使用数据描述符（bit 3）的条目名称
+    pub data_descriptors: Vec<String>,
+}
+
+impl CompatibilityReport {
+    /// 本库能否完整读取归档中的所有条目
+    /// 加密条目需要提供密码，不影响结果
+    pub fn is_fully_supported(&self) -> bool {
+        self.unsupported_methods.is_empty()
+            && self.zip64.is_empty()
+            && (cfg!(feature = "aes") || self.aes_encrypted.is_empty())
+    }
+}
+
+/// 归档各部分占用的字节数，用于区分格式开销与条目数据
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ArchiveOverhead {
+    /// 本地文件头（30 字节 + 文件名 + extra）与数据描述符
+    pub local_headers: u64,
+    /// 中央目录记录（46 字节 + 文件名 + extra + 注释）
+    pub central_directory: u64,
+    /// 中央目录之后的部分：EOCD 及归档注释
+    pub end_of_central_directory: u64,
+    /// 所有条目的压缩数据
+    pub payload: u64,
+}
+
+impl ArchiveOverhead {
+    /// 非条目数据的总字节数
+    pub fn total(&self) -> u64 {
+        self.local_headers + self.central_directory + self.end_of_central_directory
+    }
+}
+
+/// `ZipArchive::verify_against_manifest` 发现的一处不一致
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ManifestMismatch {
+    /// 清单中有、归档中没有的条目
+    Missing { name: String },
+    /// 归档中有、清单中没有的条目
+    Extra { name: String },
+    /// CRC32 与清单不同
+    Crc { name: String, expected: u32, actual: u32 },
+    /// 解压大小与清单不同
+    Size { name: String, expected: u64, actual: u64 },
+}
+
+/// 整个归档的压缩统计，由 `ZipArchive::statistics` 根据中央目录生成
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ArchiveStats {
+    /// 中央目录记录数（文件和目录）
+    pub entries: usize,
+    /// 目录条目数
+    pub directories: usize,
+    /// 以 STORE 存储的文件条目数
+    pub stored: usize,
+    /// 以 DEFLATE 压缩的文件条目数
+    pub deflated: usize,
+    /// 使用其他压缩方法的文件条目数
+    pub other: usize,
+    /// 所有条目压缩后的总字节数
+    pub compressed_size: u64,
+    /// 所有条目未压缩的总字节数
+    pub uncompressed_size: u64,
+}
+
+impl ArchiveStats {
+    /// 压缩率：压缩后总大小 / 未压缩总大小；没有任何数据时为 1.0
+    pub fn ratio(&self) -> f64 {
+        if self.uncompressed_size == 0 {
+            1.0
+        } else {
+            self.compressed_size as f64 / self.uncompressed_size as f64
         }
-        None => UNIX_EPOCH, // 无效时间返回 epoch
     }
 }
 
@@ -89,6 +592,26 @@ fn dos_to_system_time(dos_time: u16, dos_date: u16) -> std::time::SystemTime {
 /// 对应 C 版本使用 FFI 的 ZipArchive
 pub struct ZipArchive {
     path: PathBuf,
+    /// 解密加密条目使用的密码
+    password: Option<Vec<u8>>,
+    /// 读取条目时是否校验 CRC32
+    verify_crc: bool,
+    /// 本地文件头中的文件名必须与中央目录一致
+    strict_names: bool,
+    /// 解压写出条目时按块检查的取消令牌
+    cancel: Option<CancelToken>,
+    /// 整个归档文件的只读映射，由 `open_mmap` 创建
+    #[cfg(feature = "mmap")]
+    mmap: Option<memmap2::Mmap>,
+    /// `open_anchored` 记录的完整性锚点，每次读取前比对
+    anchor: Option<ArchiveAnchor>,
+}
+
+/// 归档的完整性锚点：文件大小和 EOCD 记录（含注释）的 SHA-256
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ArchiveAnchor {
+    size: u64,
+    eocd_hash: [u8; 32],
 }
 
 impl ZipArchive {
@@ -96,208 +619,2903 @@ impl ZipArchive {
     pub fn open(path: impl AsRef<Path>) -> Result<Self> {
         Ok(Self {
             path: path.as_ref().to_path_buf(),
+            password: None,
+            verify_crc: true,
+            strict_names: false,
+            cancel: None,
+            #[cfg(feature = "mmap")]
+            mmap: None,
+            anchor: None,
+        })
+    }
+
+    /// 打开 ZIP 文件，并记录文件大小和 EOCD 记录的哈希作为完整性锚点
+    ///
+    /// `ZipArchive` 的方法每次调用都重新打开文件，归档在两次调用之间被改写时，
+    /// 先前 `entries()` 得到的下标和偏移可能指向别的数据。以此方式打开后，
+    /// 每次读取中央目录前都比对锚点，不一致时返回 `ZipError::ArchiveModified`。
+    pub fn open_anchored(path: impl AsRef<Path>) -> Result<Self> {
+        let mut archive = Self::open(path)?;
+        let size = archive.file_size()?;
+        let reader = ZipReader::open(&archive.path)?;
+        archive.anchor = Some(ArchiveAnchor {
+            size,
+            eocd_hash: sha256(&reader.eocd_bytes()?),
+        });
+        Ok(archive)
+    }
+
+    /// 以内存映射方式打开 ZIP 文件
+    ///
+    /// 条目数据直接从映射的内存中读取：存储条目只做一次复制，
+    /// DEFLATE 条目直接从映射内存解压，省去每个条目的 seek + read。
+    /// 无法保证下面的条件时请使用 `open`。
+    ///
+    /// # Safety
+    ///
+    /// 在返回的 `ZipArchive` 及由它借出的切片（如 `entry_slice` 的结果）存活期间，
+    /// 文件不得被本进程或其他进程修改或截断。否则映射的内容会在使用中途改变，
+    /// 截断则会使访问映射的进程收到 SIGBUS，两者都是未定义行为。
+    #[cfg(feature = "mmap")]
+    pub unsafe fn open_mmap(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let file = File::open(&path).map_err(|e| ZipError::FileOpen {
+            path: path.clone(),
+            source: e,
+        })?;
+        // SAFETY: 映射为只读；调用方按 `open_mmap` 的约定保证映射期间文件不被修改或截断
+        let mmap = unsafe { memmap2::Mmap::map(&file) }.map_err(|e| ZipError::FileOpen {
+            path: path.clone(),
+            source: e,
+        })?;
+        Ok(Self {
+            path,
+            password: None,
+            verify_crc: true,
+            strict_names: false,
+            cancel: None,
+            mmap: Some(mmap),
+            anchor: None,
         })
     }
 
+    /// 设置解密加密条目使用的密码
+    ///
+    /// 列出条目不需要密码；读取加密条目而未设置密码时返回 `ZipError::PasswordRequired`。
+    pub fn password(mut self, password: impl AsRef<[u8]>) -> Self {
+        self.password = Some(password.as_ref().to_vec());
+        self
+    }
+
+    /// 读取条目时是否校验解压数据的 CRC32，默认开启
+    ///
+    /// 关闭后省去每个条目的一次 CRC32 计算，适合完全信任来源的高吞吐场景；
+    /// 代价是损坏或被篡改的条目会被原样返回而不报错。解压失败等结构性错误仍然报告。
+    pub fn verify_crc(mut self, verify: bool) -> Self {
+        self.verify_crc = verify;
+        self
+    }
+
+    /// 严格模式：本地文件头中的文件名与中央目录不一致时拒绝读取该条目，默认关闭
+    ///
+    /// 解压输出路径总是取中央目录中的名字；两处名字不同通常意味着归档损坏或被刻意混淆，
+    /// 安全敏感的工具可以开启此项，在读取条目数据和 `entries_with_local_names` 时报错。
+    pub fn strict_names(mut self, strict: bool) -> Self {
+        self.strict_names = strict;
+        self
+    }
+
+    /// 设置解压写出条目时检查的取消令牌，大条目在解压过程中也能及时停止
+    pub(crate) fn cancel_token(mut self, cancel: Option<CancelToken>) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
     /// 列出 ZIP 文件内容
     /// 对应 C 版本的 zip_list()
     pub fn list(path: impl AsRef<Path>) -> Result<Vec<ZipEntry>> {
+        let path = path.as_ref();
         let reader = ZipReader::open(path)?;
-        reader.entries().iter().map(|info| {
-            // 对应 C 版本 zip.c:111-123 的 zip_get_permissions()
-            // 从 external_attr 提取 Unix 权限
-            let permissions = extract_permissions(info.external_attr, info.version_made_by, info.is_dir);
-
-            Ok(ZipEntry {
-                filename: info.name.clone(),
-                compressed_size: info.compressed_size,
-                uncompressed_size: info.uncompressed_size,
-                crc32: info.crc32,
-                offset: info.local_header_offset,
-                is_directory: info.is_dir,
-                timestamp: dos_to_system_time(info.mtime_dos, info.mdate_dos),
-                permissions,
-                file_type: if info.is_dir {
-                    FileType::Directory
-                } else if info.compression_method == 8 {
-                    FileType::File
-                } else {
-                    FileType::File
-                },
-                is_symlink: false,
-            })
-        }).collect()
+        Ok(reader.entries().iter().map(|info| to_zip_entry_in(path, info)).collect())
     }
 
     /// 获取所有条目
     pub fn entries(&self) -> Result<Vec<ZipEntry>> {
-        Self::list(&self.path)
+        let reader = self.reader()?;
+        Ok(reader.entries().iter().map(|info| to_zip_entry_in(&self.path, info)).collect())
     }
 
-    /// 定位文件
-    pub fn locate_file(&self, name: &str) -> Result<Option<u32>> {
+    /// 打开中央目录；设置了完整性锚点时先确认归档自打开以来没有变化
+    fn reader(&self) -> Result<ZipReader> {
+        let Some(anchor) = self.anchor else {
+            return ZipReader::open(&self.path);
+        };
+        let modified = |reason: String| ZipError::ArchiveModified {
+            archive: self.path.clone(),
+            reason,
+        };
+
+        let size = self.file_size()?;
+        if size != anchor.size {
+            return Err(modified(format!("size changed from {} to {} bytes", anchor.size, size)));
+        }
         let reader = ZipReader::open(&self.path)?;
-        for (i, entry) in reader.entries().iter().enumerate() {
-            if entry.name == name {
-                return Ok(Some(i as u32));
-            }
+        if sha256(&reader.eocd_bytes()?) != anchor.eocd_hash {
+            return Err(modified("end of central directory record changed".to_string()));
         }
-        Ok(None)
+        Ok(reader)
     }
 
-    /// 提取单个文件到指定路径
-    pub fn extract_to(&self, file_index: u32, output: &Path) -> Result<()> {
-        let reader = ZipReader::open(&self.path)?;
-        let entries = reader.entries();
+    /// 归档文件当前的大小
+    fn file_size(&self) -> Result<u64> {
+        Ok(std::fs::metadata(&self.path)
+            .map_err(|e| ZipError::file_read(&self.path, e))?
+            .len())
+    }
 
-        if file_index as usize >= entries.len() {
+    /// 与 `entries()` 相同，并读取每个条目的本地文件头，把其中的文件名填入 `local_name`
+    ///
+    /// 严格模式下任一条目的两处名字不一致时返回 `ZipError::CorruptEntry`。
+    pub fn entries_with_local_names(&self) -> Result<Vec<ZipEntry>> {
+        let reader = self.reader()?;
+        let mut file = File::open(&self.path).map_err(|e| ZipError::file_open(&self.path, e))?;
+        let mut entries = Vec::with_capacity(reader.entries().len());
+        for info in reader.entries() {
+            let (name, _) = read_local_fields(&self.path, &mut file, info)?;
+            self.check_local_name(info, &name)?;
+            let mut entry = to_zip_entry_in(&self.path, info);
+            entry.local_name = Some(decode_local_name(&name, info.flags));
+            entries.push(entry);
+        }
+        Ok(entries)
+    }
+
+    /// 严格模式下确认本地文件头中的文件名与中央目录一致
+    fn check_local_name(&self, entry: &ZipEntryInfo, local_name: &[u8]) -> Result<()> {
+        if !self.strict_names {
+            return Ok(());
+        }
+        let local_name = decode_local_name(local_name, entry.flags);
+        if local_name != entry.name {
             return Err(ZipError::CorruptEntry {
-                name: format!("index {}", file_index),
+                name: entry.name.clone(),
                 archive: self.path.clone(),
-                reason: "file index out of bounds".to_string(),
+                reason: format!(
+                    "local header name {:?} differs from central directory name",
+                    local_name
+                ),
             });
         }
+        Ok(())
+    }
 
-        let entry = &entries[file_index as usize];
-
-        // 打开 ZIP 文件读取数据
-        let file = File::open(&self.path).map_err(|e| ZipError::FileOpen {
-            path: self.path.clone(),
-            source: e,
-        })?;
-        let mut reader = BufReader::new(file);
+    /// 按本地文件头在文件中的物理位置排序的条目
+    ///
+    /// `entries()` 按中央目录顺序返回，两者不同时说明条目被重排过。
+    pub fn entries_physical_order(&self) -> Result<Vec<ZipEntry>> {
+        let mut entries = self.entries()?;
+        entries.sort_by_key(|entry| entry.offset);
+        Ok(entries)
+    }
 
-        // 定位到本地文件头
-        reader
-            .seek(SeekFrom::Start(entry.local_header_offset))
-            .map_err(|e| {
-                ZipError::generic(&format!("Failed to seek to local header: {:?}", e))
-            })?;
+    /// 中央目录中下标位于 `[start, end)` 的条目，顺序与中央目录一致
+    ///
+    /// 供分页浏览大型归档使用。`end` 超出条目数时截断到条目数，
+    /// `start >= end` 时返回空列表。
+    pub fn entries_range(&self, start: usize, end: usize) -> Result<Vec<ZipEntry>> {
+        let reader = self.reader()?;
+        let window = Self::window(reader.entries(), start, end);
+        Ok(window.iter().map(|info| to_zip_entry_in(&self.path, info)).collect())
+    }
 
-        // 读取本地文件头（30 字节）
-        let mut local_header = [0u8; 30];
-        reader.read_exact(&mut local_header).map_err(|e| {
-            ZipError::generic(&format!("Failed to read local header: {:?}", e))
-        })?;
+    /// 只解压中央目录中下标位于 `[start, end)` 的条目到 `exdir`
+    ///
+    /// 范围规则与 `entries_range` 相同，条目按存储的路径写出。
+    /// 写入任何文件之前先拒绝范围内不安全的条目名。
+    pub fn extract_range(&self, start: usize, end: usize, exdir: &Path) -> Result<()> {
+        let reader = self.reader()?;
+        let window = Self::window(reader.entries(), start, end);
 
-        // 验证签名
-        let sig = u32::from_le_bytes(local_header[0..4].try_into().unwrap());
-        if sig != 0x04034b50 {
-            return Err(ZipError::CorruptEntry {
-                name: entry.name.clone(),
-                archive: self.path.clone(),
-                reason: format!("invalid local header signature: 0x{:08x}", sig),
+        if let Some(entry) = window.iter().find(|entry| !is_safe_entry_name(&entry.name)) {
+            return Err(ZipError::InvalidPath {
+                path: entry.name.clone(),
+                reason: format!("unsafe entry name ({:?})", classify_entry_name(&entry.name)),
             });
         }
 
-        // 解析字段
-        let name_len =
-            u16::from_le_bytes(local_header[26..28].try_into().unwrap()) as usize;
-        let extra_len =
-            u16::from_le_bytes(local_header[28..30].try_into().unwrap()) as usize;
-        let compression_method =
-            u16::from_le_bytes(local_header[8..10].try_into().unwrap());
-        let compressed_size = u32::from_le_bytes(local_header[18..22].try_into().unwrap()) as u64;
-        let _uncompressed_size =
-            u32::from_le_bytes(local_header[22..26].try_into().unwrap()) as u64;
-        let crc32_expected = u32::from_le_bytes(local_header[14..18].try_into().unwrap());
-
-        // 跳过文件名和 extra field
-        let skip = name_len + extra_len;
-        if skip > 0 {
-            let mut skip_buf = vec![0u8; skip];
-            reader.read_exact(&mut skip_buf).map_err(|e| {
-                ZipError::generic(&format!("Failed to skip filename/extra: {:?}", e))
-            })?;
+        for entry in window {
+            let output = exdir.join(&entry.name);
+            ensure_no_symlink_escape(exdir, &output)?;
+            if entry.is_dir {
+                std::fs::create_dir_all(&output).map_err(|e| ZipError::generic(&format!(
+                    "Failed to create directory {}: {:?}",
+                    output.display(),
+                    e
+                )))?;
+            } else {
+                self.write_entry(entry, &output, cfg!(unix), true, false, None)?;
+            }
         }
 
-        // 读取压缩数据
-        let mut compressed_data = vec![0u8; compressed_size as usize];
-        reader
-            .read_exact(&mut compressed_data)
-            .map_err(|e| ZipError::generic(&format!("Failed to read compressed data: {:?}", e)))?;
+        Ok(())
+    }
 
-        // 解压数据
-        let decompressed_data = if compression_method == 8 {
-            // DEFLATE 压缩
-            // 注意：ZIP 格式的 DEFLATE 不包含 zlib 头尾
-            // 使用 parse_zlib_header=false 的 inflate 解码
-            inflate::decompress_raw(&compressed_data).map_err(|e| {
-                ZipError::CorruptEntry {
-                    name: entry.name.clone(),
-                    archive: self.path.clone(),
-                    reason: format!("decompression failed: {}", e),
-                }
+    /// 截取 `[start, end)` 窗口，越界部分被截断
+    fn window(entries: &[ZipEntryInfo], start: usize, end: usize) -> &[ZipEntryInfo] {
+        let end = end.min(entries.len());
+        &entries[start.min(end)..end]
+    }
+
+    /// 统计归档的格式开销（各类头部和中央目录）与条目数据各占多少字节
+    ///
+    /// 中央目录之前、不属于任何条目的字节（如自解压前缀或签名块）不计入任何一项。
+    pub fn overhead(&self) -> Result<ArchiveOverhead> {
+        let reader = self.reader()?;
+        let file_size = std::fs::metadata(&self.path)
+            .map_err(|e| ZipError::FileOpen {
+                path: self.path.clone(),
+                source: e,
             })?
-        } else if compression_method == 0 {
-            // 无压缩（STORE）
-            compressed_data
-        } else {
-            return Err(ZipError::CorruptEntry {
-                name: entry.name.clone(),
-                archive: self.path.clone(),
-                reason: format!("unsupported compression method: {}", compression_method),
-            });
-        };
+            .len();
 
-        // 验证 CRC32
-        use crate::miniz::crc32::crc32;
-        let crc32_actual = crc32(0, &decompressed_data);
-        if crc32_actual != crc32_expected {
-            return Err(ZipError::CorruptEntry {
-                name: entry.name.clone(),
-                archive: self.path.clone(),
-                reason: format!(
-                    "CRC32 mismatch: expected 0x{:08x}, got 0x{:08x}",
-                    crc32_expected, crc32_actual
-                ),
-            });
+        let local_headers = reader
+            .local_overheads()?
+            .into_iter()
+            .map(|(header, descriptor)| header + descriptor)
+            .sum();
+        let central_directory: u64 = reader
+            .raw_central_records()?
+            .iter()
+            .map(|record| record.len() as u64)
+            .sum();
+        let central_dir_end = reader.get_append_offset() + central_directory;
+
+        Ok(ArchiveOverhead {
+            local_headers,
+            central_directory,
+            end_of_central_directory: file_size.saturating_sub(central_dir_end),
+            payload: reader.entries().iter().map(|e| e.compressed_size).sum(),
+        })
+    }
+
+    /// 只读取中央目录，汇总归档用到的加密、ZIP64、压缩方法和数据描述符
+    /// 供调用方在处理之前判断本库能否完整处理该归档
+    pub fn compatibility_report(&self) -> Result<CompatibilityReport> {
+        let reader = self.reader()?;
+        let mut report = CompatibilityReport::default();
+        for entry in reader.entries() {
+            let name = || entry.name.clone();
+            let mut method = entry.compression_method;
+            if entry.flags & FLAG_ENCRYPTED != 0 {
+                report.encrypted.push(name());
+                if method == METHOD_AES {
+                    report.aes_encrypted.push(name());
+                    method = extra::aes_field(&entry.extra).map_or(method, |(_, _, actual)| actual);
+                }
+            }
+            if extra::find_field(&entry.extra, extra::ZIP64_TAG).is_some()
+                || entry.compressed_size == SIZE_UNKNOWN as u64
+                || entry.uncompressed_size == SIZE_UNKNOWN as u64
+                || entry.local_header_offset == SIZE_UNKNOWN as u64
+            {
+                report.zip64.push(name());
+            }
+            if !matches!(method, 0 | 8) {
+                report.unsupported_methods.push((name(), method));
+            }
+            if entry.flags & FLAG_DATA_DESCRIPTOR != 0 {
+                report.data_descriptors.push(name());
+            }
         }
+        Ok(report)
+    }
 
-        // 创建父目录
-        if let Some(parent) = output.parent() {
-            std::fs::create_dir_all(parent).map_err(|e| ZipError::generic(&format!(
-                "Failed to create output directory: {:?}",
-                e
-            )))?;
+    /// 按压缩方法汇总条目：方法 -> (条目数, 压缩后总字节数, 未压缩总字节数)
+    ///
+    /// 只读取中央目录，用于回答“归档中有多少是存储、多少是 DEFLATE”之类的问题。
+    /// 目录条目计入其记录的方法（通常为 STORE），AES 加密条目按记录的方法 99 统计。
+    pub fn method_histogram(&self) -> Result<HashMap<u16, (usize, u64, u64)>> {
+        let reader = self.reader()?;
+        let mut histogram: HashMap<u16, (usize, u64, u64)> = HashMap::new();
+        for entry in reader.entries() {
+            let (count, compressed, uncompressed) =
+                histogram.entry(entry.compression_method).or_default();
+            *count += 1;
+            *compressed += entry.compressed_size;
+            *uncompressed += entry.uncompressed_size;
         }
+        Ok(histogram)
+    }
 
-        // 检查是否为符号链接
-        // 对应 C 版本：attr = file_stat.m_external_attr >> 16; S_ISLNK(attr)
-        #[cfg(unix)]
-        {
-            use std::os::unix::fs::symlink;
-            const S_IFMT: u32 = 0o170000; // 文件类型掩码
-            const S_IFLNK: u32 = 0o120000; // 符号链接文件类型
-
-            let attr = (entry.external_attr >> 16) as u32;
-            // 正确的检查：使用 S_IFMT 掩码提取文件类型，然后比较
-            if (attr & S_IFMT) == S_IFLNK {
-                // 符号链接：解压的数据是目标路径
-                let target = String::from_utf8_lossy(&decompressed_data).to_string();
-                symlink(&target, output).map_err(|e| ZipError::generic(&format!(
-                    "Failed to create symlink '{}' -> '{}': {:?}",
-                    output.display(),
-                    target,
-                    e
-                )))?;
-                return Ok(());
+    /// 汇总整个归档的大小、压缩率以及存储/DEFLATE 条目数
+    ///
+    /// 只读取中央目录。存储/DEFLATE 的计数只统计文件条目，AES 加密条目按 extra field
+    /// 中记录的实际方法统计；大小包括所有条目。
+    pub fn statistics(&self) -> Result<ArchiveStats> {
+        let reader = self.reader()?;
+        let mut stats = ArchiveStats::default();
+        for entry in reader.entries() {
+            stats.entries += 1;
+            stats.compressed_size += entry.compressed_size;
+            stats.uncompressed_size += entry.uncompressed_size;
+            if entry.is_dir {
+                stats.directories += 1;
+                continue;
+            }
+            let mut method = entry.compression_method;
+            if method == METHOD_AES {
+                method = extra::aes_field(&entry.extra).map_or(method, |(_, _, actual)| actual);
+            }
+            match method {
+                0 => stats.stored += 1,
+                8 => stats.deflated += 1,
+                _ => stats.other += 1,
             }
         }
+        Ok(stats)
+    }
 
-        // 普通文件：写入输出文件
-        let mut output_file =
-            std::fs::File::create(output).map_err(|e| ZipError::OpenWriteFailed {
-                path: output.to_path_buf(),
+    /// 按本地头偏移分组共享同一份数据的条目名（去重写入的归档中多个中央目录记录指向同一本地头）
+    ///
+    /// 只返回包含两个及以上条目的组；组按首个条目在中央目录中的顺序排列，组内保持中央目录顺序。
+    pub fn shared_data_groups(&self) -> Result<Vec<Vec<String>>> {
+        let reader = self.reader()?;
+        let mut groups: Vec<Vec<String>> = Vec::new();
+        let mut by_offset: HashMap<u64, usize> = HashMap::new();
+        for entry in reader.entries() {
+            let group = *by_offset.entry(entry.local_header_offset).or_insert_with(|| {
+                groups.push(Vec::new());
+                groups.len() - 1
+            });
+            groups[group].push(entry.name.clone());
+        }
+        groups.retain(|names| names.len() > 1);
+        Ok(groups)
+    }
+
+    /// 按 (条目名, CRC32, 解压大小) 清单核对归档，返回所有不一致之处，完全一致时为空
+    ///
+    /// 只比较中央目录中记录的值，不解压数据；需要确认数据本身时另行调用 `read_entry`
+    /// 等会校验 CRC32 的方法。先按清单顺序报告缺失和不一致的条目，再按归档顺序报告多出的条目。
+    pub fn verify_against_manifest(&self, expected: &[(String, u32, u64)]) -> Result<Vec<ManifestMismatch>> {
+        let reader = self.reader()?;
+        let mut actual: HashMap<&str, &ZipEntryInfo> = HashMap::new();
+        for entry in reader.entries() {
+            actual.entry(entry.name.as_str()).or_insert(entry);
+        }
+
+        let mut mismatches = Vec::new();
+        for (name, crc32, size) in expected {
+            let Some(entry) = actual.get(name.as_str()) else {
+                mismatches.push(ManifestMismatch::Missing { name: name.clone() });
+                continue;
+            };
+            if entry.crc32 != *crc32 {
+                mismatches.push(ManifestMismatch::Crc {
+                    name: name.clone(),
+                    expected: *crc32,
+                    actual: entry.crc32,
+                });
+            }
+            if entry.uncompressed_size != *size {
+                mismatches.push(ManifestMismatch::Size {
+                    name: name.clone(),
+                    expected: *size,
+                    actual: entry.uncompressed_size,
+                });
+            }
+        }
+
+        let listed: HashSet<&str> = expected.iter().map(|(name, _, _)| name.as_str()).collect();
+        for entry in reader.entries() {
+            if !listed.contains(entry.name.as_str()) {
+                mismatches.push(ManifestMismatch::Extra { name: entry.name.clone() });
+            }
+        }
+        Ok(mismatches)
+    }
+
+    /// 定位文件
+    pub fn locate_file(&self, name: &str) -> Result<Option<u32>> {
+        let reader = self.reader()?;
+        for (i, entry) in reader.entries().iter().enumerate() {
+            if entry.name == name {
+                return Ok(Some(i as u32));
+            }
+        }
+        Ok(None)
+    }
+
+    /// 按名字读取条目，返回解压并校验 CRC32 后的数据
+    ///
+    /// 输出缓冲区按中央目录记录的 uncompressed_size 一次分配，不做猜测和重新分配。
+    /// 找不到条目时返回 `ZipError::EntryNotFound`。
+    pub fn read_entry(&self, name: &str) -> Result<Vec<u8>> {
+        let reader = self.reader()?;
+        let entry = reader
+            .entries()
+            .iter()
+            .find(|entry| entry.name == name)
+            .ok_or_else(|| ZipError::EntryNotFound {
+                name: name.to_string(),
+                archive: self.path.clone(),
+            })?;
+        self.read_entry_data(entry)
+    }
+
+    /// 与 `read_entry` 相同，但解压到调用方提供的缓冲区，返回条目的字节数
+    ///
+    /// `buf` 先被清空再写入，已有容量被复用：循环读取多个条目时只在遇到更大的条目时扩容。
+    /// 按 `verify_crc` 的设置校验 CRC32。找不到条目时返回 `ZipError::EntryNotFound`。
+    pub fn read_entry_into(&self, name: &str, buf: &mut Vec<u8>) -> Result<usize> {
+        let reader = self.reader()?;
+        let entry = reader
+            .entries()
+            .iter()
+            .find(|entry| entry.name == name)
+            .ok_or_else(|| ZipError::EntryNotFound {
+                name: name.to_string(),
+                archive: self.path.clone(),
+            })?;
+
+        buf.clear();
+        // 与 decode_entry_data 相同，不按超出 DEFLATE 压缩比上限的大小预留
+        let max_size = entry.compressed_size.saturating_mul(MAX_DEFLATE_RATIO);
+        buf.reserve(entry.uncompressed_size.min(max_size) as usize);
+        self.open_entry_reader(entry, self.verify_crc)?
+            .read_to_end(buf)
+            .map_err(|e| inflate_read_error(&self.path, &entry.name, e))
+    }
+
+    /// 不复制地取得存储（方法 0）条目的数据，切片直接指向映射的归档内存
+    ///
+    /// 需要用 `open_mmap` 打开归档；按 `verify_crc` 的设置校验 CRC32。
+    /// 映射只能由 `unsafe` 的 `open_mmap` 创建，切片的有效性由调用方在那里承诺的
+    /// 安全约定保证（切片存活期间文件不被修改或截断）；其他方式打开的归档总是返回 `None`。
+    /// DEFLATE 条目、加密条目以及未映射打开的归档返回 `None`，表示需要用 `read_entry` 解压读取。
+    /// 找不到条目时返回 `ZipError::EntryNotFound`。
+    #[cfg(feature = "mmap")]
+    pub fn entry_slice(&self, name: &str) -> Result<Option<&[u8]>> {
+        let reader = self.reader()?;
+        let entry = reader
+            .entries()
+            .iter()
+            .find(|entry| entry.name == name)
+            .ok_or_else(|| ZipError::EntryNotFound {
+                name: name.to_string(),
+                archive: self.path.clone(),
+            })?;
+        let Some(mmap) = &self.mmap else {
+            return Ok(None);
+        };
+        let (header, data) = self.entry_data_in_slice(mmap, entry)?;
+        if header.compression_method != 0 || header.flags & FLAG_ENCRYPTED != 0 {
+            return Ok(None);
+        }
+        if let Some(crc32_expected) = header.expected_crc().filter(|_| self.verify_crc) {
+            check_crc32(&self.path, &entry.name, crc32_expected, data)?;
+        }
+        Ok(Some(data))
+    }
+
+    /// 只解压条目开头的至多 `max_bytes` 字节，用于在不解压整个条目的情况下识别文件类型
+    ///
+    /// 产生足够的输出后立即停止解压；由于只读取了部分数据，不校验 CRC32。
+    /// 加密条目需要先完整解密，不会更快。找不到条目时返回 `ZipError::EntryNotFound`。
+    pub fn read_entry_prefix(&self, name: &str, max_bytes: usize) -> Result<Vec<u8>> {
+        let reader = self.reader()?;
+        let entry = reader
+            .entries()
+            .iter()
+            .find(|entry| entry.name == name)
+            .ok_or_else(|| ZipError::EntryNotFound {
+                name: name.to_string(),
+                archive: self.path.clone(),
+            })?;
+
+        let mut output = Vec::with_capacity(max_bytes.min(entry.uncompressed_size as usize));
+        self.open_entry_reader(entry, false)?
+            .take(max_bytes as u64)
+            .read_to_end(&mut output)
+            .map_err(|e| inflate_read_error(&self.path, &entry.name, e))?;
+        Ok(output)
+    }
+
+    /// 以流的方式读取条目，数据在 `read` 时才从归档读出并解压
+    ///
+    /// 只保留 32 KB 解压窗口和少量缓冲区，适合读取很大的条目；跨越 DEFLATE 块边界时
+    /// 解压状态在多次 `read` 之间保持。读到末尾时按 `verify_crc` 的设置校验 CRC32，
+    /// 不一致时返回 `InvalidData` 错误，内部错误为 `ZipError::CorruptEntry`。
+    /// 加密条目在打开时整体解密。找不到条目时返回 `ZipError::EntryNotFound`。
+    pub fn entry_reader(&self, name: &str) -> Result<EntryReader<'_>> {
+        let reader = self.reader()?;
+        let entry = reader
+            .entries()
+            .iter()
+            .find(|entry| entry.name == name)
+            .ok_or_else(|| ZipError::EntryNotFound {
+                name: name.to_string(),
+                archive: self.path.clone(),
+            })?;
+        self.open_entry_reader(entry, self.verify_crc)
+    }
+
+    /// 返回只读取条目原始压缩数据的 reader，长度限制为本地文件头中的压缩大小
+    ///
+    /// 数据不解压也不校验 CRC32，加密条目包含加密头；可用于自定义解压或原样复制。
+    /// 找不到条目时返回 `ZipError::EntryNotFound`。
+    pub fn entry_body_reader(&self, name: &str) -> Result<impl Read + '_> {
+        let reader = self.reader()?;
+        let entry = reader
+            .entries()
+            .iter()
+            .find(|entry| entry.name == name)
+            .ok_or_else(|| ZipError::EntryNotFound {
+                name: name.to_string(),
+                archive: self.path.clone(),
+            })?;
+        Ok(self.open_entry_body(entry)?.1)
+    }
+
+    /// 定位到条目的压缩数据并创建 `EntryReader`；`verify_crc` 为 false 时不校验 CRC32
+    fn open_entry_reader(&self, entry: &ZipEntryInfo, verify_crc: bool) -> Result<EntryReader<'_>> {
+        let (header, data) = self.open_entry_body(entry)?;
+        self.entry_reader_from(entry, header, data, verify_crc)
+    }
+
+    /// 解析条目的本地文件头，返回它和限制在压缩大小之内的原始数据 reader
+    fn open_entry_body(&self, entry: &ZipEntryInfo) -> Result<(LocalHeader, Box<dyn Read + Send + '_>)> {
+        #[cfg(feature = "mmap")]
+        if let Some(mmap) = &self.mmap {
+            let (header, data) = self.entry_data_in_slice(mmap, entry)?;
+            return Ok((header, Box::new(data)));
+        }
+
+        let file = File::open(&self.path).map_err(|e| ZipError::FileOpen {
+            path: self.path.clone(),
+            source: e,
+        })?;
+        let mut reader = BufReader::new(file);
+        reader
+            .seek(SeekFrom::Start(entry.local_header_offset))
+            .map_err(|e| {
+                ZipError::generic(&format!("Failed to seek to local header: {:?}", e))
+            })?;
+
+        let mut local_header = [0u8; 30];
+        reader.read_exact(&mut local_header).map_err(|e| {
+            ZipError::generic(&format!("Failed to read local header: {:?}", e))
+        })?;
+        let header = LocalHeader::parse(&self.path, entry, &local_header)?;
+        let mut local_name = vec![0u8; header.name_len];
+        reader.read_exact(&mut local_name).map_err(|e| {
+            ZipError::generic(&format!("Failed to skip filename/extra: {:?}", e))
+        })?;
+        self.check_local_name(entry, &local_name)?;
+        reader
+            .seek(SeekFrom::Current(header.extra_len as i64))
+            .map_err(|e| {
+                ZipError::generic(&format!("Failed to skip filename/extra: {:?}", e))
+            })?;
+
+        let data = reader.take(header.compressed_size);
+        Ok((header, Box::new(data)))
+    }
+
+    /// 在条目（可能加密的）压缩数据流之上创建 `EntryReader`
+    fn entry_reader_from<'a>(
+        &self,
+        entry: &ZipEntryInfo,
+        mut header: LocalHeader,
+        mut data: Box<dyn Read + Send + 'a>,
+        verify_crc: bool,
+    ) -> Result<EntryReader<'a>> {
+        if header.flags & FLAG_ENCRYPTED != 0 {
+            let mut encrypted = Vec::new();
+            data.read_to_end(&mut encrypted).map_err(|e| {
+                ZipError::generic(&format!("Failed to read compressed data: {:?}", e))
+            })?;
+            let plain = decrypt_entry_data(
+                &self.path,
+                entry,
+                &mut header,
+                self.password.as_deref(),
+                Cow::Owned(encrypted),
+            )?;
+            data = Box::new(std::io::Cursor::new(plain.into_owned()));
+        }
+
+        let inner: Box<dyn Read + Send + 'a> = match header.compression_method {
+            0 => data,
+            8 => Box::new(InflateReader::new(data)),
+            method => return Err(ZipError::UnsupportedCompression { method }),
+        };
+        Ok(EntryReader::new(
+            &self.path,
+            &entry.name,
+            inner,
+            header.expected_crc().filter(|_| verify_crc),
+        ))
+    }
+
+    /// 按中央目录中的下标读取条目，返回解压并校验 CRC32 后的数据
+    /// 下标与 `entries()` 的顺序一致；目录条目返回空数据，下标越界时返回 `ZipError::EntryNotFound`
+    pub fn read_entry_by_index(&self, index: usize) -> Result<Vec<u8>> {
+        let reader = self.reader()?;
+        let entry = reader.entries().get(index).ok_or_else(|| ZipError::EntryNotFound {
+            name: format!("index {}", index),
+            archive: self.path.clone(),
+        })?;
+        self.read_entry_data(entry)
+    }
+
+    /// 提取单个文件到指定路径并落盘；符号链接条目只在 Unix 上还原为符号链接
+    pub fn extract_to(&self, file_index: u32, output: &Path) -> Result<()> {
+        self.extract_entry(file_index, output, cfg!(unix), true, false, None)
+    }
+
+    /// 提取单个条目，由调用方决定符号链接条目是否还原为符号链接、写入后是否落盘
+    pub(crate) fn extract_entry(
+        &self,
+        file_index: u32,
+        output: &Path,
+        create_symlinks: bool,
+        durable: bool,
+        preallocate: bool,
+        transform: Option<&mut EntryTransform>,
+    ) -> Result<()> {
+        let reader = self.reader()?;
+        let entries = reader.entries();
+
+        if file_index as usize >= entries.len() {
+            return Err(ZipError::CorruptEntry {
+                name: format!("index {}", file_index),
+                archive: self.path.clone(),
+                reason: "file index out of bounds".to_string(),
+            });
+        }
+
+        self.write_entry(&entries[file_index as usize], output, create_symlinks, durable, preallocate, transform)
+    }
+
+    /// 解压单个条目并写到 `output`
+    /// `create_symlinks` 为 true 时符号链接条目还原为符号链接，否则写成内容为链接目标的普通文件；
+    /// `durable` 为 true 时写完后同步文件及其所在目录；
+    /// `preallocate` 为 true 时，不小于 `PREALLOCATE_MIN_SIZE` 的条目先按中央目录中的大小扩展文件再写入；
+    /// 设置了取消令牌时，读取和解压的每一块之前检查，取消时返回 `ZipError::Cancelled`，不创建输出文件；
+    /// 写成普通文件时，`transform` 以条目名和校验过 CRC32 的数据调用，写出它返回的数据
+    fn write_entry(
+        &self,
+        entry: &ZipEntryInfo,
+        output: &Path,
+        create_symlinks: bool,
+        durable: bool,
+        preallocate: bool,
+        transform: Option<&mut EntryTransform>,
+    ) -> Result<()> {
+        let decompressed_data = match &self.cancel {
+            Some(cancel) => self.read_entry_cancellable(entry, cancel)?,
+            None => self.read_entry_data(entry)?,
+        };
+
+        // 创建父目录：归档可能没有目录条目（如 `include_directories(false)`），总是从条目路径推断
+        if let Some(parent) = output.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| ZipError::generic(&format!(
+                "Failed to create output directory: {:?}",
+                e
+            )))?;
+        }
+
+        // 检查是否为符号链接
+        // 对应 C 版本：attr = file_stat.m_external_attr >> 16; S_ISLNK(attr)
+        if create_symlinks && is_symlink_attr(entry.external_attr) {
+            // 符号链接：解压的数据是目标路径
+            let target = String::from_utf8_lossy(&decompressed_data).to_string();
+            current_platform()
+                .create_symlink(Path::new(&target), output)
+                .map_err(|e| ZipError::generic(&format!(
+                    "Failed to create symlink '{}' -> '{}': {:?}",
+                    output.display(),
+                    target,
+                    e
+                )))?;
+            return Ok(());
+        }
+
+        // 普通文件：写入输出文件
+        let decompressed_data = match transform {
+            Some(transform) => transform(&entry.name, &decompressed_data),
+            None => decompressed_data,
+        };
+        let mut output_file =
+            std::fs::File::create(output).map_err(|e| ZipError::OpenWriteFailed {
+                path: output.to_path_buf(),
                 source: e,
             })?;
+        let set_len_failed = |e: std::io::Error| {
+            ZipError::generic(&format!("Failed to set length of {}: {:?}", output.display(), e))
+        };
+        let preallocated = preallocate && entry.uncompressed_size >= PREALLOCATE_MIN_SIZE;
+        if preallocated {
+            output_file.set_len(entry.uncompressed_size).map_err(set_len_failed)?;
+        }
         output_file.write_all(&decompressed_data).map_err(|e| {
             ZipError::generic(&format!("Failed to write output file: {:?}", e))
         })?;
-        output_file.sync_all().map_err(|e| {
-            ZipError::generic(&format!("Failed to sync output file: {:?}", e))
+        // 关闭 CRC 校验时实际数据可能与中央目录中的大小不同，截断到实际写入的长度
+        if preallocated && decompressed_data.len() as u64 != entry.uncompressed_size {
+            output_file.set_len(decompressed_data.len() as u64).map_err(set_len_failed)?;
+        }
+        if durable {
+            sync_written(&output_file, output)?;
+        }
+
+        Ok(())
+    }
+
+    /// 将所有文件条目解压到内存，以文件名为键
+    ///
+    /// 每个条目都会校验 CRC32，目录条目被跳过。
+    /// 整个归档内容都会驻留内存，只适用于小型归档（如嵌入的配置包），
+    /// 不要对大型归档使用。
+    pub fn extract_to_map(&self) -> Result<HashMap<String, Vec<u8>>> {
+        let reader = self.reader()?;
+        let mut map = HashMap::new();
+        for entry in reader.entries().iter().filter(|e| !e.is_dir) {
+            let data = self.read_entry_data(entry)?;
+            map.insert(entry.name.clone(), data);
+        }
+        Ok(map)
+    }
+
+    /// 按中央目录顺序逐个解压条目，产出条目元数据和解压后的数据
+    ///
+    /// 不经过磁盘，便于直接转写成 tar 等其他格式；目录条目产出空数据。
+    /// 每个条目都会校验 CRC32（除非关闭了 `verify_crc`），出错的条目产出 `Err`，
+    /// 调用方可以决定是否继续。打开归档失败时只产出一个 `Err`。
+    pub fn entries_as_stream(&self) -> impl Iterator<Item = Result<(ZipEntry, Vec<u8>)>> + '_ {
+        let (infos, open_error) = match self.reader() {
+            Ok(reader) => (reader.entries().to_vec(), None),
+            Err(e) => (Vec::new(), Some(e)),
+        };
+        open_error.map(Err).into_iter().chain(infos.into_iter().map(move |info| {
+            let data = if info.is_dir { Vec::new() } else { self.read_entry_data(&info)? };
+            Ok((to_zip_entry_in(&self.path, &info), data))
+        }))
+    }
+
+    /// 并行解压所有文件条目，返回 `(文件名, 数据)`，顺序与中央目录一致
+    ///
+    /// 每个条目都会校验 CRC32，目录条目被跳过。
+    /// 每个工作线程各自打开归档文件读取，互不共享读句柄。
+    #[cfg(feature = "rayon")]
+    pub fn par_read_all(&self) -> Result<Vec<(String, Vec<u8>)>> {
+        use rayon::prelude::*;
+
+        let reader = self.reader()?;
+        reader
+            .entries()
+            .par_iter()
+            .filter(|e| !e.is_dir)
+            .map(|entry| Ok((entry.name.clone(), self.read_entry_data(entry)?)))
+            .collect()
+    }
+
+    /// 列出归档的顶层条目（去重，保持中央目录中的首次出现顺序）
+    ///
+    /// 根目录下的文件返回文件名，嵌套条目只返回第一级目录名（带 `/` 后缀），
+    /// 例如 `a/b/c`、`a/d`、`e.txt` 的顶层为 `["a/", "e.txt"]`。
+    pub fn top_level_entries(&self) -> Result<Vec<String>> {
+        let reader = self.reader()?;
+        let mut seen = std::collections::HashSet::new();
+        let mut top_level = Vec::new();
+
+        for entry in reader.entries() {
+            let first = match entry.name.find('/') {
+                Some(pos) => &entry.name[..=pos],
+                None => entry.name.as_str(),
+            };
+            if !first.is_empty() && seen.insert(first.to_string()) {
+                top_level.push(first.to_string());
+            }
+        }
+
+        Ok(top_level)
+    }
+
+    /// 列出解压时会创建的全部目录（带 `/` 后缀），包括只由文件路径隐含、没有目录条目的中间目录
+    ///
+    /// 结果去重并按字典序排列，父目录总在其子目录之前，例如只含 `a/b/c.txt` 的归档返回
+    /// `["a/", "a/b/"]`。
+    pub fn directories_to_create(&self) -> Result<Vec<String>> {
+        let reader = self.reader()?;
+        let mut dirs = std::collections::BTreeSet::new();
+
+        for entry in reader.entries() {
+            let name = entry.name.as_str();
+            for (pos, _) in name.match_indices('/') {
+                if pos > 0 {
+                    dirs.insert(name[..=pos].to_string());
+                }
+            }
+        }
+
+        Ok(dirs.into_iter().collect())
+    }
+
+    /// 将归档文件原样写入 `w`，返回写入的字节数
+    pub fn copy_to(&self, w: &mut impl Write) -> Result<u64> {
+        let mut file = File::open(&self.path).map_err(|e| ZipError::FileOpen {
+            path: self.path.clone(),
+            source: e,
+        })?;
+        Ok(std::io::copy(&mut file, w)?)
+    }
+
+    /// 将归档写入 `w`，但重写中央目录只保留 `keep` 返回 true 的条目
+    ///
+    /// 中央目录之前的数据原样流式写出（被排除条目的数据仍然存在，只是不再被引用），
+    /// 随后写入过滤后的中央目录和新的 EOCD，归档注释保留。返回写入的字节数。
+    /// 不写 ZIP64 记录：保留的条目数或中央目录的大小、偏移超出 EOCD 的范围时，
+    /// 在写出任何数据之前返回错误。
+    pub fn copy_to_filtered(
+        &self,
+        w: &mut impl Write,
+        keep: impl Fn(&str) -> bool,
+    ) -> Result<u64> {
+        let reader = self.reader()?;
+        let records = reader.raw_central_records()?;
+        let central_dir_offset = reader.get_append_offset();
+
+        let mut kept = Vec::new();
+        for (entry, record) in reader.entries().iter().zip(&records) {
+            if keep(&entry.name) {
+                // 偏移相对于归档起点时改写为文件内偏移，与新写出的 EOCD 一致
+                let mut record = Cow::Borrowed(record.as_slice());
+                if reader.offset_base() > 0 && record[42..46] != [0xFF; 4] {
+                    record.to_mut()[42..46].copy_from_slice(&(entry.local_header_offset as u32).to_le_bytes());
+                }
+                kept.push(record);
+            }
+        }
+        let central_dir_size: u64 = kept.iter().map(|record| record.len() as u64).sum();
+        let eocd = self.filtered_eocd(kept.len(), central_dir_size, central_dir_offset, &reader.eocd_bytes()?)?;
+
+        let file = File::open(&self.path).map_err(|e| ZipError::FileOpen {
+            path: self.path.clone(),
+            source: e,
+        })?;
+        let mut written = std::io::copy(&mut file.take(central_dir_offset), w)?;
+        for record in &kept {
+            w.write_all(record)?;
+        }
+        w.write_all(&eocd)?;
+
+        written += central_dir_size + eocd.len() as u64;
+        Ok(written)
+    }
+
+    /// 构造 `copy_to_filtered` 写出的 EOCD 记录，沿用 `old_eocd` 中的归档注释
+    ///
+    /// 条目数达到 0xFFFF、中央目录大小或偏移达到 0xFFFFFFFF 时需要 ZIP64 记录，返回错误。
+    fn filtered_eocd(
+        &self,
+        num_entries: usize,
+        central_dir_size: u64,
+        central_dir_offset: u64,
+        old_eocd: &[u8],
+    ) -> Result<Vec<u8>> {
+        let too_large = |what: &str, value: u64| {
+            ZipError::generic(format!(
+                "Cannot filter {}: {} {} requires ZIP64, which is not supported",
+                self.path.display(),
+                what,
+                value
+            ))
+        };
+        let count = u16::try_from(num_entries)
+            .ok()
+            .filter(|&count| count < u16::MAX)
+            .ok_or_else(|| too_large("entry count", num_entries as u64))?;
+        let size = u32::try_from(central_dir_size)
+            .ok()
+            .filter(|&size| size < u32::MAX)
+            .ok_or_else(|| too_large("central directory size", central_dir_size))?;
+        let offset = u32::try_from(central_dir_offset)
+            .ok()
+            .filter(|&offset| offset < u32::MAX)
+            .ok_or_else(|| too_large("central directory offset", central_dir_offset))?;
+        let comment = old_eocd.get(EOCD_LEN as usize..).unwrap_or_default();
+
+        let mut eocd = vec![0u8; EOCD_LEN as usize];
+        eocd[0..4].copy_from_slice(&0x06054b50u32.to_le_bytes());
+        eocd[8..10].copy_from_slice(&count.to_le_bytes());
+        eocd[10..12].copy_from_slice(&count.to_le_bytes());
+        eocd[12..16].copy_from_slice(&size.to_le_bytes());
+        eocd[16..20].copy_from_slice(&offset.to_le_bytes());
+        eocd[20..22].copy_from_slice(&(comment.len() as u16).to_le_bytes());
+        eocd.extend_from_slice(comment);
+        Ok(eocd)
+    }
+
+    /// 原地重写归档，去掉 EOCD 及注释之后的多余字节，以及条目之间的空隙
+    ///
+    /// 各条目的本地头、压缩数据和数据描述符按在文件中的先后顺序原样紧密排列，
+    /// 中央目录记录只更新本地头偏移，归档注释保留。先写入同目录下的临时文件，成功后替换原文件。
+    /// 第一个条目之前的数据：文件以本地头开头时是未引用的条目数据，一并去掉；
+    /// 否则是自解压程序等前置数据，原样保留，偏移仍按原归档的基准记录。
+    /// 使用 ZIP64 偏移的归档返回错误。
+    pub fn compact(path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let reader = ZipReader::open(path)?;
+        let records = reader.raw_central_records()?;
+        let overheads = reader.local_overheads()?;
+        let mut eocd = reader.eocd_bytes()?;
+        if eocd.len() < 22 || records.iter().any(|record| record[42..46] == [0xFF; 4]) {
+            return Err(ZipError::generic(&format!(
+                "Cannot compact {}: ZIP64 archives are not supported",
+                path.display()
+            )));
+        }
+
+        replace_file(path, None, |temp| {
+            let mut source = BufReader::new(File::open(path).map_err(|e| ZipError::FileOpen {
+                path: path.to_path_buf(),
+                source: e,
+            })?);
+            let out = File::create(temp).map_err(|e| ZipError::OpenWriteFailed {
+                path: temp.to_path_buf(),
+                source: e,
+            })?;
+            let mut out = std::io::BufWriter::new(out);
+
+            // 前置数据（自解压程序等）原样复制；偏移未调整的自解压归档（offset_base > 0）
+            // 中的偏移相对于前置数据之后的位置，重写时沿用同一基准
+            let first_local = reader
+                .entries()
+                .iter()
+                .map(|entry| entry.local_header_offset)
+                .min()
+                .unwrap_or_else(|| reader.get_append_offset());
+            let mut signature = [0u8; 4];
+            let starts_with_entry = first_local < 4
+                || (source.read_exact(&mut signature).is_ok() && u32::from_le_bytes(signature) == 0x04034b50);
+            let (leading, base) = if starts_with_entry {
+                (0, 0)
+            } else {
+                (first_local, reader.offset_base())
+            };
+            source.seek(SeekFrom::Start(0))?;
+            let copied = std::io::copy(&mut (&mut source).take(leading), &mut out)?;
+            if copied != leading {
+                return Err(ZipError::generic(format!(
+                    "Cannot compact {}: unexpected end of data before the first entry",
+                    path.display()
+                )));
+            }
+
+            // 按物理顺序复制条目；多个中央目录记录指向同一本地头时只复制一次
+            let mut order: Vec<usize> = (0..records.len()).collect();
+            order.sort_by_key(|&i| reader.entries()[i].local_header_offset);
+            let mut new_offsets: HashMap<u64, u64> = HashMap::new();
+            let mut written = leading;
+            for i in order {
+                let entry = &reader.entries()[i];
+                if new_offsets.contains_key(&entry.local_header_offset) {
+                    continue;
+                }
+                let (header, descriptor) = overheads[i];
+                let len = header + entry.compressed_size + descriptor;
+                source.seek(SeekFrom::Start(entry.local_header_offset))?;
+                let copied = std::io::copy(&mut (&mut source).take(len), &mut out)?;
+                if copied != len {
+                    return Err(ZipError::CorruptEntry {
+                        name: entry.name.clone(),
+                        archive: path.to_path_buf(),
+                        reason: "unexpected end of data".to_string(),
+                    });
+                }
+                new_offsets.insert(entry.local_header_offset, written);
+                written += len;
+            }
+
+            let central_dir_offset = written;
+            for (entry, record) in reader.entries().iter().zip(&records) {
+                let mut record = record.clone();
+                let offset = (new_offsets[&entry.local_header_offset] - base) as u32;
+                record[42..46].copy_from_slice(&offset.to_le_bytes());
+                out.write_all(&record)?;
+                written += record.len() as u64;
+            }
+
+            eocd[12..16].copy_from_slice(&((written - central_dir_offset) as u32).to_le_bytes());
+            eocd[16..20].copy_from_slice(&((central_dir_offset - base) as u32).to_le_bytes());
+            out.write_all(&eocd)?;
+            out.into_inner()
+                .map_err(|e| ZipError::generic(&format!("Failed to flush {}: {:?}", temp.display(), e)))?
+                .sync_all()?;
+            Ok(())
+        })
+    }
+
+    /// 把归档拆分为多个不超过 `max_bytes` 字节的完整归档 `{out_prefix}.001.zip`、`{out_prefix}.002.zip`……
+    ///
+    /// 条目按中央目录顺序原样复制（不解压、不重新压缩），每个条目只出现在一个分卷中，
+    /// 分卷放不下下一个条目时开始新的分卷。单个条目加上 EOCD 就超过 `max_bytes` 时返回错误。
+    /// 没有条目的归档生成一个空分卷。返回按顺序排列的分卷路径。使用 ZIP64 偏移的归档返回错误。
+    /// 写出过程中出错时不留下任何分卷。
+    pub fn split(&self, out_prefix: &str, max_bytes: u64) -> Result<Vec<PathBuf>> {
+        let reader = self.reader()?;
+        let records = reader.raw_central_records()?;
+        let overheads = reader.local_overheads()?;
+        if records.iter().any(|record| record[42..46] == [0xFF; 4]) {
+            return Err(ZipError::generic(&format!(
+                "Cannot split {}: ZIP64 archives are not supported",
+                self.path.display()
+            )));
+        }
+
+        // 每个条目在分卷中占用的字节数：本地头、数据、数据描述符和中央目录记录
+        let mut parts: Vec<Vec<usize>> = vec![Vec::new()];
+        let mut part_len = EOCD_LEN;
+        for (i, entry) in reader.entries().iter().enumerate() {
+            let (header, descriptor) = overheads[i];
+            let cost = header + entry.compressed_size + descriptor + records[i].len() as u64;
+            if EOCD_LEN + cost > max_bytes {
+                return Err(ZipError::generic(&format!(
+                    "Cannot split {}: entry {} needs {} bytes, more than the {} byte limit",
+                    self.path.display(),
+                    entry.name,
+                    EOCD_LEN + cost,
+                    max_bytes
+                )));
+            }
+            if part_len + cost > max_bytes {
+                parts.push(Vec::new());
+                part_len = EOCD_LEN;
+            }
+            parts.last_mut().unwrap().push(i);
+            part_len += cost;
+        }
+
+        let mut source = BufReader::new(File::open(&self.path).map_err(|e| ZipError::FileOpen {
+            path: self.path.clone(),
+            source: e,
+        })?);
+        let mut write_part = |indices: &[usize], path: &Path| -> Result<()> {
+            let out = File::create(path).map_err(|e| ZipError::OpenWriteFailed {
+                path: path.to_path_buf(),
+                source: e,
+            })?;
+            let mut out = std::io::BufWriter::new(out);
+
+            let mut offsets = Vec::with_capacity(indices.len());
+            let mut written = 0u64;
+            for &i in indices {
+                let entry = &reader.entries()[i];
+                let (header, descriptor) = overheads[i];
+                let len = header + entry.compressed_size + descriptor;
+                source.seek(SeekFrom::Start(entry.local_header_offset))?;
+                let copied = std::io::copy(&mut (&mut source).take(len), &mut out)?;
+                if copied != len {
+                    return Err(ZipError::CorruptEntry {
+                        name: entry.name.clone(),
+                        archive: self.path.clone(),
+                        reason: "unexpected end of data".to_string(),
+                    });
+                }
+                offsets.push(written);
+                written += len;
+            }
+
+            let central_dir_offset = written;
+            for (&i, offset) in indices.iter().zip(offsets) {
+                let mut record = records[i].clone();
+                record[42..46].copy_from_slice(&(offset as u32).to_le_bytes());
+                out.write_all(&record)?;
+                written += record.len() as u64;
+            }
+
+            let mut eocd = [0u8; EOCD_LEN as usize];
+            eocd[0..4].copy_from_slice(&0x06054b50u32.to_le_bytes());
+            eocd[8..10].copy_from_slice(&(indices.len() as u16).to_le_bytes());
+            eocd[10..12].copy_from_slice(&(indices.len() as u16).to_le_bytes());
+            eocd[12..16].copy_from_slice(&((written - central_dir_offset) as u32).to_le_bytes());
+            eocd[16..20].copy_from_slice(&(central_dir_offset as u32).to_le_bytes());
+            out.write_all(&eocd)?;
+            out.flush()?;
+            Ok(())
+        };
+
+        // 每个分卷先写临时文件再替换；中途失败时删除已完成的分卷，不留下残缺的分卷序列
+        let mut paths = Vec::with_capacity(parts.len());
+        for (n, indices) in parts.iter().enumerate() {
+            let part_path = PathBuf::from(format!("{}.{:03}.zip", out_prefix, n + 1));
+            if let Err(e) = replace_file(&part_path, None, |temp| write_part(indices, temp)) {
+                for path in &paths {
+                    let _ = std::fs::remove_file(path);
+                }
+                return Err(e);
+            }
+            paths.push(part_path);
+        }
+        Ok(paths)
+    }
+
+    /// 归档注释的原始字节，没有注释时为空
+    pub fn comment(&self) -> Result<Vec<u8>> {
+        let eocd = self.reader()?.eocd_bytes()?;
+        Ok(eocd.get(EOCD_LEN as usize..).unwrap_or_default().to_vec())
+    }
+
+    /// 原地替换归档注释，不改动条目数据和中央目录
+    ///
+    /// 只重写 EOCD 中的注释长度和注释字节，文件按新注释的长度截断或延长。
+    /// 注释最长 65535 字节；没有 EOCD 记录的归档返回错误。
+    pub fn set_comment_in_place(path: impl AsRef<Path>, comment: impl AsRef<[u8]>) -> Result<()> {
+        let path = path.as_ref();
+        let comment = comment.as_ref();
+        let comment_len = u16::try_from(comment.len()).map_err(|_| {
+            ZipError::generic(&format!(
+                "Archive comment is {} bytes, more than the 65535 byte limit",
+                comment.len()
+            ))
         })?;
 
+        let reader = ZipReader::open(path)?;
+        if reader.eocd_bytes()?.len() < EOCD_LEN as usize {
+            return Err(ZipError::CorruptArchive {
+                archive: path.to_path_buf(),
+                reason: "no end of central directory record".to_string(),
+            });
+        }
+        let eocd_offset = reader.eocd_offset();
+
+        let mut file = std::fs::OpenOptions::new()
+            .write(true)
+            .open(path)
+            .map_err(|e| ZipError::OpenWriteFailed {
+                path: path.to_path_buf(),
+                source: e,
+            })?;
+        file.seek(SeekFrom::Start(eocd_offset + 20))?;
+        file.write_all(&comment_len.to_le_bytes())?;
+        file.write_all(comment)?;
+        file.set_len(eocd_offset + EOCD_LEN + comment.len() as u64)?;
         Ok(())
     }
+
+    /// 解压每个条目并按 `level` 重新压缩，写出新归档 `out`
+    ///
+    /// 名称、时间、权限和 extra field 保持不变；压缩后没有变小的条目改为 STORE。
+    /// 目录和加密条目原样复制，带数据描述符的 ZipCrypto 条目保留 bit 3 和描述符。
+    pub fn recompress_to(&self, out: &Path, level: CompressionLevel) -> Result<()> {
+        let reader = self.reader()?;
+        let mut writer = ZipWriter::new(out, level)?;
+        for entry in reader.entries() {
+            if entry.is_dir || entry.flags & FLAG_ENCRYPTED != 0 {
+                writer.copy_raw_entry(&reader, entry, &entry.name)?;
+            } else {
+                let data = self.read_entry_data(entry)?;
+                writer.add_recompressed(entry, &data)?;
+            }
+        }
+        writer.finalize()
+    }
+
+    /// 计算归档内容指纹
+    ///
+    /// 对按名称排序的 (名称, 解压后大小, CRC32) 序列做 SHA-256，
+    /// 与压缩级别、时间戳无关：内容相同的两个归档得到相同指纹。
+    pub fn content_hash(&self) -> Result<[u8; 32]> {
+        let reader = self.reader()?;
+        let mut entries: Vec<&ZipEntryInfo> = reader.entries().iter().collect();
+        entries.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let mut buf = Vec::new();
+        for entry in entries {
+            buf.extend_from_slice(&(entry.name.len() as u64).to_le_bytes());
+            buf.extend_from_slice(entry.name.as_bytes());
+            buf.extend_from_slice(&entry.uncompressed_size.to_le_bytes());
+            buf.extend_from_slice(&entry.crc32.to_le_bytes());
+        }
+        Ok(sha256(&buf))
+    }
+
+    /// 读取并解压单个条目的数据，校验 CRC32
+    /// 对应 C 版本 mz_zip_reader_extract_to_mem() 的逻辑
+    fn read_entry_data(&self, entry: &ZipEntryInfo) -> Result<Vec<u8>> {
+        #[cfg(feature = "mmap")]
+        if let Some(mmap) = &self.mmap {
+            return self.read_entry_from_slice(mmap, entry);
+        }
+
+        // 打开 ZIP 文件读取数据
+        let file = File::open(&self.path).map_err(|e| ZipError::FileOpen {
+            path: self.path.clone(),
+            source: e,
+        })?;
+        let mut reader = BufReader::new(file);
+
+        // 定位到本地文件头
+        reader
+            .seek(SeekFrom::Start(entry.local_header_offset))
+            .map_err(|e| {
+                ZipError::generic(&format!("Failed to seek to local header: {:?}", e))
+            })?;
+
+        self.read_entry_from(&mut reader, entry)
+    }
+
+    /// 与 `read_entry_data` 相同，但按块边读边解压，每块之前检查取消
+    fn read_entry_cancellable(&self, entry: &ZipEntryInfo, cancel: &CancelToken) -> Result<Vec<u8>> {
+        cancel.check()?;
+        let mut reader = self.open_entry_reader(entry, self.verify_crc)?;
+        // 与 decode_entry_data 相同，不按超出 DEFLATE 压缩比上限的大小预留
+        let max_size = entry.compressed_size.saturating_mul(MAX_DEFLATE_RATIO);
+        let mut data = Vec::with_capacity(entry.uncompressed_size.min(max_size) as usize);
+        let mut chunk = vec![0u8; CANCEL_CHECK_CHUNK];
+        loop {
+            cancel.check()?;
+            let n = reader
+                .read(&mut chunk)
+                .map_err(|e| inflate_read_error(&self.path, &entry.name, e))?;
+            if n == 0 {
+                return Ok(data);
+            }
+            data.extend_from_slice(&chunk[..n]);
+        }
+    }
+
+    /// 从已定位到本地文件头的流中读取并解压条目
+    fn read_entry_from<R: Read>(&self, reader: &mut R, entry: &ZipEntryInfo) -> Result<Vec<u8>> {
+        // 读取本地文件头（30 字节）
+        let mut local_header = [0u8; 30];
+        reader.read_exact(&mut local_header).map_err(|e| {
+            ZipError::generic(&format!("Failed to read local header: {:?}", e))
+        })?;
+
+        let mut header = LocalHeader::parse(&self.path, entry, &local_header)?;
+
+        // 跳过文件名和 extra field
+        let skip = header.name_len + header.extra_len;
+        let mut skip_buf = vec![0u8; skip];
+        if skip > 0 {
+            reader.read_exact(&mut skip_buf).map_err(|e| {
+                ZipError::generic(&format!("Failed to skip filename/extra: {:?}", e))
+            })?;
+        }
+        self.check_local_name(entry, &skip_buf[..header.name_len])?;
+
+        // 读取压缩数据
+        // 循环读取直到满足 compressed_size，容忍流式/不稳定来源的短读
+        let mut compressed_data = vec![0u8; header.compressed_size as usize];
+        let n = read_full(reader, &mut compressed_data)
+            .map_err(|e| ZipError::generic(&format!("Failed to read compressed data: {:?}", e)))?;
+        if n < compressed_data.len() {
+            return Err(ZipError::CorruptEntry {
+                name: entry.name.clone(),
+                archive: self.path.clone(),
+                reason: format!(
+                    "unexpected end of data: expected {} bytes, got {}",
+                    compressed_data.len(),
+                    n
+                ),
+            });
+        }
+
+        let compressed_data = decrypt_entry_data(
+            &self.path,
+            entry,
+            &mut header,
+            self.password.as_deref(),
+            Cow::Owned(compressed_data),
+        )?;
+        decode_entry_data(
+            &self.path,
+            &entry.name,
+            header.compression_method,
+            entry.uncompressed_size,
+            header.expected_crc().filter(|_| self.verify_crc),
+            compressed_data,
+        )
+    }
+
+    /// 从映射的归档内存中读取并解压条目，压缩数据不经过中间缓冲区
+    #[cfg(feature = "mmap")]
+    fn read_entry_from_slice(&self, bytes: &[u8], entry: &ZipEntryInfo) -> Result<Vec<u8>> {
+        let (mut header, compressed_data) = self.entry_data_in_slice(bytes, entry)?;
+        let compressed_data = decrypt_entry_data(
+            &self.path,
+            entry,
+            &mut header,
+            self.password.as_deref(),
+            Cow::Borrowed(compressed_data),
+        )?;
+        decode_entry_data(
+            &self.path,
+            &entry.name,
+            header.compression_method,
+            entry.uncompressed_size,
+            header.expected_crc().filter(|_| self.verify_crc),
+            compressed_data,
+        )
+    }
+
+    /// 在映射的归档内存中定位条目，返回解析后的本地文件头和（可能加密的）压缩数据
+    #[cfg(feature = "mmap")]
+    fn entry_data_in_slice<'a>(&self, bytes: &'a [u8], entry: &ZipEntryInfo) -> Result<(LocalHeader, &'a [u8])> {
+        let truncated = || ZipError::CorruptEntry {
+            name: entry.name.clone(),
+            archive: self.path.clone(),
+            reason: "unexpected end of data".to_string(),
+        };
+
+        // 偏移和长度来自归档，溢出与越界同样视为数据不完整
+        let start = usize::try_from(entry.local_header_offset).map_err(|_| truncated())?;
+        let name_start = start.checked_add(30).ok_or_else(truncated)?;
+        let local_header: &[u8; 30] = bytes
+            .get(start..name_start)
+            .and_then(|h| h.try_into().ok())
+            .ok_or_else(truncated)?;
+        let header = LocalHeader::parse(&self.path, entry, local_header)?;
+
+        let name_end = name_start.checked_add(header.name_len).ok_or_else(truncated)?;
+        let local_name = bytes.get(name_start..name_end).ok_or_else(truncated)?;
+        self.check_local_name(entry, local_name)?;
+
+        let data_start = name_end.checked_add(header.extra_len).ok_or_else(truncated)?;
+        let data_end = usize::try_from(header.compressed_size)
+            .ok()
+            .and_then(|size| data_start.checked_add(size))
+            .ok_or_else(truncated)?;
+        let compressed_data = bytes.get(data_start..data_end).ok_or_else(truncated)?;
+        Ok((header, compressed_data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::zip::ZipBuilder;
+    use std::fs;
+    use tempfile::TempDir;
+
+    /// 在临时目录中创建文件并打包为 test.zip
+    fn make_zip(files: &[(&str, &[u8])]) -> (TempDir, PathBuf) {
+        let tmp_dir = TempDir::new().unwrap();
+        for (name, data) in files {
+            let path = tmp_dir.path().join(name);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent).unwrap();
+            }
+            fs::write(&path, data).unwrap();
+        }
+
+        let zip_path = tmp_dir.path().join("test.zip");
+        let names: Vec<&str> = files.iter().map(|(name, _)| *name).collect();
+        ZipBuilder::new(&zip_path)
+            .unwrap()
+            .root(tmp_dir.path())
+            .files(&names)
+            .unwrap()
+            .build()
+            .unwrap();
+
+        (tmp_dir, zip_path)
+    }
+
+    #[test]
+    fn test_extract_to_map() {
+        let files: [(&str, &[u8]); 5] = [
+            ("a.txt", b"alpha\n"),
+            ("b.txt", b"bravo\n"),
+            ("c.cfg", b"key = value\n"),
+            ("empty", b""),
+            ("sub/d.txt", b"delta\n"),
+        ];
+        let (_tmp, zip_path) = make_zip(&files);
+
+        let map = ZipArchive::open(&zip_path).unwrap().extract_to_map().unwrap();
+
+        assert_eq!(map.len(), 5);
+        for (name, data) in files {
+            assert_eq!(map.get(name).map(|v| v.as_slice()), Some(data), "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_entries_as_stream() {
+        use std::collections::BTreeMap;
+
+        let (tmp, zip_path) = make_zip(&[
+            ("a.txt", b"alpha\n"),
+            ("dir/b.txt", "bravo ".repeat(200).as_bytes()),
+            ("dir/sub/c.bin", &[0, 1, 2, 255]),
+        ]);
+        let archive = ZipArchive::open(&zip_path).unwrap();
+
+        let mut files = BTreeMap::new();
+        for item in archive.entries_as_stream() {
+            let (entry, data) = item.unwrap();
+            assert_eq!(entry.uncompressed_size, data.len() as u64);
+            assert_eq!(entry.crc32, crc32(0, &data));
+            if !entry.is_directory {
+                files.insert(entry.filename, data);
+            }
+        }
+        let expected: BTreeMap<_, _> = archive.extract_to_map().unwrap().into_iter().collect();
+        assert_eq!(files, expected);
+        assert_eq!(files["dir/sub/c.bin"], [0, 1, 2, 255]);
+
+        let missing = ZipArchive::open(tmp.path().join("missing.zip")).unwrap();
+        let items: Vec<_> = missing.entries_as_stream().collect();
+        assert_eq!(items.len(), 1);
+        assert!(items[0].is_err());
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_par_read_all_matches_serial() {
+        let files: Vec<(String, Vec<u8>)> = (0..32)
+            .map(|i| (format!("f{:02}.bin", i), (0..=i as u8).rev().collect()))
+            .collect();
+        let refs: Vec<(&str, &[u8])> =
+            files.iter().map(|(n, d)| (n.as_str(), d.as_slice())).collect();
+        let (_temp, zipfile) = make_zip(&refs);
+
+        let archive = ZipArchive::open(&zipfile).unwrap();
+        let reader = ZipReader::open(&zipfile).unwrap();
+        let serial: Vec<(String, Vec<u8>)> = reader
+            .entries()
+            .iter()
+            .filter(|e| !e.is_dir)
+            .map(|e| (e.name.clone(), archive.read_entry_data(e).unwrap()))
+            .collect();
+
+        let parallel = archive.par_read_all().unwrap();
+        assert_eq!(parallel.len(), 32);
+        assert_eq!(parallel, serial);
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn test_open_mmap_matches_standard() {
+        let files: Vec<(String, Vec<u8>)> = (0..8)
+            .map(|i| (format!("dir/f{}.txt", i), format!("entry number {}\n", i).into_bytes()))
+            .collect();
+        let refs: Vec<(&str, &[u8])> =
+            files.iter().map(|(n, d)| (n.as_str(), d.as_slice())).collect();
+        let (temp, zipfile) = make_zip(&refs);
+
+        let standard = ZipArchive::open(&zipfile).unwrap();
+        // SAFETY: 临时目录中的归档在测试期间不会被修改
+        let mapped = unsafe { ZipArchive::open_mmap(&zipfile) }.unwrap();
+        assert_eq!(mapped.extract_to_map().unwrap(), standard.extract_to_map().unwrap());
+
+        for index in [0u32, 3, 7] {
+            let out_standard = temp.path().join(format!("standard-{}", index));
+            let out_mapped = temp.path().join(format!("mapped-{}", index));
+            standard.extract_to(index, &out_standard).unwrap();
+            mapped.extract_to(index, &out_mapped).unwrap();
+            assert_eq!(fs::read(&out_mapped).unwrap(), fs::read(&out_standard).unwrap());
+        }
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn test_entry_slice_borrows_stored_data() {
+        let stored: Vec<u8> = [0x1f, 0x8b, 0x08].iter().copied().chain(0..=255).collect();
+        let text = "deflate me ".repeat(50);
+        let temp = TempDir::new().unwrap();
+        fs::write(temp.path().join("data.gz"), &stored).unwrap();
+        fs::write(temp.path().join("text.txt"), &text).unwrap();
+        let zipfile = temp.path().join("mixed.zip");
+        ZipBuilder::new(&zipfile)
+            .unwrap()
+            .root(temp.path())
+            .files(&["data.gz", "text.txt"])
+            .unwrap()
+            .smart_store(true)
+            .build()
+            .unwrap();
+
+        // SAFETY: 临时目录中的归档在测试期间不会被修改
+        let mapped = unsafe { ZipArchive::open_mmap(&zipfile) }.unwrap();
+        let slice = mapped.entry_slice("data.gz").unwrap().unwrap();
+        assert_eq!(slice, stored.as_slice());
+        // 切片指向映射内存本身，而不是新分配的缓冲区
+        let mmap = mapped.mmap.as_ref().unwrap().as_ptr_range();
+        assert!(mmap.contains(&slice.as_ptr()));
+
+        assert_eq!(mapped.entry_slice("text.txt").unwrap(), None);
+        assert_eq!(mapped.read_entry("text.txt").unwrap(), text.as_bytes());
+        assert!(matches!(mapped.entry_slice("missing"), Err(ZipError::EntryNotFound { .. })));
+        assert_eq!(ZipArchive::open(&zipfile).unwrap().entry_slice("data.gz").unwrap(), None);
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn test_entry_data_in_slice_rejects_overflowing_offsets() {
+        let (_temp, zipfile) = make_zip(&[("a.txt", b"hello")]);
+        // SAFETY: 临时目录中的归档在测试期间不会被修改
+        let mapped = unsafe { ZipArchive::open_mmap(&zipfile) }.unwrap();
+        let bytes = mapped.mmap.as_ref().unwrap();
+        let entry = mapped.reader().unwrap().entries()[0].clone();
+
+        // 本地头偏移加上头长度溢出
+        let mut bad_offset = entry.clone();
+        bad_offset.local_header_offset = u64::MAX - 10;
+        assert!(matches!(
+            mapped.entry_data_in_slice(bytes, &bad_offset),
+            Err(ZipError::CorruptEntry { .. })
+        ));
+
+        // 本地头的压缩大小为 0xFFFFFFFF 时取中央目录的值，数据起点加上它溢出
+        let mut local = bytes[..30 + entry.name.len()].to_vec();
+        local[8..10].copy_from_slice(&8u16.to_le_bytes());
+        local[18..22].copy_from_slice(&u32::MAX.to_le_bytes());
+        let mut bad_size = entry;
+        bad_size.local_header_offset = 0;
+        bad_size.compressed_size = u64::MAX;
+        assert!(matches!(
+            mapped.entry_data_in_slice(&local, &bad_size),
+            Err(ZipError::CorruptEntry { .. })
+        ));
+    }
+
+    #[test]
+    fn test_entry_body_reader() {
+        let text = "raw deflate body\n".repeat(300);
+        let (_tmp, zip_path) = make_zip(&[("text.txt", text.as_bytes())]);
+        let archive = ZipArchive::open(&zip_path).unwrap();
+        let info = ZipReader::open(&zip_path).unwrap().entries()[0].clone();
+        assert_eq!(info.compression_method, 8);
+
+        let mut raw = Vec::new();
+        archive.entry_body_reader("text.txt").unwrap().read_to_end(&mut raw).unwrap();
+        assert_eq!(raw.len() as u64, info.compressed_size);
+        assert_eq!(inflate::decompress_raw(&raw).unwrap(), text.as_bytes());
+
+        assert!(matches!(
+            archive.entry_body_reader("missing").map(|_| ()),
+            Err(ZipError::EntryNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn test_read_entry_into() {
+        let large = "large entry line\n".repeat(400);
+        let medium = "medium\n".repeat(100);
+        let (tmp, zip_path) = make_zip(&[("large.txt", large.as_bytes()), ("small.txt", b"small\n"), ("medium.txt", medium.as_bytes())]);
+        let archive = ZipArchive::open(&zip_path).unwrap();
+
+        let mut buf = Vec::new();
+        assert_eq!(archive.read_entry_into("large.txt", &mut buf).unwrap(), large.len());
+        assert_eq!(buf, large.as_bytes());
+        let capacity = buf.capacity();
+        assert!(capacity >= large.len());
+
+        for (name, expected) in [("small.txt", &b"small\n"[..]), ("medium.txt", medium.as_bytes()), ("large.txt", large.as_bytes())] {
+            assert_eq!(archive.read_entry_into(name, &mut buf).unwrap(), expected.len());
+            assert_eq!(buf, expected);
+            assert_eq!(buf.capacity(), capacity, "{} should reuse the buffer", name);
+        }
+
+        assert!(matches!(
+            archive.read_entry_into("missing", &mut buf),
+            Err(ZipError::EntryNotFound { .. })
+        ));
+
+        // CRC32 不一致时返回 CorruptEntry
+        let bad = tmp.path().join("bad.zip");
+        let mut bytes = fs::read(&zip_path).unwrap();
+        let cd = ZipReader::open(&zip_path).unwrap().get_append_offset() as usize;
+        bytes[14] ^= 0xFF;
+        bytes[cd + 16] ^= 0xFF;
+        fs::write(&bad, &bytes).unwrap();
+        assert!(matches!(
+            ZipArchive::open(&bad).unwrap().read_entry_into("large.txt", &mut buf),
+            Err(ZipError::CorruptEntry { .. })
+        ));
+    }
+
+    #[test]
+    fn test_read_entry_prefix() {
+        let large: Vec<u8> = (0..2_000_000u32).map(|i| (i % 251) as u8 ^ (i / 997) as u8).collect();
+        let (_tmp, zip_path) = make_zip(&[("large.bin", &large), ("small.txt", b"short")]);
+        let archive = ZipArchive::open(&zip_path).unwrap();
+        let listed = ZipArchive::list(&zip_path).unwrap();
+        assert!(listed[0].compressed_size < listed[0].uncompressed_size);
+
+        assert_eq!(archive.read_entry_prefix("large.bin", 16).unwrap(), &large[..16]);
+        assert_eq!(archive.read_entry_prefix("large.bin", 100_000).unwrap(), &large[..100_000]);
+        assert_eq!(archive.read_entry_prefix("small.txt", 16).unwrap(), b"short");
+        assert_eq!(archive.read_entry_prefix("small.txt", 0).unwrap(), b"");
+        assert!(matches!(
+            archive.read_entry_prefix("missing", 16),
+            Err(ZipError::EntryNotFound { .. })
+        ));
+
+        #[cfg(feature = "mmap")]
+        {
+            // SAFETY: 临时目录中的归档在测试期间不会被修改
+            let mapped = unsafe { ZipArchive::open_mmap(&zip_path) }.unwrap();
+            assert_eq!(mapped.read_entry_prefix("large.bin", 16).unwrap(), &large[..16]);
+        }
+    }
+
+    #[test]
+    fn test_content_hash() {
+        use crate::error::CompressionLevel;
+
+        let tmp_dir = TempDir::new().unwrap();
+        let src = tmp_dir.path().join("src");
+        fs::create_dir_all(&src).unwrap();
+        fs::write(src.join("a.txt"), b"alpha\n").unwrap();
+        fs::write(src.join("b.txt"), b"bravo\n").unwrap();
+
+        let build = |name: &str, level: CompressionLevel| {
+            let zip_path = tmp_dir.path().join(name);
+            ZipBuilder::new(&zip_path)
+                .unwrap()
+                .root(&src)
+                .compression_level(level)
+                .files(&["a.txt", "b.txt"])
+                .unwrap()
+                .build()
+                .unwrap();
+            ZipArchive::open(&zip_path).unwrap().content_hash().unwrap()
+        };
+
+        let stored = build("stored.zip", CompressionLevel::NoCompression);
+        let deflated = build("deflated.zip", CompressionLevel::Level6);
+        assert_eq!(stored, deflated);
+
+        fs::write(src.join("b.txt"), b"bravo!\n").unwrap();
+        let changed = build("changed.zip", CompressionLevel::Level6);
+        assert_ne!(stored, changed);
+    }
+
+    #[test]
+    fn test_created_by_host() {
+        use crate::error::HostOs;
+
+        let (_tmp, zip_path) = make_zip(&[("a.txt", b"alpha\n")]);
+        let entries = ZipArchive::list(&zip_path).unwrap();
+
+        assert_eq!(entries[0].created_by_host(), HostOs::Unix);
+        assert_eq!(entries[0].spec_version(), (2, 3));
+    }
+
+    /// 每次最多返回 3 字节的读取器，模拟不稳定的流式来源
+    struct PartialReader<R: Read>(R);
+
+    impl<R: Read> Read for PartialReader<R> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let len = buf.len().min(3);
+            self.0.read(&mut buf[..len])
+        }
+    }
+
+    #[test]
+    fn test_read_entry_from_partial_reader() {
+        use std::io::Cursor;
+
+        let (tmp, zip_path) = make_zip(&[("a.txt", b"alpha\n"), ("b.txt", b"bravo charlie\n")]);
+        let archive = ZipArchive::open(&zip_path).unwrap();
+        let reader = ZipReader::open(&zip_path).unwrap();
+        let bytes = fs::read(&zip_path).unwrap();
+
+        for entry in reader.entries() {
+            let offset = entry.local_header_offset as usize;
+            let mut partial = PartialReader(Cursor::new(&bytes[offset..]));
+            let data = archive.read_entry_from(&mut partial, entry).unwrap();
+            assert_eq!(data, fs::read(tmp.path().join(&entry.name)).unwrap());
+        }
+
+        // 数据在条目结束前截断
+        let entry = &reader.entries()[1];
+        let offset = entry.local_header_offset as usize;
+        let truncated = &bytes[offset..offset + 30 + entry.name.len() + 2];
+        let mut partial = PartialReader(Cursor::new(truncated));
+        let err = archive.read_entry_from(&mut partial, entry).unwrap_err();
+        assert!(
+            matches!(err, ZipError::CorruptEntry { ref name, .. } if name == "b.txt"),
+            "{:?}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_top_level_entries() {
+        let (_tmp, zip_path) = make_zip(&[
+            ("a/b/c", b"charlie\n"),
+            ("a/d", b"delta\n"),
+            ("e.txt", b"echo\n"),
+        ]);
+
+        let top = ZipArchive::open(&zip_path).unwrap().top_level_entries().unwrap();
+        assert_eq!(top, vec!["a/".to_string(), "e.txt".to_string()]);
+    }
+
+    #[test]
+    fn test_directories_to_create() {
+        let (_tmp_dir, zip_path) = make_zip(&[("a/b/c.txt", b"c"), ("a-b/d.txt", b"d"), ("e.txt", b"e")]);
+        let archive = ZipArchive::open(&zip_path).unwrap();
+        assert_eq!(archive.directories_to_create().unwrap(), ["a-b/", "a/", "a/b/"]);
+
+        // 显式的目录条目与推断出的目录合并去重
+        let tmp_dir = TempDir::new().unwrap();
+        let with_dirs = tmp_dir.path().join("dirs.zip");
+        let mut writer = ZipWriter::new(&with_dirs, CompressionLevel::Level6).unwrap();
+        writer.add_directory_entry("x/").unwrap();
+        writer.add_directory_entry("x/empty/").unwrap();
+        writer.add_reader("x/y/z.txt", &mut &b"z"[..]).unwrap();
+        writer.finalize().unwrap();
+        let archive = ZipArchive::open(&with_dirs).unwrap();
+        assert_eq!(archive.directories_to_create().unwrap(), ["x/", "x/empty/", "x/y/"]);
+    }
+
+    #[test]
+    fn test_compact() {
+        let (tmp_dir, zip_path) = make_zip(&[("a.txt", b"alpha\n"), ("dir/b.txt", &[b'b'; 3000]), ("c.bin", &[0, 1, 2, 3])]);
+        let expected_entries = ZipArchive::list(&zip_path).unwrap();
+        let expected_data = ZipArchive::open(&zip_path).unwrap().extract_to_map().unwrap();
+
+        let mut bytes = fs::read(&zip_path).unwrap();
+        let tight_len = bytes.len() as u64;
+        bytes.extend_from_slice(&[0xAB; 1024]);
+        fs::write(&zip_path, &bytes).unwrap();
+
+        ZipArchive::compact(&zip_path).unwrap();
+        let compacted = fs::metadata(&zip_path).unwrap().len();
+        assert!(compacted < bytes.len() as u64);
+        assert_eq!(compacted, tight_len);
+
+        let entries = ZipArchive::list(&zip_path).unwrap();
+        assert_eq!(entries.len(), expected_entries.len());
+        for (got, want) in entries.iter().zip(&expected_entries) {
+            assert_eq!((&got.filename, got.crc32, got.compressed_size), (&want.filename, want.crc32, want.compressed_size));
+        }
+        assert_eq!(ZipArchive::open(&zip_path).unwrap().extract_to_map().unwrap(), expected_data);
+
+        // copy_to_filtered 留下的未引用条目数据也被去掉，后续条目的偏移随之更新
+        let filtered = tmp_dir.path().join("filtered.zip");
+        let mut out = File::create(&filtered).unwrap();
+        ZipArchive::open(&zip_path)
+            .unwrap()
+            .copy_to_filtered(&mut out, |name| name != "a.txt")
+            .unwrap();
+        drop(out);
+        let before = fs::metadata(&filtered).unwrap().len();
+        ZipArchive::compact(&filtered).unwrap();
+        assert!(fs::metadata(&filtered).unwrap().len() < before);
+        let map = ZipArchive::open(&filtered).unwrap().extract_to_map().unwrap();
+        assert_eq!(map.len(), 2);
+        assert_eq!(map["dir/b.txt"], expected_data["dir/b.txt"]);
+        assert_eq!(map["c.bin"], expected_data["c.bin"]);
+    }
+
+    #[test]
+    fn test_compact_keeps_leading_stub() {
+        let (tmp_dir, zip_path) = make_zip(&[("a.txt", b"alpha\n"), ("b.txt", &[b'b'; 3000])]);
+        let expected = ZipArchive::open(&zip_path).unwrap().extract_to_map().unwrap();
+        let stub = b"#!/bin/sh\necho self-extracting archive\nexit 0\n";
+        let zip = fs::read(&zip_path).unwrap();
+
+        // 偏移相对于前置数据之后（未调整）与相对于文件开头（已调整）两种自解压归档
+        let mut unadjusted = stub.to_vec();
+        unadjusted.extend_from_slice(&zip);
+        unadjusted.extend_from_slice(&[0xAB; 512]);
+        fs::write(&zip_path, &unadjusted).unwrap();
+        assert_eq!(ZipReader::open(&zip_path).unwrap().offset_base(), stub.len() as u64);
+        ZipArchive::compact(&zip_path).unwrap();
+        let bytes = fs::read(&zip_path).unwrap();
+        assert_eq!(bytes, unadjusted[..stub.len() + zip.len()]);
+        assert_eq!(ZipArchive::open(&zip_path).unwrap().extract_to_map().unwrap(), expected);
+
+        let adjusted = tmp_dir.path().join("adjusted.zip");
+        ZipBuilder::new(&adjusted)
+            .unwrap()
+            .root(tmp_dir.path())
+            .files(&["a.txt"])
+            .unwrap()
+            .sfx_prefix(stub)
+            .build()
+            .unwrap();
+        let mut bytes = fs::read(&adjusted).unwrap();
+        let tight = bytes.clone();
+        bytes.extend_from_slice(&[0xAB; 512]);
+        fs::write(&adjusted, &bytes).unwrap();
+        assert_eq!(ZipReader::open(&adjusted).unwrap().offset_base(), 0);
+        ZipArchive::compact(&adjusted).unwrap();
+        assert_eq!(fs::read(&adjusted).unwrap(), tight);
+        assert_eq!(ZipArchive::open(&adjusted).unwrap().read_entry("a.txt").unwrap(), b"alpha\n");
+    }
+
+    #[test]
+    fn test_split() {
+        let files: Vec<(String, Vec<u8>)> = (0..6)
+            .map(|i| {
+                // 伪随机内容几乎不可压缩，每个条目约 700 字节
+                let mut state = 0x9E37_79B9u32.wrapping_mul(i + 1);
+                let data = (0..700)
+                    .map(|_| {
+                        state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                        (state >> 24) as u8
+                    })
+                    .collect();
+                (format!("f{}.bin", i), data)
+            })
+            .collect();
+        let refs: Vec<(&str, &[u8])> = files.iter().map(|(name, data)| (name.as_str(), data.as_slice())).collect();
+        let (tmp_dir, zip_path) = make_zip(&refs);
+        let archive = ZipArchive::open(&zip_path).unwrap();
+        let expected = archive.extract_to_map().unwrap();
+
+        let prefix = tmp_dir.path().join("out").to_string_lossy().to_string();
+        let max_bytes = 2000;
+        let parts = archive.split(&prefix, max_bytes).unwrap();
+        assert!(parts.len() > 1);
+        assert_eq!(parts[0], PathBuf::from(format!("{}.001.zip", prefix)));
+
+        let mut seen = HashMap::new();
+        for part in &parts {
+            assert!(fs::metadata(part).unwrap().len() <= max_bytes);
+            for (name, data) in ZipArchive::open(part).unwrap().extract_to_map().unwrap() {
+                assert!(seen.insert(name, data).is_none());
+            }
+        }
+        assert_eq!(seen, expected);
+
+        assert!(archive.split(&prefix, 500).is_err());
+
+        // 最后一个分卷无法写出时，之前完成的分卷也被删除，不留下临时文件
+        let failing = tmp_dir.path().join("failing");
+        fs::create_dir_all(&failing).unwrap();
+        let prefix = failing.join("out").to_string_lossy().to_string();
+        let blocker = PathBuf::from(format!("{}.{:03}.zip", prefix, parts.len()));
+        fs::create_dir_all(&blocker).unwrap();
+        assert!(archive.split(&prefix, max_bytes).is_err());
+        let left: Vec<PathBuf> = fs::read_dir(&failing).unwrap().map(|e| e.unwrap().path()).collect();
+        assert_eq!(left, [blocker]);
+    }
+
+    #[test]
+    fn test_open_anchored_detects_modification() {
+        let (tmp, zip_path) = make_zip(&[("a.txt", b"alpha\n"), ("b.txt", b"bravo\n")]);
+        let archive = ZipArchive::open_anchored(&zip_path).unwrap();
+        assert_eq!(archive.entries().unwrap().len(), 2);
+        assert_eq!(archive.read_entry("b.txt").unwrap(), b"bravo\n");
+
+        // 同样大小的改写：只改变 EOCD 中的注释
+        ZipArchive::set_comment_in_place(&zip_path, "v2").unwrap();
+        let anchored = ZipArchive::open_anchored(&zip_path).unwrap();
+        ZipArchive::set_comment_in_place(&zip_path, "v3").unwrap();
+        assert!(matches!(anchored.read_entry("a.txt"), Err(ZipError::ArchiveModified { .. })));
+
+        // 大小变化
+        assert!(matches!(archive.entries(), Err(ZipError::ArchiveModified { .. })));
+        assert!(matches!(archive.extract_to(0, &tmp.path().join("out.txt")), Err(ZipError::ArchiveModified { .. })));
+
+        // 没有锚点时照常读取
+        assert_eq!(ZipArchive::open(&zip_path).unwrap().read_entry("a.txt").unwrap(), b"alpha\n");
+    }
+
+    #[test]
+    fn test_shared_data_groups() {
+        let tmp_dir = TempDir::new().unwrap();
+        let shared = "shared content\n".repeat(40);
+        for (name, data) in [("a.txt", shared.as_str()), ("b.txt", "unique\n"), ("c.txt", shared.as_str())] {
+            fs::write(tmp_dir.path().join(name), data).unwrap();
+        }
+        let build = |name: &str, dedup: bool| {
+            let zip_path = tmp_dir.path().join(name);
+            ZipBuilder::new(&zip_path)
+                .unwrap()
+                .root(tmp_dir.path())
+                .files(&["a.txt", "b.txt", "c.txt"])
+                .unwrap()
+                .dedup(dedup)
+                .build()
+                .unwrap();
+            ZipArchive::open(&zip_path).unwrap()
+        };
+
+        assert_eq!(build("dedup.zip", true).shared_data_groups().unwrap(), [["a.txt", "c.txt"]]);
+        assert!(build("plain.zip", false).shared_data_groups().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_verify_against_manifest() {
+        let (_tmp, zip_path) = make_zip(&[("a.txt", b"alpha\n"), ("b.txt", b"bravo\n"), ("c.txt", b"charlie\n")]);
+        let archive = ZipArchive::open(&zip_path).unwrap();
+        let entry = |name: &str, data: &[u8]| (name.to_string(), crc32(0, data), data.len() as u64);
+
+        let manifest = [entry("a.txt", b"alpha\n"), entry("b.txt", b"bravo\n"), entry("c.txt", b"charlie\n")];
+        assert!(archive.verify_against_manifest(&manifest).unwrap().is_empty());
+
+        // a.txt 不在清单中，b.txt / c.txt 内容不同，d.txt 不在归档中
+        let wrong = [
+            entry("b.txt", b"BRAVO\n"),
+            entry("c.txt", b"charlie!\n"),
+            entry("d.txt", b"delta\n"),
+        ];
+        let mismatches = archive.verify_against_manifest(&wrong).unwrap();
+        assert_eq!(
+            mismatches,
+            [
+                ManifestMismatch::Crc {
+                    name: "b.txt".to_string(),
+                    expected: crc32(0, b"BRAVO\n"),
+                    actual: crc32(0, b"bravo\n"),
+                },
+                ManifestMismatch::Crc {
+                    name: "c.txt".to_string(),
+                    expected: crc32(0, b"charlie!\n"),
+                    actual: crc32(0, b"charlie\n"),
+                },
+                ManifestMismatch::Size { name: "c.txt".to_string(), expected: 9, actual: 8 },
+                ManifestMismatch::Missing { name: "d.txt".to_string() },
+                ManifestMismatch::Extra { name: "a.txt".to_string() },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_set_comment_in_place() {
+        let (_tmp, zip_path) = make_zip(&[("a.txt", b"alpha\n"), ("b.txt", b"bravo\n")]);
+        let archive = ZipArchive::open(&zip_path).unwrap();
+        assert!(archive.comment().unwrap().is_empty());
+        let original = fs::read(&zip_path).unwrap();
+        let summary = |path: &Path| -> Vec<(String, u32, u64)> {
+            ZipArchive::list(path)
+                .unwrap()
+                .into_iter()
+                .map(|entry| (entry.filename, entry.crc32, entry.offset))
+                .collect()
+        };
+        let expected_entries = summary(&zip_path);
+        let expected_data = archive.extract_to_map().unwrap();
+
+        for comment in ["a much longer archive comment", "short", ""] {
+            ZipArchive::set_comment_in_place(&zip_path, comment).unwrap();
+            assert_eq!(archive.comment().unwrap(), comment.as_bytes());
+
+            let bytes = fs::read(&zip_path).unwrap();
+            assert_eq!(bytes.len(), original.len() + comment.len());
+            assert_eq!(bytes[..original.len() - 2], original[..original.len() - 2]);
+            assert_eq!(summary(&zip_path), expected_entries);
+            assert_eq!(archive.extract_to_map().unwrap(), expected_data);
+        }
+
+        assert!(ZipArchive::set_comment_in_place(&zip_path, vec![b'x'; 70_000]).is_err());
+    }
+
+    #[test]
+    fn test_copy_to() {
+        let (tmp, zip_path) = make_zip(&[("a.txt", b"alpha\n"), ("b.txt", b"bravo\n")]);
+        let archive = ZipArchive::open(&zip_path).unwrap();
+
+        let mut copy = Vec::new();
+        let n = archive.copy_to(&mut copy).unwrap();
+        assert_eq!(n, copy.len() as u64);
+        assert_eq!(copy, fs::read(&zip_path).unwrap());
+
+        let copy_path = tmp.path().join("copy.zip");
+        fs::write(&copy_path, &copy).unwrap();
+        let copied = ZipArchive::open(&copy_path).unwrap().extract_to_map().unwrap();
+        assert_eq!(copied, archive.extract_to_map().unwrap());
+
+        let mut filtered = Vec::new();
+        let n = archive.copy_to_filtered(&mut filtered, |name| name != "a.txt").unwrap();
+        assert_eq!(n, filtered.len() as u64);
+        let filtered_path = tmp.path().join("filtered.zip");
+        fs::write(&filtered_path, &filtered).unwrap();
+        let map = ZipArchive::open(&filtered_path).unwrap().extract_to_map().unwrap();
+        assert_eq!(map.len(), 1);
+        assert_eq!(map["b.txt"], b"bravo\n");
+
+        // 归档注释保留
+        ZipArchive::set_comment_in_place(&zip_path, "release build").unwrap();
+        let mut filtered = Vec::new();
+        archive.copy_to_filtered(&mut filtered, |name| name == "a.txt").unwrap();
+        fs::write(&filtered_path, &filtered).unwrap();
+        let filtered = ZipArchive::open(&filtered_path).unwrap();
+        assert_eq!(filtered.comment().unwrap(), b"release build");
+        assert_eq!(filtered.read_entry("a.txt").unwrap(), b"alpha\n");
+
+        // 超出 EOCD 范围的条目数、大小和偏移返回错误而不是截断
+        assert!(archive.filtered_eocd(65_534, 100, 100, &[]).is_ok());
+        assert!(archive.filtered_eocd(65_535, 100, 100, &[]).is_err());
+        assert!(archive.filtered_eocd(70_000, 100, 100, &[]).is_err());
+        assert!(archive.filtered_eocd(1, u32::MAX as u64, 100, &[]).is_err());
+        assert!(archive.filtered_eocd(1, 100, 5 << 30, &[]).is_err());
+    }
+
+    #[test]
+    fn test_entries_range() {
+        let files: Vec<(String, Vec<u8>)> = (0..100)
+            .map(|i| (format!("f{:03}.txt", i), format!("file {}\n", i).into_bytes()))
+            .collect();
+        let refs: Vec<(&str, &[u8])> =
+            files.iter().map(|(n, d)| (n.as_str(), d.as_slice())).collect();
+        let (tmp, zip_path) = make_zip(&refs);
+        let archive = ZipArchive::open(&zip_path).unwrap();
+
+        let page = archive.entries_range(10, 20).unwrap();
+        let names: Vec<&str> = page.iter().map(|e| e.filename.as_str()).collect();
+        let expected: Vec<&str> = refs[10..20].iter().map(|(n, _)| *n).collect();
+        assert_eq!(names, expected);
+
+        assert_eq!(archive.entries_range(95, 200).unwrap().len(), 5);
+        assert!(archive.entries_range(20, 10).unwrap().is_empty());
+
+        let exdir = tmp.path().join("page");
+        archive.extract_range(10, 20, &exdir).unwrap();
+        assert_eq!(fs::read_dir(&exdir).unwrap().count(), 10);
+        assert_eq!(fs::read(exdir.join("f015.txt")).unwrap(), b"file 15\n");
+        assert!(!exdir.join("f020.txt").exists());
+    }
+
+    #[test]
+    fn test_entry_times_prefer_ntfs() {
+        use crate::unzip::Extractor;
+        use std::time::{Duration, UNIX_EPOCH};
+
+        let tmp_dir = TempDir::new().unwrap();
+        let src = tmp_dir.path().join("src");
+        fs::create_dir_all(&src).unwrap();
+        fs::write(src.join("a.txt"), b"alpha\n").unwrap();
+        let mtime = UNIX_EPOCH + Duration::new(1_700_000_000, 123_456_700);
+        fs::File::options()
+            .write(true)
+            .open(src.join("a.txt"))
+            .unwrap()
+            .set_modified(mtime)
+            .unwrap();
+
+        let zip_path = tmp_dir.path().join("times.zip");
+        ZipBuilder::new(&zip_path)
+            .unwrap()
+            .root(&src)
+            .high_res_time(true)
+            .files(&["a.txt"])
+            .unwrap()
+            .build()
+            .unwrap();
+
+        // 同时带有 0x5455（1 秒精度）和 0x000a（100 ns 精度）
+        let reader = ZipReader::open(&zip_path).unwrap();
+        let info = &reader.entries()[0];
+        assert!(extra::find_field(&info.extra, extra::EXTENDED_TIMESTAMP_TAG).is_some());
+        assert!(extra::find_field(&info.extra, extra::NTFS_TAG).is_some());
+
+        let entry = &ZipArchive::list(&zip_path).unwrap()[0];
+        assert_eq!(entry.times.mtime, mtime);
+        assert_eq!(entry.timestamp, dos_to_system_time(entry.dos_time, entry.dos_date));
+        assert!(entry.times.atime.is_some());
+
+        let exdir = tmp_dir.path().join("out");
+        Extractor::new(&zip_path).unwrap().exdir(&exdir).extract().unwrap();
+        let extracted = fs::metadata(exdir.join("a.txt")).unwrap().modified().unwrap();
+        assert_eq!(extracted, mtime);
+    }
+
+    #[test]
+    fn test_overhead() {
+        let (_tmp, zip_path) = make_zip(&[("a.txt", b"alpha\n"), ("dir/b.txt", &[b'b'; 4000])]);
+        let archive = ZipArchive::open(&zip_path).unwrap();
+        let overhead = archive.overhead().unwrap();
+
+        // 默认不写 extra field：本地头 30 + 文件名，中央目录 46 + 文件名，EOCD 22 字节
+        assert_eq!(overhead.local_headers, (30 + 5) + (30 + 9));
+        assert_eq!(overhead.central_directory, (46 + 5) + (46 + 9));
+        assert_eq!(overhead.end_of_central_directory, 22);
+
+        let entries = archive.entries().unwrap();
+        let payload: u64 = entries.iter().map(|e| e.compressed_size).sum();
+        assert_eq!(overhead.payload, payload);
+        let file_size = fs::metadata(&zip_path).unwrap().len();
+        assert_eq!(overhead.total(), file_size - payload);
+        // 太短的内容压缩后反而变大：5 字节的存储块头
+        assert_eq!(entries[0].space_saved(), -5);
+        assert!(entries[1].space_saved() > 3000);
+    }
+
+    #[test]
+    fn test_streamed_entry_with_unknown_size() {
+        let content = "streamed content\n".repeat(30);
+        let (_tmp, zip_path) = make_zip(&[("s.txt", content.as_bytes())]);
+        let reader = ZipReader::open(&zip_path).unwrap();
+        let entry = &reader.entries()[0];
+        let data_end = (30 + 5 + entry.compressed_size) as usize;
+        let cd_offset = reader.get_append_offset() as usize;
+
+        // 改写成流式写入器的输出：本地头 bit 3 + CRC 为 0 + 大小为 0xFFFFFFFF，数据后跟数据描述符
+        let bytes = fs::read(&zip_path).unwrap();
+        let mut out = bytes[..data_end].to_vec();
+        out[6] |= 0x08;
+        out[14..18].fill(0);
+        out[18..26].fill(0xFF);
+        out.extend_from_slice(&0x08074b50u32.to_le_bytes());
+        out.extend_from_slice(&entry.crc32.to_le_bytes());
+        out.extend_from_slice(&(entry.compressed_size as u32).to_le_bytes());
+        out.extend_from_slice(&(entry.uncompressed_size as u32).to_le_bytes());
+        let new_cd_offset = out.len() as u32;
+        out.extend_from_slice(&bytes[cd_offset..]);
+        out[new_cd_offset as usize + 8] |= 0x08;
+        let eocd = out.len() - 22;
+        out[eocd + 16..eocd + 20].copy_from_slice(&new_cd_offset.to_le_bytes());
+        fs::write(&zip_path, &out).unwrap();
+
+        let map = ZipArchive::open(&zip_path).unwrap().extract_to_map().unwrap();
+        assert_eq!(map["s.txt"], content.as_bytes());
+
+        // 没有 bit 3、只有大小哨兵时同样回退到中央目录中的大小
+        out[6] &= !0x08;
+        out[14..18].copy_from_slice(&entry.crc32.to_le_bytes());
+        fs::write(&zip_path, &out).unwrap();
+        let map = ZipArchive::open(&zip_path).unwrap().extract_to_map().unwrap();
+        assert_eq!(map["s.txt"], content.as_bytes());
+    }
+
+    #[test]
+    fn test_entries_physical_order() {
+        let (_tmp, zip_path) = make_zip(&[("a.txt", b"alpha\n"), ("b.txt", b"bravo\n")]);
+
+        // 反转中央目录中的记录顺序
+        let reader = ZipReader::open(&zip_path).unwrap();
+        let records = reader.raw_central_records().unwrap();
+        let bytes = fs::read(&zip_path).unwrap();
+        let cd_offset = reader.get_append_offset() as usize;
+        let mut out = bytes[..cd_offset].to_vec();
+        for record in records.iter().rev() {
+            out.extend_from_slice(record);
+        }
+        out.extend_from_slice(&bytes[bytes.len() - 22..]);
+        fs::write(&zip_path, out).unwrap();
+
+        let archive = ZipArchive::open(&zip_path).unwrap();
+        let names = |entries: Vec<ZipEntry>| -> Vec<String> {
+            entries.into_iter().map(|e| e.filename).collect()
+        };
+        assert_eq!(names(archive.entries().unwrap()), ["b.txt", "a.txt"]);
+        assert_eq!(names(archive.entries_physical_order().unwrap()), ["a.txt", "b.txt"]);
+    }
+
+    /// 手工构造只含一个 ZipCrypto 加密的 STORE 条目的归档
+    fn make_encrypted_zip(path: &Path, name: &str, content: &[u8], password: &[u8]) {
+        let crc = crc32(0, content);
+        let mut body = vec![0x5Au8; crypto::ENCRYPTION_HEADER_LEN];
+        body[crypto::ENCRYPTION_HEADER_LEN - 1] = (crc >> 24) as u8;
+        body.extend_from_slice(content);
+        crypto::ZipCryptoKeys::new(password).encrypt(&mut body);
+        make_stored_zip(path, name, FLAG_ENCRYPTED, crc, &body, content.len() as u32);
+    }
+
+    /// 手工构造 bit 3 的 ZipCrypto 条目：校验字节取自修改时间的高字节（此处为 0），数据后跟描述符
+    fn make_encrypted_descriptor_zip(path: &Path, name: &str, content: &[u8], password: &[u8]) {
+        let crc = crc32(0, content);
+        assert_ne!(crc >> 24, 0, "check byte must differ from the CRC high byte");
+        let body = crypto::encrypt_entry(password, 0, content);
+        let flags = FLAG_ENCRYPTED | FLAG_DATA_DESCRIPTOR;
+        make_stored_zip(path, name, flags, crc, &body, content.len() as u32);
+    }
+
+    /// 手工构造只含一个 STORE 条目的归档，头部字段按参数原样写入；设置 bit 3 时在数据后写出描述符
+    fn make_stored_zip(path: &Path, name: &str, flags: u16, crc: u32, body: &[u8], uncompressed_size: u32) {
+        let mut fixed = Vec::new();
+        fixed.extend_from_slice(&20u16.to_le_bytes()); // version needed
+        fixed.extend_from_slice(&flags.to_le_bytes());
+        fixed.extend_from_slice(&0u16.to_le_bytes()); // STORE
+        fixed.extend_from_slice(&0u16.to_le_bytes()); // time
+        fixed.extend_from_slice(&0x5021u16.to_le_bytes()); // date
+        fixed.extend_from_slice(&crc.to_le_bytes());
+        fixed.extend_from_slice(&(body.len() as u32).to_le_bytes());
+        fixed.extend_from_slice(&uncompressed_size.to_le_bytes());
+        fixed.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        fixed.extend_from_slice(&0u16.to_le_bytes()); // extra len
+
+        let mut out = 0x04034b50u32.to_le_bytes().to_vec();
+        out.extend_from_slice(&fixed);
+        out.extend_from_slice(name.as_bytes());
+        out.extend_from_slice(body);
+        if flags & FLAG_DATA_DESCRIPTOR != 0 {
+            out.extend_from_slice(&0x08074b50u32.to_le_bytes());
+            out.extend_from_slice(&crc.to_le_bytes());
+            out.extend_from_slice(&(body.len() as u32).to_le_bytes());
+            out.extend_from_slice(&uncompressed_size.to_le_bytes());
+        }
+
+        let cd_offset = out.len();
+        out.extend_from_slice(&0x02014b50u32.to_le_bytes());
+        out.extend_from_slice(&20u16.to_le_bytes()); // version made by
+        out.extend_from_slice(&fixed);
+        out.extend_from_slice(&[0u8; 6]); // comment len, disk, internal attr
+        out.extend_from_slice(&0u32.to_le_bytes()); // external attr
+        out.extend_from_slice(&0u32.to_le_bytes()); // local header offset
+        out.extend_from_slice(name.as_bytes());
+        let cd_size = out.len() - cd_offset;
+
+        out.extend_from_slice(&0x06054b50u32.to_le_bytes());
+        out.extend_from_slice(&[0u8; 4]);
+        out.extend_from_slice(&1u16.to_le_bytes());
+        out.extend_from_slice(&1u16.to_le_bytes());
+        out.extend_from_slice(&(cd_size as u32).to_le_bytes());
+        out.extend_from_slice(&(cd_offset as u32).to_le_bytes());
+        out.extend_from_slice(&0u16.to_le_bytes());
+        fs::write(path, out).unwrap();
+    }
+
+    #[test]
+    fn test_zero_dos_date_uses_extended_timestamp() {
+        use crate::unzip::Extractor;
+        use std::time::{Duration, UNIX_EPOCH};
+
+        // DOS 日期和时间为 0，0x5455 只写在本地文件头中
+        let mtime_secs = 1_700_000_000u32;
+        let mut ut = Vec::new();
+        ut.extend_from_slice(&extra::EXTENDED_TIMESTAMP_TAG.to_le_bytes());
+        ut.extend_from_slice(&5u16.to_le_bytes());
+        ut.push(0x01);
+        ut.extend_from_slice(&mtime_secs.to_le_bytes());
+
+        let body = b"stream";
+        let fixed = |extra_len: u16| {
+            let mut fixed = Vec::new();
+            fixed.extend_from_slice(&20u16.to_le_bytes()); // version needed
+            fixed.extend_from_slice(&0u16.to_le_bytes()); // flags
+            fixed.extend_from_slice(&0u16.to_le_bytes()); // STORE
+            fixed.extend_from_slice(&0u32.to_le_bytes()); // DOS time + date
+            fixed.extend_from_slice(&crc32(0, body).to_le_bytes());
+            fixed.extend_from_slice(&(body.len() as u32).to_le_bytes());
+            fixed.extend_from_slice(&(body.len() as u32).to_le_bytes());
+            fixed.extend_from_slice(&5u16.to_le_bytes()); // name len
+            fixed.extend_from_slice(&extra_len.to_le_bytes());
+            fixed
+        };
+
+        let mut out = 0x04034b50u32.to_le_bytes().to_vec();
+        out.extend_from_slice(&fixed(ut.len() as u16));
+        out.extend_from_slice(b"a.txt");
+        out.extend_from_slice(&ut);
+        out.extend_from_slice(body);
+
+        let cd_offset = out.len();
+        out.extend_from_slice(&0x02014b50u32.to_le_bytes());
+        out.extend_from_slice(&20u16.to_le_bytes()); // version made by
+        out.extend_from_slice(&fixed(0));
+        out.extend_from_slice(&[0u8; 6]); // comment len, disk, internal attr
+        out.extend_from_slice(&0u32.to_le_bytes()); // external attr
+        out.extend_from_slice(&0u32.to_le_bytes()); // local header offset
+        out.extend_from_slice(b"a.txt");
+        let cd_size = out.len() - cd_offset;
+
+        out.extend_from_slice(&0x06054b50u32.to_le_bytes());
+        out.extend_from_slice(&[0u8; 4]);
+        out.extend_from_slice(&1u16.to_le_bytes());
+        out.extend_from_slice(&1u16.to_le_bytes());
+        out.extend_from_slice(&(cd_size as u32).to_le_bytes());
+        out.extend_from_slice(&(cd_offset as u32).to_le_bytes());
+        out.extend_from_slice(&0u16.to_le_bytes());
+
+        let tmp_dir = TempDir::new().unwrap();
+        let zip_path = tmp_dir.path().join("ut.zip");
+        fs::write(&zip_path, out).unwrap();
+
+        let expected = UNIX_EPOCH + Duration::from_secs(mtime_secs as u64);
+        let entry = &ZipArchive::list(&zip_path).unwrap()[0];
+        assert_eq!(entry.dos_date, 0);
+        assert_eq!(entry.times.mtime, expected);
+        assert_eq!(entry.timestamp, UNIX_EPOCH);
+
+        let exdir = tmp_dir.path().join("out");
+        Extractor::new(&zip_path).unwrap().exdir(&exdir).extract().unwrap();
+        let extracted = fs::metadata(exdir.join("a.txt")).unwrap().modified().unwrap();
+        assert_eq!(extracted, expected);
+    }
+
+    #[test]
+    fn test_legacy_infozip_unix_field() {
+        use crate::zip::writer::PrecompressedEntry;
+        use std::time::{Duration, UNIX_EPOCH};
+
+        // 旧版 Info-ZIP 写出的 0x5855：atime、mtime、uid、gid
+        let mut extra = extra::INFOZIP_UNIX_OLD_TAG.to_le_bytes().to_vec();
+        extra.extend_from_slice(&12u16.to_le_bytes());
+        extra.extend_from_slice(&900_000_100u32.to_le_bytes());
+        extra.extend_from_slice(&900_000_000u32.to_le_bytes());
+        extra.extend_from_slice(&501u16.to_le_bytes());
+        extra.extend_from_slice(&20u16.to_le_bytes());
+
+        let tmp_dir = TempDir::new().unwrap();
+        let zip_path = tmp_dir.path().join("old-unix.zip");
+        let content = b"legacy\n";
+        let mut writer = ZipWriter::new(&zip_path, CompressionLevel::NoCompression).unwrap();
+        writer
+            .add_precompressed(PrecompressedEntry {
+                name: "old.txt".to_string(),
+                method: 0,
+                data: content.to_vec(),
+                uncompressed_size: content.len() as u64,
+                crc32: crc32(0, content),
+                mtime: UNIX_EPOCH + Duration::from_secs(1_600_000_000),
+                mode: 0o644,
+                extra,
+            })
+            .unwrap();
+        writer.finalize().unwrap();
+
+        let entry = &ZipArchive::list(&zip_path).unwrap()[0];
+        assert_eq!(entry.times.mtime, UNIX_EPOCH + Duration::from_secs(900_000_000));
+        assert_eq!(entry.times.atime, Some(UNIX_EPOCH + Duration::from_secs(900_000_100)));
+        assert_eq!(entry.owner, Some((501, 20)));
+    }
+
+    #[test]
+    fn test_compatibility_report_flags_zstd() {
+        let tmp_dir = TempDir::new().unwrap();
+        let zip_path = tmp_dir.path().join("zstd.zip");
+        let mut writer = ZipWriter::new(&zip_path, CompressionLevel::NoCompression).unwrap();
+        writer.add_reader("plain.txt", &mut &b"plain"[..]).unwrap();
+        writer.add_reader("packed.zst", &mut &b"zstd"[..]).unwrap();
+        writer.finalize().unwrap();
+        drop(writer);
+
+        let archive = ZipArchive::open(&zip_path).unwrap();
+        let report = archive.compatibility_report().unwrap();
+        assert_eq!(report, CompatibilityReport::default());
+        assert!(report.is_fully_supported());
+
+        // 把第二个条目在中央目录中的压缩方法改为 93（zstd）
+        let mut bytes = fs::read(&zip_path).unwrap();
+        let cd = bytes
+            .windows(4)
+            .enumerate()
+            .filter(|(_, w)| *w == b"PK\x01\x02")
+            .map(|(pos, _)| pos)
+            .nth(1)
+            .unwrap();
+        bytes[cd + 10..cd + 12].copy_from_slice(&93u16.to_le_bytes());
+        fs::write(&zip_path, &bytes).unwrap();
+
+        let report = archive.compatibility_report().unwrap();
+        assert_eq!(report.unsupported_methods, [("packed.zst".to_string(), 93)]);
+        assert!(report.encrypted.is_empty() && report.zip64.is_empty());
+        assert!(!report.is_fully_supported());
+    }
+
+    #[test]
+    fn test_prefixed_archive_offsets() {
+        let big = "prefixed ".repeat(400);
+        let (tmp, zip_path) = make_zip(&[("a.txt", b"alpha\n"), ("dir/b.txt", big.as_bytes()), ("c.bin", &[0, 1, 2, 3])]);
+
+        // 直接在归档前面拼接自解压程序，中央目录中的偏移不做调整
+        let stub = b"#!/bin/sh\nexec unzip \"$0\"\n".repeat(8);
+        let mut bytes = stub.clone();
+        bytes.extend_from_slice(&fs::read(&zip_path).unwrap());
+        fs::write(&zip_path, &bytes).unwrap();
+
+        let reader = ZipReader::open(&zip_path).unwrap();
+        assert_eq!(reader.offset_base(), stub.len() as u64);
+        assert!(reader
+            .warnings()
+            .contains(&crate::zip::ReadWarning::OffsetsAdjusted { base: stub.len() as u64 }));
+
+        let archive = ZipArchive::open(&zip_path).unwrap();
+        let out = tmp.path().join("b.out");
+        archive.extract_to(1, &out).unwrap();
+        assert_eq!(fs::read(&out).unwrap(), big.as_bytes());
+
+        let mut streamed = Vec::new();
+        archive.entry_reader("c.bin").unwrap().read_to_end(&mut streamed).unwrap();
+        assert_eq!(streamed, [0, 1, 2, 3]);
+        let raw = reader.raw_entry_data(&reader.entries()[0]).unwrap();
+        assert_eq!(inflate::decompress_raw(&raw).unwrap(), b"alpha\n");
+
+        // 原样复制条目时偏移改写为文件内偏移，得到不再需要调整的归档
+        let filtered = tmp.path().join("filtered.zip");
+        archive
+            .copy_to_filtered(&mut File::create(&filtered).unwrap(), |name| name != "c.bin")
+            .unwrap();
+        let copied = ZipReader::open(&filtered).unwrap();
+        assert_eq!(copied.offset_base(), 0);
+        assert!(copied.warnings().is_empty());
+        let map = ZipArchive::open(&filtered).unwrap().extract_to_map().unwrap();
+        assert_eq!(map.len(), 2);
+        assert_eq!(map["a.txt"], b"alpha\n");
+        assert_eq!(map["dir/b.txt"], big.as_bytes());
+    }
+
+    #[test]
+    fn test_statistics() {
+        let tmp = TempDir::new().unwrap();
+        let zip_path = tmp.path().join("mixed.zip");
+        let mut writer = crate::zip::ZipWriter::new(&zip_path, CompressionLevel::Level6).unwrap();
+        writer.set_smart_store(true);
+        // gzip 魔数开头的内容直接存储：100 -> 100
+        let mut gz = vec![0x1f, 0x8b, 8, 0];
+        gz.extend_from_slice(&[0x5a; 96]);
+        writer.add_reader("a.gz", &mut gz.as_slice()).unwrap();
+        // 4000 个相同字节压缩为一个静态 Huffman 块
+        writer.add_reader("b.txt", &mut &[b'b'; 4000][..]).unwrap();
+        writer.add_directory_entry("dir/").unwrap();
+        writer.finalize().unwrap();
+
+        let archive = ZipArchive::open(&zip_path).unwrap();
+        let b_compressed = archive.entries().unwrap()[1].compressed_size;
+        assert!(b_compressed < 100);
+
+        let stats = archive.statistics().unwrap();
+        assert_eq!(
+            stats,
+            ArchiveStats {
+                entries: 3,
+                directories: 1,
+                stored: 1,
+                deflated: 1,
+                other: 0,
+                compressed_size: 100 + b_compressed,
+                uncompressed_size: 4100,
+            }
+        );
+        assert_eq!(stats.ratio(), (100 + b_compressed) as f64 / 4100.0);
+
+        let (_tmp, empty) = make_zip(&[]);
+        let stats = ZipArchive::open(&empty).unwrap().statistics().unwrap();
+        assert_eq!(stats, ArchiveStats::default());
+        assert_eq!(stats.ratio(), 1.0);
+    }
+
+    #[test]
+    fn test_method_histogram() {
+        let tmp = TempDir::new().unwrap();
+        let zip_path = tmp.path().join("mixed.zip");
+        let mut gz = vec![0x1f, 0x8b, 8, 0];
+        gz.extend_from_slice(&[0x5a; 96]);
+        let text = "deflate me ".repeat(100);
+        let mut writer = crate::zip::ZipWriter::new(&zip_path, CompressionLevel::Level6).unwrap();
+        writer.set_smart_store(true);
+        writer.add_reader("a.gz", &mut gz.as_slice()).unwrap();
+        writer.add_reader("b.txt", &mut text.as_bytes()).unwrap();
+        writer.add_reader("c.txt", &mut &b"short"[..]).unwrap();
+        writer.add_directory_entry("dir/").unwrap();
+        writer.finalize().unwrap();
+
+        let archive = ZipArchive::open(&zip_path).unwrap();
+        let histogram = archive.method_histogram().unwrap();
+        assert_eq!(histogram.len(), 2);
+        assert_eq!(histogram[&0], (2, 100, 100));
+
+        let deflated: u64 = archive
+            .entries()
+            .unwrap()
+            .iter()
+            .filter(|entry| entry.filename.ends_with(".txt"))
+            .map(|entry| entry.compressed_size)
+            .sum();
+        let (count, compressed, uncompressed) = histogram[&8];
+        assert_eq!(count, 2);
+        assert_eq!(compressed, deflated);
+        assert_eq!(uncompressed, text.len() as u64 + 5);
+        assert!(compressed < uncompressed);
+    }
+
+    #[test]
+    fn test_read_entry_allocates_exact_size() {
+        let text = "exact allocation ".repeat(1000);
+        let (_tmp, zip_path) = make_zip(&[("a.txt", text.as_bytes()), ("b.bin", &[7u8; 3])]);
+        let archive = ZipArchive::open(&zip_path).unwrap();
+
+        let data = archive.read_entry("a.txt").unwrap();
+        assert_eq!(data, text.as_bytes());
+        // 容量恰好等于解压后大小：既没有多分配，也没有中途扩容
+        assert_eq!(data.capacity(), text.len());
+
+        let entries = archive.entries().unwrap();
+        assert_eq!(entries[0].uncompressed_size, data.capacity() as u64);
+        assert_eq!(archive.read_entry("b.bin").unwrap(), [7, 7, 7]);
+        assert!(matches!(archive.read_entry("missing"), Err(ZipError::EntryNotFound { .. })));
+    }
+
+    #[test]
+    fn test_read_entry_by_index() {
+        let tmp_dir = TempDir::new().unwrap();
+        let src = tmp_dir.path().join("src");
+        fs::create_dir_all(src.join("dir")).unwrap();
+        fs::write(src.join("a.txt"), b"alpha\n").unwrap();
+        fs::write(src.join("dir").join("b.txt"), "bravo\n".repeat(100)).unwrap();
+        let zip_path = tmp_dir.path().join("test.zip");
+        ZipBuilder::new(&zip_path)
+            .unwrap()
+            .root(&src)
+            .files(&["a.txt", "dir"])
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let archive = ZipArchive::open(&zip_path).unwrap();
+        let by_name = archive.extract_to_map().unwrap();
+        let entries = archive.entries().unwrap();
+        for (index, entry) in entries.iter().enumerate() {
+            let data = archive.read_entry_by_index(index).unwrap();
+            if entry.is_directory {
+                assert!(data.is_empty());
+            } else {
+                assert_eq!(data, by_name[&entry.filename]);
+            }
+        }
+        let err = archive.read_entry_by_index(entries.len()).err().unwrap();
+        assert!(matches!(err, ZipError::EntryNotFound { .. }), "{:?}", err);
+    }
+
+    #[test]
+    fn test_recompress_to() {
+        let tmp_dir = TempDir::new().unwrap();
+        let src = tmp_dir.path().join("src");
+        fs::create_dir_all(src.join("dir")).unwrap();
+        let text: String = (0..3000)
+            .map(|i| format!("record {:05} value {}\n", i * 37 % 1000, i % 17))
+            .collect();
+        fs::write(src.join("dir").join("data.txt"), &text).unwrap();
+        fs::write(src.join("tiny.txt"), b"x").unwrap();
+
+        let level1 = tmp_dir.path().join("level1.zip");
+        ZipBuilder::new(&level1)
+            .unwrap()
+            .root(&src)
+            .files(&["dir", "tiny.txt"])
+            .unwrap()
+            .compression_level(CompressionLevel::Level1)
+            .build()
+            .unwrap();
+
+        let level9 = tmp_dir.path().join("level9.zip");
+        ZipArchive::open(&level1)
+            .unwrap()
+            .recompress_to(&level9, CompressionLevel::Level9)
+            .unwrap();
+
+        assert!(fs::metadata(&level9).unwrap().len() < fs::metadata(&level1).unwrap().len());
+        let before = ZipArchive::open(&level1).unwrap();
+        let after = ZipArchive::open(&level9).unwrap();
+        assert_eq!(after.extract_to_map().unwrap(), before.extract_to_map().unwrap());
+        let crcs = |archive: &ZipArchive| {
+            archive
+                .entries()
+                .unwrap()
+                .into_iter()
+                .map(|e| (e.filename, e.crc32, e.dos_time, e.external_attr))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(crcs(&after), crcs(&before));
+
+        // 压缩后不会变小的条目改为 STORE
+        let reader = ZipReader::open(&level9).unwrap();
+        let tiny = reader.entries().iter().find(|e| e.name == "tiny.txt").unwrap();
+        assert_eq!(tiny.compression_method, 0);
+    }
+
+    #[test]
+    fn test_recompress_to_keeps_zipcrypto_descriptor() {
+        let tmp_dir = TempDir::new().unwrap();
+        let zip_path = tmp_dir.path().join("secret.zip");
+        let content = b"top secret payload\n";
+        make_encrypted_descriptor_zip(&zip_path, "secret.txt", content, b"hunter2");
+        let archive = ZipArchive::open(&zip_path).unwrap().password("hunter2");
+        assert_eq!(archive.read_entry("secret.txt").unwrap(), content);
+
+        let out = tmp_dir.path().join("recompressed.zip");
+        archive.recompress_to(&out, CompressionLevel::Level9).unwrap();
+
+        // 原样复制后校验字节仍取自修改时间，正确的密码可以解密
+        let after = ZipArchive::open(&out).unwrap().password("hunter2");
+        assert_eq!(after.read_entry("secret.txt").unwrap(), content);
+        let reader = ZipReader::open(&out).unwrap();
+        let entry = &reader.entries()[0];
+        assert_ne!(entry.flags & FLAG_DATA_DESCRIPTOR, 0);
+        assert_eq!(
+            reader.data_descriptor(entry).unwrap().map(|d| (d.crc32, d.compressed_size)),
+            Some((crc32(0, content), content.len() as u64 + 12))
+        );
+    }
+
+    #[test]
+    fn test_stored_size_mismatch_is_corrupt() {
+        let tmp_dir = TempDir::new().unwrap();
+        let zip_path = tmp_dir.path().join("mismatch.zip");
+        let content = b"stored payload\n";
+        // 压缩大小 15，解压大小声称 40
+        make_stored_zip(&zip_path, "data.txt", 0, crc32(0, content), content, 40);
+
+        let archive = ZipArchive::open(&zip_path).unwrap();
+        match archive.extract_to_map() {
+            Err(ZipError::CorruptEntry { name, reason, .. }) => {
+                assert_eq!(name, "data.txt");
+                assert!(reason.contains("15") && reason.contains("40"), "{}", reason);
+            }
+            other => panic!("expected CorruptEntry, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_degenerate_header_values() {
+        use crate::unzip::Extractor;
+        use std::time::UNIX_EPOCH;
+
+        let tmp_dir = TempDir::new().unwrap();
+        let zip_path = tmp_dir.path().join("minimal.zip");
+        let content = b"minimal writer\n";
+        make_stored_zip(&zip_path, "data.txt", 0, crc32(0, content), content, content.len() as u32);
+
+        // version needed / version made by 为 0，DOS 日期为 0，内部属性为无意义的值
+        let mut bytes = fs::read(&zip_path).unwrap();
+        let cd = 30 + "data.txt".len() + content.len();
+        bytes[4..6].fill(0);
+        bytes[12..14].fill(0);
+        bytes[cd + 4..cd + 8].fill(0);
+        bytes[cd + 14..cd + 16].fill(0);
+        bytes[cd + 36..cd + 38].fill(0xFF);
+        fs::write(&zip_path, &bytes).unwrap();
+
+        let entries = ZipArchive::list(&zip_path).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].timestamp, UNIX_EPOCH);
+        assert_eq!(entries[0].permissions, 0o600);
+
+        let out = tmp_dir.path().join("out");
+        Extractor::new(&zip_path).unwrap().exdir(&out).extract().unwrap();
+        let path = out.join("data.txt");
+        assert_eq!(fs::read(&path).unwrap(), content);
+        assert_eq!(fs::metadata(&path).unwrap().modified().unwrap(), UNIX_EPOCH);
+    }
+
+    #[test]
+    fn test_directory_detection_precedence() {
+        use crate::zip::ReadWarning;
+
+        let tmp_dir = TempDir::new().unwrap();
+        // (名字, 内容, external_attr) -> (是否为目录, 警告)
+        let open = |file: &str, name: &str, body: &[u8], attr: u32| {
+            let zip_path = tmp_dir.path().join(file);
+            make_stored_zip(&zip_path, name, 0, crc32(0, body), body, body.len() as u32);
+            let mut bytes = fs::read(&zip_path).unwrap();
+            let cd = 30 + name.len() + body.len();
+            bytes[cd + 38..cd + 42].copy_from_slice(&attr.to_le_bytes());
+            fs::write(&zip_path, &bytes).unwrap();
+            let reader = ZipReader::open(&zip_path).unwrap();
+            (reader.entries()[0].is_dir, reader.warnings().to_vec())
+        };
+
+        // 结尾的 `/` 是决定性的
+        assert_eq!(open("slash.zip", "dir/", b"", 0), (true, vec![]));
+        assert_eq!(open("slash_attr.zip", "dir/", b"", 0x10), (true, vec![]));
+        assert_eq!(
+            open("slash_data.zip", "dir/", b"data", 0),
+            (true, vec![ReadWarning::DirectoryWithData { name: "dir/".to_string(), size: 4 }])
+        );
+
+        // 与 C 版本一致，DOS 目录属性同样决定为目录，带有数据时只给出警告
+        assert_eq!(open("attr.zip", "dir", b"", 0x10), (true, vec![]));
+        assert_eq!(
+            open("attr_data.zip", "file", b"data", 0x10),
+            (true, vec![ReadWarning::DirectoryAttributeWithData { name: "file".to_string(), size: 4 }])
+        );
+        assert_eq!(open("file.zip", "file", b"data", 0), (false, vec![]));
+    }
+
+    #[test]
+    fn test_verify_crc_toggle() {
+        use crate::unzip::Extractor;
+
+        let tmp_dir = TempDir::new().unwrap();
+        let zip_path = tmp_dir.path().join("badcrc.zip");
+        let content = b"garbage payload\n";
+        make_stored_zip(&zip_path, "data.txt", 0, crc32(0, content) ^ 1, content, content.len() as u32);
+
+        let archive = ZipArchive::open(&zip_path).unwrap();
+        match archive.read_entry_by_index(0) {
+            Err(ZipError::CorruptEntry { reason, .. }) => assert!(reason.contains("CRC32"), "{}", reason),
+            other => panic!("expected CorruptEntry, got {:?}", other),
+        }
+        let archive = archive.verify_crc(false);
+        assert_eq!(archive.read_entry_by_index(0).unwrap(), content);
+
+        let exdir = tmp_dir.path().join("out");
+        assert!(Extractor::new(&zip_path).unwrap().exdir(&exdir).extract().is_err());
+        assert!(!exdir.join("data.txt").exists());
+        Extractor::new(&zip_path)
+            .unwrap()
+            .exdir(&exdir)
+            .verify_crc(false)
+            .extract()
+            .unwrap();
+        assert_eq!(fs::read(exdir.join("data.txt")).unwrap(), content);
+    }
+
+    #[test]
+    fn test_local_name_mismatch() {
+        use crate::unzip::Extractor;
+
+        let tmp_dir = TempDir::new().unwrap();
+        let zip_path = tmp_dir.path().join("renamed.zip");
+        let content = b"payload\n";
+        make_stored_zip(&zip_path, "data.txt", 0, crc32(0, content), content, content.len() as u32);
+        // 只改本地文件头中的文件名（紧跟 30 字节固定部分）
+        let mut bytes = fs::read(&zip_path).unwrap();
+        bytes[30..38].copy_from_slice(b"evil.txt");
+        fs::write(&zip_path, bytes).unwrap();
+
+        let archive = ZipArchive::open(&zip_path).unwrap();
+        assert_eq!(archive.entries().unwrap()[0].local_name, None);
+        let entries = archive.entries_with_local_names().unwrap();
+        assert_eq!(entries[0].filename, "data.txt");
+        assert_eq!(entries[0].local_name.as_deref(), Some("evil.txt"));
+        assert_eq!(archive.read_entry_by_index(0).unwrap(), content);
+
+        let strict = archive.strict_names(true);
+        for result in [strict.read_entry_by_index(0).map(|_| ()), strict.entries_with_local_names().map(|_| ())] {
+            match result {
+                Err(ZipError::CorruptEntry { name, reason, .. }) => {
+                    assert_eq!(name, "data.txt");
+                    assert!(reason.contains("evil.txt"), "{}", reason);
+                }
+                other => panic!("expected CorruptEntry, got {:?}", other),
+            }
+        }
+
+        let exdir = tmp_dir.path().join("out");
+        assert!(Extractor::new(&zip_path)
+            .unwrap()
+            .exdir(&exdir)
+            .strict_names(true)
+            .extract()
+            .is_err());
+        Extractor::new(&zip_path).unwrap().exdir(&exdir).extract().unwrap();
+        assert_eq!(fs::read(exdir.join("data.txt")).unwrap(), content);
+    }
+
+    #[test]
+    fn test_legacy_method_rejected_by_name() {
+        use crate::unzip::streaming::StreamingZipReader;
+        use crate::unzip::Extractor;
+
+        let tmp_dir = TempDir::new().unwrap();
+        let zip_path = tmp_dir.path().join("implode.zip");
+        let body = b"\x0d\x02\x01\x12\x23\x14\x15\x36";
+        make_stored_zip(&zip_path, "old.txt", 0, 0x1234_5678, body, 64);
+        // 本地头与中央目录中的压缩方法都改为 6（implode）
+        let mut bytes = fs::read(&zip_path).unwrap();
+        let cd_offset = 30 + "old.txt".len() + body.len();
+        bytes[8..10].copy_from_slice(&6u16.to_le_bytes());
+        bytes[cd_offset + 10..cd_offset + 12].copy_from_slice(&6u16.to_le_bytes());
+        fs::write(&zip_path, bytes).unwrap();
+
+        let err = ZipArchive::open(&zip_path).unwrap().read_entry_by_index(0).unwrap_err();
+        assert!(matches!(err, ZipError::UnsupportedCompression { method: 6 }), "{:?}", err);
+        assert!(err.to_string().contains("implode"), "{}", err);
+
+        let err = Extractor::new(&zip_path)
+            .unwrap()
+            .exdir(tmp_dir.path().join("out"))
+            .extract()
+            .unwrap_err();
+        assert!(matches!(err, ZipError::UnsupportedCompression { method: 6 }), "{:?}", err);
+
+        // 其他已知但未实现的方法同样报告为不支持，而不是损坏
+        for method in [7u16, 9, 10, 12, 14, 93, 95, 98, 99, 200] {
+            let mut bytes = fs::read(&zip_path).unwrap();
+            bytes[8..10].copy_from_slice(&method.to_le_bytes());
+            bytes[cd_offset + 10..cd_offset + 12].copy_from_slice(&method.to_le_bytes());
+            fs::write(&zip_path, &bytes).unwrap();
+
+            let archive = ZipArchive::open(&zip_path).unwrap();
+            let err = archive.read_entry_by_index(0).unwrap_err();
+            assert!(matches!(err, ZipError::UnsupportedCompression { method: m } if m == method), "{:?}", err);
+            let err = archive.entry_reader("old.txt").err().unwrap();
+            assert!(matches!(err, ZipError::UnsupportedCompression { method: m } if m == method), "{:?}", err);
+            let err = StreamingZipReader::new(&bytes[..]).next_entry().unwrap_err();
+            assert!(matches!(err, ZipError::UnsupportedCompression { method: m } if m == method), "{:?}", err);
+        }
+    }
+
+    #[test]
+    fn test_corrupt_huffman_table_exposes_inflate_error() {
+        use crate::miniz::deflate::DeflateImpl;
+        use crate::miniz::inflate::InflateError;
+        use std::error::Error;
+
+        // 只有四种字节、几乎没有重复串，动态 Huffman 表明显优于静态表
+        let mut seed = 1u32;
+        let content: Vec<u8> = (0..20000)
+            .map(|_| {
+                seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                b"acgt"[(seed >> 16) as usize % 4]
+            })
+            .collect();
+        let tmp_dir = TempDir::new().unwrap();
+        fs::write(tmp_dir.path().join("data.txt"), &content).unwrap();
+        let zip_path = tmp_dir.path().join("test.zip");
+        ZipBuilder::new(&zip_path)
+            .unwrap()
+            .root(tmp_dir.path())
+            .algorithm(DeflateImpl::Good)
+            .files(&["data.txt"])
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let mut bytes = fs::read(&zip_path).unwrap();
+        let name_len = u16::from_le_bytes([bytes[26], bytes[27]]) as usize;
+        let extra_len = u16::from_le_bytes([bytes[28], bytes[29]]) as usize;
+        let data = 30 + name_len + extra_len;
+        // 第一个块使用动态 Huffman 表
+        assert_eq!((bytes[data] >> 1) & 3, 2);
+        // 块头 3 位 + HLIT/HDIST/HCLEN 14 位之后是码长码的码长，
+        // 前四个都改为 1：四个 1 位码超额分配，表无法构建
+        for k in 0..4 {
+            for (offset, one) in [(0, true), (1, false), (2, false)] {
+                let bit = 17 + 3 * k + offset;
+                if one {
+                    bytes[data + bit / 8] |= 1 << (bit % 8);
+                } else {
+                    bytes[data + bit / 8] &= !(1 << (bit % 8));
+                }
+            }
+        }
+        fs::write(&zip_path, bytes).unwrap();
+
+        let err = ZipArchive::open(&zip_path).unwrap().read_entry("data.txt").unwrap_err();
+        assert!(matches!(err, ZipError::InflateFailed { .. }), "{:?}", err);
+        let source = err.source().and_then(|e| e.downcast_ref::<InflateError>());
+        assert!(matches!(source, Some(InflateError::InvalidCode)), "{:?}", err);
+        assert_eq!(err.to_c_code(), crate::error::ZipErrorCode::BrokenEntry);
+
+        // 截断的数据报告为输入不足
+        assert!(matches!(
+            inflate::decompress_raw(&crate::miniz::deflate::compress_raw(&content, 6).unwrap()[..100]),
+            Err(InflateError::NeedMoreInput)
+        ));
+    }
+
+    #[test]
+    fn test_list_encrypted_without_password() {
+        use crate::unzip::Extractor;
+
+        let tmp_dir = TempDir::new().unwrap();
+        let zip_path = tmp_dir.path().join("secret.zip");
+        let content = b"top secret payload\n";
+        make_encrypted_zip(&zip_path, "secret.txt", content, b"hunter2");
+
+        let entries = ZipArchive::list(&zip_path).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].filename, "secret.txt");
+        assert_eq!(entries[0].uncompressed_size, content.len() as u64);
+        assert_eq!(entries[0].compressed_size, content.len() as u64 + 12);
+        assert_eq!(entries[0].crc32, crc32(0, content));
+        assert!(entries[0].is_encrypted);
+
+        let err = Extractor::new(&zip_path)
+            .unwrap()
+            .exdir(tmp_dir.path().join("out"))
+            .extract()
+            .unwrap_err();
+        assert!(matches!(err, ZipError::PasswordRequired { ref name, .. } if name == "secret.txt"), "{}", err);
+
+        let map = ZipArchive::open(&zip_path)
+            .unwrap()
+            .password("hunter2")
+            .extract_to_map()
+            .unwrap();
+        assert_eq!(map["secret.txt"], content);
+
+        let err = ZipArchive::open(&zip_path)
+            .unwrap()
+            .password("letmein")
+            .extract_to_map()
+            .unwrap_err();
+        assert!(matches!(err, ZipError::InvalidPassword { .. }), "{}", err);
+    }
 }
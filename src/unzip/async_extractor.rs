@@ -0,0 +1,390 @@
+//! 基于 tokio 的异步 Extractor
+//!
+//! 文件读写走 `tokio::fs` / `tokio::io`，CPU 密集的解压与 CRC 校验以及元数据还原
+//! 通过 `spawn_blocking` 放到阻塞线程池，解压和还原逻辑与同步版本共用。
+
+use crate::cancel::CancelToken;
+use crate::error::{PreserveSet, Result, ZipError};
+use crate::platform::{current_platform, Platform};
+use crate::unzip::archive::{
+    decode_entry_data, decrypt_entry_data, is_symlink_attr, to_zip_entry_in, LocalHeader,
+};
+use crate::unzip::entry_name::{classify_entry_name, ensure_no_symlink_escape, is_safe_entry_name};
+use crate::unzip::extractor::{ExtractorOptions, Layout, SymlinkPolicy};
+use crate::zip::reader::{ZipEntryInfo, ZipReader};
+use std::borrow::Cow;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::fs::{self, File};
+use tokio::io::{AsyncReadExt, AsyncSeekExt, SeekFrom};
+
+/// 异步 ZIP Extractor，各选项的含义与 [`Extractor`](crate::unzip::Extractor) 的同名选项相同
+///
+/// 写出每个文件后按 `restore` 还原修改时间、权限等元数据，还原逻辑与同步版本共用；
+/// 元数据相关的警告只记录到日志。
+pub struct AsyncExtractor {
+    zipfile: PathBuf,
+    options: ExtractorOptions,
+}
+
+impl AsyncExtractor {
+    pub fn new(zipfile: impl AsRef<Path>) -> Result<Self> {
+        Ok(Self {
+            zipfile: zipfile.as_ref().to_path_buf(),
+            options: ExtractorOptions::default(),
+        })
+    }
+
+    pub fn overwrite(mut self, overwrite: bool) -> Self {
+        self.options.overwrite = overwrite;
+        self
+    }
+
+    /// 丢弃路径，等价于 `layout(Layout::Flat)`；`false` 恢复为 `Layout::AsStored`
+    pub fn junk_paths(mut self, junk_paths: bool) -> Self {
+        self.options.layout = if junk_paths { Layout::Flat } else { Layout::AsStored };
+        self
+    }
+
+    /// 设置解压布局
+    pub fn layout(mut self, layout: Layout) -> Self {
+        self.options.layout = layout;
+        self
+    }
+
+    pub fn exdir(mut self, exdir: impl AsRef<Path>) -> Self {
+        self.options.exdir = exdir.as_ref().to_path_buf();
+        self
+    }
+
+    pub fn files(mut self, files: &[impl AsRef<str>]) -> Self {
+        self.options.files = Some(files.iter().map(|f| f.as_ref().to_string()).collect());
+        self
+    }
+
+    /// 设置取消令牌，在每个条目之前检查
+    pub fn cancel_token(mut self, token: CancelToken) -> Self {
+        self.options.cancel = Some(token);
+        self
+    }
+
+    /// 设置解密加密条目使用的密码
+    pub fn password(mut self, password: impl AsRef<[u8]>) -> Self {
+        self.options.password = Some(password.as_ref().to_vec());
+        self
+    }
+
+    /// 设置符号链接条目的处理方式
+    pub fn symlink_policy(mut self, policy: SymlinkPolicy) -> Self {
+        self.options.symlink_policy = policy;
+        self
+    }
+
+    /// 解压时是否校验 CRC32，默认开启；关闭后损坏的条目会被原样写出
+    pub fn verify_crc(mut self, verify: bool) -> Self {
+        self.options.verify_crc = verify;
+        self
+    }
+
+    /// 是否清理条目权限（默认开启），见 [`Extractor::sanitize_permissions`](crate::unzip::Extractor::sanitize_permissions)
+    pub fn sanitize_permissions(mut self, sanitize: bool) -> Self {
+        self.options.sanitize_permissions = sanitize;
+        self
+    }
+
+    /// 选择要还原的文件元数据，默认 `PERMISSIONS | MTIME | SYMLINKS`，
+    /// 见 [`Extractor::restore`](crate::unzip::Extractor::restore)
+    pub fn restore(mut self, set: PreserveSet) -> Self {
+        self.options.restore = set;
+        self
+    }
+
+    /// 执行提取
+    pub async fn extract(self) -> Result<()> {
+        // 中央目录解析是同步 I/O，放到阻塞线程池
+        let zipfile = self.zipfile.clone();
+        let reader = spawn_blocking(move || ZipReader::open(&zipfile)).await?;
+
+        // 与同步版本相同：写入任何文件之前拒绝不安全的条目名
+        if let Some(entry) = reader
+            .entries()
+            .iter()
+            .filter(|entry| self.options.selects(&entry.name))
+            .find(|entry| !is_safe_entry_name(&entry.name))
+        {
+            return Err(ZipError::InvalidPath {
+                path: entry.name.clone(),
+                reason: format!("unsafe entry name ({:?})", classify_entry_name(&entry.name)),
+            });
+        }
+
+        fs::create_dir_all(&self.options.exdir).await.map_err(|e| {
+            ZipError::generic(format!("Failed to create extract directory: {:?}", e))
+        })?;
+
+        let mut file = File::open(&self.zipfile).await.map_err(|e| ZipError::FileOpen {
+            path: self.zipfile.clone(),
+            source: e,
+        })?;
+
+        // 还原元数据在阻塞线程池中进行，共享同一份选项
+        let options = Arc::new(self.options.clone());
+
+        for entry in reader.entries() {
+            if !self.options.selects(&entry.name) {
+                continue;
+            }
+            if let Some(cancel) = &self.options.cancel {
+                cancel.check()?;
+            }
+
+            let output_path = match self
+                .options
+                .output_path(&self.zipfile, &entry.name, entry.is_dir)
+            {
+                Some(path) => path,
+                None => continue,
+            };
+
+            // 不经由已解压的符号链接写到 exdir 之外
+            ensure_no_symlink_escape(&self.options.exdir, &output_path)?;
+
+            if entry.is_dir {
+                fs::create_dir_all(&output_path).await.map_err(|e| {
+                    ZipError::generic(format!(
+                        "Failed to create directory {}: {:?}",
+                        output_path.display(),
+                        e
+                    ))
+                })?;
+                continue;
+            }
+
+            let as_symlink = is_symlink_attr(entry.external_attr);
+            if as_symlink && self.options.symlink_policy == SymlinkPolicy::Skip {
+                log::warn!("skipping symlink {}", entry.name);
+                continue;
+            }
+
+            if !self.options.overwrite && fs::try_exists(&output_path).await.unwrap_or(false) {
+                continue;
+            }
+
+            let data = self.read_entry(&mut file, entry).await?;
+            let create_symlink = as_symlink && self.options.creates_symlinks();
+            write_entry(&output_path, data, create_symlink).await?;
+
+            let options = Arc::clone(&options);
+            let archive = self.zipfile.clone();
+            let info = entry.clone();
+            spawn_blocking(move || {
+                let entry = to_zip_entry_in(&archive, &info);
+                options.restore_metadata(&entry, &output_path, create_symlink, &mut Vec::new())
+            })
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    /// 异步读取条目的压缩数据，在阻塞线程池中解压并校验 CRC32
+    async fn read_entry(&self, file: &mut File, entry: &ZipEntryInfo) -> Result<Vec<u8>> {
+        file.seek(SeekFrom::Start(entry.local_header_offset))
+            .await
+            .map_err(|e| ZipError::generic(format!("Failed to seek to local header: {:?}", e)))?;
+
+        let mut local_header = [0u8; 30];
+        file.read_exact(&mut local_header)
+            .await
+            .map_err(|e| ZipError::generic(format!("Failed to read local header: {:?}", e)))?;
+        let header = LocalHeader::parse(&self.zipfile, entry, &local_header)?;
+
+        // 跳过文件名和 extra field
+        let skip = (header.name_len + header.extra_len) as i64;
+        file.seek(SeekFrom::Current(skip))
+            .await
+            .map_err(|e| ZipError::generic(format!("Failed to skip filename/extra: {:?}", e)))?;
+
+        let mut compressed_data = vec![0u8; header.compressed_size as usize];
+        file.read_exact(&mut compressed_data)
+            .await
+            .map_err(|e| ZipError::CorruptEntry {
+                name: entry.name.clone(),
+                archive: self.zipfile.clone(),
+                reason: format!("unexpected end of data: {}", e),
+            })?;
+
+        let archive = self.zipfile.clone();
+        let entry = entry.clone();
+        let password = self.options.password.clone();
+        let verify_crc = self.options.verify_crc;
+        spawn_blocking(move || {
+            let mut header = header;
+            let compressed_data = decrypt_entry_data(
+                &archive,
+                &entry,
+                &mut header,
+                password.as_deref(),
+                Cow::Owned(compressed_data),
+            )?;
+            decode_entry_data(
+                &archive,
+                &entry.name,
+                header.compression_method,
+                entry.uncompressed_size,
+                header.expected_crc().filter(|_| verify_crc),
+                compressed_data,
+            )
+        })
+        .await
+    }
+}
+
+/// 写出解压后的条目；`create_symlink` 为 true 时数据是链接目标，还原为符号链接
+async fn write_entry(output: &Path, data: Vec<u8>, create_symlink: bool) -> Result<()> {
+    if let Some(parent) = output.parent() {
+        fs::create_dir_all(parent).await.map_err(|e| {
+            ZipError::generic(format!("Failed to create output directory: {:?}", e))
+        })?;
+    }
+
+    if create_symlink {
+        let target = String::from_utf8_lossy(&data).to_string();
+        let output = output.to_path_buf();
+        return spawn_blocking(move || {
+            current_platform()
+                .create_symlink(Path::new(&target), &output)
+                .map_err(|e| {
+                    ZipError::generic(format!(
+                        "Failed to create symlink '{}' -> '{}': {:?}",
+                        output.display(),
+                        target,
+                        e
+                    ))
+                })
+        })
+        .await;
+    }
+
+    fs::write(output, data).await.map_err(|e| ZipError::OpenWriteFailed {
+        path: output.to_path_buf(),
+        source: e,
+    })
+}
+
+/// 在阻塞线程池中运行 `f`
+async fn spawn_blocking<T, F>(f: F) -> Result<T>
+where
+    F: FnOnce() -> Result<T> + Send + 'static,
+    T: Send + 'static,
+{
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|e| ZipError::generic(format!("Blocking task failed: {:?}", e)))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::unzip::Extractor;
+    use crate::zip::ZipBuilder;
+    use std::collections::BTreeMap;
+    use std::time::{Duration, SystemTime};
+    use tempfile::TempDir;
+
+    /// 解压结果中每个路径的 (内容或链接目标, 权限位, 修改时间)；目录和符号链接不比较时间
+    fn read_tree(root: &Path) -> BTreeMap<String, (Vec<u8>, u32, Option<SystemTime>)> {
+        let mut out = BTreeMap::new();
+        let mut stack = vec![root.to_path_buf()];
+        while let Some(dir) = stack.pop() {
+            for entry in std::fs::read_dir(&dir).unwrap() {
+                let path = entry.unwrap().path();
+                let rel = path.strip_prefix(root).unwrap().to_string_lossy().to_string();
+                let metadata = std::fs::symlink_metadata(&path).unwrap();
+                #[cfg(unix)]
+                let mode = std::os::unix::fs::PermissionsExt::mode(&metadata.permissions()) & 0o7777;
+                #[cfg(not(unix))]
+                let mode = metadata.permissions().readonly() as u32;
+                if metadata.is_dir() {
+                    out.insert(rel + "/", (Vec::new(), mode, None));
+                    stack.push(path);
+                } else if metadata.file_type().is_symlink() {
+                    let target = std::fs::read_link(&path).unwrap();
+                    out.insert(rel, (target.to_string_lossy().into_owned().into_bytes(), 0, None));
+                } else {
+                    let mtime = metadata.modified().ok();
+                    out.insert(rel, (std::fs::read(&path).unwrap(), mode, mtime));
+                }
+            }
+        }
+        out
+    }
+
+    #[tokio::test]
+    async fn test_async_matches_sync() {
+        let temp = TempDir::new().unwrap();
+        let src = temp.path().join("src");
+        std::fs::create_dir_all(src.join("dir").join("sub")).unwrap();
+        std::fs::write(src.join("a.txt"), b"alpha\n").unwrap();
+        std::fs::write(src.join("dir").join("b.txt"), b"bravo\n").unwrap();
+        std::fs::write(src.join("dir").join("sub").join("c.bin"), [0u8, 1, 2, 3, 255]).unwrap();
+        // 偶数秒，DOS 时间可以精确表示
+        let mtime = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000_000);
+        for file in ["a.txt", "dir/b.txt", "dir/sub/c.bin"] {
+            let file = std::fs::File::options().write(true).open(src.join(file)).unwrap();
+            file.set_times(std::fs::FileTimes::new().set_modified(mtime)).unwrap();
+        }
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::Permissions::from_mode(0o751);
+            std::fs::set_permissions(src.join("dir").join("b.txt"), mode).unwrap();
+            std::os::unix::fs::symlink("a.txt", src.join("link")).unwrap();
+        }
+        let zipfile = temp.path().join("test.zip");
+        let files: &[&str] = if cfg!(unix) { &["a.txt", "dir", "link"] } else { &["a.txt", "dir"] };
+        ZipBuilder::new(&zipfile)
+            .unwrap()
+            .root(&src)
+            .files(files)
+            .unwrap()
+            .preserve(PreserveSet::PERMISSIONS | PreserveSet::MTIME | PreserveSet::SYMLINKS)
+            .build()
+            .unwrap();
+
+        let restores = [
+            PreserveSet::PERMISSIONS | PreserveSet::MTIME | PreserveSet::SYMLINKS,
+            PreserveSet::PERMISSIONS | PreserveSet::MTIME,
+        ];
+        for (i, restore) in restores.into_iter().enumerate() {
+            let sync_out = temp.path().join(format!("sync-{}", i));
+            Extractor::new(&zipfile)
+                .unwrap()
+                .exdir(&sync_out)
+                .restore(restore)
+                .extract()
+                .unwrap();
+
+            let async_out = temp.path().join(format!("async-{}", i));
+            AsyncExtractor::new(&zipfile)
+                .unwrap()
+                .exdir(&async_out)
+                .restore(restore)
+                .extract()
+                .await
+                .unwrap();
+
+            let expected = read_tree(&sync_out);
+            assert_eq!(expected.len(), if cfg!(unix) { 6 } else { 5 });
+            assert_eq!(expected["a.txt"].2, Some(mtime));
+            #[cfg(unix)]
+            {
+                assert_eq!(expected["dir/b.txt"].1, 0o751);
+                // 未选 SYMLINKS 时链接写成内容为链接目标的普通文件
+                let link_is_file = !restore.contains(PreserveSet::SYMLINKS);
+                assert_eq!(expected["link"].2.is_some(), link_is_file);
+            }
+            assert_eq!(read_tree(&async_out), expected);
+        }
+    }
+}
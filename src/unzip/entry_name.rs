@@ -0,0 +1,198 @@
+//! 条目名安全检查
+//!
+//! 解压前用于拒绝可能写出 exdir 之外（Zip Slip）或在某些平台上含义不同的条目名。
+//! `Extractor` 使用同一套规则。写入归档前可用 [`normalize_entry_name`] 得到规范的条目名。
+//!
+//! 条目名本身安全时，仍可能经由先前解压出的符号链接写到 exdir 之外
+//! （如先解压 `evil -> /etc`，再解压 `evil/passwd`），写入前用
+//! [`ensure_no_symlink_escape`] 检查输出路径。
+
+use crate::error::{Result, ZipError};
+use std::fs;
+use std::path::Path;
+
+/// 条目名的分类结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryNameClass {
+    /// 绝对路径：以 `/` 开头，或带有 Windows 盘符（如 `C:`）
+    Absolute,
+    /// 含有 `..` 路径组件
+    ParentTraversal,
+    /// 含有反斜杠，在 Windows 上会被当作路径分隔符
+    Backslash,
+    /// 含有 NUL 字节
+    Nul,
+    /// 可以安全地解压到目标目录下
+    Safe,
+}
+
+/// 对条目名分类
+///
+/// 检查顺序：NUL、绝对路径、反斜杠、`..` 组件。
+pub fn classify_entry_name(name: &str) -> EntryNameClass {
+    if name.contains('\0') {
+        return EntryNameClass::Nul;
+    }
+
+    let bytes = name.as_bytes();
+    if name.starts_with('/') || (bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':') {
+        return EntryNameClass::Absolute;
+    }
+
+    if name.contains('\\') {
+        return EntryNameClass::Backslash;
+    }
+
+    if name.split('/').any(|component| component == "..") {
+        return EntryNameClass::ParentTraversal;
+    }
+
+    EntryNameClass::Safe
+}
+
+/// 条目名是否可以安全解压
+pub fn is_safe_entry_name(name: &str) -> bool {
+    classify_entry_name(name) == EntryNameClass::Safe
+}
+
+/// 校验并规范化要写入归档的条目名
+///
+/// 反斜杠转为 `/`，去掉开头的 `/`，合并连续的 `/`，去掉 `.` 组件，保留表示目录的结尾 `/`。
+/// 含 NUL、盘符（如 `C:`）或 `..` 组件，以及规范化后为空的名字返回 `ZipError::InvalidPath`。
+pub fn normalize_entry_name(name: &str) -> Result<String> {
+    let invalid = |reason: &str| ZipError::InvalidPath {
+        path: name.to_string(),
+        reason: reason.to_string(),
+    };
+
+    if name.contains('\0') {
+        return Err(invalid("entry name contains a NUL byte"));
+    }
+    let bytes = name.as_bytes();
+    if bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':' {
+        return Err(invalid("entry name has a drive letter"));
+    }
+
+    let unified = name.replace('\\', "/");
+    let mut components = Vec::new();
+    for component in unified.split('/') {
+        match component {
+            "" | "." => {}
+            ".." => return Err(invalid("entry name contains a '..' component")),
+            _ => components.push(component),
+        }
+    }
+    if components.is_empty() {
+        return Err(invalid("entry name is empty"));
+    }
+
+    let mut normalized = components.join("/");
+    if unified.ends_with('/') {
+        normalized.push('/');
+    }
+    Ok(normalized)
+}
+
+/// 确认写入 `output` 不会经由符号链接离开 `exdir`
+///
+/// 逐级检查 `output` 相对 `exdir` 的各个路径组件（包括 `output` 本身）：
+/// 已存在的符号链接必须解析到 exdir 之内，无法解析的悬空链接同样拒绝。
+/// 不在 `exdir` 之下的 `output` 不做检查。
+pub(crate) fn ensure_no_symlink_escape(exdir: &Path, output: &Path) -> Result<()> {
+    let Ok(relative) = output.strip_prefix(exdir) else {
+        return Ok(());
+    };
+
+    let mut root = None;
+    let mut current = exdir.to_path_buf();
+    for component in relative.components() {
+        current.push(component);
+        match fs::symlink_metadata(&current) {
+            Ok(metadata) if metadata.file_type().is_symlink() => {}
+            Ok(_) => continue,
+            // 不存在的路径之下不会再有符号链接
+            Err(_) => break,
+        }
+
+        if root.is_none() {
+            root = Some(fs::canonicalize(exdir).map_err(|e| {
+                ZipError::generic(format!(
+                    "Failed to resolve extract directory {}: {:?}",
+                    exdir.display(),
+                    e
+                ))
+            })?);
+        }
+        let inside = matches!(
+            (fs::canonicalize(&current), &root),
+            (Ok(resolved), Some(root)) if resolved.starts_with(root)
+        );
+        if !inside {
+            return Err(ZipError::InvalidPath {
+                path: relative.to_string_lossy().to_string(),
+                reason: format!(
+                    "symlink {} points outside the extract directory",
+                    current.display()
+                ),
+            });
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_entry_name() {
+        assert_eq!(classify_entry_name("../x"), EntryNameClass::ParentTraversal);
+        assert_eq!(classify_entry_name("a/../../x"), EntryNameClass::ParentTraversal);
+        assert_eq!(classify_entry_name("/x"), EntryNameClass::Absolute);
+        assert_eq!(classify_entry_name("C:/x"), EntryNameClass::Absolute);
+        assert_eq!(classify_entry_name("a\\b"), EntryNameClass::Backslash);
+        assert_eq!(classify_entry_name("a\0b"), EntryNameClass::Nul);
+        assert_eq!(classify_entry_name("a/b/c"), EntryNameClass::Safe);
+        assert_eq!(classify_entry_name("dir/"), EntryNameClass::Safe);
+        assert_eq!(classify_entry_name("a..b/c"), EntryNameClass::Safe);
+
+        assert!(is_safe_entry_name("a/b/c"));
+        assert!(!is_safe_entry_name("../x"));
+    }
+
+    #[test]
+    fn test_normalize_entry_name() {
+        assert_eq!(normalize_entry_name("/abs").unwrap(), "abs");
+        assert_eq!(normalize_entry_name("a//b").unwrap(), "a/b");
+        assert_eq!(normalize_entry_name("a\\b").unwrap(), "a/b");
+        assert_eq!(normalize_entry_name("./a/./b/").unwrap(), "a/b/");
+        assert_eq!(normalize_entry_name("dir/file.txt").unwrap(), "dir/file.txt");
+        assert_eq!(normalize_entry_name("dir/").unwrap(), "dir/");
+
+        for name in ["a/../b", "..", "C:/x", "a\0b", "", "//"] {
+            assert!(
+                matches!(normalize_entry_name(name), Err(ZipError::InvalidPath { .. })),
+                "{:?} should be rejected",
+                name
+            );
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_ensure_no_symlink_escape() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let exdir = temp.path().join("out");
+        fs::create_dir_all(exdir.join("dir")).unwrap();
+        std::os::unix::fs::symlink("dir", exdir.join("inside")).unwrap();
+        std::os::unix::fs::symlink(temp.path(), exdir.join("outside")).unwrap();
+        std::os::unix::fs::symlink("missing", exdir.join("dangling")).unwrap();
+
+        assert!(ensure_no_symlink_escape(&exdir, &exdir.join("dir/a.txt")).is_ok());
+        assert!(ensure_no_symlink_escape(&exdir, &exdir.join("new/a.txt")).is_ok());
+        assert!(ensure_no_symlink_escape(&exdir, &exdir.join("inside/a.txt")).is_ok());
+        assert!(ensure_no_symlink_escape(&exdir, &exdir.join("outside/a.txt")).is_err());
+        assert!(ensure_no_symlink_escape(&exdir, &exdir.join("outside")).is_err());
+        assert!(ensure_no_symlink_escape(&exdir, &exdir.join("dangling")).is_err());
+    }
+}
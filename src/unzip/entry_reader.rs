@@ -0,0 +1,179 @@
+//! 条目的流式读取
+//!
+//! [`EntryReader`] 由 [`ZipArchive::entry_reader`](crate::unzip::ZipArchive::entry_reader) 创建，
+//! 在 `read` 时才从归档读出压缩数据并增量解压，读到末尾时校验 CRC32。
+
+use crate::error::ZipError;
+use crate::miniz::Crc32;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+/// 按需解压单个条目的 reader
+pub struct EntryReader<'a> {
+    /// 解压后的数据流（存储条目即原始数据）
+    inner: Box<dyn Read + Send + 'a>,
+    crc: Crc32,
+    /// 读到末尾时要校验的 CRC32，`None` 表示不校验
+    expected_crc: Option<u32>,
+    name: String,
+    archive: PathBuf,
+    finished: bool,
+}
+
+impl<'a> EntryReader<'a> {
+    pub(crate) fn new(
+        archive: &Path,
+        name: &str,
+        inner: Box<dyn Read + Send + 'a>,
+        expected_crc: Option<u32>,
+    ) -> Self {
+        Self {
+            inner,
+            crc: Crc32::new(),
+            expected_crc,
+            name: name.to_string(),
+            archive: archive.to_path_buf(),
+            finished: false,
+        }
+    }
+
+    /// 条目名
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl Read for EntryReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() || self.finished {
+            return Ok(0);
+        }
+        let n = self.inner.read(buf)?;
+        if n > 0 {
+            self.crc.update(&buf[..n]);
+            return Ok(n);
+        }
+
+        self.finished = true;
+        if let Some(expected) = self.expected_crc {
+            let actual = self.crc.value();
+            if actual != expected {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    ZipError::CorruptEntry {
+                        name: self.name.clone(),
+                        archive: self.archive.clone(),
+                        reason: format!(
+                            "CRC32 mismatch: expected 0x{:08x}, got 0x{:08x}",
+                            expected, actual
+                        ),
+                    },
+                ));
+            }
+        }
+        Ok(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::CompressionLevel;
+    use crate::miniz::bitstream::BitWriter;
+    use crate::miniz::crc32;
+    use crate::miniz::deflate::{compress_raw_with, DeflateEncoder, DeflateImpl, DeflateOptions};
+    use crate::unzip::ZipArchive;
+    use crate::zip::{PrecompressedEntry, ZipWriter};
+    use std::io::{self, Read};
+    use std::time::SystemTime;
+    use tempfile::TempDir;
+
+    /// 静态 Huffman 块只含字面量，存储块，最后是动态 Huffman 块组成的 DEFLATE 流
+    fn mixed_blocks(parts: [&[u8]; 3]) -> Vec<u8> {
+        let mut bits = BitWriter::new();
+        bits.write_bits(0b010, 3);
+        for &byte in parts[0] {
+            let (code, len) = if byte < 144 {
+                (0x30 + byte as u32, 8)
+            } else {
+                (0x190 + byte as u32 - 144, 9)
+            };
+            bits.write_bits(code.reverse_bits() >> (32 - len), len as u8);
+        }
+        bits.write_bits(0, 7);
+
+        bits.write_bits(0b000, 3);
+        bits.align_to_byte();
+        let len = parts[1].len() as u16;
+        bits.write_bytes(&len.to_le_bytes());
+        bits.write_bytes(&(!len).to_le_bytes());
+        bits.write_bytes(parts[1]);
+
+        let mut good = DeflateEncoder::new(DeflateOptions {
+            algorithm: DeflateImpl::Good,
+            ..Default::default()
+        })
+        .unwrap();
+        let dynamic = compress_raw_with(&mut good, parts[2]).unwrap();
+        assert_eq!((dynamic[0] >> 1) & 3, 2, "expected a dynamic Huffman block");
+
+        let mut stream = bits.into_bytes();
+        stream.extend_from_slice(&dynamic);
+        stream
+    }
+
+    fn write_entry(path: &std::path::Path, data: Vec<u8>, content: &[u8], crc32: u32) {
+        let mut writer = ZipWriter::new(path, CompressionLevel::Level6).unwrap();
+        writer
+            .add_precompressed(PrecompressedEntry {
+                name: "mixed.bin".to_string(),
+                method: 8,
+                data,
+                uncompressed_size: content.len() as u64,
+                crc32,
+                mtime: SystemTime::UNIX_EPOCH,
+                mode: 0o644,
+                extra: Vec::new(),
+            })
+            .unwrap();
+        writer.finalize().unwrap();
+    }
+
+    #[test]
+    fn test_multiple_block_types_in_small_reads() {
+        let text: Vec<u8> = (0..40_000u32).map(|i| b"abcdefgh "[(i % 9) as usize] ^ (i / 5000) as u8).collect();
+        let binary: Vec<u8> = (0..=255).cycle().take(3000).collect();
+        let parts: [&[u8]; 3] = [&binary[..600], &text[..5000], &text[5000..]];
+        let content = parts.concat();
+        let stream = mixed_blocks(parts);
+
+        let temp = TempDir::new().unwrap();
+        let zipfile = temp.path().join("mixed.zip");
+        write_entry(&zipfile, stream.clone(), &content, crc32(0, &content));
+
+        let archive = ZipArchive::open(&zipfile).unwrap();
+        let mut reader = archive.entry_reader("mixed.bin").unwrap();
+        assert_eq!(reader.name(), "mixed.bin");
+        let mut output = Vec::new();
+        let mut chunk = [0u8; 7];
+        loop {
+            let n = reader.read(&mut chunk).unwrap();
+            if n == 0 {
+                break;
+            }
+            output.extend_from_slice(&chunk[..n]);
+        }
+        assert_eq!(output, content);
+        assert_eq!(archive.read_entry("mixed.bin").unwrap(), content);
+
+        // CRC32 在读到末尾时校验
+        let bad = temp.path().join("bad-crc.zip");
+        write_entry(&bad, stream, &content, crc32(0, &content) ^ 1);
+        let archive = ZipArchive::open(&bad).unwrap();
+        let err = archive.entry_reader("mixed.bin").unwrap().read_to_end(&mut Vec::new()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().contains("CRC32 mismatch"), "{}", err);
+        let unchecked = archive.verify_crc(false);
+        let mut reader = unchecked.entry_reader("mixed.bin").unwrap();
+        assert_eq!(reader.read_to_end(&mut Vec::new()).unwrap(), content.len());
+    }
+}
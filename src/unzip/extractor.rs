@@ -1,27 +1,265 @@
 //! 纯 Rust ZIP Extractor 实现
 //! 完全复刻 C 版本 Extractor 的行为，不使用 FFI
 
-use crate::error::{Result, ZipError};
-use crate::unzip::archive::ZipArchive;
+use crate::cancel::CancelToken;
+use crate::error::{HostOs, PreserveSet, Result, ZipEntry, ZipError};
+use crate::unzip::archive::{is_symlink_attr, EntryTransform, ZipArchive};
+use crate::unzip::entry_name::{classify_entry_name, ensure_no_symlink_escape, is_safe_entry_name};
+use crate::miniz::crc32::crc32;
+use std::collections::{HashMap, HashSet};
 use std::fs;
+use std::io::Read;
 use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// 解压后的目录布局
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Layout {
+    /// 按归档中存储的路径解压到 exdir
+    #[default]
+    AsStored,
+    /// 解压到 exdir 下以归档文件名（去掉扩展名）命名的子目录
+    IntoNamedSubdir,
+    /// 丢弃路径，所有文件直接放在 exdir 下（对应 C 版本的 junkpaths）
+    Flat,
+    /// 去掉前 N 级路径；路径层级不超过 N 的条目被跳过
+    StripComponents(usize),
+}
+
+/// 符号链接条目的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymlinkPolicy {
+    /// 还原为符号链接（Unix 上的默认值）
+    Create,
+    /// 写成普通文件，内容为链接目标（Windows 上的默认值，避免因无权创建符号链接而失败）
+    AsFile,
+    /// 跳过该条目并记录 `ExtractWarning::SymlinkSkipped`
+    Skip,
+}
+
+impl Default for SymlinkPolicy {
+    fn default() -> Self {
+        if cfg!(windows) {
+            SymlinkPolicy::AsFile
+        } else {
+            SymlinkPolicy::Create
+        }
+    }
+}
+
+/// 解压过程中的警告
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExtractWarning {
+    /// 条目权限中的 setuid/setgid/sticky 或其他用户可写位被去掉
+    PermissionsSanitized { name: String, original: u32, applied: u32 },
+    /// 按 `SymlinkPolicy::Skip` 跳过的符号链接条目
+    SymlinkSkipped { name: String },
+    /// 要求还原所有者，但没有权限把文件改为归档中记录的 UID/GID
+    OwnershipNotRestored { name: String, uid: u32, gid: u32 },
+    /// 要求还原扩展属性，但文件系统不支持或没有权限设置该属性
+    XattrNotRestored { name: String, attr: String },
+    /// 输出路径与之前的条目只有大小写不同，按 `case_insensitive_guard` 改名后写出
+    CaseCollision { name: String, renamed_to: PathBuf },
+}
+
+/// `extract_with_warnings` 的结果
+#[derive(Debug, Clone)]
+pub struct ExtractOutput {
+    pub exdir: PathBuf,
+    pub warnings: Vec<ExtractWarning>,
+    /// 启用 `resume` 时，因目标文件已完整存在而跳过的条目
+    pub skipped: Vec<String>,
+    /// 启用 `skip_mac_metadata` 时跳过的 `__MACOSX/` 和 `._*` 条目数
+    pub mac_metadata_skipped: usize,
+    /// 仅由 `extract_report` 填充：新建或内容发生变化而被写出的文件
+    pub modified: Vec<PathBuf>,
+    /// 仅由 `extract_report` 填充：大小和 CRC32 已与条目一致、没有重写的文件
+    pub unchanged: Vec<PathBuf>,
+}
+
+/// 清理权限时保留的位：去掉 setuid/setgid/sticky 和其他用户可写
+const SAFE_PERMISSION_MASK: u32 = 0o775;
 
 /// 提取选项
 #[derive(Debug, Clone)]
 pub struct ExtractorOptions {
     pub overwrite: bool,
-    pub junk_paths: bool,
+    pub layout: Layout,
     pub exdir: PathBuf,
     pub files: Option<Vec<String>>,
+    pub cancel: Option<CancelToken>,
+    pub sanitize_permissions: bool,
+    pub resume: bool,
+    pub password: Option<Vec<u8>>,
+    pub symlink_policy: SymlinkPolicy,
+    pub newer_than: Option<SystemTime>,
+    pub durable: bool,
+    pub verify_crc: bool,
+    pub strict_names: bool,
+    pub restore: PreserveSet,
+    pub case_insensitive_guard: bool,
+    pub preallocate: bool,
+    pub skip_mac_metadata: bool,
 }
 
 impl Default for ExtractorOptions {
     fn default() -> Self {
         Self {
             overwrite: true,
-            junk_paths: false,
+            layout: Layout::AsStored,
             exdir: PathBuf::from("."),
             files: None,
+            cancel: None,
+            sanitize_permissions: true,
+            resume: false,
+            password: None,
+            symlink_policy: SymlinkPolicy::default(),
+            newer_than: None,
+            durable: false,
+            verify_crc: true,
+            strict_names: false,
+            restore: PreserveSet::PERMISSIONS | PreserveSet::MTIME | PreserveSet::SYMLINKS,
+            case_insensitive_guard: false,
+            preallocate: false,
+            skip_mac_metadata: false,
+        }
+    }
+}
+
+impl ExtractorOptions {
+    /// 符号链接条目是否还原为符号链接；否则写成内容为链接目标的普通文件
+    pub(crate) fn creates_symlinks(&self) -> bool {
+        self.symlink_policy == SymlinkPolicy::Create && self.restore.contains(PreserveSet::SYMLINKS)
+    }
+
+    /// 按 `restore` 还原已写出条目的元数据（时间、权限、所有者、扩展属性）
+    ///
+    /// `create_symlink` 表示条目已还原为符号链接，此时不设置时间和扩展属性（会作用到链接目标上）。
+    pub(crate) fn restore_metadata(
+        &self,
+        entry: &ZipEntry,
+        output: &Path,
+        create_symlink: bool,
+        warnings: &mut Vec<ExtractWarning>,
+    ) -> Result<()> {
+        if !create_symlink && self.restore.contains(PreserveSet::MTIME) {
+            restore_times(entry, output)?;
+        }
+        // 符号链接的权限位对写成普通文件的链接目标没有意义
+        #[cfg(unix)]
+        if !is_symlink(entry) && self.restore.contains(PreserveSet::PERMISSIONS) {
+            self.restore_permissions(entry, output, warnings)?;
+        }
+        #[cfg(unix)]
+        if self.restore.contains(PreserveSet::OWNERSHIP) {
+            restore_owner(entry, output, warnings)?;
+        }
+        #[cfg(all(feature = "xattr", any(target_os = "linux", target_os = "android", target_os = "macos")))]
+        if !create_symlink && self.restore.contains(PreserveSet::XATTR) {
+            restore_xattrs(entry, output, warnings)?;
+        }
+        #[cfg(not(unix))]
+        let _ = warnings;
+        Ok(())
+    }
+
+    /// 按归档中记录的 Unix 权限设置已解压文件的权限
+    /// 对应 C 版本 zip.c 的 zip_set_permissions()；非 Unix 创建或权限为 0 的条目保持默认权限
+    #[cfg(unix)]
+    fn restore_permissions(
+        &self,
+        entry: &ZipEntry,
+        output: &Path,
+        warnings: &mut Vec<ExtractWarning>,
+    ) -> Result<()> {
+        use std::os::unix::fs::PermissionsExt;
+
+        let original = (entry.external_attr >> 16) & 0o7777;
+        if entry.created_by_host() != HostOs::Unix || original == 0 {
+            return Ok(());
+        }
+
+        let applied = if self.sanitize_permissions {
+            original & SAFE_PERMISSION_MASK
+        } else {
+            original
+        };
+        if applied != original {
+            log::warn!(
+                "{}: permissions {:o} sanitized to {:o}",
+                entry.filename,
+                original,
+                applied
+            );
+            warnings.push(ExtractWarning::PermissionsSanitized {
+                name: entry.filename.clone(),
+                original,
+                applied,
+            });
+        }
+
+        fs::set_permissions(output, fs::Permissions::from_mode(applied)).map_err(|e| {
+            ZipError::generic(&format!(
+                "Failed to set permissions on {}: {:?}",
+                output.display(),
+                e
+            ))
+        })
+    }
+
+    /// 条目是否在 `files` 指定的提取范围内
+    pub(crate) fn selects(&self, filename: &str) -> bool {
+        match &self.files {
+            Some(files) => files.iter().any(|f| filename == f || filename.contains(f.as_str())),
+            None => true,
+        }
+    }
+
+    /// 文件条目的修改时间是否晚于 `newer_than`；目录条目总是通过
+    pub(crate) fn is_newer(&self, entry: &ZipEntry) -> bool {
+        match self.newer_than {
+            Some(cutoff) => entry.is_directory || entry.times.mtime > cutoff,
+            None => true,
+        }
+    }
+
+    /// 按布局计算条目的输出路径；返回 `None` 表示该条目不输出
+    pub(crate) fn output_path(
+        &self,
+        zipfile: &Path,
+        filename: &str,
+        is_directory: bool,
+    ) -> Option<PathBuf> {
+        let exdir = &self.exdir;
+        match self.layout {
+            Layout::AsStored => Some(exdir.join(filename)),
+            Layout::IntoNamedSubdir => {
+                let subdir = zipfile
+                    .file_stem()
+                    .map(|s| s.to_os_string())
+                    .unwrap_or_else(|| "archive".into());
+                Some(exdir.join(subdir).join(filename))
+            }
+            Layout::Flat => {
+                // 目录在 Flat 布局下没有意义
+                if is_directory {
+                    return None;
+                }
+                // 丢弃路径，只使用文件名
+                let name = PathBuf::from(filename)
+                    .file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_else(|| filename.to_string());
+                Some(exdir.join(name))
+            }
+            Layout::StripComponents(n) => {
+                let components: Vec<&str> =
+                    filename.split('/').filter(|c| !c.is_empty()).collect();
+                if components.len() <= n {
+                    return None;
+                }
+                Some(exdir.join(components[n..].join("/")))
+            }
         }
     }
 }
@@ -31,6 +269,7 @@ impl Default for ExtractorOptions {
 pub struct Extractor {
     zipfile: PathBuf,
     options: ExtractorOptions,
+    transform: Option<Box<EntryTransform>>,
 }
 
 impl Extractor {
@@ -38,6 +277,7 @@ impl Extractor {
         Ok(Self {
             zipfile: zipfile.as_ref().to_path_buf(),
             options: ExtractorOptions::default(),
+            transform: None,
         })
     }
 
@@ -46,8 +286,15 @@ impl Extractor {
         self
     }
 
+    /// 丢弃路径，等价于 `layout(Layout::Flat)`；`false` 恢复为 `Layout::AsStored`
     pub fn junk_paths(mut self, junk_paths: bool) -> Self {
-        self.options.junk_paths = junk_paths;
+        self.options.layout = if junk_paths { Layout::Flat } else { Layout::AsStored };
+        self
+    }
+
+    /// 设置解压布局
+    pub fn layout(mut self, layout: Layout) -> Self {
+        self.options.layout = layout;
         self
     }
 
@@ -66,48 +313,218 @@ impl Extractor {
         self
     }
 
+    /// 设置取消令牌
+    /// 在每个条目之前以及大条目解压的每一块之前检查；被取消时返回 `ZipError::Cancelled`，
+    /// 正在解压的条目和之后的条目都不再写入
+    pub fn cancel_token(mut self, token: CancelToken) -> Self {
+        self.options.cancel = Some(token);
+        self
+    }
+
+    /// 是否清理条目权限（默认开启）
+    /// 开启时去掉 setuid/setgid/sticky 和其他用户可写位，并记录 `ExtractWarning::PermissionsSanitized`；
+    /// 关闭时按归档中的权限原样还原。只对 Unix 创建的文件条目生效
+    pub fn sanitize_permissions(mut self, sanitize: bool) -> Self {
+        self.options.sanitize_permissions = sanitize;
+        self
+    }
+
+    /// 断点续解：目标文件已存在且大小和 CRC32 都与条目一致时跳过该条目
+    /// 与 `overwrite(false)` 不同，这里校验的是内容而不只是文件是否存在
+    pub fn resume(mut self, resume: bool) -> Self {
+        self.options.resume = resume;
+        self
+    }
+
+    /// 设置解密加密条目使用的密码
+    /// 未设置时遇到加密条目返回 `ZipError::PasswordRequired`
+    pub fn password(mut self, password: impl AsRef<[u8]>) -> Self {
+        self.options.password = Some(password.as_ref().to_vec());
+        self
+    }
+
+    /// 设置符号链接条目的处理方式，默认见 [`SymlinkPolicy::default`]
+    pub fn symlink_policy(mut self, policy: SymlinkPolicy) -> Self {
+        self.options.symlink_policy = policy;
+        self
+    }
+
+    /// 只解压修改时间晚于 `time` 的文件条目，用于增量恢复
+    ///
+    /// 修改时间取最精确的来源（NTFS、扩展时间戳，最后是 DOS 时间）。
+    /// 目录条目不受影响；与 `files`、`overwrite` 等选项同时生效。
+    pub fn only_newer_than(mut self, time: SystemTime) -> Self {
+        self.options.newer_than = Some(time);
+        self
+    }
+
+    /// 写完每个文件后调用 `File::sync_all`，Unix 上同时同步其所在目录（默认关闭）
+    ///
+    /// 用于备份恢复等场景，保证崩溃后不会留下内容不完整的文件；代价是解压明显变慢。
+    pub fn durable(mut self, durable: bool) -> Self {
+        self.options.durable = durable;
+        self
+    }
+
+    /// 写入之前按中央目录中的解压大小预先扩展较大（至少 1 MiB）的输出文件（默认关闭）
+    ///
+    /// 可以减少部分文件系统上大文件的碎片；实际写入的长度不同时截断到实际长度。
+    pub fn preallocate(mut self, preallocate: bool) -> Self {
+        self.options.preallocate = preallocate;
+        self
+    }
+
+    /// 跳过 macOS 归档中的 `__MACOSX/` 目录和 `._*` AppleDouble（资源分支）条目（默认关闭）
+    ///
+    /// 跳过的条目数记录在 `ExtractOutput::mac_metadata_skipped` 中。
+    pub fn skip_mac_metadata(mut self, skip: bool) -> Self {
+        self.options.skip_mac_metadata = skip;
+        self
+    }
+
+    /// 解压时是否校验 CRC32，默认开启
+    ///
+    /// 关闭后可以更快地解压完全可信的归档，但损坏或被篡改的条目会被原样写出而不报错。
+    pub fn verify_crc(mut self, verify: bool) -> Self {
+        self.options.verify_crc = verify;
+        self
+    }
+
+    /// 本地文件头中的文件名与中央目录不一致时报错，见 [`ZipArchive::strict_names`]（默认关闭）
+    pub fn strict_names(mut self, strict: bool) -> Self {
+        self.options.strict_names = strict;
+        self
+    }
+
+    /// 选择要还原的文件元数据，默认 `PERMISSIONS | MTIME | SYMLINKS`
+    ///
+    /// 与 [`ZipBuilder::preserve`](crate::zip::ZipBuilder::preserve) 对应：未选 `PERMISSIONS`
+    /// 或 `MTIME` 时文件保持新建时的权限和时间；未选 `SYMLINKS` 时符号链接条目写成普通文件；
+    /// 选了 `OWNERSHIP` 时（仅 Unix）按 `ux` 字段设置 UID/GID，没有权限时记录
+    /// `ExtractWarning::OwnershipNotRestored` 并继续；选了 `XATTR` 时（需 `xattr` feature）
+    /// 还原 `0x5a52` 字段中的扩展属性，无法设置的属性记录 `ExtractWarning::XattrNotRestored`。
+    pub fn restore(mut self, set: PreserveSet) -> Self {
+        self.options.restore = set;
+        self
+    }
+
+    /// 是否避免只有大小写不同的条目在不区分大小写的文件系统（Windows、macOS）上互相覆盖，默认关闭
+    ///
+    /// 开启后在解压之前按中央目录找出输出路径只有大小写不同的文件条目：第一个保持原名，
+    /// 之后的在扩展名之前加 `~1`、`~2`……（如 `readme~1`、`a~1.txt`），并记录
+    /// `ExtractWarning::CaseCollision`。
+    pub fn case_insensitive_guard(mut self, guard: bool) -> Self {
+        self.options.case_insensitive_guard = guard;
+        self
+    }
+
+    /// 写出前用 `transform` 处理每个文件条目的数据，参数为条目名和解压后的数据，写出其返回值
+    ///
+    /// CRC32 校验针对变换之前的数据；还原为符号链接的条目不经过变换。
+    pub fn transform(mut self, transform: impl FnMut(&str, &[u8]) -> Vec<u8> + Send + 'static) -> Self {
+        self.transform = Some(Box::new(transform));
+        self
+    }
+
     /// 执行提取
     pub fn extract(self) -> Result<()> {
+        self.extract_with_warnings().map(|_| ())
+    }
+
+    /// 执行提取，并返回过程中产生的警告
+    pub fn extract_with_warnings(self) -> Result<ExtractOutput> {
+        self.run(false)
+    }
+
+    /// 执行提取，并在 `ExtractOutput::modified` / `unchanged` 中报告哪些文件实际被改动
+    ///
+    /// 写入前与 `resume` 一样按大小和 CRC32 比较已存在的文件：一致的文件不重写（修改时间等
+    /// 元数据也不还原），记入 `unchanged`；其余新建或覆盖的文件记入 `modified`。
+    /// 适合只为变化的文件触发后续构建步骤；目录和跳过的符号链接不计入。
+    pub fn extract_report(self) -> Result<ExtractOutput> {
+        self.run(true)
+    }
+
+    /// 执行提取；`report_changes` 为 true 时跳过内容一致的文件并记录改动
+    fn run(mut self, report_changes: bool) -> Result<ExtractOutput> {
+        let mut warnings = Vec::new();
+        let mut skipped = Vec::new();
+        let mut modified = Vec::new();
+        let mut unchanged = Vec::new();
+
         // 打开 ZIP 文件
-        let archive = ZipArchive::open(&self.zipfile)?;
+        let mut archive = ZipArchive::open(&self.zipfile)?
+            .verify_crc(self.options.verify_crc)
+            .strict_names(self.options.strict_names)
+            .cancel_token(self.options.cancel.clone());
+        if let Some(password) = &self.options.password {
+            archive = archive.password(password);
+        }
 
         // 获取所有条目
         let all_entries = archive.entries()?;
 
         // 过滤出要提取的文件
-        let entries_to_extract: Vec<_> = if let Some(ref files) = self.options.files {
-            // 只提取指定的文件
-            all_entries
-                .into_iter()
-                .filter(|entry| {
-                    files.iter().any(|f| entry.filename == *f || entry.filename.contains(f))
-                })
-                .collect()
-        } else {
-            // 提取所有文件
-            all_entries
-        };
+        let mut entries_to_extract: Vec<_> = all_entries
+            .into_iter()
+            .filter(|entry| self.options.selects(&entry.filename) && self.options.is_newer(entry))
+            .collect();
+
+        let mut mac_metadata_skipped = 0;
+        if self.options.skip_mac_metadata {
+            entries_to_extract.retain(|entry| {
+                let skip = is_mac_metadata(&entry.filename);
+                if skip {
+                    log::debug!("skipping macOS metadata {}", entry.filename);
+                    mac_metadata_skipped += 1;
+                }
+                !skip
+            });
+        }
+
+        // 写入任何文件之前拒绝不安全的条目名
+        if let Some(entry) = entries_to_extract
+            .iter()
+            .find(|entry| !is_safe_entry_name(&entry.filename))
+        {
+            return Err(ZipError::InvalidPath {
+                path: entry.filename.clone(),
+                reason: format!(
+                    "unsafe entry name ({:?})",
+                    classify_entry_name(&entry.filename)
+                ),
+            });
+        }
 
         // 创建输出目录
         fs::create_dir_all(&self.options.exdir).map_err(|e| {
             ZipError::generic(&format!("Failed to create extract directory: {:?}", e))
         })?;
 
+        let mut renamed = if self.options.case_insensitive_guard {
+            self.case_collision_renames(&entries_to_extract, &mut warnings)
+        } else {
+            HashMap::new()
+        };
+
         // 提取每个文件
-        for entry in entries_to_extract {
+        for (index, entry) in entries_to_extract.into_iter().enumerate() {
+            if let Some(cancel) = &self.options.cancel {
+                cancel.check()?;
+            }
+
             // 计算输出路径
-            let output_path = if self.options.junk_paths {
-                // 丢弃路径，只使用文件名
-                let filename = PathBuf::from(&entry.filename)
-                    .file_name()
-                    .map(|n| n.to_string_lossy().to_string())
-                    .unwrap_or_else(|| entry.filename.clone());
-                self.options.exdir.join(filename)
-            } else {
-                // 保留完整路径
-                self.options.exdir.join(&entry.filename)
+            let output_path = match renamed.remove(&index).or_else(|| {
+                self.options
+                    .output_path(&self.zipfile, &entry.filename, entry.is_directory)
+            }) {
+                Some(path) => path,
+                None => continue,
             };
 
+            // 不经由已解压的符号链接写到 exdir 之外
+            ensure_no_symlink_escape(&self.options.exdir, &output_path)?;
+
             // 如果是目录，创建目录
             if entry.is_directory {
                 fs::create_dir_all(&output_path).map_err(|e| {
@@ -120,8 +537,35 @@ impl Extractor {
                 continue;
             }
 
+            let as_symlink = is_symlink(&entry);
+            if as_symlink && self.options.symlink_policy == SymlinkPolicy::Skip {
+                log::warn!("skipping symlink {}", entry.filename);
+                warnings.push(ExtractWarning::SymlinkSkipped {
+                    name: entry.filename,
+                });
+                continue;
+            }
+            let create_symlink = as_symlink && self.options.creates_symlinks();
+
+            if self.options.resume && already_extracted(&entry, &output_path) {
+                log::debug!("{} already extracted, skipping", entry.filename);
+                skipped.push(entry.filename);
+                continue;
+            }
+
+            if report_changes && !create_symlink && already_extracted(&entry, &output_path) {
+                log::debug!("{} is unchanged, not rewriting", entry.filename);
+                unchanged.push(output_path);
+                continue;
+            }
+
             // 检查文件是否已存在
             if output_path.exists() && !self.options.overwrite {
+                log::warn!(
+                    "skipping {}: {} already exists",
+                    entry.filename,
+                    output_path.display()
+                );
                 continue;
             }
 
@@ -129,10 +573,1009 @@ impl Extractor {
             // 注意：这里需要找到文件在 ZIP 中的索引
             // 暂时通过 locate_file 实现
             if let Some(index) = archive.locate_file(&entry.filename)? {
-                archive.extract_to(index, &output_path)?;
+                log::debug!("extracting {} to {}", entry.filename, output_path.display());
+                archive
+                    .extract_entry(
+                        index,
+                        &output_path,
+                        create_symlink,
+                        self.options.durable,
+                        self.options.preallocate,
+                        self.transform.as_deref_mut(),
+                    )
+                    .inspect_err(|e| log::error!("failed to extract {}: {}", entry.filename, e))?;
+                self.options
+                    .restore_metadata(&entry, &output_path, create_symlink, &mut warnings)?;
+                if report_changes {
+                    modified.push(output_path);
+                }
             }
         }
 
-        Ok(())
+        Ok(ExtractOutput {
+            exdir: self.options.exdir,
+            warnings,
+            skipped,
+            mac_metadata_skipped,
+            modified,
+            unchanged,
+        })
+    }
+
+    /// 找出输出路径只有大小写不同的文件条目，返回 条目下标 -> 改名后的输出路径
+    ///
+    /// 按条目顺序第一个保持原路径；新名字避开所有条目的输出路径（不区分大小写）。
+    /// 完全同名的条目不算冲突，目录条目之间合并即可，不改名。
+    fn case_collision_renames(
+        &self,
+        entries: &[ZipEntry],
+        warnings: &mut Vec<ExtractWarning>,
+    ) -> HashMap<usize, PathBuf> {
+        let outputs: Vec<Option<PathBuf>> = entries
+            .iter()
+            .map(|entry| {
+                self.options
+                    .output_path(&self.zipfile, &entry.filename, entry.is_directory)
+            })
+            .collect();
+        let fold = |path: &Path| path.to_string_lossy().to_lowercase();
+        let mut taken: HashSet<String> = outputs.iter().flatten().map(|path| fold(path)).collect();
+
+        let mut first: HashMap<String, &PathBuf> = HashMap::new();
+        let mut renames = HashMap::new();
+        for (index, (entry, output)) in entries.iter().zip(&outputs).enumerate() {
+            let Some(output) = output else { continue };
+            if entry.is_directory {
+                continue;
+            }
+            let holder = *first.entry(fold(output)).or_insert(output);
+            if holder == output {
+                continue;
+            }
+
+            let renamed = (1..)
+                .map(|n| with_collision_suffix(output, n))
+                .find(|path| !taken.contains(&fold(path)))
+                .expect("unbounded suffix search");
+            taken.insert(fold(&renamed));
+            log::warn!(
+                "{} collides with {} on case-insensitive filesystems, extracting as {}",
+                entry.filename,
+                holder.display(),
+                renamed.display()
+            );
+            warnings.push(ExtractWarning::CaseCollision {
+                name: entry.filename.clone(),
+                renamed_to: renamed.clone(),
+            });
+            renames.insert(index, renamed);
+        }
+        renames
+    }
+}
+
+/// 按 `ux` 字段还原所有者；没有权限（EPERM）时记录警告，其他错误返回
+#[cfg(unix)]
+fn restore_owner(entry: &ZipEntry, output: &Path, warnings: &mut Vec<ExtractWarning>) -> Result<()> {
+    let Some((uid, gid)) = entry.owner else {
+        return Ok(());
+    };
+    match std::os::unix::fs::lchown(output, Some(uid), Some(gid)) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::PermissionDenied => {
+            log::warn!("{}: cannot change owner to {}:{}: {}", entry.filename, uid, gid, e);
+            warnings.push(ExtractWarning::OwnershipNotRestored {
+                name: entry.filename.clone(),
+                uid,
+                gid,
+            });
+            Ok(())
+        }
+        Err(e) => Err(ZipError::generic(&format!(
+            "Failed to set owner on {}: {:?}",
+            output.display(),
+            e
+        ))),
+    }
+}
+
+/// 按 `0x5a52` 字段设置扩展属性；不支持（ENOTSUP）或没有权限时记录警告，其他错误返回
+#[cfg(all(feature = "xattr", any(target_os = "linux", target_os = "android", target_os = "macos")))]
+fn restore_xattrs(entry: &ZipEntry, output: &Path, warnings: &mut Vec<ExtractWarning>) -> Result<()> {
+    for (attr, value) in &entry.xattrs {
+        match crate::platform::write_xattr(output, attr, value) {
+            Ok(()) => {}
+            Err(e)
+                if e.kind() == std::io::ErrorKind::PermissionDenied
+                    || e.raw_os_error() == Some(libc::ENOTSUP) =>
+            {
+                log::warn!("{}: cannot set xattr {}: {}", entry.filename, attr, e);
+                warnings.push(ExtractWarning::XattrNotRestored {
+                    name: entry.filename.clone(),
+                    attr: attr.clone(),
+                });
+            }
+            Err(e) => {
+                return Err(ZipError::generic(&format!(
+                    "Failed to set xattr {} on {}: {:?}",
+                    attr,
+                    output.display(),
+                    e
+                )))
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 目标文件是否已经是该条目的完整解压结果（普通文件，大小和 CRC32 一致）
+fn already_extracted(entry: &ZipEntry, output: &Path) -> bool {
+    if is_symlink(entry) {
+        return false;
+    }
+    match fs::symlink_metadata(output) {
+        Ok(metadata) if metadata.is_file() && metadata.len() == entry.uncompressed_size => {}
+        _ => return false,
+    }
+
+    let Ok(mut file) = fs::File::open(output) else {
+        return false;
+    };
+    let mut crc = 0;
+    let mut chunk = vec![0u8; 64 * 1024];
+    loop {
+        match file.read(&mut chunk) {
+            Ok(0) => break,
+            Ok(n) => crc = crc32(crc, &chunk[..n]),
+            Err(_) => return false,
+        }
+    }
+    crc == entry.crc32
+}
+
+/// 在文件名的扩展名之前加 `~n`：`readme` -> `readme~1`，`a.txt` -> `a~1.txt`
+fn with_collision_suffix(path: &Path, n: usize) -> PathBuf {
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let name = match path.extension() {
+        Some(ext) => format!("{}~{}.{}", stem, n, ext.to_string_lossy()),
+        None => format!("{}~{}", stem, n),
+    };
+    path.with_file_name(name)
+}
+
+/// 条目是否为 macOS 生成的元数据：`__MACOSX/` 之下的条目，或 `._` 开头的 AppleDouble 文件
+fn is_mac_metadata(name: &str) -> bool {
+    let trimmed = name.trim_end_matches('/');
+    trimmed.split('/').next() == Some("__MACOSX")
+        || trimmed.rsplit('/').next().is_some_and(|base| base.starts_with("._"))
+}
+
+/// 条目是否为符号链接（external_attr 中的 Unix 模式为 S_IFLNK）
+fn is_symlink(entry: &ZipEntry) -> bool {
+    is_symlink_attr(entry.external_attr)
+}
+
+/// 还原已解压文件的修改时间和访问时间；创建时间只在支持的平台上还原
+/// 对应 C 版本 zip.c 的 zip_set_mtime()
+fn restore_times(entry: &ZipEntry, output: &Path) -> Result<()> {
+    let mut times = fs::FileTimes::new().set_modified(entry.times.mtime);
+    if let Some(atime) = entry.times.atime {
+        times = times.set_accessed(atime);
+    }
+    #[cfg(any(target_os = "macos", windows))]
+    if let Some(ctime) = entry.times.ctime {
+        #[cfg(target_os = "macos")]
+        use std::os::macos::fs::FileTimesExt;
+        #[cfg(windows)]
+        use std::os::windows::fs::FileTimesExt;
+        times = times.set_created(ctime);
+    }
+
+    let set_failed = |e: std::io::Error| {
+        ZipError::generic(&format!(
+            "Failed to set file times on {}: {:?}",
+            output.display(),
+            e
+        ))
+    };
+    fs::File::options()
+        .write(true)
+        .open(output)
+        .and_then(|file| file.set_times(times))
+        .map_err(set_failed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::CompressionLevel;
+    use crate::zip::{ZipBuilder, ZipWriter};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_cancelled_extraction_writes_nothing() {
+        let temp = TempDir::new().unwrap();
+        let src = temp.path().join("src");
+        fs::create_dir_all(&src).unwrap();
+        fs::write(src.join("a.txt"), b"alpha\n").unwrap();
+        fs::write(src.join("b.txt"), b"bravo\n").unwrap();
+        let zipfile = temp.path().join("test.zip");
+        ZipBuilder::new(&zipfile)
+            .unwrap()
+            .root(&src)
+            .files(&["a.txt", "b.txt"])
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let token = CancelToken::new();
+        token.cancel();
+        let exdir = temp.path().join("out");
+        let result = Extractor::new(&zipfile)
+            .unwrap()
+            .exdir(&exdir)
+            .cancel_token(token)
+            .extract();
+
+        assert!(matches!(result, Err(ZipError::Cancelled)));
+        assert_eq!(fs::read_dir(&exdir).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_cancel_after_first_entry() {
+        use crate::unzip::ZipArchive;
+
+        let temp = TempDir::new().unwrap();
+        let zipfile = temp.path().join("test.zip");
+        // 第二个条目跨越多个解压块
+        let big: Vec<u8> = (0..256 * 1024u32).map(|i| (i % 251) as u8).collect();
+        let mut writer = ZipWriter::new(&zipfile, CompressionLevel::default()).unwrap();
+        writer.add_reader("a.txt", &mut &b"alpha\n"[..]).unwrap();
+        writer.add_reader("big.bin", &mut &big[..]).unwrap();
+        writer.finalize().unwrap();
+
+        // 第一个条目写出后才取消：它完整保留，之后的条目不写出
+        let token = CancelToken::new();
+        let cancel = token.clone();
+        let exdir = temp.path().join("out");
+        let result = Extractor::new(&zipfile)
+            .unwrap()
+            .exdir(&exdir)
+            .cancel_token(token)
+            .transform(move |_, data| {
+                cancel.cancel();
+                data.to_vec()
+            })
+            .extract();
+        assert!(matches!(result, Err(ZipError::Cancelled)));
+        assert_eq!(fs::read(exdir.join("a.txt")).unwrap(), b"alpha\n");
+        assert!(!exdir.join("big.bin").exists());
+
+        // 令牌由 extract_entry 在读取和解压时检查，而不只是在条目之间
+        let token = CancelToken::new();
+        token.cancel();
+        let archive = ZipArchive::open(&zipfile).unwrap().cancel_token(Some(token));
+        let index = archive.locate_file("big.bin").unwrap().unwrap();
+        let output = temp.path().join("big.bin");
+        assert!(matches!(archive.extract_entry(index, &output, false, false, false, None), Err(ZipError::Cancelled)));
+        assert!(!output.exists());
+    }
+
+    #[test]
+    fn test_unsafe_entry_name_rejected_before_writing() {
+        let temp = TempDir::new().unwrap();
+        let src = temp.path().join("payload.txt");
+        fs::write(&src, b"payload\n").unwrap();
+        let zipfile = temp.path().join("slip.zip");
+        let mut writer = ZipWriter::new(&zipfile, CompressionLevel::default()).unwrap();
+        writer.add_file("ok.txt", &src).unwrap();
+        writer.add_reader("../evil.txt", &mut &b"payload\n"[..]).unwrap();
+        writer.finalize().unwrap();
+
+        let exdir = temp.path().join("out");
+        let result = Extractor::new(&zipfile).unwrap().exdir(&exdir).extract();
+
+        assert!(matches!(result, Err(ZipError::InvalidPath { .. })), "{:?}", result);
+        assert!(!exdir.join("ok.txt").exists());
+        assert!(!temp.path().join("evil.txt").exists());
+    }
+
+    #[test]
+    fn test_case_insensitive_guard() {
+        let temp = TempDir::new().unwrap();
+        let zipfile = temp.path().join("case.zip");
+        let mut writer = ZipWriter::new(&zipfile, CompressionLevel::default()).unwrap();
+        for (name, data) in [("A.txt", "upper\n"), ("a.txt", "lower\n"), ("a~1.txt", "taken\n"), ("README", "readme\n")] {
+            writer.add_reader(name, &mut data.as_bytes()).unwrap();
+        }
+        writer.finalize().unwrap();
+
+        let exdir = temp.path().join("out");
+        let output = Extractor::new(&zipfile)
+            .unwrap()
+            .exdir(&exdir)
+            .case_insensitive_guard(true)
+            .extract_with_warnings()
+            .unwrap();
+
+        assert_eq!(
+            output.warnings,
+            [ExtractWarning::CaseCollision {
+                name: "a.txt".to_string(),
+                renamed_to: exdir.join("a~2.txt"),
+            }]
+        );
+        assert_eq!(fs::read(exdir.join("A.txt")).unwrap(), b"upper\n");
+        assert_eq!(fs::read(exdir.join("a~2.txt")).unwrap(), b"lower\n");
+        assert_eq!(fs::read(exdir.join("a~1.txt")).unwrap(), b"taken\n");
+        assert_eq!(fs::read(exdir.join("README")).unwrap(), b"readme\n");
+    }
+
+    #[test]
+    fn test_transform() {
+        let temp = TempDir::new().unwrap();
+        let zipfile = temp.path().join("mixed.zip");
+        let binary = [0u8, 1, 2, b'a', b'b', 255];
+        let mut writer = ZipWriter::new(&zipfile, CompressionLevel::default()).unwrap();
+        writer.add_reader("notes.txt", &mut &b"hello world\n"[..]).unwrap();
+        writer.add_reader("dir/more.txt", &mut &b"mixed Case\n"[..]).unwrap();
+        writer.add_reader("data.bin", &mut &binary[..]).unwrap();
+        writer.finalize().unwrap();
+
+        let exdir = temp.path().join("out");
+        Extractor::new(&zipfile)
+            .unwrap()
+            .exdir(&exdir)
+            .transform(|name, data| {
+                if name.ends_with(".txt") {
+                    data.to_ascii_uppercase()
+                } else {
+                    data.to_vec()
+                }
+            })
+            .extract()
+            .unwrap();
+
+        assert_eq!(fs::read(exdir.join("notes.txt")).unwrap(), b"HELLO WORLD\n");
+        assert_eq!(fs::read(exdir.join("dir").join("more.txt")).unwrap(), b"MIXED CASE\n");
+        assert_eq!(fs::read(exdir.join("data.bin")).unwrap(), binary);
+    }
+
+    #[test]
+    fn test_preallocate() {
+        let temp = TempDir::new().unwrap();
+        let zipfile = temp.path().join("large.zip");
+        let large = "preallocated line\n".repeat(100_000);
+        let mut writer = ZipWriter::new(&zipfile, CompressionLevel::default()).unwrap();
+        writer.add_reader("large.txt", &mut large.as_bytes()).unwrap();
+        writer.add_reader("small.txt", &mut &b"small\n"[..]).unwrap();
+        writer.finalize().unwrap();
+
+        let exdir = temp.path().join("out");
+        Extractor::new(&zipfile)
+            .unwrap()
+            .exdir(&exdir)
+            .preallocate(true)
+            .extract()
+            .unwrap();
+
+        let entries = ZipArchive::list(&zipfile).unwrap();
+        assert!(entries[0].uncompressed_size > 1024 * 1024);
+        assert_eq!(fs::metadata(exdir.join("large.txt")).unwrap().len(), entries[0].uncompressed_size);
+        assert_eq!(fs::read(exdir.join("large.txt")).unwrap(), large.as_bytes());
+        assert_eq!(fs::read(exdir.join("small.txt")).unwrap(), b"small\n");
+    }
+
+    /// 用 `ZipWriter` 写出条目，再把 `links` 中列出的条目在中央目录里标记为符号链接
+    fn make_symlink_zip(zipfile: &Path, entries: &[(&str, &[u8])], links: &[&str]) {
+        let mut writer = ZipWriter::new(zipfile, CompressionLevel::default()).unwrap();
+        for (name, data) in entries {
+            writer.add_reader(name, &mut &data[..]).unwrap();
+        }
+        writer.finalize().unwrap();
+
+        let mut bytes = fs::read(zipfile).unwrap();
+        let mut pos = 0;
+        while let Some(found) = bytes[pos..].windows(4).position(|w| w == b"PK\x01\x02") {
+            let cd = pos + found;
+            let name_len = u16::from_le_bytes([bytes[cd + 28], bytes[cd + 29]]) as usize;
+            let name = String::from_utf8_lossy(&bytes[cd + 46..cd + 46 + name_len]).to_string();
+            if links.contains(&name.as_str()) {
+                let attr: u32 = 0o120777 << 16;
+                bytes[cd + 38..cd + 42].copy_from_slice(&attr.to_le_bytes());
+            }
+            pos = cd + 46;
+        }
+        fs::write(zipfile, &bytes).unwrap();
+    }
+
+    #[test]
+    fn test_symlink_policy_as_file_and_skip() {
+        let temp = TempDir::new().unwrap();
+        let zipfile = temp.path().join("links.zip");
+        make_symlink_zip(
+            &zipfile,
+            &[("target.txt", b"data\n"), ("link", b"target.txt")],
+            &["link"],
+        );
+
+        let exdir = temp.path().join("as_file");
+        let output = Extractor::new(&zipfile)
+            .unwrap()
+            .exdir(&exdir)
+            .symlink_policy(SymlinkPolicy::AsFile)
+            .extract_with_warnings()
+            .unwrap();
+        let metadata = fs::symlink_metadata(exdir.join("link")).unwrap();
+        assert!(metadata.file_type().is_file());
+        assert_eq!(fs::read(exdir.join("link")).unwrap(), b"target.txt");
+        assert!(output.warnings.is_empty());
+
+        let exdir = temp.path().join("skip");
+        let output = Extractor::new(&zipfile)
+            .unwrap()
+            .exdir(&exdir)
+            .symlink_policy(SymlinkPolicy::Skip)
+            .extract_with_warnings()
+            .unwrap();
+        assert!(fs::symlink_metadata(exdir.join("link")).is_err());
+        assert!(exdir.join("target.txt").is_file());
+        assert_eq!(
+            output.warnings,
+            [ExtractWarning::SymlinkSkipped {
+                name: "link".to_string()
+            }]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlink_policy_create() {
+        let temp = TempDir::new().unwrap();
+        let zipfile = temp.path().join("links.zip");
+        make_symlink_zip(
+            &zipfile,
+            &[("target.txt", b"data\n"), ("link", b"target.txt")],
+            &["link"],
+        );
+
+        let exdir = temp.path().join("out");
+        Extractor::new(&zipfile).unwrap().exdir(&exdir).extract().unwrap();
+        let link = exdir.join("link");
+        assert!(fs::symlink_metadata(&link).unwrap().file_type().is_symlink());
+        assert_eq!(fs::read_link(&link).unwrap(), Path::new("target.txt"));
+        assert_eq!(fs::read(&link).unwrap(), b"data\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlink_traversal_rejected() {
+        let temp = TempDir::new().unwrap();
+        // 链接指向 exdir 之外的目录（相当于 `link -> /tmp`，用独立目录避免干扰）
+        let outside = temp.path().join("outside");
+        fs::create_dir_all(&outside).unwrap();
+        let zipfile = temp.path().join("traversal.zip");
+        make_symlink_zip(
+            &zipfile,
+            &[
+                ("link", outside.to_str().unwrap().as_bytes()),
+                ("link/pwned", b"pwned\n"),
+            ],
+            &["link"],
+        );
+
+        let exdir = temp.path().join("out");
+        let result = Extractor::new(&zipfile).unwrap().exdir(&exdir).extract();
+
+        assert!(matches!(result, Err(ZipError::InvalidPath { .. })), "{:?}", result);
+        assert!(!outside.join("pwned").exists());
+        assert_eq!(fs::read_dir(&outside).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_layouts() {
+        let temp = TempDir::new().unwrap();
+        let src = temp.path().join("src");
+        fs::create_dir_all(src.join("top").join("sub")).unwrap();
+        fs::write(src.join("top").join("a.txt"), b"alpha\n").unwrap();
+        fs::write(src.join("top").join("sub").join("b.txt"), b"bravo\n").unwrap();
+        fs::write(src.join("c.txt"), b"charlie\n").unwrap();
+        let zipfile = temp.path().join("bundle.zip");
+        ZipBuilder::new(&zipfile)
+            .unwrap()
+            .root(&src)
+            .files(&["top", "c.txt"])
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let extract = |name: &str, layout: Layout| {
+            let exdir = temp.path().join(name);
+            Extractor::new(&zipfile)
+                .unwrap()
+                .exdir(&exdir)
+                .layout(layout)
+                .extract()
+                .unwrap();
+            exdir
+        };
+
+        let out = extract("as_stored", Layout::AsStored);
+        assert!(out.join("top/a.txt").is_file());
+        assert!(out.join("top/sub/b.txt").is_file());
+        assert!(out.join("c.txt").is_file());
+
+        let out = extract("named", Layout::IntoNamedSubdir);
+        assert!(out.join("bundle/top/a.txt").is_file());
+        assert!(out.join("bundle/top/sub/b.txt").is_file());
+        assert!(out.join("bundle/c.txt").is_file());
+
+        let out = extract("flat", Layout::Flat);
+        let mut names: Vec<String> = fs::read_dir(&out)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        names.sort();
+        assert_eq!(names, ["a.txt", "b.txt", "c.txt"]);
+
+        let out = extract("strip", Layout::StripComponents(1));
+        assert!(out.join("a.txt").is_file());
+        assert!(out.join("sub/b.txt").is_file());
+        assert!(!out.join("c.txt").exists());
+    }
+
+    #[test]
+    fn test_extract_report() {
+        let temp = TempDir::new().unwrap();
+        let src = temp.path().join("src");
+        fs::create_dir_all(src.join("sub")).unwrap();
+        fs::write(src.join("a.txt"), b"alpha\n").unwrap();
+        fs::write(src.join("sub").join("b.txt"), b"bravo\n").unwrap();
+        let zipfile = temp.path().join("report.zip");
+        ZipBuilder::new(&zipfile)
+            .unwrap()
+            .root(&src)
+            .files(&["a.txt", "sub"])
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let exdir = temp.path().join("out");
+        let report = || Extractor::new(&zipfile).unwrap().exdir(&exdir).extract_report().unwrap();
+
+        let first = report();
+        assert_eq!(first.modified, [exdir.join("a.txt"), exdir.join("sub/b.txt")]);
+        assert!(first.unchanged.is_empty());
+
+        let second = report();
+        assert!(second.modified.is_empty());
+        assert_eq!(second.unchanged, [exdir.join("a.txt"), exdir.join("sub/b.txt")]);
+
+        fs::write(exdir.join("sub/b.txt"), b"edited\n").unwrap();
+        let third = report();
+        assert_eq!(third.modified, [exdir.join("sub/b.txt")]);
+        assert_eq!(fs::read(exdir.join("sub/b.txt")).unwrap(), b"bravo\n");
+    }
+
+    #[test]
+    fn test_skip_mac_metadata() {
+        let temp = TempDir::new().unwrap();
+        let zipfile = temp.path().join("mac.zip");
+        let mut writer = ZipWriter::new(&zipfile, CompressionLevel::Level6).unwrap();
+        writer.add_directory_entry("photos/").unwrap();
+        writer.add_reader("photos/a.jpg", &mut &b"jpeg"[..]).unwrap();
+        writer.add_reader("photos/._a.jpg", &mut &b"fork"[..]).unwrap();
+        writer.add_directory_entry("__MACOSX/").unwrap();
+        writer.add_directory_entry("__MACOSX/photos/").unwrap();
+        writer.add_reader("__MACOSX/photos/._a.jpg", &mut &b"fork"[..]).unwrap();
+        writer.add_reader("notes._txt", &mut &b"note"[..]).unwrap();
+        writer.finalize().unwrap();
+        drop(writer);
+
+        let exdir = temp.path().join("out");
+        let output = Extractor::new(&zipfile)
+            .unwrap()
+            .exdir(&exdir)
+            .skip_mac_metadata(true)
+            .extract_with_warnings()
+            .unwrap();
+        assert_eq!(output.mac_metadata_skipped, 4);
+        assert_eq!(fs::read(exdir.join("photos/a.jpg")).unwrap(), b"jpeg");
+        assert_eq!(fs::read(exdir.join("notes._txt")).unwrap(), b"note");
+        assert!(!exdir.join("photos/._a.jpg").exists());
+        assert!(!exdir.join("__MACOSX").exists());
+
+        // 默认不跳过
+        let exdir = temp.path().join("all");
+        let output = Extractor::new(&zipfile).unwrap().exdir(&exdir).extract_with_warnings().unwrap();
+        assert_eq!(output.mac_metadata_skipped, 0);
+        assert!(exdir.join("__MACOSX/photos/._a.jpg").is_file());
+    }
+
+    #[test]
+    fn test_extract_without_directory_entries() {
+        let temp = TempDir::new().unwrap();
+        let src = temp.path().join("src");
+        fs::create_dir_all(src.join("top").join("sub").join("deep")).unwrap();
+        fs::write(src.join("top").join("a.txt"), b"alpha\n").unwrap();
+        fs::write(src.join("top").join("sub").join("b.txt"), b"bravo\n").unwrap();
+        fs::write(src.join("top").join("sub").join("deep").join("c.txt"), b"charlie\n").unwrap();
+        let zipfile = temp.path().join("nodirs.zip");
+        ZipBuilder::new(&zipfile)
+            .unwrap()
+            .root(&src)
+            .files(&["top"])
+            .unwrap()
+            .include_directories(false)
+            .build()
+            .unwrap();
+
+        let archive = ZipArchive::open(&zipfile).unwrap();
+        assert!(archive.entries().unwrap().iter().all(|entry| !entry.is_directory));
+
+        let exdir = temp.path().join("out");
+        Extractor::new(&zipfile).unwrap().exdir(&exdir).extract().unwrap();
+        assert!(exdir.join("top/sub/deep").is_dir());
+        assert_eq!(fs::read(exdir.join("top/a.txt")).unwrap(), b"alpha\n");
+        assert_eq!(fs::read(exdir.join("top/sub/b.txt")).unwrap(), b"bravo\n");
+        assert_eq!(fs::read(exdir.join("top/sub/deep/c.txt")).unwrap(), b"charlie\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_sanitize_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp = TempDir::new().unwrap();
+        let src = temp.path().join("src");
+        fs::create_dir_all(&src).unwrap();
+        fs::write(src.join("tool"), b"#!/bin/sh\n").unwrap();
+        let zipfile = temp.path().join("setuid.zip");
+        ZipBuilder::new(&zipfile)
+            .unwrap()
+            .root(&src)
+            .files(&["tool"])
+            .unwrap()
+            .build()
+            .unwrap();
+
+        // 把中央目录中的权限改成 setuid + 所有人可写
+        let mut bytes = fs::read(&zipfile).unwrap();
+        let cd = bytes.windows(4).position(|w| w == b"PK\x01\x02").unwrap();
+        let attr: u32 = 0o104777 << 16;
+        bytes[cd + 38..cd + 42].copy_from_slice(&attr.to_le_bytes());
+        fs::write(&zipfile, &bytes).unwrap();
+
+        let extract = |name: &str, sanitize: bool| {
+            let exdir = temp.path().join(name);
+            let output = Extractor::new(&zipfile)
+                .unwrap()
+                .exdir(&exdir)
+                .sanitize_permissions(sanitize)
+                .extract_with_warnings()
+                .unwrap();
+            let mode = fs::metadata(exdir.join("tool")).unwrap().permissions().mode() & 0o7777;
+            (mode, output.warnings)
+        };
+
+        let (mode, warnings) = extract("sanitized", true);
+        assert_eq!(mode, 0o775);
+        assert_eq!(
+            warnings,
+            [ExtractWarning::PermissionsSanitized {
+                name: "tool".to_string(),
+                original: 0o4777,
+                applied: 0o775,
+            }]
+        );
+
+        let (mode, warnings) = extract("preserved", false);
+        assert_eq!(mode, 0o4777);
+        assert!(warnings.is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_preserve_permissions_only() {
+        use crate::unzip::ZipArchive;
+        use crate::zip::extra;
+        use crate::zip::reader::ZipReader;
+        use std::os::unix::fs::{MetadataExt, PermissionsExt};
+
+        let temp = TempDir::new().unwrap();
+        let src = temp.path().join("src");
+        fs::create_dir_all(&src).unwrap();
+        fs::write(src.join("tool"), b"#!/bin/sh\n").unwrap();
+        fs::set_permissions(src.join("tool"), fs::Permissions::from_mode(0o751)).unwrap();
+        let build = |name: &str, set: PreserveSet| {
+            let zipfile = temp.path().join(name);
+            ZipBuilder::new(&zipfile)
+                .unwrap()
+                .root(&src)
+                .files(&["tool"])
+                .unwrap()
+                .preserve(set)
+                .build()
+                .unwrap();
+            zipfile
+        };
+
+        let zipfile = build("perms.zip", PreserveSet::PERMISSIONS);
+        let entry = &ZipArchive::list(&zipfile).unwrap()[0];
+        assert_eq!((entry.external_attr >> 16) & 0o777, 0o751);
+        assert_eq!(entry.owner, None);
+        let reader = ZipReader::open(&zipfile).unwrap();
+        let info = &reader.entries()[0];
+        assert!(extra::find_field(&info.extra, extra::UNIX_OWNER_TAG).is_none());
+        assert!(extra::find_field(&info.extra, extra::EXTENDED_TIMESTAMP_TAG).is_none());
+
+        let exdir = temp.path().join("out");
+        let output = Extractor::new(&zipfile)
+            .unwrap()
+            .exdir(&exdir)
+            .restore(PreserveSet::PERMISSIONS | PreserveSet::OWNERSHIP)
+            .extract_with_warnings()
+            .unwrap();
+        assert!(output.warnings.is_empty());
+        let extracted = fs::metadata(exdir.join("tool")).unwrap();
+        assert_eq!(extracted.permissions().mode() & 0o777, 0o751);
+        // 未保存所有者：文件属于解压的用户
+        let fresh = fs::metadata(&exdir).unwrap();
+        assert_eq!((extracted.uid(), extracted.gid()), (fresh.uid(), fresh.gid()));
+
+        // 选了 OWNERSHIP 时写入 ux 字段
+        let zipfile = build("owner.zip", PreserveSet::PERMISSIONS | PreserveSet::OWNERSHIP);
+        let source = fs::metadata(src.join("tool")).unwrap();
+        assert_eq!(ZipArchive::list(&zipfile).unwrap()[0].owner, Some((source.uid(), source.gid())));
+
+        // 选了 SYMLINKS 时符号链接按链接本身存储，解压后还原为链接
+        std::os::unix::fs::symlink("tool", src.join("link")).unwrap();
+        let links = temp.path().join("links.zip");
+        ZipBuilder::new(&links)
+            .unwrap()
+            .root(&src)
+            .files(&["link"])
+            .unwrap()
+            .preserve(PreserveSet::SYMLINKS)
+            .build()
+            .unwrap();
+        assert!(is_symlink_attr(ZipArchive::list(&links).unwrap()[0].external_attr));
+        let exdir = temp.path().join("links");
+        Extractor::new(&links).unwrap().exdir(&exdir).extract().unwrap();
+        assert_eq!(fs::read_link(exdir.join("link")).unwrap(), Path::new("tool"));
+
+        // 未还原权限时保持新建文件的默认权限
+        let exdir = temp.path().join("default");
+        Extractor::new(&zipfile)
+            .unwrap()
+            .exdir(&exdir)
+            .restore(PreserveSet::MTIME)
+            .extract()
+            .unwrap();
+        fs::write(temp.path().join("fresh"), b"").unwrap();
+        let default_mode = fs::metadata(temp.path().join("fresh")).unwrap().permissions().mode();
+        assert_eq!(fs::metadata(exdir.join("tool")).unwrap().permissions().mode(), default_mode);
+    }
+
+    #[cfg(all(feature = "xattr", any(target_os = "linux", target_os = "macos")))]
+    #[test]
+    fn test_xattr_roundtrip() {
+        use crate::platform::{read_xattrs, write_xattr};
+
+        let temp = TempDir::new().unwrap();
+        let src = temp.path().join("src");
+        fs::create_dir_all(&src).unwrap();
+        fs::write(src.join("a.txt"), b"alpha\n").unwrap();
+        if write_xattr(&src.join("a.txt"), "user.zip_rs.test", b"hello").is_err() {
+            eprintln!("skipping: filesystem does not support user xattrs");
+            return;
+        }
+
+        let zipfile = temp.path().join("xattr.zip");
+        ZipBuilder::new(&zipfile)
+            .unwrap()
+            .root(&src)
+            .files(&["a.txt"])
+            .unwrap()
+            .preserve(PreserveSet::PERMISSIONS | PreserveSet::XATTR)
+            .build()
+            .unwrap();
+        let entry = &ZipArchive::list(&zipfile).unwrap()[0];
+        assert!(entry.xattrs.contains(&("user.zip_rs.test".to_string(), b"hello".to_vec())));
+
+        let exdir = temp.path().join("out");
+        let output = Extractor::new(&zipfile)
+            .unwrap()
+            .exdir(&exdir)
+            .restore(PreserveSet::PERMISSIONS | PreserveSet::XATTR)
+            .extract_with_warnings()
+            .unwrap();
+        assert!(output.warnings.is_empty());
+        let restored = read_xattrs(&exdir.join("a.txt")).unwrap();
+        assert!(restored.contains(&("user.zip_rs.test".to_string(), b"hello".to_vec())));
+
+        // 默认不还原扩展属性
+        let exdir = temp.path().join("default");
+        Extractor::new(&zipfile).unwrap().exdir(&exdir).extract().unwrap();
+        let restored = read_xattrs(&exdir.join("a.txt")).unwrap();
+        assert!(!restored.iter().any(|(name, _)| name == "user.zip_rs.test"));
+    }
+
+    #[test]
+    fn test_resume_skips_complete_files() {
+        let temp = TempDir::new().unwrap();
+        let src = temp.path().join("src");
+        fs::create_dir_all(&src).unwrap();
+        let names: Vec<String> = (0..6).map(|i| format!("f{}.txt", i)).collect();
+        for (i, name) in names.iter().enumerate() {
+            fs::write(src.join(name), format!("file {}\n", i).repeat(10)).unwrap();
+        }
+        let zipfile = temp.path().join("resume.zip");
+        ZipBuilder::new(&zipfile)
+            .unwrap()
+            .root(&src)
+            .files(&names)
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let exdir = temp.path().join("out");
+        Extractor::new(&zipfile).unwrap().exdir(&exdir).extract().unwrap();
+
+        // 删除一半，另外把一个保留的文件截断，模拟中断时写了一半
+        for name in &names[3..] {
+            fs::remove_file(exdir.join(name)).unwrap();
+        }
+        fs::write(exdir.join(&names[2]), b"file").unwrap();
+
+        let output = Extractor::new(&zipfile)
+            .unwrap()
+            .exdir(&exdir)
+            .resume(true)
+            .extract_with_warnings()
+            .unwrap();
+
+        assert_eq!(output.skipped, names[..2]);
+        for (i, name) in names.iter().enumerate() {
+            assert_eq!(
+                fs::read_to_string(exdir.join(name)).unwrap(),
+                format!("file {}\n", i).repeat(10)
+            );
+        }
+    }
+
+    #[test]
+    fn test_only_newer_than() {
+        use std::time::{Duration, UNIX_EPOCH};
+
+        let temp = TempDir::new().unwrap();
+        let src = temp.path().join("src");
+        fs::create_dir_all(&src).unwrap();
+        let old = UNIX_EPOCH + Duration::from_secs(1_000_000_000);
+        let new = UNIX_EPOCH + Duration::from_secs(1_600_000_000);
+        let names = ["a.txt", "b.txt", "c.txt", "d.txt"];
+        for (i, name) in names.iter().enumerate() {
+            let path = src.join(name);
+            fs::write(&path, name).unwrap();
+            let mtime = if i < 2 { old } else { new };
+            fs::File::options().write(true).open(&path).unwrap().set_modified(mtime).unwrap();
+        }
+        let zipfile = temp.path().join("times.zip");
+        ZipBuilder::new(&zipfile)
+            .unwrap()
+            .root(&src)
+            .files(&names)
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let exdir = temp.path().join("out");
+        Extractor::new(&zipfile)
+            .unwrap()
+            .exdir(&exdir)
+            .only_newer_than(UNIX_EPOCH + Duration::from_secs(1_300_000_000))
+            .extract()
+            .unwrap();
+
+        let mut written: Vec<String> = fs::read_dir(&exdir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        written.sort();
+        assert_eq!(written, ["c.txt", "d.txt"]);
+    }
+
+    #[test]
+    fn test_durable_syncs_each_file() {
+        use crate::unzip::archive::sync_calls;
+
+        let temp = TempDir::new().unwrap();
+        let src = temp.path().join("src");
+        fs::create_dir_all(src.join("dir")).unwrap();
+        for name in ["a.txt", "b.txt", "dir/c.txt"] {
+            fs::write(src.join(name), name).unwrap();
+        }
+        let zipfile = temp.path().join("durable.zip");
+        ZipBuilder::new(&zipfile)
+            .unwrap()
+            .root(&src)
+            .files(&["a.txt", "b.txt", "dir"])
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let extract = |name: &str, durable: bool| {
+            let before = sync_calls();
+            Extractor::new(&zipfile)
+                .unwrap()
+                .exdir(temp.path().join(name))
+                .durable(durable)
+                .extract()
+                .unwrap();
+            sync_calls() - before
+        };
+
+        assert_eq!(extract("fast", false), 0);
+        assert_eq!(extract("durable", true), 3);
+        assert_eq!(fs::read(temp.path().join("durable/dir/c.txt")).unwrap(), b"dir/c.txt");
+    }
+
+    /// 记录 warn 及以上级别日志的全局 logger
+    struct CapturingLogger(std::sync::Mutex<Vec<String>>);
+
+    impl log::Log for CapturingLogger {
+        fn enabled(&self, metadata: &log::Metadata) -> bool {
+            metadata.level() <= log::Level::Warn
+        }
+
+        fn log(&self, record: &log::Record) {
+            if self.enabled(record.metadata()) {
+                self.0.lock().unwrap().push(record.args().to_string());
+            }
+        }
+
+        fn flush(&self) {}
+    }
+
+    static LOGGER: CapturingLogger = CapturingLogger(std::sync::Mutex::new(Vec::new()));
+
+    #[test]
+    fn test_skipped_entry_logs_warning() {
+        let _ = log::set_logger(&LOGGER);
+        log::set_max_level(log::LevelFilter::Warn);
+
+        let temp = TempDir::new().unwrap();
+        let src = temp.path().join("src");
+        fs::create_dir_all(&src).unwrap();
+        fs::write(src.join("logged-skip.txt"), b"new\n").unwrap();
+        let zipfile = temp.path().join("log.zip");
+        ZipBuilder::new(&zipfile)
+            .unwrap()
+            .root(&src)
+            .files(&["logged-skip.txt"])
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let exdir = temp.path().join("out");
+        fs::create_dir_all(&exdir).unwrap();
+        fs::write(exdir.join("logged-skip.txt"), b"old\n").unwrap();
+        Extractor::new(&zipfile)
+            .unwrap()
+            .exdir(&exdir)
+            .overwrite(false)
+            .extract()
+            .unwrap();
+
+        assert_eq!(fs::read(exdir.join("logged-skip.txt")).unwrap(), b"old\n");
+        let logged = LOGGER.0.lock().unwrap();
+        assert!(
+            logged.iter().any(|m| m.starts_with("skipping logged-skip.txt")),
+            "{:?}",
+            logged
+        );
     }
 }
@@ -1,7 +1,17 @@
 //! ZIP archive reading and extraction.
 
-mod archive;
+pub(crate) mod archive;
+#[cfg(feature = "async")]
+mod async_extractor;
+mod entry_name;
+mod entry_reader;
 mod extractor;
+mod streaming;
 
-pub use archive::ZipArchive;
-pub use extractor::{Extractor, ExtractorOptions};
+pub use archive::{external_attr_to_mode, ArchiveOverhead, ArchiveStats, CompatibilityReport, ManifestMismatch, ZipArchive};
+#[cfg(feature = "async")]
+pub use async_extractor::AsyncExtractor;
+pub use entry_name::{classify_entry_name, is_safe_entry_name, normalize_entry_name, EntryNameClass};
+pub use entry_reader::EntryReader;
+pub use extractor::{ExtractOutput, ExtractWarning, Extractor, ExtractorOptions, Layout, SymlinkPolicy};
+pub use streaming::StreamingZipReader;
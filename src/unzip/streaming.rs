@@ -0,0 +1,363 @@
+//! 只向前读取的流式 ZIP 读取器
+//!
+//! [`StreamingZipReader`] 从任意 `Read`（管道、网络连接等不能 seek 的来源）依次读取
+//! 本地文件头和条目数据，遇到中央目录即停止，从不读取中央目录。
+//! 本地头设置了 bit 3 时，CRC 和大小取自数据之后的数据描述符。
+//!
+//! 由于不读中央目录，无法用它校验条目：被中央目录删除或替换的旧条目同样会被产出，
+//! 只存在于中央目录的信息（外部属性、注释、创建系统）也拿不到，权限按默认值给出。
+
+use crate::error::{Result, ZipEntry, ZipError};
+use crate::miniz::InflateReader;
+use crate::unzip::archive::{check_crc32, decode_local_name, to_zip_entry};
+use crate::zip::extra;
+use crate::zip::reader::ZipEntryInfo;
+use std::io::{self, Read};
+use std::path::PathBuf;
+
+const LOCAL_HEADER_SIG: u32 = 0x04034b50;
+const CENTRAL_DIR_HEADER_SIG: u32 = 0x02014b50;
+const END_OF_CENTRAL_DIR_SIG: u32 = 0x06054b50;
+const ZIP64_END_OF_CENTRAL_DIR_SIG: u32 = 0x06064b50;
+const ARCHIVE_EXTRA_DATA_SIG: u32 = 0x08064b50;
+const DATA_DESCRIPTOR_SIG: u32 = 0x08074b50;
+
+const FLAG_ENCRYPTED: u16 = 0x0001;
+const FLAG_DATA_DESCRIPTOR: u16 = 0x0008;
+
+/// 按物理顺序逐个产出条目的流式读取器
+///
+/// 每个条目产出 `(ZipEntry, 解压后的数据)`，并校验 CRC32。`ZipEntry::offset` 为本地头
+/// 在流中的偏移。STORE 条目必须在本地头中给出大小：设置了 bit 3 且大小为 0 的 STORE
+/// 文件条目无法确定数据长度，返回错误。加密条目返回 `ZipError::PasswordRequired`。
+/// 出错后读取器停止，之后只产出 `None`。
+pub struct StreamingZipReader<R> {
+    inner: InflateReader<R>,
+    finished: bool,
+}
+
+impl<R: Read> StreamingZipReader<R> {
+    /// 从 `reader` 的当前位置开始读取，第一个字节应为首个本地文件头
+    pub fn new(reader: R) -> Self {
+        Self {
+            inner: InflateReader::new(reader),
+            finished: false,
+        }
+    }
+
+    /// 读取下一个条目；到达中央目录或输入结束时返回 `None`
+    pub fn next_entry(&mut self) -> Result<Option<(ZipEntry, Vec<u8>)>> {
+        if self.finished {
+            return Ok(None);
+        }
+        let result = self.read_entry();
+        if !matches!(result, Ok(Some(_))) {
+            self.finished = true;
+        }
+        result
+    }
+
+    fn read_entry(&mut self) -> Result<Option<(ZipEntry, Vec<u8>)>> {
+        let offset = self.inner.total_in();
+        if !self.inner.has_more_input().map_err(|e| corrupt(offset, e))? {
+            return Ok(None);
+        }
+
+        let mut sig = [0u8; 4];
+        self.inner.read_trailer(&mut sig).map_err(|e| corrupt(offset, e))?;
+        match u32::from_le_bytes(sig) {
+            LOCAL_HEADER_SIG => {}
+            CENTRAL_DIR_HEADER_SIG
+            | END_OF_CENTRAL_DIR_SIG
+            | ZIP64_END_OF_CENTRAL_DIR_SIG
+            | ARCHIVE_EXTRA_DATA_SIG => return Ok(None),
+            other => {
+                return Err(ZipError::CorruptArchive {
+                    archive: PathBuf::new(),
+                    reason: format!("unexpected signature 0x{:08x} at offset {}", other, offset),
+                })
+            }
+        }
+
+        let mut header = [0u8; 26];
+        self.inner.read_trailer(&mut header).map_err(|e| corrupt(offset, e))?;
+        let u16_at = |i: usize| u16::from_le_bytes([header[i], header[i + 1]]);
+        let u32_at = |i: usize| u32::from_le_bytes([header[i], header[i + 1], header[i + 2], header[i + 3]]);
+        let flags = u16_at(2);
+        let compression_method = u16_at(4);
+        let mut name = vec![0u8; u16_at(22) as usize];
+        self.inner.read_trailer(&mut name).map_err(|e| corrupt(offset, e))?;
+        let mut extra_field = vec![0u8; u16_at(24) as usize];
+        self.inner.read_trailer(&mut extra_field).map_err(|e| corrupt(offset, e))?;
+
+        let mut info = ZipEntryInfo {
+            name: decode_local_name(&name, flags),
+            uncompressed_size: u32_at(18) as u64,
+            compressed_size: u32_at(14) as u64,
+            crc32: u32_at(10),
+            local_header_offset: offset,
+            is_dir: false,
+            flags,
+            compression_method,
+            external_attr: 0,
+            version_made_by: 0,
+            mtime_dos: u16_at(6),
+            mdate_dos: u16_at(8),
+            extra: extra_field,
+            raw_name: name,
+        };
+        info.is_dir = info.name.ends_with('/');
+        let mut unused_offset = 0;
+        extra::apply_zip64(
+            &info.extra,
+            &mut info.uncompressed_size,
+            &mut info.compressed_size,
+            &mut unused_offset,
+        );
+
+        if flags & FLAG_ENCRYPTED != 0 {
+            return Err(ZipError::PasswordRequired {
+                name: info.name,
+                archive: PathBuf::new(),
+            });
+        }
+
+        let has_descriptor = flags & FLAG_DATA_DESCRIPTOR != 0;
+        let data = match compression_method {
+            8 => self.inflate_entry(&info, has_descriptor)?,
+            0 if has_descriptor && info.compressed_size == 0 && !info.is_dir => {
+                return Err(corrupt_entry(
+                    &info.name,
+                    "stored entry with a data descriptor has no size in its local header".to_string(),
+                ));
+            }
+            0 => {
+                // 大小来自不可信的本地头，缓冲区只随实际读到的字节增长
+                let mut data = Vec::new();
+                RawInput(&mut self.inner)
+                    .take(info.compressed_size)
+                    .read_to_end(&mut data)
+                    .map_err(|e| corrupt_entry(&info.name, format!("unexpected end of data: {}", e)))?;
+                if (data.len() as u64) < info.compressed_size {
+                    return Err(corrupt_entry(
+                        &info.name,
+                        format!("unexpected end of data: expected {} bytes, got {}", info.compressed_size, data.len()),
+                    ));
+                }
+                data
+            }
+            method => return Err(ZipError::UnsupportedCompression { method }),
+        };
+
+        if has_descriptor {
+            self.read_descriptor(&mut info)?;
+        }
+        if data.len() as u64 != info.uncompressed_size {
+            return Err(corrupt_entry(
+                &info.name,
+                format!(
+                    "size mismatch: expected {}, got {}",
+                    info.uncompressed_size,
+                    data.len()
+                ),
+            ));
+        }
+        check_crc32(&PathBuf::new(), &info.name, info.crc32, &data)?;
+
+        Ok(Some((to_zip_entry(&info), data)))
+    }
+
+    /// 解压 DEFLATE 数据；本地头给出了压缩大小时跳过流之后剩余的字节
+    fn inflate_entry(&mut self, info: &ZipEntryInfo, has_descriptor: bool) -> Result<Vec<u8>> {
+        self.inner.reset();
+        let start = self.inner.total_in();
+        let mut data = Vec::new();
+        self.inner
+            .read_to_end(&mut data)
+            .map_err(|e| corrupt_entry(&info.name, format!("failed to inflate: {}", e)))?;
+        self.inner.reset();
+
+        let consumed = self.inner.total_in() - start;
+        if !has_descriptor && consumed < info.compressed_size {
+            let rest = info.compressed_size - consumed;
+            let skipped = io::copy(&mut RawInput(&mut self.inner).take(rest), &mut io::sink())
+                .map_err(|e| corrupt_entry(&info.name, format!("unexpected end of data: {}", e)))?;
+            if skipped < rest {
+                return Err(corrupt_entry(&info.name, "unexpected end of data".to_string()));
+            }
+        } else if !has_descriptor && consumed > info.compressed_size {
+            return Err(corrupt_entry(
+                &info.name,
+                format!(
+                    "compressed data is {} bytes, local header says {}",
+                    consumed, info.compressed_size
+                ),
+            ));
+        }
+        Ok(data)
+    }
+
+    /// 读取数据描述符，用其中的 CRC 和大小替换本地头中的值
+    ///
+    /// 签名可选；本地头带有 ZIP64 extra field 时大小为 8 字节。
+    fn read_descriptor(&mut self, info: &mut ZipEntryInfo) -> Result<()> {
+        let zip64 = extra::find_field(&info.extra, extra::ZIP64_TAG).is_some();
+        let mut buf = [0u8; 4];
+        let mut read = |buf: &mut [u8]| {
+            self.inner
+                .read_trailer(buf)
+                .map_err(|e| corrupt_entry(&info.name, format!("truncated data descriptor: {}", e)))
+        };
+        read(&mut buf)?;
+        if u32::from_le_bytes(buf) == DATA_DESCRIPTOR_SIG {
+            read(&mut buf)?;
+        }
+        let crc32 = u32::from_le_bytes(buf);
+        let (compressed_size, uncompressed_size) = if zip64 {
+            let mut sizes = [0u8; 16];
+            read(&mut sizes)?;
+            (
+                u64::from_le_bytes(sizes[..8].try_into().unwrap()),
+                u64::from_le_bytes(sizes[8..].try_into().unwrap()),
+            )
+        } else {
+            let mut sizes = [0u8; 8];
+            read(&mut sizes)?;
+            (
+                u32::from_le_bytes(sizes[..4].try_into().unwrap()) as u64,
+                u32::from_le_bytes(sizes[4..].try_into().unwrap()) as u64,
+            )
+        };
+        info.crc32 = crc32;
+        info.compressed_size = compressed_size;
+        info.uncompressed_size = uncompressed_size;
+        Ok(())
+    }
+}
+
+impl<R: Read> Iterator for StreamingZipReader<R> {
+    type Item = Result<(ZipEntry, Vec<u8>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_entry().transpose()
+    }
+}
+
+/// 不解压地读取 `InflateReader` 中的原始输入，用于 STORE 数据和跳过多余字节
+struct RawInput<'a, R>(&'a mut InflateReader<R>);
+
+impl<R: Read> Read for RawInput<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.read_raw(buf)
+    }
+}
+
+/// 读取本地文件头失败
+fn corrupt(offset: u64, e: io::Error) -> ZipError {
+    ZipError::CorruptArchive {
+        archive: PathBuf::new(),
+        reason: format!("failed to read local header at offset {}: {}", offset, e),
+    }
+}
+
+fn corrupt_entry(name: &str, reason: String) -> ZipError {
+    ZipError::CorruptEntry {
+        name: name.to_string(),
+        archive: PathBuf::new(),
+        reason,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::CompressionLevel;
+    use crate::zip::reader::ZipReader;
+    use crate::zip::ZipWriter;
+    use std::fs;
+    use tempfile::TempDir;
+
+    /// 只实现 `Read` 的来源，每次最多给出 7 字节
+    struct ReadOnly(Vec<u8>, usize);
+
+    impl Read for ReadOnly {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let n = buf.len().min(7).min(self.0.len() - self.1);
+            buf[..n].copy_from_slice(&self.0[self.1..self.1 + n]);
+            self.1 += n;
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn test_streaming_reader_forward_only() {
+        let temp = TempDir::new().unwrap();
+        let zip_path = temp.path().join("test.zip");
+        let big = "streamed content\n".repeat(200);
+        let mut writer = ZipWriter::new(&zip_path, CompressionLevel::Level6).unwrap();
+        writer.add_reader("a.txt", &mut big.as_bytes()).unwrap();
+        writer.add_directory_entry("dir/").unwrap();
+        writer.add_reader("dir/b.txt", &mut &b"bravo\n"[..]).unwrap();
+        writer.finalize().unwrap();
+
+        // 把第一个条目改写成流式写入器的输出：bit 3、CRC 和大小为 0，数据后跟数据描述符
+        let reader = ZipReader::open(&zip_path).unwrap();
+        let first = reader.entries()[0].clone();
+        let bytes = fs::read(&zip_path).unwrap();
+        let fields_len = u16::from_le_bytes([bytes[26], bytes[27]]) + u16::from_le_bytes([bytes[28], bytes[29]]);
+        let data_end = (30 + fields_len as u64 + first.compressed_size) as usize;
+        let mut out = bytes[..data_end].to_vec();
+        out[6] |= 0x08;
+        out[14..26].fill(0);
+        out.extend_from_slice(&DATA_DESCRIPTOR_SIG.to_le_bytes());
+        out.extend_from_slice(&first.crc32.to_le_bytes());
+        out.extend_from_slice(&(first.compressed_size as u32).to_le_bytes());
+        out.extend_from_slice(&(first.uncompressed_size as u32).to_le_bytes());
+        out.extend_from_slice(&bytes[data_end..]);
+
+        let entries: Vec<(ZipEntry, Vec<u8>)> = StreamingZipReader::new(ReadOnly(out.clone(), 0))
+            .collect::<Result<_>>()
+            .unwrap();
+        let names: Vec<&str> = entries.iter().map(|(entry, _)| entry.filename.as_str()).collect();
+        assert_eq!(names, ["a.txt", "dir/", "dir/b.txt"]);
+        assert_eq!(entries[0].1, big.as_bytes());
+        assert_eq!(entries[0].0.compressed_size, first.compressed_size);
+        assert!(entries[1].0.is_directory);
+        assert_eq!(entries[2].1, b"bravo\n");
+        assert_eq!(entries[2].0.offset, reader.entries()[2].local_header_offset + 16);
+
+        // 数据损坏时返回错误，之后不再产出条目
+        out[data_end + 4] ^= 0xFF;
+        let mut streaming = StreamingZipReader::new(ReadOnly(out, 0));
+        assert!(matches!(streaming.next_entry(), Err(ZipError::CorruptEntry { .. })));
+        assert!(streaming.next().is_none());
+    }
+
+    #[test]
+    fn test_forged_zip64_size_does_not_preallocate() {
+        // STORE 条目，本地头大小为 0xFFFFFFFF，ZIP64 extra 声称 2^60 字节，实际只有几个字节
+        let huge = 1u64 << 60;
+        let mut out = LOCAL_HEADER_SIG.to_le_bytes().to_vec();
+        out.extend_from_slice(&45u16.to_le_bytes()); // version needed
+        out.extend_from_slice(&0u16.to_le_bytes()); // flags
+        out.extend_from_slice(&0u16.to_le_bytes()); // STORE
+        out.extend_from_slice(&[0u8; 4]); // time, date
+        out.extend_from_slice(&0u32.to_le_bytes()); // crc32
+        out.extend_from_slice(&u32::MAX.to_le_bytes());
+        out.extend_from_slice(&u32::MAX.to_le_bytes());
+        out.extend_from_slice(&5u16.to_le_bytes()); // name len
+        out.extend_from_slice(&20u16.to_le_bytes()); // extra len
+        out.extend_from_slice(b"a.bin");
+        out.extend_from_slice(&extra::ZIP64_TAG.to_le_bytes());
+        out.extend_from_slice(&16u16.to_le_bytes());
+        out.extend_from_slice(&huge.to_le_bytes());
+        out.extend_from_slice(&huge.to_le_bytes());
+        out.extend_from_slice(b"tiny payload");
+
+        let mut streaming = StreamingZipReader::new(ReadOnly(out, 0));
+        match streaming.next_entry() {
+            Err(ZipError::CorruptEntry { reason, .. }) => assert!(reason.contains("unexpected end"), "{}", reason),
+            other => panic!("expected CorruptEntry, got {:?}", other.map(|entry| entry.map(|(e, _)| e.filename))),
+        }
+    }
+}
@@ -0,0 +1,194 @@
+//! WinZip AES 加密（AE-2）
+//!
+//! 条目数据布局：盐 + 2 字节密码校验值 + AES-CTR 密文 + 10 字节 HMAC-SHA1 认证码。
+//! 密钥由 PBKDF2-HMAC-SHA1（1000 次迭代）从密码派生。AE-2 不写 CRC32，
+//! 完整性由认证码保证。
+
+use crate::aes_ctr::{self, Aes};
+use crate::error::AesStrength;
+use crate::sha1::{self, HmacSha1};
+
+/// 密码校验值长度
+pub const PASSWORD_VERIFIER_LEN: usize = 2;
+/// 认证码长度（截断的 HMAC-SHA1）
+pub const AUTH_CODE_LEN: usize = 10;
+
+/// PBKDF2 迭代次数
+const PBKDF2_ITERATIONS: u32 = 1000;
+
+impl AesStrength {
+    /// `0x9901` 字段中的强度值
+    pub fn to_u8(self) -> u8 {
+        match self {
+            AesStrength::Aes128 => 1,
+            AesStrength::Aes192 => 2,
+            AesStrength::Aes256 => 3,
+        }
+    }
+
+    /// 从 `0x9901` 字段中的强度值解析
+    pub fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            1 => Some(AesStrength::Aes128),
+            2 => Some(AesStrength::Aes192),
+            3 => Some(AesStrength::Aes256),
+            _ => None,
+        }
+    }
+
+    /// 密钥长度（字节）
+    fn key_len(self) -> usize {
+        match self {
+            AesStrength::Aes128 => 16,
+            AesStrength::Aes192 => 24,
+            AesStrength::Aes256 => 32,
+        }
+    }
+
+    /// 盐长度：密钥长度的一半
+    pub fn salt_len(self) -> usize {
+        self.key_len() / 2
+    }
+}
+
+/// 派生出的加密密钥、认证密钥和密码校验值
+struct DerivedKeys {
+    encryption: Vec<u8>,
+    authentication: Vec<u8>,
+    verifier: [u8; PASSWORD_VERIFIER_LEN],
+}
+
+fn derive_keys(password: &[u8], salt: &[u8], strength: AesStrength) -> DerivedKeys {
+    let key_len = strength.key_len();
+    let mut derived = vec![0u8; key_len * 2 + PASSWORD_VERIFIER_LEN];
+    sha1::pbkdf2_hmac_sha1(password, salt, PBKDF2_ITERATIONS, &mut derived);
+    DerivedKeys {
+        encryption: derived[..key_len].to_vec(),
+        authentication: derived[key_len..key_len * 2].to_vec(),
+        verifier: [derived[key_len * 2], derived[key_len * 2 + 1]],
+    }
+}
+
+/// AES-CTR，计数器为小端序、从 1 开始
+fn apply_keystream(key: &[u8], buf: &mut [u8]) {
+    aes_ctr::apply_ctr_le(&Aes::new(key), 1, buf);
+}
+
+fn auth_code(key: &[u8], ciphertext: &[u8]) -> [u8; AUTH_CODE_LEN] {
+    let mut mac = HmacSha1::new(key);
+    mac.update(ciphertext);
+    let tag = mac.finalize();
+    let mut code = [0u8; AUTH_CODE_LEN];
+    code.copy_from_slice(&tag[..AUTH_CODE_LEN]);
+    code
+}
+
+/// 加密一个条目的（已压缩）数据，返回完整的条目数据
+pub fn encrypt_entry(password: &[u8], strength: AesStrength, data: &[u8]) -> std::io::Result<Vec<u8>> {
+    let mut salt = vec![0u8; strength.salt_len()];
+    getrandom::fill(&mut salt).map_err(std::io::Error::other)?;
+    let keys = derive_keys(password, &salt, strength);
+
+    let mut ciphertext = data.to_vec();
+    apply_keystream(&keys.encryption, &mut ciphertext);
+
+    let mut out = Vec::with_capacity(salt.len() + PASSWORD_VERIFIER_LEN + data.len() + AUTH_CODE_LEN);
+    out.extend_from_slice(&salt);
+    out.extend_from_slice(&keys.verifier);
+    out.extend_from_slice(&ciphertext);
+    out.extend_from_slice(&auth_code(&keys.authentication, &ciphertext));
+    Ok(out)
+}
+
+/// AES 条目解密失败的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecryptError {
+    /// 数据比盐、校验值和认证码加起来还短
+    Truncated,
+    /// 密码校验值不匹配
+    WrongPassword,
+    /// 认证码不匹配：数据被篡改或损坏
+    AuthenticationFailed,
+}
+
+/// 解密一个条目的数据，返回（仍是压缩状态的）明文
+pub fn decrypt_entry(password: &[u8], strength: AesStrength, data: &[u8]) -> Result<Vec<u8>, DecryptError> {
+    let salt_len = strength.salt_len();
+    if data.len() < salt_len + PASSWORD_VERIFIER_LEN + AUTH_CODE_LEN {
+        return Err(DecryptError::Truncated);
+    }
+    let (salt, rest) = data.split_at(salt_len);
+    let (verifier, rest) = rest.split_at(PASSWORD_VERIFIER_LEN);
+    let (ciphertext, code) = rest.split_at(rest.len() - AUTH_CODE_LEN);
+
+    let keys = derive_keys(password, salt, strength);
+    if keys.verifier != verifier {
+        return Err(DecryptError::WrongPassword);
+    }
+    if auth_code(&keys.authentication, ciphertext) != code {
+        return Err(DecryptError::AuthenticationFailed);
+    }
+
+    let mut plain = ciphertext.to_vec();
+    apply_keystream(&keys.encryption, &mut plain);
+    Ok(plain)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roundtrip_all_strengths() {
+        let plain = b"attack at dawn, attack at dawn, attack at dawn".to_vec();
+        for strength in [AesStrength::Aes128, AesStrength::Aes192, AesStrength::Aes256] {
+            let data = encrypt_entry(b"pw", strength, &plain).unwrap();
+            assert_eq!(
+                data.len(),
+                strength.salt_len() + PASSWORD_VERIFIER_LEN + plain.len() + AUTH_CODE_LEN
+            );
+            assert_eq!(decrypt_entry(b"pw", strength, &data).unwrap(), plain);
+
+            let mut tampered = data.clone();
+            tampered[strength.salt_len() + PASSWORD_VERIFIER_LEN] ^= 1;
+            assert_eq!(
+                decrypt_entry(b"pw", strength, &tampered),
+                Err(DecryptError::AuthenticationFailed)
+            );
+        }
+    }
+
+    /// 第三方工具（libarchive `bsdtar --options zip:encryption=...`）写出的归档，
+    /// 用来发现加密和解密两端对称的错误，例如计数器字节序或密钥切分位置
+    fn fixture(name: &str) -> std::path::PathBuf {
+        std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(name)
+    }
+
+    #[test]
+    fn test_decrypt_third_party_archives() {
+        use crate::unzip::ZipArchive;
+        use crate::zip::extra;
+        use crate::zip::reader::ZipReader;
+
+        let aes_field = |name: &str| {
+            let reader = ZipReader::open(fixture(name)).unwrap();
+            extra::aes_field(&reader.entries()[0].extra)
+        };
+        let extract = |name: &str, password: &str| {
+            ZipArchive::open(fixture(name)).unwrap().password(password).extract_to_map()
+        };
+
+        // AE-2、AES-256、存储：19 字节，跨越两个计数器分组
+        assert_eq!(aes_field("ae2-aes256.zip"), Some((2, 3, 0)));
+        let map = extract("ae2-aes256.zip", "correct horse").unwrap();
+        assert_eq!(map["short.txt"], b"attack at dawn!!!!\n");
+
+        // AE-1（另带 CRC32）、AES-128、存储
+        assert_eq!(aes_field("ae1-aes128.zip"), Some((1, 1, 0)));
+        let map = extract("ae1-aes128.zip", "correct horse").unwrap();
+        assert_eq!(map["fox.txt"], b"The quick brown fox jumps over the lazy dog.\n");
+
+        let err = extract("ae2-aes256.zip", "battery staple").unwrap_err();
+        assert!(matches!(err, crate::error::ZipError::InvalidPassword { .. }), "{}", err);
+    }
+}
@@ -1,8 +1,17 @@
-use crate::error::{CompressionLevel, Result, ZipError, ZipMode};
-use crate::zip::data::{get_zip_data, ZipData, ZipWarning};
+use crate::cancel::CancelToken;
+use crate::error::{Compat, CompressionLevel, DuplicatePolicy, Encoding, Encryption, PreserveSet, Result, ZipError, ZipMode};
+use crate::miniz::deflate::DeflateImpl;
+use crate::unzip::{normalize_entry_name, ZipArchive};
+use crate::zip::data::{get_zip_data, ZipData, ZipDataEntry, ZipWarning};
+use crate::zip::writer::{EntrySummary, DEFAULT_WRITE_BUFFER_SIZE};
+use crate::zip::reader::ZipReader;
+use crate::zip::replace;
 use crate::zip::ZipWriter;
+use std::cmp::Ordering;
 use std::fs;
+use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
+use std::sync::mpsc::Receiver;
 
 /// ZIP builder options.
 #[derive(Debug, Clone)]
@@ -10,9 +19,29 @@ pub struct ZipBuilderOptions {
     pub compression_level: CompressionLevel,
     pub recurse: bool,
     pub include_directories: bool,
+    pub include_root_entry: bool,
     pub root: PathBuf,
     pub mode: ZipMode,
     pub append: bool,
+    pub compact_on_append: bool,
+    pub prefix: Option<String>,
+    pub high_res_time: bool,
+    pub filename_encoding: Encoding,
+    pub algorithm: DeflateImpl,
+    pub smart_store: bool,
+    pub dedup: bool,
+    pub atomic: bool,
+    pub temp_dir: Option<PathBuf>,
+    pub pad_to: Option<u64>,
+    pub manifest: Option<String>,
+    pub preserve: Option<PreserveSet>,
+    pub on_duplicate: DuplicatePolicy,
+    pub write_buffer_size: usize,
+    pub compat: Compat,
+    pub encryption: Option<Encryption>,
+    pub cancel: Option<CancelToken>,
+    pub sfx_prefix: Option<Vec<u8>>,
+    pub prune_empty_dirs: bool,
 }
 
 impl Default for ZipBuilderOptions {
@@ -21,23 +50,62 @@ impl Default for ZipBuilderOptions {
             compression_level: CompressionLevel::Level6,
             recurse: true,
             include_directories: true,
+            include_root_entry: true,
             root: PathBuf::from("."),
             mode: ZipMode::Mirror,
             append: false,
+            compact_on_append: false,
+            prefix: None,
+            high_res_time: false,
+            filename_encoding: Encoding::Utf8,
+            algorithm: DeflateImpl::Fast,
+            smart_store: false,
+            dedup: false,
+            atomic: false,
+            temp_dir: None,
+            pad_to: None,
+            manifest: None,
+            preserve: None,
+            on_duplicate: DuplicatePolicy::Allow,
+            write_buffer_size: DEFAULT_WRITE_BUFFER_SIZE,
+            compat: Compat::Miniz,
+            encryption: None,
+            cancel: None,
+            sfx_prefix: None,
+            prune_empty_dirs: false,
         }
     }
 }
 
+/// `ZipBuilder::build_from_channel` 接收的条目
+pub enum BuildItem {
+    /// 添加文件条目，内容从 `reader` 读取到 EOF
+    AddFile { name: String, reader: Box<dyn Read + Send> },
+    /// 添加目录条目
+    AddDir { name: String },
+    /// 结束并 finalize 归档
+    Finish,
+}
+
 #[derive(Debug, Clone)]
 pub struct ZipBuildOutput {
     pub zipfile: PathBuf,
     pub warnings: Vec<ZipWarning>,
+    /// 本次构建写入的条目，顺序与中央目录一致；追加模式下不含原有条目
+    pub entries: Vec<EntrySummary>,
 }
 
 pub struct ZipBuilder {
     zipfile: PathBuf,
     options: ZipBuilderOptions,
     files: Vec<String>,
+    dir_contents: Vec<PathBuf>,
+    /// `add_all` 添加的 (源路径, 条目名)
+    explicit: Vec<(PathBuf, String)>,
+    /// 条目写入顺序的比较函数，见 `sort_by`
+    order: Option<Box<dyn Fn(&str, &str) -> Ordering + Send + Sync>>,
+    /// 由源路径决定条目名的回调，见 `name_mapper`
+    name_mapper: Option<Box<dyn Fn(&Path) -> Option<String> + Send + Sync>>,
 }
 
 impl ZipBuilder {
@@ -46,6 +114,10 @@ impl ZipBuilder {
             zipfile: zipfile.as_ref().to_path_buf(),
             options: ZipBuilderOptions::default(),
             files: Vec::new(),
+            dir_contents: Vec::new(),
+            explicit: Vec::new(),
+            order: None,
+            name_mapper: None,
         })
     }
 
@@ -59,11 +131,32 @@ impl ZipBuilder {
         self
     }
 
+    /// 是否写入目录条目，默认写入
+    ///
+    /// 关闭后只写文件条目，本 crate 的 `Extractor` 解压时从文件条目的路径推断并创建父目录；
+    /// 不含文件的空目录不会被还原。
     pub fn include_directories(mut self, include: bool) -> Self {
         self.options.include_directories = include;
         self
     }
 
+    /// 是否为 `files` 中列出的目录本身写入目录条目（如 `mydir/`），默认写入
+    ///
+    /// 为 `false` 时目录下的条目仍以 `mydir/` 为前缀，只是没有单独的 `mydir/` 条目；
+    /// 子目录的条目不受影响。`include_directories(false)` 时所有目录条目都不写入。
+    pub fn include_root_entry(mut self, include: bool) -> Self {
+        self.options.include_root_entry = include;
+        self
+    }
+
+    /// 不写入（递归地）不含任何文件的目录条目，默认关闭，保留完整的目录结构
+    ///
+    /// 只含空子目录的目录同样被省略；含有文件的目录及其上级目录照常写入。
+    pub fn prune_empty_dirs(mut self, prune: bool) -> Self {
+        self.options.prune_empty_dirs = prune;
+        self
+    }
+
     pub fn root(mut self, root: impl AsRef<Path>) -> Self {
         self.options.root = root.as_ref().to_path_buf();
         self
@@ -79,6 +172,152 @@ impl ZipBuilder {
         self
     }
 
+    /// 为每个条目名添加路径前缀，例如 `prefix("myapp-1.0")` 得到 `myapp-1.0/...`
+    /// 反斜杠会被规范化为 `/`；前缀不能是绝对路径，也不能包含 `.` 或 `..` 组件
+    pub fn prefix(mut self, prefix: &str) -> Self {
+        self.options.prefix = Some(prefix.to_string());
+        self
+    }
+
+    /// 在归档之前写入自解压程序的存根（如可执行文件），生成自解压归档
+    ///
+    /// 条目偏移按包含存根的绝对位置写入，结果既能作为存根运行，也是有效的 ZIP；
+    /// 读取时的偏移基准检测另外兼容偏移相对于 ZIP 数据开头的自解压归档。
+    /// 不能与 `append` 同时使用。
+    pub fn sfx_prefix(mut self, stub: &[u8]) -> Self {
+        self.options.sfx_prefix = Some(stub.to_vec());
+        self
+    }
+
+    /// 写入高精度修改时间
+    /// 启用后每个条目额外带有 `0x000a` NTFS 时间（100 ns 精度）和 `0x5455` 扩展时间戳
+    pub fn high_res_time(mut self, enabled: bool) -> Self {
+        self.options.high_res_time = enabled;
+        self
+    }
+
+    /// 设置条目名编码
+    /// 默认 UTF-8（非 ASCII 文件名设置 bit 11）；CP437 下无法表示的字符会导致错误
+    pub fn filename_encoding(mut self, encoding: Encoding) -> Self {
+        self.options.filename_encoding = encoding;
+        self
+    }
+
+    /// 选择 DEFLATE 压缩实现
+    /// `Fast` 为单次探测 LZ77 + 静态 Huffman；`Good` 使用更长的哈希链和动态 Huffman，更慢但压缩率更高
+    pub fn algorithm(mut self, algorithm: DeflateImpl) -> Self {
+        self.options.algorithm = algorithm;
+        self
+    }
+
+    /// 按内容魔数识别已压缩的文件（gzip、zip、zstd、xz、bzip2）并直接存储
+    /// 与扩展名无关；默认关闭
+    pub fn smart_store(mut self, enabled: bool) -> Self {
+        self.options.smart_store = enabled;
+        self
+    }
+
+    /// 内容完全相同的文件只存储一份数据，多个条目指向同一个本地文件头
+    /// 符合规范但不常见的布局，详见 [`ZipWriter::set_dedup`]；默认关闭
+    pub fn dedup(mut self, enabled: bool) -> Self {
+        self.options.dedup = enabled;
+        self
+    }
+
+    /// 模仿参考工具的输出约定（version made by / version needed、extra field 顺序、外部属性）
+    /// 默认 `Compat::Miniz`，即本库原有的输出
+    pub fn compatibility(mut self, compat: Compat) -> Self {
+        self.options.compat = compat;
+        self
+    }
+
+    /// 加密所有文件条目（目录条目和中央目录保持明文）
+    /// 解压时需要通过 `Extractor::password` 提供相同的密码
+    pub fn encrypt(mut self, encryption: Encryption) -> Self {
+        self.options.encryption = Some(encryption);
+        self
+    }
+
+    /// 先写到同目录下的临时文件，成功 finalize 后再重命名覆盖目标
+    /// 目标要么保持原样，要么是完整的新归档；失败时删除临时文件。默认关闭
+    pub fn atomic(mut self, atomic: bool) -> Self {
+        self.options.atomic = atomic;
+        self
+    }
+
+    /// 设置 `atomic` 写入使用的临时文件目录，默认是输出文件所在目录
+    ///
+    /// 与输出文件不在同一文件系统时无法直接重命名，完成后改为复制到目标再删除临时文件，
+    /// 复制过程中目标不再保持原子性。
+    pub fn temp_dir(mut self, dir: impl AsRef<Path>) -> Self {
+        self.options.temp_dir = Some(dir.as_ref().to_path_buf());
+        self
+    }
+
+    /// 在 EOCD 注释中填充零字节，使生成的文件大小为 `multiple` 的整数倍
+    ///
+    /// 用于固定大小的容器镜像或与块设备对齐；归档仍然有效。
+    /// 所需填充超过注释上限（65535 字节）时构建失败。默认不填充
+    pub fn pad_to(mut self, multiple: u64) -> Self {
+        self.options.pad_to = Some(multiple);
+        self
+    }
+
+    /// 在归档最后加入名为 `name` 的清单条目，列出本次写入的其他所有条目
+    ///
+    /// 每行一个条目，按写入顺序：`<CRC32 八位十六进制> <解压后大小> <条目名>`，
+    /// 目录条目的 CRC32 和大小为 0。清单不列出自身；与其他条目重名时构建失败。
+    pub fn include_manifest(mut self, name: &str) -> Self {
+        self.options.manifest = Some(name.to_string());
+        self
+    }
+
+    /// 只保存 `set` 中选定的文件元数据，在保真度与可移植性之间显式取舍
+    ///
+    /// 例如备份工具可以选 `PreserveSet::all()`，分发用的归档可以只选 `PERMISSIONS`。
+    /// 未调用时保持原有输出，详见 [`ZipWriter::set_preserve`]。
+    /// 解压时用 [`Extractor::restore`](crate::unzip::Extractor::restore) 选择要还原的部分。
+    pub fn preserve(mut self, set: PreserveSet) -> Self {
+        self.options.preserve = Some(set);
+        self
+    }
+
+    /// 追加模式下新条目与已有条目重名时的处理方式，默认 `DuplicatePolicy::Allow`
+    ///
+    /// `Warn` 照常添加并记录 `ZipWarning::DuplicateEntry`；`Error` 在写入任何数据之前返回
+    /// `ZipError::InvalidPath`；`Replace` 从中央目录去掉旧条目，写完后再压缩掉其数据留下的空隙，
+    /// 整个归档会被重写一遍。非追加模式下不生效。
+    pub fn on_duplicate(mut self, policy: DuplicatePolicy) -> Self {
+        self.options.on_duplicate = policy;
+        self
+    }
+
+    /// 追加之前先原地压缩已有归档，默认关闭
+    ///
+    /// 其他工具追加时可能把旧的中央目录留在条目之间；开启后这些孤立的中央目录以及
+    /// 条目之间、最后一个条目与中央目录之间的其他空隙都被去掉，新条目紧接在最后一个条目的数据之后。
+    /// 含 APK 签名块的归档不压缩，照常拒绝追加。非追加模式下不生效。
+    pub fn compact_on_append(mut self, compact: bool) -> Self {
+        self.options.compact_on_append = compact;
+        self
+    }
+
+    /// 输出文件 `BufWriter` 的容量（字节），默认 8 KiB
+    ///
+    /// 大量小条目时调大可减少写系统调用；只影响写入吞吐，归档内容不变。
+    pub fn write_buffer_size(mut self, size: usize) -> Self {
+        self.options.write_buffer_size = size;
+        self
+    }
+
+    /// 设置取消令牌
+    /// 在条目之间以及大文件的读取块之间检查；被取消时返回 `ZipError::Cancelled`，
+    /// 已写入的条目仍会被 finalize 成一个有效（但不完整）的归档
+    pub fn cancel_token(mut self, token: CancelToken) -> Self {
+        self.options.cancel = Some(token);
+        self
+    }
+
     pub fn files(mut self, files: &[impl AsRef<str>]) -> Result<Self> {
         for file in files {
             self.files.push(file.as_ref().to_string());
@@ -86,6 +325,43 @@ impl ZipBuilder {
         Ok(self)
     }
 
+    /// 添加目录 `dir` 下的全部内容，条目名相对于 `dir` 而不是 `root`
+    ///
+    /// 归档中不会出现 `dir/` 这一层包装目录，例如 `mydir/file1` 存为 `file1`。
+    /// 目录在 `build()` 时才被遍历；`recurse`、`mode`、`include_directories` 照常生效。
+    pub fn add_dir_contents(mut self, dir: impl AsRef<Path>) -> Self {
+        self.dir_contents.push(dir.as_ref().to_path_buf());
+        self
+    }
+
+    /// 按给定的 (源路径, 条目名) 逐一添加，条目名不经 `root` 推导
+    ///
+    /// 源路径是目录时只添加一个目录条目，不遍历其内容；`recurse`、`mode` 对这些条目不生效。
+    /// 条目写在 `files`、`add_dir_contents` 的条目之后，`prefix` 和 `sort_by` 照常生效。
+    pub fn add_all(mut self, entries: impl IntoIterator<Item = (PathBuf, String)>) -> Self {
+        self.explicit.extend(entries);
+        self
+    }
+
+    /// 按 `compare` 对条目名（含前缀）排序后再写入，排序是稳定的
+    ///
+    /// 默认按遍历顺序写入。部分工具要求目录条目出现在其内容之前，
+    /// 比较函数需要自行保证这一点。
+    pub fn sort_by(mut self, compare: impl Fn(&str, &str) -> Ordering + Send + Sync + 'static) -> Self {
+        self.order = Some(Box::new(compare));
+        self
+    }
+
+    /// 由 `mapper` 决定每个条目（文件和目录）的条目名
+    ///
+    /// `mapper` 收到条目的源路径，返回 `None` 跳过该条目，返回 `Some(name)` 以 `name` 作为条目名；
+    /// `name` 经 [`normalize_entry_name`] 校验和规范化，目录条目自动补上结尾的 `/`。
+    /// 设置后 `prefix` 不再生效，`sort_by` 按映射后的名字排序。
+    pub fn name_mapper(mut self, mapper: impl Fn(&Path) -> Option<String> + Send + Sync + 'static) -> Self {
+        self.name_mapper = Some(Box::new(mapper));
+        self
+    }
+
     pub fn build(self) -> Result<PathBuf> {
         Ok(self.build_with_warnings()?.zipfile)
     }
@@ -104,48 +380,257 @@ impl ZipBuilder {
             });
         }
 
-        // 获取文件数据（包括递归扫描和警告检测）
-        // 注意：不在这里验证文件存在性，让 C 层面的 zip_zip() 来处理
-        // 这样可以完全复刻 C 版本的行为：在实际添加文件时打开文件
-        let data = get_zip_data(
-            &self.files,
-            self.options.recurse,
-            self.options.mode,
-            self.options.include_directories,
-            &self.options.root,
-        )?;
+        let mut data = self.plan()?;
+        self.check_duplicates(&mut data)?;
 
         // 处理空 ZIP 文件列表
         // 注意：追加模式下，即使没有新文件，也需要保留原有条目
         // 非追加模式下，创建空 ZIP 文件（只有 EOCD 记录）
-        if data.entries.is_empty() && !self.options.append {
-            self.create_empty_zip()?;
+        if self.is_bare_eocd(&data) {
+            self.write_output(|path| self.create_empty_zip(path))?;
             return Ok(ZipBuildOutput {
                 zipfile: self.zipfile,
                 warnings: data.warnings,
+                entries: Vec::new(),
             });
         }
 
         // 调用底层 C 函数创建 ZIP
         // C 层面会在实际添加文件时打开文件，如果失败会返回错误
         // 在追加模式下，即使 data.entries 为空，也会保留原有条目
-        self.call_zip_zip(&data)?;
+        let entries = self.write_output(|path| self.call_zip_zip(path, &data))?;
 
         Ok(ZipBuildOutput {
             zipfile: self.zipfile,
             warnings: data.warnings,
+            entries,
+        })
+    }
+
+    /// 估算 `build()` 将生成的归档大小（字节），不写任何文件
+    ///
+    /// 每个条目都按当前选项实际压缩一遍，只统计输出字节数，
+    /// 因此结果与 `build()` 生成的文件长度一致；代价是与一次构建相当的 CPU 时间。
+    pub fn estimate_size(&self) -> Result<u64> {
+        let data = self.plan()?;
+        if self.is_bare_eocd(&data) {
+            // 空归档只有 EOCD
+            return Ok(22);
+        }
+
+        let mut zip_writer = ZipWriter::counting(
+            &self.zipfile,
+            self.options.compression_level,
+            self.options.append,
+        )?;
+        self.configure_writer(&mut zip_writer)?;
+        let existing = zip_writer.entry_summaries().len();
+        self.add_entries(&mut zip_writer, &data)?;
+        self.write_manifest(&mut zip_writer, existing)?;
+        zip_writer.finalize()?;
+        Ok(zip_writer.written_len())
+    }
+
+    /// 输出是否只是一个 22 字节的 EOCD：没有条目、清单和存根，不追加，也不要求 `pad_to` 对齐
+    ///
+    /// 其余情况（包括需要填充的空归档）都经由 `ZipWriter::finalize` 写出。
+    fn is_bare_eocd(&self, data: &ZipData) -> bool {
+        data.entries.is_empty()
+            && !self.options.append
+            && self.options.manifest.is_none()
+            && self.options.sfx_prefix.is_none()
+            && !matches!(self.options.pad_to, Some(multiple) if multiple > 1)
+    }
+
+    /// 按选项收集要写入的条目（文件列表、`add_dir_contents` 和前缀）
+    fn plan(&self) -> Result<ZipData> {
+        if self.options.append && self.options.sfx_prefix.is_some() {
+            return Err(ZipError::generic("sfx_prefix cannot be combined with append"));
+        }
+
+        // 获取文件数据（包括递归扫描和警告检测）
+        // 注意：不在这里验证文件存在性，让 C 层面的 zip_zip() 来处理
+        // 这样可以完全复刻 C 版本的行为：在实际添加文件时打开文件
+        let mut data = get_zip_data(
+            &self.files,
+            self.options.recurse,
+            self.options.mode,
+            self.options.include_directories,
+            self.options.include_root_entry,
+            &self.options.root,
+        )?;
+
+        for dir in &self.dir_contents {
+            let contents = get_zip_data(
+                &list_dir_children(dir)?,
+                self.options.recurse,
+                self.options.mode,
+                self.options.include_directories,
+                true,
+                dir,
+            )?;
+            data.entries.extend(contents.entries);
+            data.warnings.extend(contents.warnings);
+        }
+
+        for (file, name) in &self.explicit {
+            let dir = file.is_dir();
+            data.entries.push(ZipDataEntry {
+                key: if dir && !name.ends_with('/') { format!("{}/", name) } else { name.clone() },
+                file: file.clone(),
+                dir,
+            });
+        }
+
+        if let Some(mapper) = &self.name_mapper {
+            let mut mapped = Vec::with_capacity(data.entries.len());
+            for mut entry in data.entries {
+                let Some(name) = mapper(&entry.file) else { continue };
+                entry.key = normalize_entry_name(&name)?;
+                if entry.dir && !entry.key.ends_with('/') {
+                    entry.key.push('/');
+                }
+                mapped.push(entry);
+            }
+            data.entries = mapped;
+        } else if let Some(prefix) = &self.options.prefix {
+            let prefix = normalize_prefix(prefix)?;
+            if !prefix.is_empty() {
+                for entry in &mut data.entries {
+                    entry.key = format!("{}/{}", prefix, entry.key);
+                }
+            }
+        }
+
+        if self.options.prune_empty_dirs {
+            let files: Vec<String> = data
+                .entries
+                .iter()
+                .filter(|entry| !entry.dir)
+                .map(|entry| entry.key.clone())
+                .collect();
+            data.entries
+                .retain(|entry| !entry.dir || files.iter().any(|file| file.starts_with(&entry.key)));
+        }
+
+        if let Some(compare) = &self.order {
+            data.entries.sort_by(|a, b| compare(&a.key, &b.key));
+        }
+
+        Ok(data)
+    }
+
+    /// 按 `on_duplicate` 检查追加的条目是否与已有条目重名
+    fn check_duplicates(&self, data: &mut ZipData) -> Result<()> {
+        if !self.options.append || self.options.on_duplicate == DuplicatePolicy::Allow {
+            return Ok(());
+        }
+        let reader = ZipReader::open(&self.zipfile)?;
+        let existing: std::collections::HashSet<&str> =
+            reader.entries().iter().map(|entry| entry.name.as_str()).collect();
+        for entry in &data.entries {
+            if !existing.contains(entry.key.as_str()) {
+                continue;
+            }
+            match self.options.on_duplicate {
+                DuplicatePolicy::Warn => data.warnings.push(ZipWarning::DuplicateEntry(entry.key.clone())),
+                DuplicatePolicy::Error => {
+                    return Err(ZipError::InvalidPath {
+                        path: entry.key.clone(),
+                        reason: "entry already exists in the archive".to_string(),
+                    })
+                }
+                DuplicatePolicy::Allow | DuplicatePolicy::Replace => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// 从通道逐个接收条目并写入归档，直到收到 `BuildItem::Finish`
+    ///
+    /// 生产者线程负责枚举文件，当前线程负责压缩和写出，条目按接收顺序写入。
+    /// `files()` 设置的文件列表被忽略；`prefix` 和压缩相关选项照常生效。
+    /// 收到 `Finish` 之前通道被关闭视为错误，已写入的条目仍会被 finalize。
+    pub fn build_from_channel(self, rx: Receiver<BuildItem>) -> Result<PathBuf> {
+        if self.zipfile.is_dir() {
+            return Err(ZipError::generic("zipfile is a directory"));
+        }
+
+        let prefix = match &self.options.prefix {
+            Some(prefix) => normalize_prefix(prefix)?,
+            None => String::new(),
+        };
+        let key = |name: &str| {
+            if prefix.is_empty() {
+                name.to_string()
+            } else {
+                format!("{}/{}", prefix, name)
+            }
+        };
+
+        self.write_output(|path| self.receive_entries(path, &rx, &key))?;
+
+        Ok(self.zipfile)
+    }
+
+    /// `build_from_channel` 的写入部分：接收条目直到 `Finish` 并 finalize
+    fn receive_entries(
+        &self,
+        path: &Path,
+        rx: &Receiver<BuildItem>,
+        key: &dyn Fn(&str) -> String,
+    ) -> Result<()> {
+        let mut zip_writer = self.open_writer(path)?;
+        let existing = zip_writer.entry_summaries().len();
+        loop {
+            if let Some(cancel) = &self.options.cancel {
+                cancel.check()?;
+            }
+            match rx.recv() {
+                Ok(BuildItem::AddFile { name, mut reader }) => {
+                    zip_writer.add_reader(&key(&name), &mut reader)?;
+                }
+                Ok(BuildItem::AddDir { name }) => zip_writer.add_directory_entry(&key(&name))?,
+                Ok(BuildItem::Finish) => break,
+                Err(_) => {
+                    return Err(ZipError::generic(
+                        "build channel closed before BuildItem::Finish",
+                    ))
+                }
+            }
+        }
+        self.write_manifest(&mut zip_writer, existing)?;
+        zip_writer.finalize()
+    }
+
+    /// 把归档写到 `write` 收到的路径
+    /// 启用 `atomic` 时先写临时文件（追加模式下先复制原归档），成功后重命名为目标；
+    /// 临时文件位于 `temp_dir`，未设置时与目标同目录
+    fn write_output<T>(&self, write: impl FnOnce(&Path) -> Result<T>) -> Result<T> {
+        if !self.options.atomic {
+            return write(&self.zipfile);
+        }
+
+        replace::replace_file(&self.zipfile, self.options.temp_dir.as_deref(), |temp| {
+            if self.options.append {
+                fs::copy(&self.zipfile, temp).map_err(|e| ZipError::OpenWriteFailed {
+                    path: temp.to_path_buf(),
+                    source: e,
+                })?;
+            }
+            write(temp)
         })
     }
 
     /// 创建空 ZIP 文件（只有 EOCD 记录）
     /// 完全复刻 C 版本：当 num_files = 0 时，循环不执行，直接 finalize archive
-    fn create_empty_zip(&self) -> Result<()> {
+    fn create_empty_zip(&self, path: &Path) -> Result<()> {
         use std::io::Write;
 
         // 创建 ZIP 文件
-        let mut file = fs::File::create(&self.zipfile).map_err(|e| {
+        let mut file = fs::File::create(path).map_err(|e| {
             ZipError::OpenWriteFailed {
-                path: self.zipfile.clone(),
+                path: path.to_path_buf(),
                 source: e,
             }
         })?;
@@ -174,7 +659,48 @@ impl ZipBuilder {
         Ok(())
     }
 
-    fn call_zip_zip(&self, data: &ZipData) -> Result<()> {
+    /// 按选项创建 ZipWriter（追加模式下打开已有归档）
+    fn open_writer(&self, path: &Path) -> Result<ZipWriter> {
+        let mut zip_writer = if self.options.append {
+            if self.options.compact_on_append && !ZipReader::open(path)?.has_apk_signing_block()? {
+                ZipArchive::compact(path)?;
+            }
+            ZipWriter::append_buffered(
+                path,
+                self.options.compression_level,
+                self.options.write_buffer_size,
+            )?
+        } else {
+            ZipWriter::create_buffered(
+                path,
+                self.options.compression_level,
+                self.options.write_buffer_size,
+            )?
+        };
+        self.configure_writer(&mut zip_writer)?;
+        Ok(zip_writer)
+    }
+
+    /// 把压缩、编码、加密等选项应用到 writer，并写入自解压存根
+    fn configure_writer<W: Write>(&self, zip_writer: &mut ZipWriter<W>) -> Result<()> {
+        zip_writer.set_cancel_token(self.options.cancel.clone());
+        zip_writer.set_high_res_time(self.options.high_res_time);
+        zip_writer.set_filename_encoding(self.options.filename_encoding);
+        zip_writer.set_algorithm(self.options.algorithm);
+        zip_writer.set_smart_store(self.options.smart_store);
+        zip_writer.set_dedup(self.options.dedup);
+        zip_writer.set_compat(self.options.compat);
+        zip_writer.set_encryption(self.options.encryption.clone());
+        zip_writer.set_pad_to(self.options.pad_to);
+        zip_writer.set_preserve(self.options.preserve);
+        match &self.options.sfx_prefix {
+            Some(stub) => zip_writer.write_sfx_prefix(stub),
+            None => Ok(()),
+        }
+    }
+
+    /// 写出归档，返回本次写入的条目摘要
+    fn call_zip_zip(&self, path: &Path, data: &ZipData) -> Result<Vec<EntrySummary>> {
         // 对应 C 版本的 zip_zip() 函数（zip.c:319-431）
         // 使用纯 Rust 实现，不调用 FFI
 
@@ -182,21 +708,55 @@ impl ZipBuilder {
         // 对应 C 版本：根据 cappend 参数选择初始化方式
         // - cappend == 0: mz_zip_writer_init_cfile() (zip.c:346)
         // - cappend == 1: mz_zip_writer_init_from_reader() (zip.c:339-340)
-        let mut zip_writer = if self.options.append {
-            ZipWriter::new_with_append(
-                &self.zipfile,
-                self.options.compression_level,
-            )?
-        } else {
-            ZipWriter::new(
-                &self.zipfile,
-                self.options.compression_level,
-            )?
+        let mut zip_writer = self.open_writer(path)?;
+        let replaced = self.options.append
+            && self.options.on_duplicate == DuplicatePolicy::Replace
+            && zip_writer.remove_entries(|name| data.entries.iter().any(|entry| entry.key == name)) > 0;
+        let existing = zip_writer.entry_summaries().len();
+        self.add_entries(&mut zip_writer, data)?;
+        self.write_manifest(&mut zip_writer, existing)?;
+
+        // 完成 ZIP 文件写入
+        // 对应 C 版本：mz_zip_writer_finalize_archive() + mz_zip_writer_end() (zip.c:413-424)
+        zip_writer.finalize()?;
+        let entries = zip_writer.entry_summaries().split_off(existing);
+        drop(zip_writer);
+
+        // 被替换的旧条目的数据仍在文件中，重写一遍去掉这些空隙
+        if replaced {
+            ZipArchive::compact(path)?;
+        }
+        Ok(entries)
+    }
+
+    /// 设置了 `include_manifest` 时写入清单条目，列出 writer 中第 `existing` 个之后的条目
+    fn write_manifest<W: Write>(&self, zip_writer: &mut ZipWriter<W>, existing: usize) -> Result<()> {
+        let Some(name) = &self.options.manifest else {
+            return Ok(());
         };
+        let entries = zip_writer.entry_summaries().split_off(existing);
+        if entries.iter().any(|entry| &entry.name == name) {
+            return Err(ZipError::InvalidPath {
+                path: name.clone(),
+                reason: "manifest name collides with an archive entry".to_string(),
+            });
+        }
 
+        let mut manifest = String::new();
+        for entry in &entries {
+            manifest.push_str(&format!("{:08x} {} {}\n", entry.crc32, entry.uncompressed, entry.name));
+        }
+        zip_writer.add_reader(name, &mut manifest.as_bytes())
+    }
+
+    /// 按顺序把条目写入 writer
+    fn add_entries<W: Write>(&self, zip_writer: &mut ZipWriter<W>, data: &ZipData) -> Result<()> {
         // 遍历所有文件并添加到 ZIP
         // 对应 C 版本的循环：for (i = 0; i < n; i++)
         for entry in &data.entries {
+            if let Some(cancel) = &self.options.cancel {
+                cancel.check()?;
+            }
             if entry.dir {
                 // 添加目录
                 // 对应 C 版本：mz_zip_writer_add_mem_ex_v2() (zip.c:364-372)
@@ -205,14 +765,1164 @@ impl ZipBuilder {
                 // 添加文件
                 // 对应 C 版本：mz_zip_writer_add_cfile() (zip.c:389-402)
                 // 完全复刻 C 版本的错误检测：File::open() 会自动检测文件不存在、权限等错误
-                zip_writer.add_file(&entry.key, &entry.file)?;
+                zip_writer.add_file_as_is(&entry.key, &entry.file)?;
             }
         }
+        Ok(())
+    }
+}
 
-        // 完成 ZIP 文件写入
-        // 对应 C 版本：mz_zip_writer_finalize_archive() + mz_zip_writer_end() (zip.c:413-424)
-        zip_writer.finalize()?;
+/// 列出目录的直接子项（按名称排序），作为相对于该目录的文件列表
+fn list_dir_children(dir: &Path) -> Result<Vec<String>> {
+    let read_dir = fs::read_dir(dir).map_err(|e| {
+        ZipError::generic(&format!("Failed to read directory {}: {}", dir.display(), e))
+    })?;
+    let mut children = Vec::new();
+    for entry in read_dir {
+        let entry = entry.map_err(|e| {
+            ZipError::generic(&format!("Failed to read directory {}: {}", dir.display(), e))
+        })?;
+        children.push(entry.file_name().to_string_lossy().to_string());
+    }
+    children.sort();
+    Ok(children)
+}
 
-        Ok(())
+/// 校验并规范化条目名前缀：反斜杠转为 `/`，去掉首尾多余的 `/`
+fn normalize_prefix(prefix: &str) -> Result<String> {
+    let normalized = prefix.replace('\\', "/");
+    let invalid = |reason: &str| ZipError::InvalidPath {
+        path: prefix.to_string(),
+        reason: reason.to_string(),
+    };
+
+    if normalized.starts_with('/') {
+        return Err(invalid("prefix must be a relative path"));
+    }
+
+    let mut components = Vec::new();
+    for component in normalized.split('/') {
+        match component {
+            "" => continue,
+            "." | ".." => return Err(invalid("prefix must not contain '.' or '..' components")),
+            _ => components.push(component),
+        }
+    }
+
+    Ok(components.join("/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::unzip::ZipArchive;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_prefix() {
+        let temp = TempDir::new().unwrap();
+        let src = temp.path().join("src");
+        fs::create_dir_all(src.join("sub")).unwrap();
+        fs::write(src.join("a.txt"), b"alpha\n").unwrap();
+        fs::write(src.join("sub").join("b.txt"), b"bravo\n").unwrap();
+        let zipfile = temp.path().join("test.zip");
+
+        ZipBuilder::new(&zipfile)
+            .unwrap()
+            .root(&src)
+            .prefix("myapp-1.0")
+            .files(&["a.txt", "sub"])
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let entries = ZipArchive::list(&zipfile).unwrap();
+        assert_eq!(entries.len(), 3);
+        for entry in &entries {
+            assert!(entry.filename.starts_with("myapp-1.0/"), "{}", entry.filename);
+        }
+    }
+
+    #[test]
+    fn test_prune_empty_dirs() {
+        let temp = TempDir::new().unwrap();
+        let src = temp.path().join("src");
+        fs::create_dir_all(src.join("a").join("b").join("c")).unwrap();
+        fs::create_dir_all(src.join("keep").join("empty")).unwrap();
+        fs::write(src.join("keep").join("x.txt"), b"x\n").unwrap();
+
+        let build = |name: &str, prune: bool| {
+            let zipfile = temp.path().join(name);
+            ZipBuilder::new(&zipfile)
+                .unwrap()
+                .root(&src)
+                .prune_empty_dirs(prune)
+                .files(&["a", "keep"])
+                .unwrap()
+                .build()
+                .unwrap();
+            let mut names: Vec<String> = ZipArchive::list(&zipfile)
+                .unwrap()
+                .into_iter()
+                .map(|entry| entry.filename)
+                .collect();
+            names.sort();
+            names
+        };
+
+        assert_eq!(build("pruned.zip", true), ["keep/", "keep/x.txt"]);
+        assert_eq!(
+            build("full.zip", false),
+            ["a/", "a/b/", "a/b/c/", "keep/", "keep/empty/", "keep/x.txt"]
+        );
+    }
+
+    #[test]
+    fn test_sfx_prefix() {
+        let temp = TempDir::new().unwrap();
+        let src = temp.path().join("src");
+        fs::create_dir_all(src.join("sub")).unwrap();
+        fs::write(src.join("a.txt"), b"alpha\n").unwrap();
+        fs::write(src.join("sub").join("b.txt"), b"bravo\n").unwrap();
+        let zipfile = temp.path().join("setup.exe");
+        let stub = b"MZ\x90\x00dummy self-extractor stub\n".repeat(20);
+
+        let builder = ZipBuilder::new(&zipfile)
+            .unwrap()
+            .root(&src)
+            .sfx_prefix(&stub)
+            .files(&["a.txt", "sub"])
+            .unwrap();
+        let estimated = builder.estimate_size().unwrap();
+        builder.build().unwrap();
+
+        let bytes = fs::read(&zipfile).unwrap();
+        assert!(bytes.starts_with(&stub));
+        assert_eq!(bytes.len() as u64, estimated);
+        assert_eq!(ZipReader::open(&zipfile).unwrap().offset_base(), 0);
+
+        let names: Vec<String> = ZipArchive::list(&zipfile)
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.filename)
+            .collect();
+        assert_eq!(names, ["a.txt", "sub/", "sub/b.txt"]);
+
+        let exdir = temp.path().join("out");
+        crate::unzip::Extractor::new(&zipfile).unwrap().exdir(&exdir).extract().unwrap();
+        assert_eq!(fs::read(exdir.join("a.txt")).unwrap(), b"alpha\n");
+        assert_eq!(fs::read(exdir.join("sub/b.txt")).unwrap(), b"bravo\n");
+
+        let err = ZipBuilder::new(&zipfile).unwrap().append(true).sfx_prefix(&stub).build();
+        assert!(err.is_err());
+    }
+
+    #[test]
+    fn test_normalize_prefix() {
+        assert_eq!(normalize_prefix("a\\b/").unwrap(), "a/b");
+        assert!(normalize_prefix("/abs").is_err());
+        assert!(normalize_prefix("a/../b").is_err());
+    }
+
+    #[test]
+    fn test_high_res_time_roundtrip() {
+        use std::time::{Duration, UNIX_EPOCH};
+
+        let temp = TempDir::new().unwrap();
+        let src = temp.path().join("src");
+        fs::create_dir_all(&src).unwrap();
+        let file = src.join("a.txt");
+        fs::write(&file, b"alpha\n").unwrap();
+        let mtime = UNIX_EPOCH + Duration::new(1_700_000_000, 123_456_789);
+        fs::File::options()
+            .write(true)
+            .open(&file)
+            .unwrap()
+            .set_modified(mtime)
+            .unwrap();
+        let expected = fs::metadata(&file).unwrap().modified().unwrap();
+
+        let zipfile = temp.path().join("test.zip");
+        ZipBuilder::new(&zipfile)
+            .unwrap()
+            .root(&src)
+            .high_res_time(true)
+            .files(&["a.txt"])
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let entries = ZipArchive::list(&zipfile).unwrap();
+        let restored = entries[0].times.mtime;
+        let diff = expected
+            .duration_since(restored)
+            .unwrap_or_else(|e| e.duration());
+        assert!(diff < Duration::from_nanos(100), "diff {:?}", diff);
+        assert_ne!(restored.duration_since(UNIX_EPOCH).unwrap().subsec_nanos(), 0);
+    }
+
+    #[test]
+    fn test_filename_encoding_cp437() {
+        use crate::zip::ZipReader;
+
+        let temp = TempDir::new().unwrap();
+        let src = temp.path().join("src");
+        fs::create_dir_all(&src).unwrap();
+        fs::write(src.join("╔═╗.txt"), b"box\n").unwrap();
+        let zipfile = temp.path().join("test.zip");
+
+        ZipBuilder::new(&zipfile)
+            .unwrap()
+            .root(&src)
+            .filename_encoding(Encoding::Cp437)
+            .files(&["╔═╗.txt"])
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let bytes = fs::read(&zipfile).unwrap();
+        let encoded: &[u8] = b"\xc9\xcd\xbb.txt";
+        // 本地头和中央目录各出现一次
+        assert_eq!(bytes.windows(encoded.len()).filter(|w| *w == encoded).count(), 2);
+
+        let reader = ZipReader::open(&zipfile).unwrap();
+        let entry = &reader.entries()[0];
+        assert_eq!(entry.flags & 0x0800, 0);
+        assert_eq!(entry.name, "╔═╗.txt");
+
+        let err = ZipBuilder::new(temp.path().join("bad.zip"))
+            .unwrap()
+            .root(&src)
+            .filename_encoding(Encoding::Cp437)
+            .files(&["╔═╗.txt"])
+            .unwrap()
+            .prefix("日本")
+            .build()
+            .unwrap_err();
+        assert!(matches!(err, ZipError::InvalidPath { .. }), "{:?}", err);
+    }
+
+    #[test]
+    fn test_algorithm_good_smaller_than_fast() {
+        let temp = TempDir::new().unwrap();
+        let src = temp.path().join("src");
+        fs::create_dir_all(&src).unwrap();
+
+        // 可压缩的文本语料：伪随机排列的单词
+        let words = ["zip", "entry", "header", "deflate", "central", "local", "offset", "method"];
+        let mut seed = 7u32;
+        let mut text = String::new();
+        while text.len() < 64 * 1024 {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            text.push_str(words[(seed >> 16) as usize % words.len()]);
+            text.push(if (seed >> 8) & 7 == 0 { '\n' } else { ' ' });
+        }
+        fs::write(src.join("a.txt"), &text).unwrap();
+        fs::write(src.join("b.txt"), text.to_uppercase()).unwrap();
+
+        let build = |name: &str, algorithm: DeflateImpl| {
+            let zipfile = temp.path().join(name);
+            ZipBuilder::new(&zipfile)
+                .unwrap()
+                .root(&src)
+                .files(&["a.txt", "b.txt"])
+                .unwrap()
+                .algorithm(algorithm)
+                .build()
+                .unwrap();
+            zipfile
+        };
+        let fast = build("fast.zip", DeflateImpl::Fast);
+        let good = build("good.zip", DeflateImpl::Good);
+
+        let fast_len = fs::metadata(&fast).unwrap().len();
+        let good_len = fs::metadata(&good).unwrap().len();
+        assert!(good_len < fast_len, "good {} >= fast {}", good_len, fast_len);
+
+        for zipfile in [&fast, &good] {
+            let map = ZipArchive::open(zipfile).unwrap().extract_to_map().unwrap();
+            assert_eq!(map["a.txt"], text.as_bytes());
+            assert_eq!(map["b.txt"], text.to_uppercase().as_bytes());
+        }
+    }
+
+    #[test]
+    fn test_smart_store_gzip() {
+        use crate::zip::reader::ZipReader;
+
+        let temp = TempDir::new().unwrap();
+        let src = temp.path().join("src");
+        fs::create_dir_all(&src).unwrap();
+        let text = "compressible text\n".repeat(200);
+        // 最小的 gzip 成员：10 字节头 + 原始 DEFLATE + CRC32 + ISIZE
+        let mut gz = vec![0x1f, 0x8b, 8, 0, 0, 0, 0, 0, 0, 3];
+        gz.extend(crate::miniz::deflate::compress_raw(text.as_bytes(), 6).unwrap());
+        gz.extend(crate::miniz::crc32::crc32(0, text.as_bytes()).to_le_bytes());
+        gz.extend((text.len() as u32).to_le_bytes());
+        fs::write(src.join("data.gz"), &gz).unwrap();
+        fs::write(src.join("plain.txt"), &text).unwrap();
+
+        let build = |name: &str, smart_store: bool| {
+            let zipfile = temp.path().join(name);
+            ZipBuilder::new(&zipfile)
+                .unwrap()
+                .root(&src)
+                .files(&["data.gz", "plain.txt"])
+                .unwrap()
+                .smart_store(smart_store)
+                .build()
+                .unwrap();
+            let reader = ZipReader::open(&zipfile).unwrap();
+            reader
+                .entries()
+                .iter()
+                .map(|e| (e.name.clone(), e.compression_method))
+                .collect::<Vec<_>>()
+        };
+
+        let smart = build("smart.zip", true);
+        assert_eq!(smart, [("data.gz".to_string(), 0), ("plain.txt".to_string(), 8)]);
+        let plain = build("plain.zip", false);
+        assert_eq!(plain, [("data.gz".to_string(), 8), ("plain.txt".to_string(), 8)]);
+    }
+
+    #[test]
+    fn test_atomic_build_failure_leaves_target_intact() {
+        let temp = TempDir::new().unwrap();
+        let src = temp.path().join("src");
+        fs::create_dir_all(&src).unwrap();
+        fs::write(src.join("a.txt"), b"alpha\n").unwrap();
+        let zipfile = temp.path().join("out.zip");
+
+        // a.txt 写入之后才遇到不存在的文件
+        let failing_build = |atomic: bool| {
+            ZipBuilder::new(&zipfile)
+                .unwrap()
+                .root(&src)
+                .files(&["a.txt", "missing.txt"])
+                .unwrap()
+                .atomic(atomic)
+                .build()
+        };
+        let siblings = || fs::read_dir(temp.path()).unwrap().count();
+
+        // 没有目标时失败不留下任何文件
+        assert!(failing_build(true).is_err());
+        assert!(!zipfile.exists());
+        assert_eq!(siblings(), 1);
+
+        // 已有目标时失败保持原内容
+        ZipBuilder::new(&zipfile)
+            .unwrap()
+            .root(&src)
+            .files(&["a.txt"])
+            .unwrap()
+            .atomic(true)
+            .build()
+            .unwrap();
+        let original = fs::read(&zipfile).unwrap();
+        assert!(failing_build(true).is_err());
+        assert_eq!(fs::read(&zipfile).unwrap(), original);
+        assert_eq!(siblings(), 2);
+    }
+
+    #[test]
+    fn test_dedup_identical_files() {
+        let temp = TempDir::new().unwrap();
+        let src = temp.path().join("src");
+        fs::create_dir_all(&src).unwrap();
+        let text: String = (0..2000).map(|i| format!("line {} of shared content\n", i * 7919 % 1013)).collect();
+        for name in ["a.txt", "b.txt", "c.txt"] {
+            fs::write(src.join(name), &text).unwrap();
+        }
+
+        let build = |name: &str, dedup: bool| {
+            let zipfile = temp.path().join(name);
+            ZipBuilder::new(&zipfile)
+                .unwrap()
+                .root(&src)
+                .files(&["a.txt", "b.txt", "c.txt"])
+                .unwrap()
+                .dedup(dedup)
+                .build()
+                .unwrap();
+            zipfile
+        };
+
+        let deduped = build("dedup.zip", true);
+        let plain = build("plain.zip", false);
+        let deduped_len = fs::metadata(&deduped).unwrap().len();
+        let plain_len = fs::metadata(&plain).unwrap().len();
+        assert!(deduped_len * 2 < plain_len, "{} vs {}", deduped_len, plain_len);
+
+        let map = ZipArchive::open(&deduped).unwrap().extract_to_map().unwrap();
+        for name in ["a.txt", "b.txt", "c.txt"] {
+            assert_eq!(map[name], text.as_bytes());
+        }
+        let offsets: Vec<u64> = crate::zip::reader::ZipReader::open(&deduped)
+            .unwrap()
+            .entries()
+            .iter()
+            .map(|e| e.local_header_offset)
+            .collect();
+        assert!(offsets.iter().all(|&o| o == offsets[0]));
+    }
+
+    #[test]
+    fn test_build_from_channel() {
+        use crate::zip::reader::ZipReader;
+        use std::io::Cursor;
+        use std::sync::mpsc;
+
+        let temp = TempDir::new().unwrap();
+        let zipfile = temp.path().join("channel.zip");
+
+        let (tx, rx) = mpsc::channel();
+        let producer = std::thread::spawn(move || {
+            tx.send(BuildItem::AddDir { name: "docs".to_string() }).unwrap();
+            for i in 0..3 {
+                tx.send(BuildItem::AddFile {
+                    name: format!("docs/{}.txt", i),
+                    reader: Box::new(Cursor::new(format!("file {}\n", i).repeat(50))),
+                })
+                .unwrap();
+            }
+            tx.send(BuildItem::Finish).unwrap();
+        });
+
+        ZipBuilder::new(&zipfile).unwrap().build_from_channel(rx).unwrap();
+        producer.join().unwrap();
+
+        let reader = ZipReader::open(&zipfile).unwrap();
+        let names: Vec<&str> = reader.entries().iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, ["docs/", "docs/0.txt", "docs/1.txt", "docs/2.txt"]);
+        let map = ZipArchive::open(&zipfile).unwrap().extract_to_map().unwrap();
+        assert_eq!(map["docs/2.txt"], "file 2\n".repeat(50).as_bytes());
+
+        // 没有 Finish 就关闭通道
+        let (tx, rx) = mpsc::channel();
+        drop(tx);
+        let err = ZipBuilder::new(temp.path().join("unfinished.zip"))
+            .unwrap()
+            .build_from_channel(rx)
+            .unwrap_err();
+        assert!(err.to_string().contains("Finish"), "{}", err);
+    }
+
+    #[test]
+    fn test_compatibility_infozip() {
+        use crate::zip::extra;
+        use crate::zip::reader::ZipReader;
+
+        let temp = TempDir::new().unwrap();
+        let src = temp.path().join("src");
+        fs::create_dir_all(src.join("dir")).unwrap();
+        fs::write(src.join("dir").join("a.txt"), "alpha\n".repeat(20)).unwrap();
+        let zipfile = temp.path().join("infozip.zip");
+        ZipBuilder::new(&zipfile)
+            .unwrap()
+            .root(&src)
+            .files(&["dir"])
+            .unwrap()
+            .compatibility(Compat::InfoZip)
+            .build()
+            .unwrap();
+
+        let reader = ZipReader::open(&zipfile).unwrap();
+        let records = reader.raw_central_records().unwrap();
+        let versions: Vec<(u16, u16)> = records
+            .iter()
+            .map(|r| {
+                (
+                    u16::from_le_bytes([r[4], r[5]]),
+                    u16::from_le_bytes([r[6], r[7]]),
+                )
+            })
+            .collect();
+        // 目录 "dir/" 需要 1.0，deflate 的 "dir/a.txt" 需要 2.0
+        assert_eq!(versions, [(0x031E, 10), (0x031E, 20)]);
+
+        let extra_data = &reader.entries()[1].extra;
+        let tag = u16::from_le_bytes([extra_data[0], extra_data[1]]);
+        assert_eq!(tag, extra::EXTENDED_TIMESTAMP_TAG);
+        #[cfg(unix)]
+        assert!(extra::find_field(extra_data, extra::UNIX_OWNER_TAG).is_some());
+
+        let map = ZipArchive::open(&zipfile).unwrap().extract_to_map().unwrap();
+        assert_eq!(map["dir/a.txt"], "alpha\n".repeat(20).as_bytes());
+    }
+
+    #[test]
+    fn test_add_dir_contents() {
+        let temp = TempDir::new().unwrap();
+        let mydir = temp.path().join("mydir");
+        fs::create_dir_all(mydir.join("sub")).unwrap();
+        fs::write(mydir.join("file1"), "one\n").unwrap();
+        fs::write(mydir.join("sub").join("file2"), "two\n").unwrap();
+        let zipfile = temp.path().join("contents.zip");
+        ZipBuilder::new(&zipfile)
+            .unwrap()
+            .add_dir_contents(&mydir)
+            .build()
+            .unwrap();
+
+        let names: Vec<String> = crate::list(&zipfile)
+            .unwrap()
+            .into_iter()
+            .map(|e| e.filename)
+            .collect();
+        assert_eq!(names, ["file1", "sub/", "sub/file2"]);
+
+        let map = ZipArchive::open(&zipfile).unwrap().extract_to_map().unwrap();
+        assert_eq!(map["sub/file2"], b"two\n");
+    }
+
+    #[test]
+    fn test_on_duplicate_policies() {
+        let temp = TempDir::new().unwrap();
+        // 不可压缩的旧内容，便于确认替换后其数据不再留在文件中
+        let mut seed = 1u32;
+        let old: Vec<u8> = (0..4096)
+            .map(|_| {
+                seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                (seed >> 16) as u8
+            })
+            .collect();
+        fs::write(temp.path().join("b.txt"), "bravo\n").unwrap();
+        let zipfile = temp.path().join("dup.zip");
+        let base = || {
+            fs::write(temp.path().join("a.txt"), &old).unwrap();
+            ZipBuilder::new(&zipfile)
+                .unwrap()
+                .root(temp.path())
+                .files(&["a.txt", "b.txt"])
+                .unwrap()
+                .build()
+                .unwrap();
+            fs::write(temp.path().join("a.txt"), "new\n").unwrap();
+        };
+        let append = |policy| {
+            ZipBuilder::new(&zipfile)
+                .unwrap()
+                .root(temp.path())
+                .files(&["a.txt"])
+                .unwrap()
+                .append(true)
+                .on_duplicate(policy)
+                .build_with_warnings()
+        };
+        let names = || -> Vec<String> { ZipArchive::list(&zipfile).unwrap().into_iter().map(|e| e.filename).collect() };
+
+        base();
+        let output = append(DuplicatePolicy::Allow).unwrap();
+        assert!(output.warnings.is_empty());
+        assert_eq!(names(), ["a.txt", "b.txt", "a.txt"]);
+
+        base();
+        let output = append(DuplicatePolicy::Warn).unwrap();
+        assert_eq!(output.warnings, [ZipWarning::DuplicateEntry("a.txt".to_string())]);
+        assert_eq!(names(), ["a.txt", "b.txt", "a.txt"]);
+
+        base();
+        let before = fs::read(&zipfile).unwrap();
+        assert!(matches!(append(DuplicatePolicy::Error), Err(ZipError::InvalidPath { .. })));
+        assert_eq!(fs::read(&zipfile).unwrap(), before);
+
+        base();
+        let size_before = fs::metadata(&zipfile).unwrap().len();
+        append(DuplicatePolicy::Replace).unwrap();
+        assert_eq!(names(), ["b.txt", "a.txt"]);
+        let map = ZipArchive::open(&zipfile).unwrap().extract_to_map().unwrap();
+        assert_eq!(map["a.txt"], b"new\n");
+        // 旧条目的数据被去掉，而不是留在文件中
+        assert!(fs::metadata(&zipfile).unwrap().len() < size_before - 4000);
+    }
+
+    #[test]
+    fn test_add_all_custom_names() {
+        let temp = TempDir::new().unwrap();
+        fs::create_dir_all(temp.path().join("deep").join("nested")).unwrap();
+        let sources = [
+            temp.path().join("a.txt"),
+            temp.path().join("deep").join("nested").join("b.bin"),
+            temp.path().join("deep").join("c"),
+        ];
+        for (i, source) in sources.iter().enumerate() {
+            fs::write(source, format!("file {}\n", i)).unwrap();
+        }
+
+        let zipfile = temp.path().join("custom.zip");
+        ZipBuilder::new(&zipfile)
+            .unwrap()
+            .add_all([
+                (sources[0].clone(), "docs/readme.txt".to_string()),
+                (sources[1].clone(), "data.bin".to_string()),
+                (sources[2].clone(), "x/y/z".to_string()),
+            ])
+            .build()
+            .unwrap();
+
+        let names: Vec<String> = crate::list(&zipfile)
+            .unwrap()
+            .into_iter()
+            .map(|e| e.filename)
+            .collect();
+        assert_eq!(names, ["docs/readme.txt", "data.bin", "x/y/z"]);
+
+        let map = ZipArchive::open(&zipfile).unwrap().extract_to_map().unwrap();
+        assert_eq!(map["data.bin"], b"file 1\n");
+    }
+
+    #[test]
+    fn test_encrypt_zipcrypto_roundtrip() {
+        use crate::unzip::Extractor;
+
+        let temp = TempDir::new().unwrap();
+        let src = temp.path().join("src");
+        fs::create_dir_all(src.join("docs")).unwrap();
+        fs::write(src.join("docs").join("a.txt"), "alpha\n".repeat(100)).unwrap();
+        fs::write(src.join("docs").join("empty"), "").unwrap();
+        let zipfile = temp.path().join("encrypted.zip");
+        ZipBuilder::new(&zipfile)
+            .unwrap()
+            .root(&src)
+            .files(&["docs"])
+            .unwrap()
+            .encrypt(Encryption::ZipCrypto {
+                password: "s3cret".to_string(),
+            })
+            .build()
+            .unwrap();
+
+        let entries = crate::list(&zipfile).unwrap();
+        let encrypted: Vec<(&str, bool)> = entries
+            .iter()
+            .map(|e| (e.filename.as_str(), e.is_encrypted))
+            .collect();
+        assert_eq!(
+            encrypted,
+            [("docs/", false), ("docs/a.txt", true), ("docs/empty", true)]
+        );
+
+        let out = temp.path().join("out");
+        Extractor::new(&zipfile)
+            .unwrap()
+            .exdir(&out)
+            .password("s3cret")
+            .extract()
+            .unwrap();
+        assert_eq!(fs::read(out.join("docs").join("a.txt")).unwrap(), "alpha\n".repeat(100).as_bytes());
+        assert_eq!(fs::read(out.join("docs").join("empty")).unwrap(), b"");
+
+        // 错误密码通常在校验字节处被拒绝，偶尔（1/256）校验字节碰巧一致时由 CRC 拒绝
+        let err = ZipArchive::open(&zipfile)
+            .unwrap()
+            .password("wrong")
+            .extract_to_map()
+            .unwrap_err();
+        assert!(
+            matches!(err, ZipError::InvalidPassword { .. } | ZipError::CorruptEntry { .. }),
+            "{}",
+            err
+        );
+    }
+
+    #[cfg(feature = "aes")]
+    #[test]
+    fn test_encrypt_aes256_roundtrip() {
+        use crate::error::AesStrength;
+        use crate::unzip::Extractor;
+        use crate::zip::reader::ZipReader;
+
+        let temp = TempDir::new().unwrap();
+        let src = temp.path().join("src");
+        fs::create_dir_all(&src).unwrap();
+        fs::write(src.join("a.txt"), "alpha\n".repeat(100)).unwrap();
+        fs::write(src.join("tiny"), "x").unwrap();
+        let zipfile = temp.path().join("aes.zip");
+        ZipBuilder::new(&zipfile)
+            .unwrap()
+            .root(&src)
+            .files(&["a.txt", "tiny"])
+            .unwrap()
+            .encrypt(Encryption::Aes {
+                password: "correct horse".to_string(),
+                strength: AesStrength::Aes256,
+            })
+            .build()
+            .unwrap();
+
+        let reader = ZipReader::open(&zipfile).unwrap();
+        let records = reader.raw_central_records().unwrap();
+        for (entry, record) in reader.entries().iter().zip(&records) {
+            assert_eq!(entry.compression_method, 99);
+            assert_eq!(entry.crc32, 0);
+            assert_eq!(u16::from_le_bytes([record[6], record[7]]), 51);
+            assert_eq!(crate::zip::extra::aes_field(&entry.extra), Some((2, 3, 8)));
+        }
+
+        let out = temp.path().join("out");
+        Extractor::new(&zipfile)
+            .unwrap()
+            .exdir(&out)
+            .password("correct horse")
+            .extract()
+            .unwrap();
+        assert_eq!(fs::read(out.join("a.txt")).unwrap(), "alpha\n".repeat(100).as_bytes());
+        assert_eq!(fs::read(out.join("tiny")).unwrap(), b"x");
+
+        let err = ZipArchive::open(&zipfile)
+            .unwrap()
+            .password("battery staple")
+            .extract_to_map()
+            .unwrap_err();
+        assert!(
+            matches!(err, ZipError::InvalidPassword { .. } | ZipError::CorruptEntry { .. }),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_estimate_size_matches_build() {
+        let temp = TempDir::new().unwrap();
+        let src = temp.path().join("src");
+        fs::create_dir_all(src.join("dir")).unwrap();
+        fs::write(src.join("a.txt"), "alpha beta gamma\n".repeat(200)).unwrap();
+        fs::write(src.join("dir").join("b.bin"), (0..5000u32).map(|i| (i * 7919 % 251) as u8).collect::<Vec<_>>()).unwrap();
+        fs::write(src.join("dir").join("empty"), "").unwrap();
+
+        for level in [CompressionLevel::NoCompression, CompressionLevel::Level6, CompressionLevel::Level9] {
+            let zipfile = temp.path().join(format!("estimate-{}.zip", level.as_u8()));
+            let builder = ZipBuilder::new(&zipfile)
+                .unwrap()
+                .root(&src)
+                .files(&["a.txt", "dir"])
+                .unwrap()
+                .compression_level(level)
+                .high_res_time(true);
+
+            let estimate = builder.estimate_size().unwrap();
+            assert!(!zipfile.exists(), "estimate_size must not write the archive");
+            builder.build().unwrap();
+            assert_eq!(estimate, fs::metadata(&zipfile).unwrap().len(), "{:?}", level);
+        }
+
+        let empty = ZipBuilder::new(temp.path().join("empty.zip")).unwrap();
+        assert_eq!(empty.estimate_size().unwrap(), 22);
+    }
+
+    #[test]
+    fn test_sort_by_reverse() {
+        let temp = TempDir::new().unwrap();
+        let src = temp.path().join("src");
+        fs::create_dir_all(&src).unwrap();
+        for name in ["a.txt", "b.txt", "c.txt"] {
+            fs::write(src.join(name), name).unwrap();
+        }
+
+        let zipfile = temp.path().join("sorted.zip");
+        ZipBuilder::new(&zipfile)
+            .unwrap()
+            .root(&src)
+            .files(&["b.txt", "a.txt", "c.txt"])
+            .unwrap()
+            .sort_by(|a, b| b.cmp(a))
+            .build()
+            .unwrap();
+
+        let names: Vec<String> = ZipArchive::list(&zipfile)
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.filename)
+            .collect();
+        assert_eq!(names, ["c.txt", "b.txt", "a.txt"]);
+    }
+
+    #[test]
+    fn test_atomic_temp_dir() {
+        use std::io::Cursor;
+        use std::sync::mpsc;
+        use std::time::{Duration, Instant};
+
+        let temp = TempDir::new().unwrap();
+        let scratch = temp.path().join("scratch");
+        fs::create_dir_all(&scratch).unwrap();
+        let out_dir = temp.path().join("out");
+        fs::create_dir_all(&out_dir).unwrap();
+        let zipfile = out_dir.join("out.zip");
+
+        let (tx, rx) = mpsc::channel();
+        let builder = ZipBuilder::new(&zipfile).unwrap().atomic(true).temp_dir(&scratch);
+        let build = std::thread::spawn(move || builder.build_from_channel(rx));
+
+        tx.send(BuildItem::AddFile {
+            name: "a.txt".to_string(),
+            reader: Box::new(Cursor::new(b"alpha\n".to_vec())),
+        })
+        .unwrap();
+
+        // 构建过程中临时文件位于 scratch，目标尚未出现
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while fs::read_dir(&scratch).unwrap().count() == 0 {
+            assert!(Instant::now() < deadline, "temp file never appeared in temp_dir");
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        assert!(!zipfile.exists());
+        assert_eq!(fs::read_dir(&out_dir).unwrap().count(), 0);
+
+        tx.send(BuildItem::Finish).unwrap();
+        assert_eq!(build.join().unwrap().unwrap(), zipfile);
+
+        assert_eq!(fs::read_dir(&scratch).unwrap().count(), 0);
+        let map = ZipArchive::open(&zipfile).unwrap().extract_to_map().unwrap();
+        assert_eq!(map["a.txt"], b"alpha\n");
+    }
+
+    #[test]
+    fn test_pad_to() {
+        use crate::unzip::Extractor;
+
+        let temp = TempDir::new().unwrap();
+        let src = temp.path().join("src");
+        fs::create_dir_all(src.join("dir")).unwrap();
+        fs::write(src.join("a.txt"), b"alpha\n").unwrap();
+        fs::write(src.join("dir").join("b.txt"), "bravo ".repeat(300)).unwrap();
+        let zipfile = temp.path().join("padded.zip");
+        ZipBuilder::new(&zipfile)
+            .unwrap()
+            .root(&src)
+            .files(&["a.txt", "dir"])
+            .unwrap()
+            .pad_to(4096)
+            .build()
+            .unwrap();
+
+        assert_eq!(fs::metadata(&zipfile).unwrap().len() % 4096, 0);
+        let names: Vec<String> = ZipArchive::list(&zipfile)
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.filename)
+            .collect();
+        assert_eq!(names, ["a.txt", "dir/", "dir/b.txt"]);
+
+        let exdir = temp.path().join("out");
+        Extractor::new(&zipfile).unwrap().exdir(&exdir).extract().unwrap();
+        assert_eq!(fs::read(exdir.join("a.txt")).unwrap(), b"alpha\n");
+        assert_eq!(fs::read_to_string(exdir.join("dir").join("b.txt")).unwrap(), "bravo ".repeat(300));
+
+        // 没有条目的归档同样按 pad_to 对齐，估算结果与实际长度一致
+        let empty = temp.path().join("empty.zip");
+        let builder = ZipBuilder::new(&empty)
+            .unwrap()
+            .root(&src)
+            .files(&[] as &[&str])
+            .unwrap()
+            .pad_to(4096);
+        assert_eq!(builder.estimate_size().unwrap(), 4096);
+        builder.build().unwrap();
+        assert_eq!(fs::metadata(&empty).unwrap().len(), 4096);
+        assert!(ZipArchive::list(&empty).unwrap().is_empty());
+
+        // 超过注释上限的填充无法满足
+        let err = ZipBuilder::new(temp.path().join("huge.zip"))
+            .unwrap()
+            .root(&src)
+            .files(&["a.txt"])
+            .unwrap()
+            .pad_to(1 << 20)
+            .build()
+            .unwrap_err();
+        assert!(err.to_string().contains("EOCD comment"), "{}", err);
+    }
+
+    #[test]
+    fn test_build_entry_summaries() {
+        let temp = TempDir::new().unwrap();
+        let src = temp.path().join("src");
+        fs::create_dir_all(src.join("dir")).unwrap();
+        fs::write(src.join("a.txt"), b"alpha\n").unwrap();
+        fs::write(src.join("dir").join("b.txt"), "bravo ".repeat(300)).unwrap();
+        let zipfile = temp.path().join("summary.zip");
+        let output = ZipBuilder::new(&zipfile)
+            .unwrap()
+            .root(&src)
+            .files(&["a.txt", "dir"])
+            .unwrap()
+            .build_with_warnings()
+            .unwrap();
+
+        let listed = ZipArchive::list(&zipfile).unwrap();
+        assert_eq!(output.entries.len(), listed.len());
+        for (summary, entry) in output.entries.iter().zip(&listed) {
+            assert_eq!(summary.name, entry.filename);
+            assert_eq!(summary.compressed, entry.compressed_size);
+            assert_eq!(summary.uncompressed, entry.uncompressed_size);
+            assert_eq!(summary.crc32, entry.crc32);
+        }
+        let methods: Vec<u16> = output.entries.iter().map(|entry| entry.method).collect();
+        assert_eq!(methods, [8, 0, 8]);
+        assert_eq!(output.entries[2].crc32, crate::miniz::crc32::crc32(0, "bravo ".repeat(300).as_bytes()));
+
+        // 追加模式只报告新写入的条目
+        fs::write(src.join("c.txt"), b"charlie\n").unwrap();
+        let appended = ZipBuilder::new(&zipfile)
+            .unwrap()
+            .append(true)
+            .root(&src)
+            .files(&["c.txt"])
+            .unwrap()
+            .build_with_warnings()
+            .unwrap();
+        let names: Vec<&str> = appended.entries.iter().map(|entry| entry.name.as_str()).collect();
+        assert_eq!(names, ["c.txt"]);
+    }
+
+    #[test]
+    fn test_include_manifest() {
+        let temp = TempDir::new().unwrap();
+        let src = temp.path().join("src");
+        fs::create_dir_all(src.join("dir")).unwrap();
+        fs::write(src.join("a.txt"), b"alpha\n").unwrap();
+        fs::write(src.join("dir").join("b c.txt"), "bravo ".repeat(300)).unwrap();
+        let zipfile = temp.path().join("manifest.zip");
+        ZipBuilder::new(&zipfile)
+            .unwrap()
+            .root(&src)
+            .files(&["a.txt", "dir"])
+            .unwrap()
+            .include_manifest("MANIFEST")
+            .build()
+            .unwrap();
+
+        let listed = ZipArchive::list(&zipfile).unwrap();
+        assert_eq!(listed.last().unwrap().filename, "MANIFEST");
+        let manifest = ZipArchive::open(&zipfile).unwrap().read_entry("MANIFEST").unwrap();
+        let manifest = String::from_utf8(manifest).unwrap();
+
+        let parsed: Vec<(u32, u64, String)> = manifest
+            .lines()
+            .map(|line| {
+                let mut fields = line.splitn(3, ' ');
+                let crc = u32::from_str_radix(fields.next().unwrap(), 16).unwrap();
+                let size = fields.next().unwrap().parse().unwrap();
+                (crc, size, fields.next().unwrap().to_string())
+            })
+            .collect();
+        let expected: Vec<(u32, u64, String)> = listed[..listed.len() - 1]
+            .iter()
+            .map(|entry| (entry.crc32, entry.uncompressed_size, entry.filename.clone()))
+            .collect();
+        assert_eq!(parsed, expected);
+        assert_eq!(parsed.len(), 3);
+        assert_eq!(parsed[2].2, "dir/b c.txt");
+
+        // 与已有条目重名
+        let err = ZipBuilder::new(temp.path().join("clash.zip"))
+            .unwrap()
+            .root(&src)
+            .files(&["a.txt"])
+            .unwrap()
+            .include_manifest("a.txt")
+            .build()
+            .unwrap_err();
+        assert!(matches!(err, ZipError::InvalidPath { .. }), "{:?}", err);
+    }
+
+    #[test]
+    fn test_include_root_entry() {
+        let temp = TempDir::new().unwrap();
+        let src = temp.path().join("mydir");
+        fs::create_dir_all(src.join("sub")).unwrap();
+        fs::write(src.join("file"), b"file\n").unwrap();
+        fs::write(src.join("sub").join("nested"), b"nested\n").unwrap();
+
+        let names = |mode: ZipMode, include: bool| {
+            let zipfile = temp.path().join("out.zip");
+            ZipBuilder::new(&zipfile)
+                .unwrap()
+                .root(temp.path())
+                .mode(mode)
+                .files(&["mydir"])
+                .unwrap()
+                .include_root_entry(include)
+                .build()
+                .unwrap();
+            let mut names: Vec<String> = ZipArchive::list(&zipfile)
+                .unwrap()
+                .into_iter()
+                .map(|entry| entry.filename)
+                .collect();
+            names.sort();
+            names
+        };
+
+        assert_eq!(
+            names(ZipMode::Mirror, true),
+            ["mydir/", "mydir/file", "mydir/sub/", "mydir/sub/nested"]
+        );
+        assert_eq!(
+            names(ZipMode::Mirror, false),
+            ["mydir/file", "mydir/sub/", "mydir/sub/nested"]
+        );
+        assert_eq!(
+            names(ZipMode::CherryPick, false),
+            ["mydir/file", "mydir/sub/", "mydir/sub/nested"]
+        );
+    }
+
+    #[test]
+    fn test_name_mapper() {
+        let temp = TempDir::new().unwrap();
+        let src = temp.path().join("src");
+        fs::create_dir_all(src.join("Docs")).unwrap();
+        fs::write(src.join("README.md"), b"readme\n").unwrap();
+        fs::write(src.join("build.tmp"), b"scratch\n").unwrap();
+        fs::write(src.join("Docs").join("Guide.TXT"), b"guide\n").unwrap();
+
+        let root = src.clone();
+        let zipfile = temp.path().join("out.zip");
+        ZipBuilder::new(&zipfile)
+            .unwrap()
+            .root(&src)
+            .files(&["README.md", "build.tmp", "Docs"])
+            .unwrap()
+            .prefix("ignored")
+            .name_mapper(move |path| {
+                if path.extension().is_some_and(|ext| ext == "tmp") {
+                    return None;
+                }
+                let relative = path.strip_prefix(&root).ok()?;
+                Some(relative.to_string_lossy().to_lowercase())
+            })
+            .build()
+            .unwrap();
+
+        let mut names: Vec<String> = ZipArchive::list(&zipfile)
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.filename)
+            .collect();
+        names.sort();
+        assert_eq!(names, ["docs/", "docs/guide.txt", "readme.md"]);
+
+        let err = ZipBuilder::new(&zipfile)
+            .unwrap()
+            .root(&src)
+            .files(&["README.md"])
+            .unwrap()
+            .name_mapper(|_| Some("../escape".to_string()))
+            .build()
+            .unwrap_err();
+        assert!(matches!(err, ZipError::InvalidPath { .. }), "{:?}", err);
+    }
+
+    #[test]
+    fn test_compact_on_append() {
+        let temp = TempDir::new().unwrap();
+        for name in ["a.txt", "b.txt", "c.txt"] {
+            fs::write(temp.path().join(name), format!("{} contents\n", name)).unwrap();
+        }
+        let zipfile = temp.path().join("out.zip");
+        let append = |name: &str, append: bool| {
+            ZipBuilder::new(&zipfile)
+                .unwrap()
+                .root(temp.path())
+                .append(append)
+                .compact_on_append(true)
+                .files(&[name])
+                .unwrap()
+                .build()
+                .unwrap();
+        };
+        append("a.txt", false);
+
+        // 模拟其他工具的追加：旧的中央目录和 EOCD 留在条目之后、新中央目录之前
+        let bytes = fs::read(&zipfile).unwrap();
+        let eocd_pos = bytes.len() - 22;
+        let cd_offset = u32::from_le_bytes(bytes[eocd_pos + 16..eocd_pos + 20].try_into().unwrap()) as usize;
+        let orphan = bytes[cd_offset..].to_vec();
+        let mut out = bytes[..cd_offset].to_vec();
+        out.extend_from_slice(&orphan);
+        out.extend_from_slice(&bytes[cd_offset..]);
+        let eocd_pos = out.len() - 22;
+        out[eocd_pos + 16..eocd_pos + 20].copy_from_slice(&((cd_offset + orphan.len()) as u32).to_le_bytes());
+        fs::write(&zipfile, out).unwrap();
+
+        append("b.txt", true);
+        append("c.txt", true);
+
+        let bytes = fs::read(&zipfile).unwrap();
+        let count = |sig: &[u8]| bytes.windows(4).filter(|w| *w == sig).count();
+        assert_eq!(count(b"PK\x01\x02"), 3);
+        assert_eq!(count(b"PK\x05\x06"), 1);
+
+        let reader = ZipReader::open(&zipfile).unwrap();
+        assert_eq!(reader.get_append_offset(), reader.entries_end_offset().unwrap());
+        let map = ZipArchive::open(&zipfile).unwrap().extract_to_map().unwrap();
+        assert_eq!(map.len(), 3);
+        assert_eq!(map["b.txt"], b"b.txt contents\n");
+
+        // 自解压归档的存根在整理时保留
+        let stub = b"#!/bin/sh\nexit 0\n";
+        ZipBuilder::new(&zipfile)
+            .unwrap()
+            .root(temp.path())
+            .files(&["a.txt"])
+            .unwrap()
+            .sfx_prefix(stub)
+            .build()
+            .unwrap();
+        append("b.txt", true);
+        assert!(fs::read(&zipfile).unwrap().starts_with(stub));
+        let map = ZipArchive::open(&zipfile).unwrap().extract_to_map().unwrap();
+        assert_eq!(map.len(), 2);
+        assert_eq!(map["a.txt"], b"a.txt contents\n");
+    }
+
+    #[test]
+    fn test_write_buffer_size() {
+        let temp = TempDir::new().unwrap();
+        let src = temp.path().join("src");
+        fs::create_dir_all(&src).unwrap();
+        let mut files = Vec::new();
+        for i in 0..50 {
+            let name = format!("f{:02}.txt", i);
+            fs::write(src.join(&name), format!("tiny {}\n", i)).unwrap();
+            files.push(name);
+        }
+        let large: Vec<u8> = (0..300_000u32).map(|i| (i % 251) as u8 ^ (i / 997) as u8).collect();
+        fs::write(src.join("large.bin"), &large).unwrap();
+        files.push("large.bin".to_string());
+
+        let build = |name: &str, size: Option<usize>| {
+            let zipfile = temp.path().join(name);
+            let mut builder = ZipBuilder::new(&zipfile).unwrap().root(&src).files(&files).unwrap();
+            if let Some(size) = size {
+                builder = builder.write_buffer_size(size);
+            }
+            builder.build().unwrap();
+            fs::read(&zipfile).unwrap()
+        };
+        let expected = build("default.zip", None);
+        assert_eq!(build("small.zip", Some(16)), expected);
+        assert_eq!(build("large.zip", Some(4 << 20)), expected);
+
+        // 追加模式同样不受影响
+        let append = |name: &str, size: usize| {
+            let zipfile = temp.path().join(name);
+            fs::write(&zipfile, &expected).unwrap();
+            ZipBuilder::new(&zipfile)
+                .unwrap()
+                .root(&src)
+                .files(&["f00.txt"])
+                .unwrap()
+                .append(true)
+                .write_buffer_size(size)
+                .build()
+                .unwrap();
+            fs::read(&zipfile).unwrap()
+        };
+        assert_eq!(append("append-small.zip", 1), append("append-large.zip", 1 << 20));
     }
 }
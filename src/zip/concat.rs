@@ -0,0 +1,255 @@
+//! 合并多个 ZIP 归档
+//!
+//! 每个条目的压缩数据原样复制（不解压、不重新压缩），
+//! 最后写出一个统一的中央目录。
+
+use crate::error::{CompressionLevel, Result, ZipError};
+use crate::zip::reader::ZipReader;
+use crate::zip::replace::replace_file;
+use crate::zip::ZipWriter;
+use std::collections::HashSet;
+use std::path::Path;
+
+/// 合并时遇到重名条目的处理策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CollisionPolicy {
+    /// 保留先出现的条目，跳过后面的同名条目
+    #[default]
+    KeepFirst,
+    /// 给后面的同名条目改名：`name.txt` -> `name (2).txt`
+    Rename,
+    /// 返回错误
+    Error,
+}
+
+/// 将多个归档合并为一个，重名条目保留先出现的那个
+pub fn concat(output: impl AsRef<Path>, archives: &[impl AsRef<Path>]) -> Result<()> {
+    concat_with(output, archives, CollisionPolicy::default())
+}
+
+/// 将多个归档合并为一个，按 `policy` 处理重名条目
+///
+/// 目录条目重名时总是合并，不受策略影响。
+pub fn concat_with(
+    output: impl AsRef<Path>,
+    archives: &[impl AsRef<Path>],
+    policy: CollisionPolicy,
+) -> Result<()> {
+    let output = output.as_ref();
+
+    let readers = archives
+        .iter()
+        .map(ZipReader::open)
+        .collect::<Result<Vec<_>>>()?;
+
+    // 条目数据在写出时才从输入读取，因此先写入临时文件再替换输出，输出可以是某个输入
+    replace_file(output, None, |temp| {
+        let mut writer = ZipWriter::new(temp, CompressionLevel::default())?;
+        let mut names = HashSet::new();
+
+        for reader in &readers {
+            for info in reader.entries() {
+                let name = if names.contains(&info.name) {
+                    if info.is_dir {
+                        continue;
+                    }
+                    match policy {
+                        CollisionPolicy::KeepFirst => continue,
+                        CollisionPolicy::Rename => rename_unique(&info.name, &names),
+                        CollisionPolicy::Error => {
+                            return Err(ZipError::generic(format!(
+                                "duplicate entry '{}' while concatenating into '{}'",
+                                info.name,
+                                output.display()
+                            )));
+                        }
+                    }
+                } else {
+                    info.name.clone()
+                };
+
+                writer.copy_raw_entry(reader, info, &name)?;
+                names.insert(name);
+            }
+        }
+
+        writer.finalize()
+    })
+}
+
+/// 生成一个未被占用的新名字：`dir/name.txt` -> `dir/name (2).txt`
+fn rename_unique(name: &str, taken: &HashSet<String>) -> String {
+    let file_start = name.rfind('/').map_or(0, |i| i + 1);
+    let (stem, ext) = match name[file_start..].rfind('.') {
+        Some(i) if i > 0 => name.split_at(file_start + i),
+        _ => (name, ""),
+    };
+
+    (2..)
+        .map(|n| format!("{} ({}){}", stem, n, ext))
+        .find(|candidate| !taken.contains(candidate))
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::unzip::ZipArchive;
+    use crate::zip::ZipBuilder;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn shard(dir: &Path, name: &str, files: &[(&str, &[u8])]) -> std::path::PathBuf {
+        let src = dir.join(format!("{}-src", name));
+        for (file, data) in files {
+            let path = src.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, data).unwrap();
+        }
+        let names: Vec<&str> = files.iter().map(|(file, _)| *file).collect();
+        let zipfile = dir.join(format!("{}.zip", name));
+        ZipBuilder::new(&zipfile)
+            .unwrap()
+            .root(&src)
+            .files(&names)
+            .unwrap()
+            .build()
+            .unwrap();
+        zipfile
+    }
+
+    #[test]
+    fn test_concat() {
+        let temp = TempDir::new().unwrap();
+        let shards = [
+            shard(temp.path(), "one", &[("a.txt", b"alpha\n"), ("dir/b.txt", b"bravo\n")]),
+            shard(temp.path(), "two", &[("c.txt", b"charlie\n")]),
+            shard(temp.path(), "three", &[("d.txt", b"delta\n"), ("a.txt", b"other\n")]),
+        ];
+
+        let output = temp.path().join("all.zip");
+        concat(&output, &shards).unwrap();
+        let map = ZipArchive::open(&output).unwrap().extract_to_map().unwrap();
+        assert_eq!(map.len(), 4);
+        assert_eq!(map["a.txt"], b"alpha\n");
+        assert_eq!(map["dir/b.txt"], b"bravo\n");
+        assert_eq!(map["c.txt"], b"charlie\n");
+        assert_eq!(map["d.txt"], b"delta\n");
+
+        let renamed = temp.path().join("renamed.zip");
+        concat_with(&renamed, &shards, CollisionPolicy::Rename).unwrap();
+        let map = ZipArchive::open(&renamed).unwrap().extract_to_map().unwrap();
+        assert_eq!(map.len(), 5);
+        assert_eq!(map["a (2).txt"], b"other\n");
+
+        let err = concat_with(temp.path().join("err.zip"), &shards, CollisionPolicy::Error);
+        assert!(err.is_err());
+        assert!(!temp.path().join("err.zip").exists());
+    }
+
+    #[test]
+    fn test_concat_into_an_input() {
+        let temp = TempDir::new().unwrap();
+        let first = shard(temp.path(), "one", &[("a.txt", &b"alpha\n".repeat(1000)), ("b.txt", b"bravo\n")]);
+        let second = shard(temp.path(), "two", &[("c.txt", b"charlie\n")]);
+
+        concat(&first, &[&first, &second]).unwrap();
+        let map = ZipArchive::open(&first).unwrap().extract_to_map().unwrap();
+        assert_eq!(map.len(), 3);
+        assert_eq!(map["a.txt"], b"alpha\n".repeat(1000));
+        assert_eq!(map["b.txt"], b"bravo\n");
+        assert_eq!(map["c.txt"], b"charlie\n");
+    }
+
+    /// 按 Info-ZIP `zip -e` 从标准输入压缩的形式构造归档：一个 ZipCrypto 加密的 STORE 条目，
+    /// 设置 bit 3，本地头中 CRC 和大小为 0，加密头的校验字节取自修改时间的高字节
+    fn zipcrypto_descriptor_zip(path: &Path, name: &str, content: &[u8], password: &[u8]) {
+        use crate::miniz::crc32::crc32;
+        use crate::zip::crypto;
+
+        let mod_time: u16 = 0x6b5a;
+        let crc = crc32(0, content);
+        assert_ne!((crc >> 24) as u8, (mod_time >> 8) as u8);
+        let body = crypto::encrypt_entry(password, (mod_time >> 8) as u8, content);
+
+        let header = |crc: u32, compressed: u32, uncompressed: u32| {
+            let mut fixed = Vec::new();
+            fixed.extend_from_slice(&20u16.to_le_bytes()); // version needed
+            fixed.extend_from_slice(&0x0009u16.to_le_bytes()); // 加密 + 数据描述符
+            fixed.extend_from_slice(&0u16.to_le_bytes()); // STORE
+            fixed.extend_from_slice(&mod_time.to_le_bytes());
+            fixed.extend_from_slice(&0x5021u16.to_le_bytes()); // date
+            fixed.extend_from_slice(&crc.to_le_bytes());
+            fixed.extend_from_slice(&compressed.to_le_bytes());
+            fixed.extend_from_slice(&uncompressed.to_le_bytes());
+            fixed.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            fixed.extend_from_slice(&0u16.to_le_bytes()); // extra len
+            fixed
+        };
+
+        let mut out = 0x04034b50u32.to_le_bytes().to_vec();
+        out.extend_from_slice(&header(0, 0, 0));
+        out.extend_from_slice(name.as_bytes());
+        out.extend_from_slice(&body);
+        out.extend_from_slice(&0x08074b50u32.to_le_bytes());
+        out.extend_from_slice(&crc.to_le_bytes());
+        out.extend_from_slice(&(body.len() as u32).to_le_bytes());
+        out.extend_from_slice(&(content.len() as u32).to_le_bytes());
+
+        let cd_offset = out.len();
+        out.extend_from_slice(&0x02014b50u32.to_le_bytes());
+        out.extend_from_slice(&20u16.to_le_bytes()); // version made by
+        out.extend_from_slice(&header(crc, body.len() as u32, content.len() as u32));
+        out.extend_from_slice(&[0u8; 6]); // comment len, disk, internal attr
+        out.extend_from_slice(&0u32.to_le_bytes()); // external attr
+        out.extend_from_slice(&0u32.to_le_bytes()); // local header offset
+        out.extend_from_slice(name.as_bytes());
+        let cd_size = out.len() - cd_offset;
+
+        out.extend_from_slice(&0x06054b50u32.to_le_bytes());
+        out.extend_from_slice(&[0u8; 4]);
+        out.extend_from_slice(&1u16.to_le_bytes());
+        out.extend_from_slice(&1u16.to_le_bytes());
+        out.extend_from_slice(&(cd_size as u32).to_le_bytes());
+        out.extend_from_slice(&(cd_offset as u32).to_le_bytes());
+        out.extend_from_slice(&0u16.to_le_bytes());
+        fs::write(path, out).unwrap();
+    }
+
+    #[test]
+    fn test_concat_keeps_zipcrypto_descriptor() {
+        let temp = TempDir::new().unwrap();
+        let secret = temp.path().join("secret.zip");
+        zipcrypto_descriptor_zip(&secret, "secret.txt", b"attack at dawn\n", b"hunter2");
+        let plain = shard(temp.path(), "plain", &[("a.txt", b"alpha\n")]);
+        let archive = ZipArchive::open(&secret).unwrap().password("hunter2");
+        assert_eq!(archive.read_entry("secret.txt").unwrap(), b"attack at dawn\n");
+
+        let output = temp.path().join("all.zip");
+        concat(&output, &[&secret, &plain]).unwrap();
+
+        // 校验字节仍取自修改时间，正确的密码可以解密
+        let archive = ZipArchive::open(&output).unwrap().password("hunter2");
+        assert_eq!(archive.read_entry("secret.txt").unwrap(), b"attack at dawn\n");
+        assert_eq!(archive.read_entry("a.txt").unwrap(), b"alpha\n");
+
+        let reader = ZipReader::open(&output).unwrap();
+        let copied = &reader.entries()[0];
+        assert_ne!(copied.flags & 0x0008, 0);
+        let descriptor = reader.data_descriptor(copied).unwrap().unwrap();
+        assert_eq!(descriptor.crc32, copied.crc32);
+        assert_eq!(descriptor.compressed_size, copied.compressed_size);
+        assert_eq!(reader.data_descriptor(&reader.entries()[1]).unwrap(), None);
+    }
+
+    #[test]
+    fn test_rename_unique() {
+        let taken: HashSet<String> = ["a.txt", "a (2).txt", "dir.d/file"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert_eq!(rename_unique("a.txt", &taken), "a (3).txt");
+        assert_eq!(rename_unique("dir.d/file", &taken), "dir.d/file (2)");
+        assert_eq!(rename_unique(".hidden", &taken), ".hidden (2)");
+    }
+}
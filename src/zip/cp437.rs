@@ -0,0 +1,50 @@
+//! IBM Code Page 437 文件名编解码
+//!
+//! 未设置通用标志位 bit 11 的 ZIP 条目名按规范应解释为 CP437。
+//! 0x00-0x7F 与 ASCII 相同，这里只需要 0x80-0xFF 的映射表。
+
+/// CP437 0x80-0xFF 对应的 Unicode 字符
+const HIGH: [char; 128] = [
+    'Ç', 'ü', 'é', 'â', 'ä', 'à', 'å', 'ç', 'ê', 'ë', 'è', 'ï', 'î', 'ì', 'Ä', 'Å',
+    'É', 'æ', 'Æ', 'ô', 'ö', 'ò', 'û', 'ù', 'ÿ', 'Ö', 'Ü', '¢', '£', '¥', '₧', 'ƒ',
+    'á', 'í', 'ó', 'ú', 'ñ', 'Ñ', 'ª', 'º', '¿', '⌐', '¬', '½', '¼', '¡', '«', '»',
+    '░', '▒', '▓', '│', '┤', '╡', '╢', '╖', '╕', '╣', '║', '╗', '╝', '╜', '╛', '┐',
+    '└', '┴', '┬', '├', '─', '┼', '╞', '╟', '╚', '╔', '╩', '╦', '╠', '═', '╬', '╧',
+    '╨', '╤', '╥', '╙', '╘', '╒', '╓', '╫', '╪', '┘', '┌', '█', '▄', '▌', '▐', '▀',
+    'α', 'ß', 'Γ', 'π', 'Σ', 'σ', 'µ', 'τ', 'Φ', 'Θ', 'Ω', 'δ', '∞', 'φ', 'ε', '∩',
+    '≡', '±', '≥', '≤', '⌠', '⌡', '÷', '≈', '°', '∙', '·', '√', 'ⁿ', '²', '■', '\u{a0}',
+];
+
+/// 将字符串编码为 CP437，遇到无法表示的字符返回 `None`
+pub fn encode(name: &str) -> Option<Vec<u8>> {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii() {
+                Some(c as u8)
+            } else {
+                HIGH.iter().position(|&h| h == c).map(|i| 0x80 + i as u8)
+            }
+        })
+        .collect()
+}
+
+/// 将 CP437 字节解码为字符串
+pub fn decode(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|&b| if b < 0x80 { b as char } else { HIGH[(b - 0x80) as usize] })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cp437_roundtrip() {
+        let bytes = encode("╔═╗ café.txt").unwrap();
+        assert_eq!(bytes, b"\xc9\xcd\xbb caf\x82.txt");
+        assert_eq!(decode(&bytes), "╔═╗ café.txt");
+        assert_eq!(encode("日本"), None);
+    }
+}
@@ -0,0 +1,147 @@
+//! 传统 PKWARE 加密（ZipCrypto）
+//!
+//! 对应 APPNOTE 6.1 "Traditional PKWARE Encryption"。算法强度很弱，
+//! 仅用于与现有工具生成的加密归档互通。
+
+use crate::miniz::crc32::crc32;
+
+/// 加密头长度：每个加密条目的数据之前有 12 字节加密头
+pub const ENCRYPTION_HEADER_LEN: usize = 12;
+
+/// ZipCrypto 的三个 32 位密钥
+#[derive(Debug, Clone)]
+pub struct ZipCryptoKeys {
+    key0: u32,
+    key1: u32,
+    key2: u32,
+}
+
+impl ZipCryptoKeys {
+    /// 用密码初始化密钥
+    pub fn new(password: &[u8]) -> Self {
+        let mut keys = Self {
+            key0: 0x1234_5678,
+            key1: 0x2345_6789,
+            key2: 0x3456_7890,
+        };
+        for &byte in password {
+            keys.update(byte);
+        }
+        keys
+    }
+
+    /// 用一个明文字节更新密钥
+    fn update(&mut self, byte: u8) {
+        self.key0 = crc32_byte(self.key0, byte);
+        self.key1 = self
+            .key1
+            .wrapping_add(self.key0 & 0xFF)
+            .wrapping_mul(134_775_813)
+            .wrapping_add(1);
+        self.key2 = crc32_byte(self.key2, (self.key1 >> 24) as u8);
+    }
+
+    /// 当前密钥流字节
+    fn stream_byte(&self) -> u8 {
+        let temp = (self.key2 | 2) as u16;
+        (temp.wrapping_mul(temp ^ 1) >> 8) as u8
+    }
+
+    /// 原地解密
+    pub fn decrypt(&mut self, buf: &mut [u8]) {
+        for byte in buf {
+            *byte ^= self.stream_byte();
+            self.update(*byte);
+        }
+    }
+
+    /// 原地加密
+    pub fn encrypt(&mut self, buf: &mut [u8]) {
+        for byte in buf {
+            let plain = *byte;
+            *byte ^= self.stream_byte();
+            self.update(plain);
+        }
+    }
+}
+
+/// 解密一个条目的数据（含 12 字节加密头）
+///
+/// `check_byte` 是加密头最后一个字节的期望值：CRC32 的最高字节，
+/// 使用数据描述符（bit 3）时为 DOS 修改时间的高字节。
+/// 校验字节不匹配（密码错误）时返回 `None`。
+pub fn decrypt_entry(password: &[u8], check_byte: u8, data: &[u8]) -> Option<Vec<u8>> {
+    if data.len() < ENCRYPTION_HEADER_LEN {
+        return None;
+    }
+    let mut keys = ZipCryptoKeys::new(password);
+    let mut header = [0u8; ENCRYPTION_HEADER_LEN];
+    header.copy_from_slice(&data[..ENCRYPTION_HEADER_LEN]);
+    keys.decrypt(&mut header);
+    if header[ENCRYPTION_HEADER_LEN - 1] != check_byte {
+        return None;
+    }
+
+    let mut body = data[ENCRYPTION_HEADER_LEN..].to_vec();
+    keys.decrypt(&mut body);
+    Some(body)
+}
+
+/// 加密一个条目的数据，返回 12 字节加密头加上密文
+///
+/// 加密头前 11 字节随机，最后一个字节为 `check_byte`，供解密时校验密码。
+pub fn encrypt_entry(password: &[u8], check_byte: u8, data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(ENCRYPTION_HEADER_LEN + data.len());
+    out.extend_from_slice(&random_header(check_byte));
+    out.extend_from_slice(data);
+    ZipCryptoKeys::new(password).encrypt(&mut out);
+    out
+}
+
+/// 生成加密头：11 个随机字节加校验字节
+///
+/// 随机数取自标准库 `RandomState` 的随机种子，足以让相同内容的加密头互不相同；
+/// ZipCrypto 本身并不提供密码学强度的保护。
+fn random_header(check_byte: u8) -> [u8; ENCRYPTION_HEADER_LEN] {
+    use std::collections::hash_map::RandomState;
+    use std::hash::{BuildHasher, Hasher};
+
+    let mut header = [0u8; ENCRYPTION_HEADER_LEN];
+    for (i, chunk) in header[..ENCRYPTION_HEADER_LEN - 1].chunks_mut(8).enumerate() {
+        let mut hasher = RandomState::new().build_hasher();
+        hasher.write_usize(i);
+        let bytes = hasher.finish().to_le_bytes();
+        chunk.copy_from_slice(&bytes[..chunk.len()]);
+    }
+    header[ENCRYPTION_HEADER_LEN - 1] = check_byte;
+    header
+}
+
+/// 不带首尾取反的单字节 CRC32 更新，ZipCrypto 的密钥更新使用这种形式
+fn crc32_byte(crc: u32, byte: u8) -> u32 {
+    !crc32(!crc, &[byte])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roundtrip_and_check_byte() {
+        let plain = b"The quick brown fox jumps over the lazy dog".to_vec();
+        let check_byte = 0xA5;
+
+        let mut data = vec![7u8; ENCRYPTION_HEADER_LEN];
+        data[ENCRYPTION_HEADER_LEN - 1] = check_byte;
+        data.extend_from_slice(&plain);
+        ZipCryptoKeys::new(b"secret").encrypt(&mut data);
+        assert_ne!(&data[ENCRYPTION_HEADER_LEN..], plain.as_slice());
+
+        assert_eq!(decrypt_entry(b"secret", check_byte, &data), Some(plain.clone()));
+        assert_eq!(decrypt_entry(b"wrong", check_byte, &data), None);
+
+        let encrypted = encrypt_entry(b"secret", check_byte, &plain);
+        assert_eq!(encrypted.len(), ENCRYPTION_HEADER_LEN + plain.len());
+        assert_eq!(decrypt_entry(b"secret", check_byte, &encrypted), Some(plain));
+    }
+}
@@ -17,6 +17,8 @@ pub enum ZipWarning {
     DotSlashPaths,
     DotDotPaths,
     ColonPaths,
+    /// 追加的条目与归档中已有的条目重名（`DuplicatePolicy::Warn`）
+    DuplicateEntry(String),
 }
 
 #[derive(Debug, Clone)]
@@ -30,6 +32,7 @@ pub fn get_zip_data(
     recurse: bool,
     mode: ZipMode,
     include_directories: bool,
+    include_root_entry: bool,
     root: &Path,
 ) -> Result<ZipData> {
     let mut warnings = Vec::new();
@@ -41,6 +44,17 @@ pub fn get_zip_data(
 
     if !include_directories {
         entries.retain(|entry| !entry.dir);
+    } else if !include_root_entry {
+        // 列出的目录本身对应的条目，其内容的条目名仍带有该目录前缀
+        let root_keys: HashSet<String> = if mode == ZipMode::Mirror {
+            files.iter().map(|file| ensure_dir_suffix(file)).collect()
+        } else {
+            expand_dot_in_nopath(files, root)?
+                .iter()
+                .map(|file| ensure_dir_suffix(&basename(file)))
+                .collect()
+        };
+        entries.retain(|entry| !(entry.dir && root_keys.contains(&entry.key)));
     }
 
     apply_key_warnings(&mut entries, &mut warnings);
@@ -290,7 +304,15 @@ fn resolve_path(root: &Path, file: &str) -> PathBuf {
     }
 }
 
+/// 规范化路径；最后一级是符号链接时只规范化其父目录，保留链接本身，
+/// 以便按需把链接作为链接存储
 fn normalize_path(path: &Path) -> Result<PathBuf> {
+    let is_link = fs::symlink_metadata(path).is_ok_and(|m| m.file_type().is_symlink());
+    if let (true, Some(parent), Some(name)) = (is_link, path.parent(), path.file_name()) {
+        let parent = if parent.as_os_str().is_empty() { Path::new(".") } else { parent };
+        let parent = fs::canonicalize(parent).map_err(|e| ZipError::file_open(path, e))?;
+        return Ok(parent.join(name));
+    }
     fs::canonicalize(path).map_err(|e| ZipError::file_open(path, e))
 }
 
@@ -0,0 +1,176 @@
+//! 一次调用打包整个目录
+//!
+//! [`zip_directory`] 遍历源目录、先报告要写入的总字节数（便于进度条设置上限），
+//! 再用常用默认值构建归档：条目名相对于源目录、对已压缩的媒体文件直接存储、
+//! 可选跳过隐藏文件。
+
+use crate::error::{CompressionLevel, Result, ZipError};
+use crate::zip::builder::ZipBuilder;
+use crate::zip::data::ZipWarning;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// [`zip_directory`] 的选项
+pub struct ZipDirectoryOptions {
+    /// 压缩级别
+    pub compression_level: CompressionLevel,
+    /// 对已压缩格式（jpg、png、zip 等）直接存储，默认开启
+    pub smart_store: bool,
+    /// 跳过名字以 `.` 开头的文件和目录（及其全部内容）
+    pub skip_hidden: bool,
+    /// 遍历完成、开始写入之前以待写入的总字节数调用一次
+    pub on_total: Option<Box<dyn FnMut(u64)>>,
+}
+
+impl Default for ZipDirectoryOptions {
+    fn default() -> Self {
+        Self {
+            compression_level: CompressionLevel::Level6,
+            smart_store: true,
+            skip_hidden: false,
+            on_total: None,
+        }
+    }
+}
+
+/// [`zip_directory`] 的结果
+#[derive(Debug, Clone)]
+pub struct ZipDirectorySummary {
+    /// 生成的 ZIP 文件
+    pub zipfile: PathBuf,
+    /// 写入的文件条目数
+    pub entries: usize,
+    /// 写入的文件内容总字节数（未压缩）
+    pub input_bytes: u64,
+    /// 生成的归档大小
+    pub output_bytes: u64,
+    /// 构建过程中的警告
+    pub warnings: Vec<ZipWarning>,
+}
+
+/// 把目录 `src` 下的全部文件打包到 `dest`
+///
+/// 条目名相对于 `src`，归档中不会出现 `src` 这一层目录；只写文件条目，空目录不保留。
+/// `dest` 已存在时被覆盖。
+pub fn zip_directory(
+    src: impl AsRef<Path>,
+    dest: impl AsRef<Path>,
+    mut options: ZipDirectoryOptions,
+) -> Result<ZipDirectorySummary> {
+    let src = src.as_ref();
+    if !src.is_dir() {
+        return Err(ZipError::generic(format!("{} is not a directory", src.display())));
+    }
+
+    let mut files = Vec::new();
+    let mut input_bytes = 0u64;
+    collect_files(src, "", options.skip_hidden, &mut files, &mut input_bytes)?;
+
+    if let Some(on_total) = options.on_total.as_mut() {
+        on_total(input_bytes);
+    }
+
+    let output = ZipBuilder::new(dest)?
+        .root(src)
+        .recurse(false)
+        .compression_level(options.compression_level)
+        .smart_store(options.smart_store)
+        .files(&files)?
+        .build_with_warnings()?;
+    let output_bytes = fs::metadata(&output.zipfile)
+        .map_err(|e| ZipError::file_read(&output.zipfile, e))?
+        .len();
+
+    Ok(ZipDirectorySummary {
+        zipfile: output.zipfile,
+        entries: files.len(),
+        input_bytes,
+        output_bytes,
+        warnings: output.warnings,
+    })
+}
+
+/// 按名字顺序递归收集 `dir` 下的文件，`prefix` 为 `dir` 相对于源目录的条目名前缀
+fn collect_files(
+    dir: &Path,
+    prefix: &str,
+    skip_hidden: bool,
+    files: &mut Vec<String>,
+    total: &mut u64,
+) -> Result<()> {
+    let mut children = fs::read_dir(dir)
+        .map_err(|e| ZipError::file_read(dir, e))?
+        .map(|entry| entry.map(|entry| entry.file_name()))
+        .collect::<std::io::Result<Vec<_>>>()
+        .map_err(|e| ZipError::file_read(dir, e))?;
+    children.sort();
+
+    for name in children {
+        let name = name.to_string_lossy().to_string();
+        if skip_hidden && name.starts_with('.') {
+            continue;
+        }
+        let path = dir.join(&name);
+        let key = format!("{}{}", prefix, name);
+        let metadata = fs::metadata(&path).map_err(|e| ZipError::file_read(&path, e))?;
+        if metadata.is_dir() {
+            collect_files(&path, &format!("{}/", key), skip_hidden, files, total)?;
+        } else {
+            *total += metadata.len();
+            files.push(key);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::unzip::ZipArchive;
+    use std::cell::Cell;
+    use std::rc::Rc;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_zip_directory_summary() {
+        let temp = TempDir::new().unwrap();
+        let src = temp.path().join("src");
+        fs::create_dir_all(src.join("dir").join(".git")).unwrap();
+        fs::write(src.join("a.txt"), b"alpha\n").unwrap();
+        fs::write(src.join("dir").join("b.txt"), "bravo ".repeat(100)).unwrap();
+        fs::write(src.join(".hidden"), b"secret").unwrap();
+        fs::write(src.join("dir").join(".git").join("HEAD"), b"ref").unwrap();
+
+        let reported = Rc::new(Cell::new(0));
+        let seen = Rc::clone(&reported);
+        let zipfile = temp.path().join("out.zip");
+        let summary = zip_directory(
+            &src,
+            &zipfile,
+            ZipDirectoryOptions {
+                skip_hidden: true,
+                on_total: Some(Box::new(move |total| seen.set(total))),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        assert_eq!(summary.zipfile, zipfile);
+        assert_eq!(summary.entries, 2);
+        assert_eq!(summary.input_bytes, 606);
+        assert_eq!(reported.get(), 606);
+        assert_eq!(summary.output_bytes, fs::metadata(&zipfile).unwrap().len());
+        assert!(summary.warnings.is_empty());
+
+        let names: Vec<String> = ZipArchive::list(&zipfile)
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.filename)
+            .collect();
+        assert_eq!(names, ["a.txt", "dir/b.txt"]);
+
+        let all = zip_directory(&src, &zipfile, ZipDirectoryOptions::default()).unwrap();
+        assert_eq!(all.entries, 4);
+        assert_eq!(all.input_bytes, 615);
+    }
+}
@@ -0,0 +1,473 @@
+//! ZIP extra field 编解码
+//!
+//! extra field 由若干 `[tag: u16][size: u16][data]` 记录组成（APPNOTE 4.5）。
+//! 这里实现时间相关的字段：
+//! - `0x5455` 扩展时间戳（Unix 秒，1 秒精度）
+//! - `0x000a` NTFS 时间（FILETIME，100 ns 精度）
+//! - `0x7875` Info-ZIP Unix UID/GID（不含时间）
+//! - `0x5855` 旧版 Info-ZIP Unix 字段（atime、mtime，本地头中可带 16 位 UID/GID，只读取）
+//!
+//! 以及 WinZip AES 加密使用的 `0x9901` 字段和 ZIP64 的 `0x0001` 字段（只读取）。
+//!
+//! `0x5a52` 扩展属性字段是本库私有的非标准扩展，其他工具会忽略它。
+
+use crate::error::EntryTimes;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// NTFS extra field 标签
+pub const NTFS_TAG: u16 = 0x000a;
+/// 扩展时间戳 extra field 标签
+pub const EXTENDED_TIMESTAMP_TAG: u16 = 0x5455;
+
+/// Info-ZIP Unix UID/GID extra field 标签
+pub const UNIX_OWNER_TAG: u16 = 0x7875;
+
+/// 旧版 Info-ZIP Unix extra field 标签，`0x5455` 与 `0x7875` 出现之前使用
+pub const INFOZIP_UNIX_OLD_TAG: u16 = 0x5855;
+
+/// WinZip AES 加密 extra field 标签
+pub const AES_TAG: u16 = 0x9901;
+
+/// ZIP64 扩展信息 extra field 标签
+pub const ZIP64_TAG: u16 = 0x0001;
+
+/// 扩展属性（xattr）extra field 标签，本库私有，不在 APPNOTE 中
+pub const XATTR_TAG: u16 = 0x5a52;
+
+/// 32 位大小或偏移字段中表示“实际值在 ZIP64 字段中”的占位值
+const ZIP64_PLACEHOLDER: u64 = 0xFFFF_FFFF;
+
+/// 1601-01-01 到 1970-01-01 之间的 100 ns 间隔数
+const FILETIME_UNIX_EPOCH: u64 = 116_444_736_000_000_000;
+
+/// 查找指定标签的 extra field，返回其数据部分
+pub fn find_field(extra: &[u8], tag: u16) -> Option<&[u8]> {
+    let mut pos = 0;
+    while pos + 4 <= extra.len() {
+        let field_tag = u16::from_le_bytes([extra[pos], extra[pos + 1]]);
+        let size = u16::from_le_bytes([extra[pos + 2], extra[pos + 3]]) as usize;
+        let start = pos + 4;
+        let end = start + size;
+        if end > extra.len() {
+            return None;
+        }
+        if field_tag == tag {
+            return Some(&extra[start..end]);
+        }
+        pos = end;
+    }
+    None
+}
+
+/// 追加一条 extra field 记录
+fn push_field(out: &mut Vec<u8>, tag: u16, data: &[u8]) {
+    out.extend_from_slice(&tag.to_le_bytes());
+    out.extend_from_slice(&(data.len() as u16).to_le_bytes());
+    out.extend_from_slice(data);
+}
+
+/// SystemTime 转换为 FILETIME（1601 年起的 100 ns 间隔数）
+fn system_time_to_filetime(time: SystemTime) -> u64 {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(d) => FILETIME_UNIX_EPOCH + d.as_secs() * 10_000_000 + (d.subsec_nanos() / 100) as u64,
+        Err(e) => {
+            let d = e.duration();
+            FILETIME_UNIX_EPOCH.saturating_sub(d.as_secs() * 10_000_000 + (d.subsec_nanos() / 100) as u64)
+        }
+    }
+}
+
+/// FILETIME 转换为 SystemTime
+fn filetime_to_system_time(filetime: u64) -> SystemTime {
+    if filetime >= FILETIME_UNIX_EPOCH {
+        let ticks = filetime - FILETIME_UNIX_EPOCH;
+        UNIX_EPOCH + Duration::new(ticks / 10_000_000, ((ticks % 10_000_000) * 100) as u32)
+    } else {
+        let ticks = FILETIME_UNIX_EPOCH - filetime;
+        UNIX_EPOCH - Duration::new(ticks / 10_000_000, ((ticks % 10_000_000) * 100) as u32)
+    }
+}
+
+/// SystemTime 转换为 Unix 秒（截断到 i32 范围）
+fn system_time_to_unix_secs(time: SystemTime) -> i32 {
+    let secs = match time.duration_since(UNIX_EPOCH) {
+        Ok(d) => d.as_secs() as i64,
+        Err(e) => -(e.duration().as_secs() as i64),
+    };
+    secs.clamp(i32::MIN as i64, i32::MAX as i64) as i32
+}
+
+/// 写入 `0x5455` 扩展时间戳字段（只包含 mtime）
+pub fn push_extended_timestamp(out: &mut Vec<u8>, mtime: SystemTime) {
+    let mut data = Vec::with_capacity(5);
+    data.push(0x01); // flags: bit 0 = mtime 存在
+    data.extend_from_slice(&system_time_to_unix_secs(mtime).to_le_bytes());
+    push_field(out, EXTENDED_TIMESTAMP_TAG, &data);
+}
+
+/// 写入 `0x7875` Unix UID/GID 字段（版本 1，4 字节 UID 和 GID）
+pub fn push_unix_owner(out: &mut Vec<u8>, uid: u32, gid: u32) {
+    let mut data = Vec::with_capacity(11);
+    data.push(1); // version
+    data.push(4);
+    data.extend_from_slice(&uid.to_le_bytes());
+    data.push(4);
+    data.extend_from_slice(&gid.to_le_bytes());
+    push_field(out, UNIX_OWNER_TAG, &data);
+}
+
+/// 用 `0x0001` ZIP64 字段中的 64 位值替换取值为 0xFFFFFFFF 的解压大小、压缩大小和本地头偏移
+///
+/// 字段中只按此顺序出现被替换的值；字段缺失或过短时保留原值。
+pub fn apply_zip64(extra: &[u8], uncompressed: &mut u64, compressed: &mut u64, offset: &mut u64) {
+    let Some(mut data) = find_field(extra, ZIP64_TAG) else {
+        return;
+    };
+    for value in [uncompressed, compressed, offset] {
+        if *value != ZIP64_PLACEHOLDER {
+            continue;
+        }
+        let Some((bytes, rest)) = data.split_first_chunk::<8>() else {
+            return;
+        };
+        *value = u64::from_le_bytes(*bytes);
+        data = rest;
+    }
+}
+
+/// 写入 `0x5a52` 扩展属性字段：依次为 `[名字长度: u16][名字][值长度: u16][值]`
+///
+/// `out` 连同本字段最长 65535 字节，放不下的属性被跳过并返回其名字。
+pub fn push_xattrs(out: &mut Vec<u8>, attrs: &[(String, Vec<u8>)]) -> Vec<String> {
+    let limit = (u16::MAX as usize).saturating_sub(out.len() + 4);
+    let mut data = Vec::new();
+    let mut skipped = Vec::new();
+    for (name, value) in attrs {
+        if data.len() + 4 + name.len() + value.len() > limit {
+            skipped.push(name.clone());
+            continue;
+        }
+        data.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        data.extend_from_slice(name.as_bytes());
+        data.extend_from_slice(&(value.len() as u16).to_le_bytes());
+        data.extend_from_slice(value);
+    }
+    if !data.is_empty() {
+        push_field(out, XATTR_TAG, &data);
+    }
+    skipped
+}
+
+/// 解析 `0x5a52` 扩展属性字段；字段缺失时为空，截断处之后的记录被忽略
+pub fn xattrs(extra: &[u8]) -> Vec<(String, Vec<u8>)> {
+    let mut attrs = Vec::new();
+    let Some(mut data) = find_field(extra, XATTR_TAG) else {
+        return attrs;
+    };
+    while !data.is_empty() {
+        let (Some(name), Some(value)) = (take_u16_prefixed(&mut data), take_u16_prefixed(&mut data)) else {
+            break;
+        };
+        attrs.push((String::from_utf8_lossy(name).into_owned(), value.to_vec()));
+    }
+    attrs
+}
+
+/// 取出一段 `[长度: u16][数据]`
+fn take_u16_prefixed<'a>(data: &mut &'a [u8]) -> Option<&'a [u8]> {
+    let (len, rest) = data.split_first_chunk::<2>()?;
+    let len = u16::from_le_bytes(*len) as usize;
+    let value = rest.get(..len)?;
+    *data = &rest[len..];
+    Some(value)
+}
+
+/// 解析 `0x7875` Unix UID/GID 字段，返回 (uid, gid)
+///
+/// UID/GID 是变长小端整数，超出 u32 范围时视为无效。
+pub fn unix_owner_field(extra: &[u8]) -> Option<(u32, u32)> {
+    let data = find_field(extra, UNIX_OWNER_TAG)?;
+    let (&version, mut rest) = data.split_first()?;
+    if version != 1 {
+        return None;
+    }
+    let mut ids = [0u32; 2];
+    for id in &mut ids {
+        let (&size, tail) = rest.split_first()?;
+        let size = size as usize;
+        if tail.len() < size || tail[..size].iter().skip(4).any(|&b| b != 0) {
+            return None;
+        }
+        *id = tail[..size.min(4)]
+            .iter()
+            .rev()
+            .fold(0, |acc, &b| (acc << 8) | b as u32);
+        rest = &tail[size..];
+    }
+    Some((ids[0], ids[1]))
+}
+
+/// 旧版 Info-ZIP Unix 字段的内容：(atime, mtime, 本地头中的 (uid, gid))
+pub type InfoZipUnixOld = (SystemTime, SystemTime, Option<(u32, u32)>);
+
+/// 解析 `0x5855` 旧版 Info-ZIP Unix 字段，返回 (atime, mtime, (uid, gid))
+///
+/// 中央目录中只有 8 字节的 atime 和 mtime（无符号 Unix 秒）；
+/// 本地头中可能再跟 16 位的 UID 和 GID。
+pub fn infozip_unix_old(extra: &[u8]) -> Option<InfoZipUnixOld> {
+    let data = find_field(extra, INFOZIP_UNIX_OLD_TAG)?;
+    if data.len() < 8 {
+        return None;
+    }
+    let secs = |at: usize| u32::from_le_bytes(data[at..at + 4].try_into().unwrap()) as i64;
+    let owner = (data.len() >= 12).then(|| {
+        (
+            u16::from_le_bytes([data[8], data[9]]) as u32,
+            u16::from_le_bytes([data[10], data[11]]) as u32,
+        )
+    });
+    Some((unix_secs_to_system_time(secs(0)), unix_secs_to_system_time(secs(4)), owner))
+}
+
+/// 条目的 Unix 所有者 (uid, gid)：`0x7875` 优先，其次旧版 `0x5855`
+pub fn unix_owner(extra: &[u8]) -> Option<(u32, u32)> {
+    unix_owner_field(extra).or_else(|| infozip_unix_old(extra).and_then(|(_, _, owner)| owner))
+}
+
+/// 写入 `0x9901` AES 字段：AE-2、厂商 "AE"、密钥强度（1/2/3）和实际压缩方法
+pub fn push_aes(out: &mut Vec<u8>, strength: u8, method: u16) {
+    let mut data = Vec::with_capacity(7);
+    data.extend_from_slice(&2u16.to_le_bytes());
+    data.extend_from_slice(b"AE");
+    data.push(strength);
+    data.extend_from_slice(&method.to_le_bytes());
+    push_field(out, AES_TAG, &data);
+}
+
+/// 解析 `0x9901` AES 字段，返回 (AE 版本, 密钥强度, 实际压缩方法)
+pub fn aes_field(extra: &[u8]) -> Option<(u16, u8, u16)> {
+    let data = find_field(extra, AES_TAG)?;
+    if data.len() < 7 || &data[2..4] != b"AE" {
+        return None;
+    }
+    Some((
+        u16::from_le_bytes([data[0], data[1]]),
+        data[4],
+        u16::from_le_bytes([data[5], data[6]]),
+    ))
+}
+
+/// 写入 `0x000a` NTFS 时间字段
+pub fn push_ntfs_times(out: &mut Vec<u8>, mtime: SystemTime, atime: SystemTime, ctime: SystemTime) {
+    let mut data = Vec::with_capacity(32);
+    data.extend_from_slice(&0u32.to_le_bytes()); // reserved
+    data.extend_from_slice(&0x0001u16.to_le_bytes()); // attribute tag 1
+    data.extend_from_slice(&24u16.to_le_bytes()); // attribute size
+    data.extend_from_slice(&system_time_to_filetime(mtime).to_le_bytes());
+    data.extend_from_slice(&system_time_to_filetime(atime).to_le_bytes());
+    data.extend_from_slice(&system_time_to_filetime(ctime).to_le_bytes());
+    push_field(out, NTFS_TAG, &data);
+}
+
+/// 解析 `0x000a` NTFS 字段中的 mtime
+pub fn ntfs_mtime(extra: &[u8]) -> Option<SystemTime> {
+    let data = find_field(extra, NTFS_TAG)?;
+    // 跳过 4 字节 reserved，逐个查找 attribute tag 1
+    let mut pos = 4;
+    while pos + 4 <= data.len() {
+        let attr_tag = u16::from_le_bytes([data[pos], data[pos + 1]]);
+        let attr_size = u16::from_le_bytes([data[pos + 2], data[pos + 3]]) as usize;
+        let start = pos + 4;
+        if start + attr_size > data.len() {
+            return None;
+        }
+        if attr_tag == 0x0001 && attr_size >= 8 {
+            let filetime = u64::from_le_bytes(data[start..start + 8].try_into().unwrap());
+            return Some(filetime_to_system_time(filetime));
+        }
+        pos = start + attr_size;
+    }
+    None
+}
+
+/// 解析 `0x000a` NTFS 字段中的 (mtime, atime, ctime)
+pub fn ntfs_times(extra: &[u8]) -> Option<(SystemTime, SystemTime, SystemTime)> {
+    let data = find_field(extra, NTFS_TAG)?;
+    let mut pos = 4;
+    while pos + 4 <= data.len() {
+        let attr_tag = u16::from_le_bytes([data[pos], data[pos + 1]]);
+        let attr_size = u16::from_le_bytes([data[pos + 2], data[pos + 3]]) as usize;
+        let start = pos + 4;
+        if start + attr_size > data.len() {
+            return None;
+        }
+        if attr_tag == 0x0001 && attr_size >= 24 {
+            let filetime = |i: usize| {
+                let at = start + i * 8;
+                filetime_to_system_time(u64::from_le_bytes(data[at..at + 8].try_into().unwrap()))
+            };
+            return Some((filetime(0), filetime(1), filetime(2)));
+        }
+        pos = start + attr_size;
+    }
+    None
+}
+
+/// 解析 `0x5455` 扩展时间戳字段中的 (mtime, atime, ctime)
+///
+/// 标志位 bit 0/1/2 分别表示 mtime/atime/ctime 存在；中央目录中的记录
+/// 通常只带 mtime，缺少的时间返回 `None`。
+pub fn extended_timestamps(
+    extra: &[u8],
+) -> (Option<SystemTime>, Option<SystemTime>, Option<SystemTime>) {
+    let mut times = [None; 3];
+    let Some(data) = find_field(extra, EXTENDED_TIMESTAMP_TAG) else {
+        return (None, None, None);
+    };
+    let Some((&flags, mut rest)) = data.split_first() else {
+        return (None, None, None);
+    };
+    for (bit, time) in times.iter_mut().enumerate() {
+        if flags & (1 << bit) == 0 || rest.len() < 4 {
+            continue;
+        }
+        let secs = i32::from_le_bytes(rest[..4].try_into().unwrap()) as i64;
+        *time = Some(unix_secs_to_system_time(secs));
+        rest = &rest[4..];
+    }
+    (times[0], times[1], times[2])
+}
+
+/// Unix 秒转换为 SystemTime
+fn unix_secs_to_system_time(secs: i64) -> SystemTime {
+    if secs >= 0 {
+        UNIX_EPOCH + Duration::from_secs(secs as u64)
+    } else {
+        UNIX_EPOCH - Duration::from_secs((-secs) as u64)
+    }
+}
+
+/// 解析 `0x5455` 扩展时间戳字段中的 mtime
+pub fn extended_timestamp_mtime(extra: &[u8]) -> Option<SystemTime> {
+    let data = find_field(extra, EXTENDED_TIMESTAMP_TAG)?;
+    if data.len() < 5 || data[0] & 0x01 == 0 {
+        return None;
+    }
+    let secs = i32::from_le_bytes(data[1..5].try_into().unwrap()) as i64;
+    Some(unix_secs_to_system_time(secs))
+}
+
+/// 从 extra field 中取最精确的 mtime：NTFS 优先，其次扩展时间戳，最后旧版 `0x5855`
+pub fn modified_time(extra: &[u8]) -> Option<SystemTime> {
+    ntfs_mtime(extra)
+        .or_else(|| extended_timestamp_mtime(extra))
+        .or_else(|| infozip_unix_old(extra).map(|(_, mtime, _)| mtime))
+}
+
+/// 汇总所有时间来源，每个时间取最精确的一个：NTFS 优先，其次扩展时间戳，
+/// 再次旧版 `0x5855`；mtime 都没有时使用 DOS 时间 `dos_mtime`
+pub fn entry_times(extra: &[u8], dos_mtime: SystemTime) -> EntryTimes {
+    let ntfs = ntfs_times(extra);
+    let (_, ut_atime, ut_ctime) = extended_timestamps(extra);
+    let old_atime = infozip_unix_old(extra).map(|(atime, _, _)| atime);
+    EntryTimes {
+        mtime: modified_time(extra).unwrap_or(dos_mtime),
+        atime: ntfs.map(|(_, atime, _)| atime).or(ut_atime).or(old_atime),
+        ctime: ntfs.map(|(_, _, ctime)| ctime).or(ut_ctime),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_time_fields_roundtrip() {
+        let mtime = UNIX_EPOCH + Duration::new(1_700_000_000, 123_456_789);
+
+        let mut extra = Vec::new();
+        push_extended_timestamp(&mut extra, mtime);
+        push_ntfs_times(&mut extra, mtime, mtime, mtime);
+
+        assert_eq!(
+            extended_timestamp_mtime(&extra),
+            Some(UNIX_EPOCH + Duration::from_secs(1_700_000_000))
+        );
+        assert_eq!(
+            ntfs_mtime(&extra),
+            Some(UNIX_EPOCH + Duration::new(1_700_000_000, 123_456_700))
+        );
+        assert_eq!(modified_time(&extra), ntfs_mtime(&extra));
+        assert_eq!(find_field(&extra, 0x7875), None);
+    }
+
+    #[test]
+    fn test_entry_times() {
+        let dos = UNIX_EPOCH + Duration::from_secs(1_600_000_000);
+        let mtime = UNIX_EPOCH + Duration::new(1_700_000_000, 500_000_000);
+        let atime = UNIX_EPOCH + Duration::from_secs(1_700_000_100);
+        let ctime = UNIX_EPOCH + Duration::from_secs(1_600_000_100);
+
+        assert_eq!(
+            entry_times(&[], dos),
+            EntryTimes { mtime: dos, atime: None, ctime: None }
+        );
+
+        // 本地头形式的 0x5455：mtime + atime + ctime
+        let mut extra = Vec::new();
+        let mut data = vec![0x07];
+        for t in [mtime, atime, ctime] {
+            data.extend_from_slice(&system_time_to_unix_secs(t).to_le_bytes());
+        }
+        push_field(&mut extra, EXTENDED_TIMESTAMP_TAG, &data);
+        assert_eq!(
+            entry_times(&extra, dos),
+            EntryTimes {
+                mtime: UNIX_EPOCH + Duration::from_secs(1_700_000_000),
+                atime: Some(atime),
+                ctime: Some(ctime),
+            }
+        );
+
+        push_ntfs_times(&mut extra, mtime, atime, ctime);
+        assert_eq!(
+            entry_times(&extra, dos),
+            EntryTimes { mtime, atime: Some(atime), ctime: Some(ctime) }
+        );
+    }
+
+    #[test]
+    fn test_infozip_unix_old() {
+        let dos = UNIX_EPOCH + Duration::from_secs(1_600_000_000);
+        let atime = UNIX_EPOCH + Duration::from_secs(900_000_100);
+        let mtime = UNIX_EPOCH + Duration::from_secs(900_000_000);
+
+        // 本地头形式：atime、mtime、16 位 uid 和 gid
+        let mut data = Vec::new();
+        data.extend_from_slice(&900_000_100u32.to_le_bytes());
+        data.extend_from_slice(&900_000_000u32.to_le_bytes());
+        data.extend_from_slice(&501u16.to_le_bytes());
+        data.extend_from_slice(&20u16.to_le_bytes());
+        let mut extra = Vec::new();
+        push_field(&mut extra, INFOZIP_UNIX_OLD_TAG, &data);
+
+        assert_eq!(infozip_unix_old(&extra), Some((atime, mtime, Some((501, 20)))));
+        assert_eq!(unix_owner(&extra), Some((501, 20)));
+        assert_eq!(
+            entry_times(&extra, dos),
+            EntryTimes { mtime, atime: Some(atime), ctime: None }
+        );
+
+        // 中央目录形式没有 uid/gid
+        let mut central = Vec::new();
+        push_field(&mut central, INFOZIP_UNIX_OLD_TAG, &data[..8]);
+        assert_eq!(infozip_unix_old(&central), Some((atime, mtime, None)));
+        assert_eq!(unix_owner(&central), None);
+
+        // 新字段优先
+        push_extended_timestamp(&mut extra, dos);
+        push_unix_owner(&mut extra, 1000, 1000);
+        assert_eq!(modified_time(&extra), Some(dos));
+        assert_eq!(unix_owner(&extra), Some((1000, 1000)));
+    }
+}
@@ -1,13 +1,23 @@
 //! ZIP writer module.
 
+#[cfg(feature = "aes")]
+pub mod aes;
 pub mod builder;
+pub mod concat;
+pub mod cp437;
+pub mod crypto;
+pub mod directory;
 pub mod writer;
 pub mod data;
+pub mod extra;
 pub mod reader;
+pub(crate) mod replace;
 
-pub use builder::{ZipBuildOutput, ZipBuilder, ZipBuilderOptions};
-pub use writer::ZipWriter;
-pub use reader::{ZipReader, ZipEntryInfo};
+pub use builder::{BuildItem, ZipBuildOutput, ZipBuilder, ZipBuilderOptions};
+pub use concat::{concat, concat_with, CollisionPolicy};
+pub use directory::{zip_directory, ZipDirectoryOptions, ZipDirectorySummary};
+pub use writer::{mode_to_external_attr, EntrySummary, PrecompressedEntry, ZipWriter};
+pub use reader::{DataDescriptor, ErrorMode, NamePolicy, ReadWarning, ZipReader, ZipEntryInfo};
 
 use crate::error::Result;
 use std::path::Path;
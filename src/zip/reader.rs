@@ -3,6 +3,10 @@
 
 use crate::error::{Result, ZipError};
 use crate::miniz::crc32::crc32;
+use crate::unzip::archive::{decode_entry_data, decrypt_entry_data, LocalHeader};
+use crate::zip::cp437;
+use crate::zip::extra;
+use std::borrow::Cow;
 use std::fs::File;
 use std::io::{BufReader, Read, Seek, SeekFrom};
 use std::path::Path;
@@ -66,6 +70,8 @@ pub struct ZipEntryInfo {
     pub local_header_offset: u64,
     /// 是否为目录
     pub is_dir: bool,
+    /// 通用标志位（bit 11 = 文件名为 UTF-8）
+    pub flags: u16,
     /// 压缩方法 (0=store, 8=deflate)
     pub compression_method: u16,
     /// 外部属性（包含权限）
@@ -76,11 +82,26 @@ pub struct ZipEntryInfo {
     pub mtime_dos: u16,
     /// 修改日期（DOS 日期格式）
     pub mdate_dos: u16,
+    /// 中央目录中的 extra field 原始数据
+    pub extra: Vec<u8>,
+    /// 中央目录中存储的原始文件名字节（解码前）
+    pub(crate) raw_name: Vec<u8>,
+}
+
+impl ZipEntryInfo {
+    /// 中央目录中存储的原始文件名字节，未做 UTF-8 / CP437 解码
+    pub fn raw_name(&self) -> &[u8] {
+        &self.raw_name
+    }
 }
 
 /// EOCD (End of Central Directory) 信息
 #[derive(Debug, Clone)]
 struct EocdRecord {
+    /// EOCD 记录自身的偏移量
+    eocd_offset: u64,
+    /// EOCD 记录（含注释）在文件中占用的字节数
+    eocd_len: u64,
     /// 中央目录偏移量
     central_dir_offset: u64,
     /// 中央目录大小
@@ -101,6 +122,50 @@ mod zip_format {
 
     // EOCD 最大搜索长度
     pub const MAX_EOCD_SEARCH_LEN: usize = 65557 + 22; // comment + signature
+
+    // 通用标志位：文件名和注释为 UTF-8
+    pub const FLAG_UTF8: u16 = 0x0800;
+    // 通用标志位：CRC 和大小写在数据之后的数据描述符中
+    pub const FLAG_DATA_DESCRIPTOR: u16 = 0x0008;
+    // 数据描述符签名（可选）
+    pub const DATA_DESCRIPTOR_SIG: u32 = 0x08074b50;
+}
+
+/// `open_with_footer` 使用的内存视图：`[start, len)` 来自调用方提供的字节
+///
+/// `start` 之前的位置读出零字节，只会在 EOCD 向前搜索时被读到；零字节中不会出现签名，
+/// 因此搜索结果与读取整个文件相同（只要 EOCD 本身位于 footer 中）。
+struct FooterReader {
+    start: u64,
+    data: Vec<u8>,
+    len: u64,
+    pos: u64,
+}
+
+impl Read for FooterReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = buf.len().min(self.len.saturating_sub(self.pos) as usize);
+        for (i, byte) in buf[..n].iter_mut().enumerate() {
+            let pos = self.pos + i as u64;
+            *byte = pos.checked_sub(self.start).map_or(0, |offset| self.data[offset as usize]);
+        }
+        self.pos += n as u64;
+        Ok(n)
+    }
+}
+
+impl Seek for FooterReader {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        let target = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(delta) => self.len.checked_add_signed(delta),
+            SeekFrom::Current(delta) => self.pos.checked_add_signed(delta),
+        };
+        self.pos = target.ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::InvalidInput, "seek before start of archive")
+        })?;
+        Ok(self.pos)
+    }
 }
 
 /// 纯 Rust ZIP Reader
@@ -112,12 +177,79 @@ pub struct ZipReader {
     entries: Vec<ZipEntryInfo>,
     /// 中央目录偏移量
     central_dir_offset: u64,
+    /// EOCD 记录偏移量
+    eocd_offset: u64,
+    /// EOCD 记录（含注释）的字节数
+    eocd_len: u64,
+    /// 读取过程中恢复的非致命问题
+    warnings: Vec<ReadWarning>,
+    /// 设置了 bit 11 但不是合法 UTF-8、已按替换字符解码的条目下标
+    lossy_names: Vec<usize>,
+    /// 已加到每个条目 `local_header_offset` 上的偏移基准，见 `offset_base`
+    offset_base: u64,
+}
+
+/// 数据描述符（通用标志位 bit 3）中记录的 CRC32 和大小
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DataDescriptor {
+    pub crc32: u32,
+    pub compressed_size: u64,
+    pub uncompressed_size: u64,
+    /// 描述符占用的字节数，包括可选的 `PK\x07\x08` 签名
+    pub len: u64,
+}
+
+/// 条目名解码策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NamePolicy {
+    /// 非法 UTF-8 字节替换为 U+FFFD
+    #[default]
+    Lossy,
+    /// 设置了 bit 11 但不是合法 UTF-8 的条目名视为损坏
+    Strict,
+}
+
+/// 中央目录中出现无法解析的记录时的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ErrorMode {
+    /// 遇到第一个损坏的记录即返回错误
+    #[default]
+    FailFast,
+    /// 跳过损坏的记录并记录 `ReadWarning::EntrySkipped`，返回其余能解析的条目
+    Collect,
+}
+
+/// 读取归档时发现并已恢复的问题
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReadWarning {
+    /// EOCD 中的中央目录偏移不正确，已通过扫描 `PK\x01\x02` 签名找到实际位置
+    CentralDirectoryRelocated { claimed: u64, actual: u64 },
+    /// EOCD 只有 20 字节（缺少注释长度字段），已按注释长度 0 处理
+    TruncatedEndOfCentralDirectory,
+    /// 中央目录中的偏移相对于归档起点而不是文件起点（如直接在前面拼接了自解压程序），
+    /// 每个条目的本地头偏移都已加上 `base`
+    OffsetsAdjusted { base: u64 },
+    /// `ErrorMode::Collect` 下跳过的第 `index` 条中央目录记录，`offset` 为记录起点
+    EntrySkipped { index: usize, offset: u64, reason: String },
+    /// 条目名以 `/` 结尾但解压大小不为 0；按目录处理，数据被忽略
+    DirectoryWithData { name: String, size: u64 },
+    /// 条目带有 DOS 目录属性（`0x10`）但名字不以 `/` 结尾且有数据；与 C 版本一致按目录处理，数据被忽略
+    DirectoryAttributeWithData { name: String, size: u64 },
 }
 
 impl ZipReader {
     /// 打开 ZIP 文件并读取中央目录
     /// 对应 C 版本的 mz_zip_reader_init_file()
     pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        Self::open_with_error_mode(path, ErrorMode::FailFast)
+    }
+
+    /// 与 `open` 相同，按 `mode` 处理中央目录中无法解析的记录
+    ///
+    /// `ErrorMode::Collect` 跳过签名错误或被截断的记录，从下一个 `PK\x01\x02` 签名继续解析，
+    /// 每条被跳过的记录在 [`warnings`](Self::warnings) 中记录一次 `ReadWarning::EntrySkipped`。
+    /// 适合配合恢复工具读取部分损坏的归档；EOCD 本身损坏时仍然返回错误。
+    pub fn open_with_error_mode(path: impl AsRef<Path>, mode: ErrorMode) -> Result<Self> {
         let path = path.as_ref().to_path_buf();
 
         // 打开文件
@@ -129,18 +261,358 @@ impl ZipReader {
         let mut reader = BufReader::new(file);
 
         // 查找并解析 EOCD
-        let eocd = Self::find_and_parse_eocd(&mut reader)?;
+        let mut warnings = Vec::new();
+        let eocd = Self::find_and_parse_eocd(&mut reader, &mut warnings)?;
 
         // 解析中央目录
-        let entries = Self::parse_central_directory(&mut reader, &eocd)?;
+        // 声明的偏移处不是中央目录签名时（例如偏移指向某个本地文件头），尝试扫描恢复
+        let mut lossy_names = Vec::new();
+        let mut offset_base = 0;
+        let (mut entries, central_dir_offset) =
+            if eocd.total_entries == 0
+                || Self::has_central_dir_sig_at(&mut reader, eocd.central_dir_offset)?
+            {
+                let entries = Self::parse_central_records(
+                    &mut reader,
+                    &eocd,
+                    &mut lossy_names,
+                    mode,
+                    &mut warnings,
+                )?;
+                (entries, eocd.central_dir_offset)
+            } else {
+                let (entries, actual, lossy) = Self::recover_central_directory(&mut reader, &eocd)
+                    .ok_or_else(|| ZipError::CorruptArchive {
+                        archive: path.clone(),
+                        reason: format!(
+                            "no central directory signature at offset {} and none found by scanning",
+                            eocd.central_dir_offset
+                        ),
+                    })?;
+                log::warn!(
+                    "{}: central directory offset {} is wrong, found it at {}",
+                    path.display(),
+                    eocd.central_dir_offset,
+                    actual
+                );
+                warnings.push(ReadWarning::CentralDirectoryRelocated {
+                    claimed: eocd.central_dir_offset,
+                    actual,
+                });
+                lossy_names = lossy;
+                if let Some(shift) = actual.checked_sub(eocd.central_dir_offset) {
+                    offset_base = Self::detect_offset_base(&mut reader, &entries, shift)?;
+                }
+                (entries, actual)
+            };
+
+        warnings.extend(directory_warnings(&path, &entries));
+
+        // 偏移基准只在这里加到条目偏移上，之后所有读取路径都直接使用调整后的偏移
+        if offset_base > 0 {
+            log::warn!(
+                "{}: entry offsets are relative to an archive starting at {}, adjusting",
+                path.display(),
+                offset_base
+            );
+            warnings.push(ReadWarning::OffsetsAdjusted { base: offset_base });
+            for entry in &mut entries {
+                entry.local_header_offset += offset_base;
+            }
+        }
+
+        log::debug!("opened {} with {} entries", path.display(), entries.len());
+        Ok(Self {
+            path,
+            entries,
+            central_dir_offset,
+            eocd_offset: eocd.eocd_offset,
+            eocd_len: eocd.eocd_len,
+            warnings,
+            lossy_names,
+            offset_base,
+        })
+    }
+
+    /// 跳过 EOCD 查找，直接从 `offset` 开始解析 `count` 条中央目录记录
+    ///
+    /// 用于 EOCD 损坏但中央目录位置已知（来自恢复工具或外部分析）的归档。
+    /// 不做签名扫描等恢复，中央目录无法按给定位置解析时返回错误。
+    pub fn open_with_cd_offset(path: impl AsRef<Path>, offset: u64, count: u16) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let file = File::open(&path).map_err(|e| ZipError::FileOpen {
+            path: path.clone(),
+            source: e,
+        })?;
+        let file_size = file.metadata()?.len();
+        let mut reader = BufReader::new(file);
 
+        let eocd = EocdRecord {
+            eocd_offset: file_size,
+            eocd_len: 0,
+            central_dir_offset: offset,
+            central_dir_size: file_size.saturating_sub(offset),
+            total_entries: count,
+        };
+        let mut lossy_names = Vec::new();
+        let entries = Self::parse_central_directory(&mut reader, &eocd, &mut lossy_names).map_err(|e| {
+            ZipError::CorruptArchive {
+                archive: path.clone(),
+                reason: format!("no central directory at offset {}: {}", offset, e),
+            }
+        })?;
+
+        log::debug!(
+            "opened {} with {} entries from central directory at {}",
+            path.display(),
+            entries.len(),
+            offset
+        );
+        let warnings = directory_warnings(&path, &entries);
         Ok(Self {
             path,
             entries,
+            central_dir_offset: offset,
+            eocd_offset: eocd.eocd_offset,
+            eocd_len: eocd.eocd_len,
+            warnings,
+            lossy_names,
+            offset_base: 0,
+        })
+    }
+
+    /// 用调用方预先取得的归档末尾字节（`footer`）定位 EOCD 并解析中央目录
+    ///
+    /// 面向 HTTP Range 请求之类每次读取代价很高的来源：调用方用一次范围请求取回最后
+    /// 约 64 KB，EOCD 查找和中央目录解析都在这段内存中完成。`reader` 只用于获取归档长度；
+    /// 中央目录起点早于 `footer` 时，再对缺少的部分做一次读取。
+    ///
+    /// 返回的 `ZipReader` 不关联文件路径，条目数据用 [`read_entry_from`](Self::read_entry_from)
+    /// 从同一个 `reader` 读取；中央目录偏移错误时不做签名扫描恢复。
+    pub fn open_with_footer<R: Read + Seek>(reader: &mut R, footer: &[u8]) -> Result<Self> {
+        let archive_len = reader.seek(SeekFrom::End(0))?;
+        let footer_start = archive_len.checked_sub(footer.len() as u64).ok_or_else(|| {
+            ZipError::generic(&format!(
+                "footer ({} bytes) is longer than the archive ({} bytes)",
+                footer.len(),
+                archive_len
+            ))
+        })?;
+
+        let mut tail = FooterReader {
+            start: footer_start,
+            data: footer.to_vec(),
+            len: archive_len,
+            pos: 0,
+        };
+        let mut warnings = Vec::new();
+        let eocd = Self::find_and_parse_eocd(&mut tail, &mut warnings)?;
+
+        // 中央目录不完全在 footer 中：一次读取补齐缺少的前半部分
+        if eocd.central_dir_offset < tail.start {
+            let mut missing = vec![0u8; (tail.start - eocd.central_dir_offset) as usize];
+            reader.seek(SeekFrom::Start(eocd.central_dir_offset))?;
+            reader.read_exact(&mut missing)?;
+            missing.extend_from_slice(&tail.data);
+            tail.data = missing;
+            tail.start = eocd.central_dir_offset;
+        }
+
+        if eocd.total_entries > 0 && !Self::has_central_dir_sig_at(&mut tail, eocd.central_dir_offset)? {
+            return Err(ZipError::generic(&format!(
+                "no central directory signature at offset {}",
+                eocd.central_dir_offset
+            )));
+        }
+        let mut lossy_names = Vec::new();
+        let entries = Self::parse_central_directory(&mut tail, &eocd, &mut lossy_names)?;
+        warnings.extend(directory_warnings(Path::new(""), &entries));
+
+        log::debug!("opened archive from a {}-byte footer with {} entries", footer.len(), entries.len());
+        Ok(Self {
+            path: std::path::PathBuf::new(),
+            entries,
             central_dir_offset: eocd.central_dir_offset,
+            eocd_offset: eocd.eocd_offset,
+            eocd_len: eocd.eocd_len,
+            warnings,
+            lossy_names,
+            offset_base: 0,
         })
     }
 
+    /// 从 `reader` 读取并解压一个条目，校验 CRC32
+    ///
+    /// 定位到本地文件头后只做两次连续读取（固定头部，再是文件名、extra 和压缩数据），
+    /// 适合配合 `open_with_footer` 按范围读取。加密条目返回 `ZipError::PasswordRequired`。
+    pub fn read_entry_from<R: Read + Seek>(&self, reader: &mut R, entry: &ZipEntryInfo) -> Result<Vec<u8>> {
+        if entry.is_dir {
+            return Ok(Vec::new());
+        }
+        reader.seek(SeekFrom::Start(entry.local_header_offset))?;
+        let mut local_header = [0u8; 30];
+        reader.read_exact(&mut local_header)?;
+        let mut header = LocalHeader::parse(&self.path, entry, &local_header)?;
+
+        let skip = header.name_len + header.extra_len;
+        let mut body = vec![0u8; skip + header.compressed_size as usize];
+        reader.read_exact(&mut body).map_err(|e| ZipError::CorruptEntry {
+            name: entry.name.clone(),
+            archive: self.path.clone(),
+            reason: format!("unexpected end of data: {}", e),
+        })?;
+        body.drain(..skip);
+
+        let data = decrypt_entry_data(&self.path, entry, &mut header, None, Cow::Owned(body))?;
+        decode_entry_data(
+            &self.path,
+            &entry.name,
+            header.compression_method,
+            entry.uncompressed_size,
+            header.expected_crc(),
+            data,
+        )
+    }
+
+    /// 按 `policy` 校验条目名
+    ///
+    /// 默认的 `NamePolicy::Lossy` 把设置了 bit 11 但不是合法 UTF-8 的名字中的非法字节
+    /// 替换为 U+FFFD；`NamePolicy::Strict` 对这样的条目返回 `ZipError::CorruptEntry`。
+    /// 未设置 bit 11 的名字按 CP437 解码，总是有效。
+    pub fn name_encoding(self, policy: NamePolicy) -> Result<Self> {
+        if policy == NamePolicy::Strict {
+            if let Some(&index) = self.lossy_names.first() {
+                return Err(ZipError::CorruptEntry {
+                    name: self.entries[index].name.clone(),
+                    archive: self.path.clone(),
+                    reason: "entry name is flagged UTF-8 (bit 11) but is not valid UTF-8".to_string(),
+                });
+            }
+        }
+        Ok(self)
+    }
+
+    /// 读取过程中恢复的非致命问题
+    pub fn warnings(&self) -> &[ReadWarning] {
+        &self.warnings
+    }
+
+    /// 加到每个条目本地头偏移上的偏移基准
+    ///
+    /// 归档前面拼接了数据（自解压程序等）但中央目录中的偏移没有随之调整时，
+    /// 为中央目录实际位置与 EOCD 声明位置之差，否则为 0。
+    /// [`entries`](Self::entries) 中的 `local_header_offset` 已经是调整后的文件内偏移。
+    pub fn offset_base(&self) -> u64 {
+        self.offset_base
+    }
+
+    /// 中央目录比声明的位置靠后 `shift` 字节时，判断条目偏移是否也需要整体后移
+    ///
+    /// 第一个条目的声明偏移处不是本地头、后移 `shift` 之后是本地头时返回 `shift`，否则返回 0。
+    fn detect_offset_base<R: Read + Seek>(
+        reader: &mut R,
+        entries: &[ZipEntryInfo],
+        shift: u64,
+    ) -> Result<u64> {
+        let Some(first) = entries.first() else {
+            return Ok(0);
+        };
+        if shift == 0 || Self::has_local_header_sig_at(reader, first.local_header_offset)? {
+            return Ok(0);
+        }
+        if Self::has_local_header_sig_at(reader, first.local_header_offset + shift)? {
+            Ok(shift)
+        } else {
+            Ok(0)
+        }
+    }
+
+    /// 检查指定偏移处是否为本地文件头签名
+    fn has_local_header_sig_at<R: Read + Seek>(reader: &mut R, offset: u64) -> Result<bool> {
+        reader.seek(SeekFrom::Start(offset))?;
+        let mut sig = [0u8; 4];
+        match reader.read_exact(&mut sig) {
+            Ok(()) => Ok(u32::from_le_bytes(sig) == zip_format::LOCAL_DIR_HEADER_SIG),
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// 检查指定偏移处是否为中央目录头签名
+    fn has_central_dir_sig_at<R: Read + Seek>(reader: &mut R, offset: u64) -> Result<bool> {
+        reader.seek(SeekFrom::Start(offset))?;
+        let mut sig = [0u8; 4];
+        match reader.read_exact(&mut sig) {
+            Ok(()) => Ok(u32::from_le_bytes(sig) == zip_format::CENTRAL_DIR_HEADER_SIG),
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// 声明的偏移处没有中央目录时，按以下顺序寻找真正的中央目录，返回其条目、偏移
+    /// 以及按替换字符解码的条目下标：
+    ///
+    /// 1. EOCD 之前恰好 `central_dir_size` 字节处：只有偏移基准错误（如前面拼接了自解压程序）时，
+    ///    中央目录仍然紧挨着 EOCD；
+    /// 2. 从 EOCD 向前分块扫描 `PK\x01\x02` 签名，取离 EOCD 最近的候选。
+    ///
+    /// 候选的记录必须恰好延伸到 EOCD 才被接受，这样存储在归档中的嵌套 zip/jar
+    /// 的中央目录不会被误认为外层归档的中央目录。
+    fn recover_central_directory<R: Read + Seek>(
+        reader: &mut R,
+        eocd: &EocdRecord,
+    ) -> Option<(Vec<ZipEntryInfo>, u64, Vec<usize>)> {
+        const CHUNK_SIZE: u64 = 64 * 1024;
+
+        let adjacent = eocd.eocd_offset.checked_sub(eocd.central_dir_size);
+        if let Some(found) = adjacent.and_then(|pos| Self::central_directory_at(reader, eocd, pos)) {
+            return Some(found);
+        }
+
+        let sig = zip_format::CENTRAL_DIR_HEADER_SIG.to_le_bytes();
+        let mut buffer = Vec::new();
+        let mut chunk_end = eocd.eocd_offset;
+        while chunk_end > 0 {
+            let chunk_start = chunk_end.saturating_sub(CHUNK_SIZE);
+            // 多读 3 字节，跨越块边界的签名也能找到
+            let read_end = (chunk_end + 3).min(eocd.eocd_offset);
+            buffer.resize((read_end - chunk_start) as usize, 0);
+            reader.seek(SeekFrom::Start(chunk_start)).ok()?;
+            reader.read_exact(&mut buffer).ok()?;
+
+            let candidates: Vec<u64> = buffer
+                .windows(4)
+                .enumerate()
+                .rev()
+                .filter(|(_, w)| *w == sig)
+                .map(|(i, _)| chunk_start + i as u64)
+                .filter(|&pos| pos < chunk_end && Some(pos) != adjacent)
+                .collect();
+            for pos in candidates {
+                if let Some(found) = Self::central_directory_at(reader, eocd, pos) {
+                    return Some(found);
+                }
+            }
+            chunk_end = chunk_start;
+        }
+        None
+    }
+
+    /// 从 `pos` 开始解析 `eocd` 声明数量的中央目录记录，记录恰好结束于 EOCD 时返回
+    fn central_directory_at<R: Read + Seek>(
+        reader: &mut R,
+        eocd: &EocdRecord,
+        pos: u64,
+    ) -> Option<(Vec<ZipEntryInfo>, u64, Vec<usize>)> {
+        let candidate = EocdRecord {
+            central_dir_offset: pos,
+            ..eocd.clone()
+        };
+        let mut lossy_names = Vec::new();
+        let entries = Self::parse_central_directory(reader, &candidate, &mut lossy_names).ok()?;
+        (reader.stream_position().ok()? == eocd.eocd_offset).then_some((entries, pos, lossy_names))
+    }
+
     /// 获取所有文件条目
     pub fn entries(&self) -> &[ZipEntryInfo] {
         &self.entries
@@ -151,7 +623,10 @@ impl ZipReader {
     ///
     /// 关键修复：必须找到最接近文件末尾的有效 EOCD，而不是第一个匹配
     /// 因为 EOCD 签名可能出现在文件数据中（如数据描述符）
-    fn find_and_parse_eocd<R: Read + Seek>(reader: &mut R) -> Result<EocdRecord> {
+    fn find_and_parse_eocd<R: Read + Seek>(
+        reader: &mut R,
+        warnings: &mut Vec<ReadWarning>,
+    ) -> Result<EocdRecord> {
         const RECORD_SIZE: u64 = 22; // EOCD 记录大小
         const MAX_SCAN_SIZE: u64 = 65535 + RECORD_SIZE; // 最大注释长度 + 记录大小
         const BUF_SIZE: usize = 4096; // 每次读取的缓冲区大小（对应 C 版本的 buf_u32）
@@ -160,7 +635,8 @@ impl ZipReader {
         let file_size = reader.seek(SeekFrom::End(0))?;
 
         if file_size < RECORD_SIZE {
-            return Err(ZipError::generic("File too small to be a ZIP archive"));
+            return Self::parse_truncated_eocd(reader, file_size, warnings)?
+                .ok_or_else(|| ZipError::generic("File too small to be a ZIP archive"));
         }
 
         // 从文件末尾开始搜索 EOCD 签名
@@ -172,7 +648,7 @@ impl ZipReader {
         };
 
         let record_sig = zip_format::END_OF_CENTRAL_DIR_SIG;
-        let mut best_eocd_offset: Option<u64> = None;
+        let mut candidates: Vec<u64> = Vec::new();
 
         loop {
             // 计算本次要读取的大小
@@ -194,14 +670,9 @@ impl ZipReader {
                     let eocd_offset = cur_file_ofs + i as u64;
                     let bytes_from_sig_to_end = file_size - eocd_offset;
 
-                    if bytes_from_sig_to_end >= RECORD_SIZE {
+                    if bytes_from_sig_to_end >= RECORD_SIZE && !candidates.contains(&eocd_offset) {
                         // 找到一个候选 EOCD
-                        // 保留最接近文件末尾的（偏移量最大的）
-                        match best_eocd_offset {
-                            None => best_eocd_offset = Some(eocd_offset),
-                            Some(best) if eocd_offset > best => best_eocd_offset = Some(eocd_offset),
-                            _ => {} // 保留当前的 best
-                        }
+                        candidates.push(eocd_offset);
                     }
                 }
             }
@@ -226,11 +697,53 @@ impl ZipReader {
             };
         }
 
-        // 返回找到的最接近文件末尾的有效 EOCD
-        match best_eocd_offset {
-            Some(offset) => Self::parse_eocd_at(reader, offset, file_size),
-            None => Err(ZipError::generic("Cannot find end of central directory")),
+        // 优先选择注释长度恰好延伸到文件末尾的候选：
+        // 注释里伪造的 EOCD 签名几乎不可能同时满足这一点。
+        // 都不满足时（例如文件尾部有填充）退回到最接近文件末尾的候选。
+        candidates.sort_unstable_by(|a, b| b.cmp(a));
+        for &offset in &candidates {
+            reader.seek(SeekFrom::Start(offset + 20))?;
+            let mut comment_len = [0u8; 2];
+            reader.read_exact(&mut comment_len)?;
+            if offset + RECORD_SIZE + u16::from_le_bytes(comment_len) as u64 == file_size {
+                return Self::parse_eocd_at(reader, offset, file_size);
+            }
+        }
+
+        match candidates.first() {
+            Some(&offset) => {
+                log::warn!("no EOCD comment reaches end of file, using the record at {}", offset);
+                Self::parse_eocd_at(reader, offset, file_size)
+            }
+            None => Self::parse_truncated_eocd(reader, file_size, warnings)?
+                .ok_or_else(|| ZipError::generic("Cannot find end of central directory")),
+        }
+    }
+
+    /// 个别工具写出的 EOCD 只有 20 字节，缺少最后的注释长度字段
+    /// 文件末尾 20 字节是以 EOCD 签名开头的记录时按注释长度 0 解析并记录警告
+    fn parse_truncated_eocd<R: Read + Seek>(
+        reader: &mut R,
+        file_size: u64,
+        warnings: &mut Vec<ReadWarning>,
+    ) -> Result<Option<EocdRecord>> {
+        const TRUNCATED_SIZE: u64 = 20;
+        if file_size < TRUNCATED_SIZE {
+            return Ok(None);
+        }
+
+        let offset = file_size - TRUNCATED_SIZE;
+        reader.seek(SeekFrom::Start(offset))?;
+        let mut eocd_data = [0u8; 22];
+        reader.read_exact(&mut eocd_data[..TRUNCATED_SIZE as usize])?;
+        if u32::from_le_bytes(eocd_data[0..4].try_into().unwrap()) != zip_format::END_OF_CENTRAL_DIR_SIG {
+            return Ok(None);
         }
+
+        let eocd = Self::parse_eocd_record(&eocd_data, offset, TRUNCATED_SIZE, file_size)?;
+        log::warn!("EOCD at {} is missing its comment length, assuming no comment", offset);
+        warnings.push(ReadWarning::TruncatedEndOfCentralDirectory);
+        Ok(Some(eocd))
     }
 
     /// 在指定偏移量解析 EOCD 记录
@@ -239,7 +752,16 @@ impl ZipReader {
 
         let mut eocd_data = [0u8; 22];
         reader.read_exact(&mut eocd_data)?;
+        Self::parse_eocd_record(&eocd_data, offset, 22, file_size)
+    }
 
+    /// 校验并解析 EOCD 记录的字段；`record_len` 是记录在文件中实际占用的长度（不含注释）
+    fn parse_eocd_record(
+        eocd_data: &[u8; 22],
+        offset: u64,
+        record_len: u64,
+        file_size: u64,
+    ) -> Result<EocdRecord> {
         // 验证签名
         let sig = u32::from_le_bytes(eocd_data[0..4].try_into().unwrap());
         if sig != zip_format::END_OF_CENTRAL_DIR_SIG {
@@ -277,11 +799,13 @@ impl ZipReader {
         }
 
         // 验证注释长度不会导致 EOCD 超出文件
-        if offset + 22 + comment_len > file_size {
+        if offset + record_len + comment_len > file_size {
             return Err(ZipError::generic("EOCD comment extends beyond file"));
         }
 
         Ok(EocdRecord {
+            eocd_offset: offset,
+            eocd_len: record_len + comment_len,
             central_dir_offset,
             central_dir_size,
             total_entries,
@@ -293,6 +817,18 @@ impl ZipReader {
     fn parse_central_directory<R: Read + Seek>(
         reader: &mut R,
         eocd: &EocdRecord,
+        lossy_names: &mut Vec<usize>,
+    ) -> Result<Vec<ZipEntryInfo>> {
+        Self::parse_central_records(reader, eocd, lossy_names, ErrorMode::FailFast, &mut Vec::new())
+    }
+
+    /// 按 `mode` 解析中央目录；`ErrorMode::Collect` 时损坏的记录被跳过并记入 `warnings`
+    fn parse_central_records<R: Read + Seek>(
+        reader: &mut R,
+        eocd: &EocdRecord,
+        lossy_names: &mut Vec<usize>,
+        mode: ErrorMode,
+        warnings: &mut Vec<ReadWarning>,
     ) -> Result<Vec<ZipEntryInfo>> {
         let mut entries = Vec::new();
 
@@ -300,73 +836,135 @@ impl ZipReader {
         reader.seek(SeekFrom::Start(eocd.central_dir_offset))?;
 
         // 解析所有中央目录条目
-        for _ in 0..eocd.total_entries {
-            // 读取完整的中央目录头（46 字节，包括签名）
-            // 对应 miniz.c:3083-3100
-            let mut header = [0u8; 46];
-            reader.read_exact(&mut header).map_err(|e| {
-                ZipError::generic(&format!("Failed to read central directory header: {:?}", e))
-            })?;
-
-            // 验证签名（前 4 字节）
-            if u32::from_le_bytes(header[0..4].try_into().unwrap()) != zip_format::CENTRAL_DIR_HEADER_SIG {
-                return Err(ZipError::generic(&format!(
-                    "Invalid central directory header signature: got 0x{:08x}",
-                    u32::from_le_bytes(header[0..4].try_into().unwrap())
-                )));
+        for index in 0..eocd.total_entries as usize {
+            let offset = reader.stream_position()?;
+            match Self::parse_central_record(reader, lossy_names, entries.len()) {
+                Ok(entry) => entries.push(entry),
+                Err(e) if mode == ErrorMode::Collect => {
+                    log::warn!("skipping central directory record {} at {}: {}", index, offset, e);
+                    warnings.push(ReadWarning::EntrySkipped {
+                        index,
+                        offset,
+                        reason: e.to_string(),
+                    });
+                    match Self::find_central_dir_sig(reader, offset + 1, eocd.eocd_offset)? {
+                        Some(next) => reader.seek(SeekFrom::Start(next))?,
+                        None => break,
+                    };
+                }
+                Err(e) => return Err(e),
             }
+        }
 
-            // 解析字段（偏移量从签名之后开始）
-            // 对应 C 版本 miniz.c:3083-3100
-            let version_made_by = u16::from_le_bytes(header[4..6].try_into().unwrap());
-            let compression_method = u16::from_le_bytes(header[10..12].try_into().unwrap());
-            let mtime_dos = u16::from_le_bytes(header[12..14].try_into().unwrap()); // DOS 时间
-            let mdate_dos = u16::from_le_bytes(header[14..16].try_into().unwrap()); // DOS 日期
-            let crc32 = u32::from_le_bytes(header[16..20].try_into().unwrap());
-            let compressed_size = u32::from_le_bytes(header[20..24].try_into().unwrap()) as u64;
-            let uncompressed_size = u32::from_le_bytes(header[24..28].try_into().unwrap()) as u64;
-            let external_attr = u32::from_le_bytes(header[38..42].try_into().unwrap());
-            let name_len = u16::from_le_bytes(header[28..30].try_into().unwrap()) as usize;
-            let extra_len = u16::from_le_bytes(header[30..32].try_into().unwrap()) as usize;
-            let comment_len = u16::from_le_bytes(header[32..34].try_into().unwrap()) as usize;
-            let local_header_offset = u32::from_le_bytes(header[42..46].try_into().unwrap()) as u64;
-
-            // 读取文件名
-            let mut name_bytes = vec![0u8; name_len];
-            reader.read_exact(&mut name_bytes).map_err(|e| {
-                ZipError::generic(&format!("Failed to read filename: {:?}", e))
-            })?;
-            let name = String::from_utf8_lossy(&name_bytes).to_string();
-
-            // 跳过 extra field 和 comment
-            let skip_len = extra_len + comment_len;
-            if skip_len > 0 {
-                let mut skip_buf = vec![0u8; skip_len];
-                reader.read_exact(&mut skip_buf).map_err(|e| {
-                    ZipError::generic(&format!("Failed to skip extra/comment: {:?}", e))
-                })?;
+        Ok(entries)
+    }
+
+    /// 在 `[from, end)` 中查找下一个中央目录头签名
+    fn find_central_dir_sig<R: Read + Seek>(reader: &mut R, from: u64, end: u64) -> Result<Option<u64>> {
+        if from >= end {
+            return Ok(None);
+        }
+        let mut data = vec![0u8; (end - from) as usize];
+        reader.seek(SeekFrom::Start(from))?;
+        reader.read_exact(&mut data)?;
+        let sig = zip_format::CENTRAL_DIR_HEADER_SIG.to_le_bytes();
+        Ok(data.windows(4).position(|w| w == sig).map(|pos| from + pos as u64))
+    }
+
+    /// 解析当前位置的一条中央目录记录；`index` 为该条目在结果中的下标
+    fn parse_central_record<R: Read + Seek>(
+        reader: &mut R,
+        lossy_names: &mut Vec<usize>,
+        index: usize,
+    ) -> Result<ZipEntryInfo> {
+        // 读取完整的中央目录头（46 字节，包括签名）
+        // 对应 miniz.c:3083-3100
+        let mut header = [0u8; 46];
+        reader.read_exact(&mut header).map_err(|e| {
+            ZipError::generic(&format!("Failed to read central directory header: {:?}", e))
+        })?;
+
+        // 验证签名（前 4 字节）
+        if u32::from_le_bytes(header[0..4].try_into().unwrap()) != zip_format::CENTRAL_DIR_HEADER_SIG {
+            return Err(ZipError::generic(&format!(
+                "Invalid central directory header signature: got 0x{:08x}",
+                u32::from_le_bytes(header[0..4].try_into().unwrap())
+            )));
+        }
+
+        // 解析字段（偏移量从签名之后开始）
+        // 对应 C 版本 miniz.c:3083-3100
+        // version needed、内部属性和起始磁盘号不参与解析，也不做校验：极简写入器常把它们写成 0
+        // 或无意义的值；DOS 日期为 0 时修改时间按 UNIX_EPOCH 处理
+        let version_made_by = u16::from_le_bytes(header[4..6].try_into().unwrap());
+        let flags = u16::from_le_bytes(header[8..10].try_into().unwrap());
+        let compression_method = u16::from_le_bytes(header[10..12].try_into().unwrap());
+        let mtime_dos = u16::from_le_bytes(header[12..14].try_into().unwrap()); // DOS 时间
+        let mdate_dos = u16::from_le_bytes(header[14..16].try_into().unwrap()); // DOS 日期
+        let crc32 = u32::from_le_bytes(header[16..20].try_into().unwrap());
+        let mut compressed_size = u32::from_le_bytes(header[20..24].try_into().unwrap()) as u64;
+        let mut uncompressed_size = u32::from_le_bytes(header[24..28].try_into().unwrap()) as u64;
+        let external_attr = u32::from_le_bytes(header[38..42].try_into().unwrap());
+        let name_len = u16::from_le_bytes(header[28..30].try_into().unwrap()) as usize;
+        let extra_len = u16::from_le_bytes(header[30..32].try_into().unwrap()) as usize;
+        let comment_len = u16::from_le_bytes(header[32..34].try_into().unwrap()) as usize;
+        let mut local_header_offset = u32::from_le_bytes(header[42..46].try_into().unwrap()) as u64;
+
+        // 读取文件名
+        let mut name_bytes = vec![0u8; name_len];
+        reader.read_exact(&mut name_bytes).map_err(|e| {
+            ZipError::generic(&format!("Failed to read filename: {:?}", e))
+        })?;
+        // bit 11 未设置且不是合法 UTF-8 时按 CP437 解码
+        let raw_name = name_bytes.clone();
+        let name = match String::from_utf8(name_bytes) {
+            Ok(name) => name,
+            Err(e) if flags & zip_format::FLAG_UTF8 == 0 => cp437::decode(e.as_bytes()),
+            Err(e) => {
+                lossy_names.push(index);
+                String::from_utf8_lossy(e.as_bytes()).to_string()
             }
+        };
 
-            // 判断是否为目录
-            // 对应 C 版本：m_zip_archive_file_stat.m_is_directory
-            let is_dir = (external_attr & 0x10) != 0 || name.ends_with('/');
-
-            entries.push(ZipEntryInfo {
-                name,
-                uncompressed_size,
-                compressed_size,
-                crc32,
-                local_header_offset,
-                is_dir,
-                compression_method,
-                external_attr,
-                version_made_by,
-                mtime_dos,
-                mdate_dos,
-            });
+        // 读取 extra field
+        let mut extra = vec![0u8; extra_len];
+        reader.read_exact(&mut extra).map_err(|e| {
+            ZipError::generic(&format!("Failed to read extra field: {:?}", e))
+        })?;
+
+        // 0xFFFFFFFF 占位的大小和偏移取 ZIP64 字段中的值
+        extra::apply_zip64(&extra, &mut uncompressed_size, &mut compressed_size, &mut local_header_offset);
+
+        // 跳过 comment
+        if comment_len > 0 {
+            let mut skip_buf = vec![0u8; comment_len];
+            reader.read_exact(&mut skip_buf).map_err(|e| {
+                ZipError::generic(&format!("Failed to skip comment: {:?}", e))
+            })?;
         }
 
-        Ok(entries)
+        // 判断是否为目录
+        // 对应 C 版本：m_zip_archive_file_stat.m_is_directory
+        // 与 C 版本 mz_zip_reader_is_file_a_directory() 相同：结尾的 `/` 或 DOS 目录属性
+        // 任一成立即为目录，与条目是否带有数据无关；带有数据的情况见 `directory_warnings`
+        let is_dir = name.ends_with('/') || (external_attr & 0x10) != 0;
+
+        Ok(ZipEntryInfo {
+            name,
+            uncompressed_size,
+            compressed_size,
+            crc32,
+            local_header_offset,
+            is_dir,
+            flags,
+            compression_method,
+            external_attr,
+            version_made_by,
+            mtime_dos,
+            mdate_dos,
+            extra,
+            raw_name,
+        })
     }
 
     /// 获取中央目录之后的数据位置（追加模式的写入位置）
@@ -375,6 +973,257 @@ impl ZipReader {
         // 中央目录之前的位置
         self.central_dir_offset
     }
+
+    /// 中央目录的原始字节：从中央目录起点到 EOCD 记录之前
+    ///
+    /// 供签名工具直接计算摘要，不需要重新序列化。
+    /// 通过 `open_with_cd_offset` 打开时没有 EOCD，范围延伸到文件末尾。
+    pub fn central_directory_bytes(&self) -> Result<Vec<u8>> {
+        self.read_range(self.central_dir_offset, self.eocd_offset - self.central_dir_offset)
+    }
+
+    /// EOCD 记录的原始字节，包括归档注释
+    ///
+    /// 通过 `open_with_cd_offset` 打开时没有 EOCD，返回空数据。
+    pub fn eocd_bytes(&self) -> Result<Vec<u8>> {
+        self.read_range(self.eocd_offset, self.eocd_len)
+    }
+
+    /// EOCD 记录在文件中的偏移；通过 `open_with_cd_offset` 打开时为文件末尾
+    pub(crate) fn eocd_offset(&self) -> u64 {
+        self.eocd_offset
+    }
+
+    /// 读取归档中 `[offset, offset + len)` 的字节
+    fn read_range(&self, offset: u64, len: u64) -> Result<Vec<u8>> {
+        let mut file = File::open(&self.path).map_err(|e| ZipError::FileOpen {
+            path: self.path.clone(),
+            source: e,
+        })?;
+        file.seek(SeekFrom::Start(offset))?;
+        let mut buf = vec![0u8; len as usize];
+        file.read_exact(&mut buf)?;
+        Ok(buf)
+    }
+
+    /// 计算最后一个条目数据结束的位置（本地头 + 文件名 + extra + 压缩数据 + 数据描述符）
+    ///
+    /// 与 `get_append_offset()` 之间的差值就是条目数据与中央目录之间的空隙，
+    /// 例如 APK/JAR 签名块。
+    pub(crate) fn entries_end_offset(&self) -> Result<u64> {
+        let overheads = self.local_overheads()?;
+        Ok(self
+            .entries
+            .iter()
+            .zip(overheads)
+            .map(|(entry, (header, descriptor))| {
+                entry.local_header_offset + header + entry.compressed_size + descriptor
+            })
+            .max()
+            .unwrap_or(0))
+    }
+
+    /// 按中央目录顺序返回每个条目的 (本地文件头字节数, 数据描述符字节数)
+    ///
+    /// 本地文件头包括 30 字节固定部分、文件名和 extra；没有数据描述符时为 0。
+    pub(crate) fn local_overheads(&self) -> Result<Vec<(u64, u64)>> {
+        let file = File::open(&self.path).map_err(|e| ZipError::FileOpen {
+            path: self.path.clone(),
+            source: e,
+        })?;
+        let mut reader = BufReader::new(file);
+
+        let mut overheads = Vec::with_capacity(self.entries.len());
+        for entry in &self.entries {
+            let (header, descriptor) = Self::read_local_overhead(&mut reader, entry)?;
+            overheads.push((header, descriptor.map_or(0, |descriptor| descriptor.len)));
+        }
+
+        Ok(overheads)
+    }
+
+    /// 读取条目的数据描述符；没有设置 bit 3 的条目返回 `None`
+    ///
+    /// 本地头或中央目录带有 ZIP64 字段时，描述符中的两个大小为 64 位。
+    pub fn data_descriptor(&self, entry: &ZipEntryInfo) -> Result<Option<DataDescriptor>> {
+        let file = File::open(&self.path).map_err(|e| ZipError::FileOpen {
+            path: self.path.clone(),
+            source: e,
+        })?;
+        Ok(Self::read_local_overhead(&mut BufReader::new(file), entry)?.1)
+    }
+
+    /// 读取条目的本地头长度（30 字节 + 文件名 + extra）和数据描述符
+    fn read_local_overhead<R: Read + Seek>(
+        reader: &mut R,
+        entry: &ZipEntryInfo,
+    ) -> Result<(u64, Option<DataDescriptor>)> {
+        reader.seek(SeekFrom::Start(entry.local_header_offset))?;
+        let mut local_header = [0u8; 30];
+        reader.read_exact(&mut local_header)?;
+        let name_len = u16::from_le_bytes(local_header[26..28].try_into().unwrap()) as u64;
+        let extra_len = u16::from_le_bytes(local_header[28..30].try_into().unwrap()) as usize;
+        let header = 30 + name_len + extra_len as u64;
+        if entry.flags & zip_format::FLAG_DATA_DESCRIPTOR == 0 {
+            return Ok((header, None));
+        }
+
+        reader.seek(SeekFrom::Current(name_len as i64))?;
+        let mut local_extra = vec![0u8; extra_len];
+        reader.read_exact(&mut local_extra)?;
+        let zip64 = extra::find_field(&local_extra, extra::ZIP64_TAG).is_some()
+            || extra::find_field(&entry.extra, extra::ZIP64_TAG).is_some();
+
+        // 数据描述符：可选签名 + CRC + 两个大小（ZIP64 时各 8 字节，否则各 4 字节）
+        reader.seek(SeekFrom::Current(entry.compressed_size as i64))?;
+        let size_len = if zip64 { 8 } else { 4 };
+        let mut buf = [0u8; 24];
+        reader.read_exact(&mut buf[..4])?;
+        let signed = u32::from_le_bytes(buf[0..4].try_into().unwrap()) == zip_format::DATA_DESCRIPTOR_SIG;
+        let start = if signed { 4 } else { 0 };
+        let len = start + 4 + 2 * size_len;
+        reader.read_exact(&mut buf[4..len])?;
+
+        let fields = &buf[start..len];
+        let size = |bytes: &[u8]| {
+            let mut value = [0u8; 8];
+            value[..bytes.len()].copy_from_slice(bytes);
+            u64::from_le_bytes(value)
+        };
+        Ok((
+            header,
+            Some(DataDescriptor {
+                crc32: u32::from_le_bytes(fields[0..4].try_into().unwrap()),
+                compressed_size: size(&fields[4..4 + size_len]),
+                uncompressed_size: size(&fields[4 + size_len..]),
+                len: len as u64,
+            }),
+        ))
+    }
+
+    /// 条目数据与中央目录之间是否有 APK 签名块（以 `APK Sig Block 42` 结尾）
+    pub(crate) fn has_apk_signing_block(&self) -> Result<bool> {
+        const MAGIC: &[u8; 16] = b"APK Sig Block 42";
+
+        if self.central_dir_offset < self.entries_end_offset()? + MAGIC.len() as u64 {
+            return Ok(false);
+        }
+
+        let mut file = File::open(&self.path).map_err(|e| ZipError::FileOpen {
+            path: self.path.clone(),
+            source: e,
+        })?;
+        file.seek(SeekFrom::Start(self.central_dir_offset - MAGIC.len() as u64))?;
+        let mut magic = [0u8; 16];
+        file.read_exact(&mut magic)?;
+        Ok(&magic == MAGIC)
+    }
+
+    /// 读取条目的原始（未解压）数据，不做 CRC 校验
+    /// 对应 C 版本 mz_zip_reader_extract_to_mem() 中 MZ_ZIP_FLAG_COMPRESSED_DATA 的路径
+    pub(crate) fn raw_entry_data(&self, entry: &ZipEntryInfo) -> Result<Vec<u8>> {
+        let file = File::open(&self.path).map_err(|e| ZipError::FileOpen {
+            path: self.path.clone(),
+            source: e,
+        })?;
+        let mut reader = BufReader::new(file);
+        reader.seek(SeekFrom::Start(entry.local_header_offset))?;
+
+        let mut local_header = [0u8; 30];
+        reader.read_exact(&mut local_header)?;
+        if u32::from_le_bytes(local_header[0..4].try_into().unwrap()) != zip_format::LOCAL_DIR_HEADER_SIG {
+            return Err(ZipError::CorruptEntry {
+                name: entry.name.clone(),
+                archive: self.path.clone(),
+                reason: "invalid local header signature".to_string(),
+            });
+        }
+        let name_len = u16::from_le_bytes(local_header[26..28].try_into().unwrap()) as i64;
+        let extra_len = u16::from_le_bytes(local_header[28..30].try_into().unwrap()) as i64;
+        reader.seek(SeekFrom::Current(name_len + extra_len))?;
+
+        let mut data = vec![0u8; entry.compressed_size as usize];
+        reader.read_exact(&mut data).map_err(|e| ZipError::CorruptEntry {
+            name: entry.name.clone(),
+            archive: self.path.clone(),
+            reason: format!("failed to read compressed data: {}", e),
+        })?;
+        Ok(data)
+    }
+
+    /// 按中央目录顺序读取每条中央目录记录的原始字节（头 + 文件名 + extra + 注释）
+    pub(crate) fn raw_central_records(&self) -> Result<Vec<Vec<u8>>> {
+        let mut reader = self.central_dir_reader()?;
+        let mut records = Vec::with_capacity(self.entries.len());
+        for _ in 0..self.entries.len() {
+            records.push(Self::read_raw_central_record(&mut reader)?);
+        }
+
+        Ok(records)
+    }
+
+    /// 第 `index` 个条目在中央目录中存储的原始字节：46 字节固定头、文件名、extra 和注释
+    ///
+    /// 与 `entries()` 的下标一致，原样写回即可逐字节重现该记录。
+    pub fn raw_central_header(&self, index: usize) -> Result<Vec<u8>> {
+        if index >= self.entries.len() {
+            return Err(ZipError::CorruptEntry {
+                name: format!("index {}", index),
+                archive: self.path.clone(),
+                reason: "file index out of bounds".to_string(),
+            });
+        }
+
+        let mut reader = self.central_dir_reader()?;
+        for _ in 0..index {
+            Self::read_raw_central_record(&mut reader)?;
+        }
+        Self::read_raw_central_record(&mut reader)
+    }
+
+    /// 打开归档并定位到中央目录起点
+    fn central_dir_reader(&self) -> Result<BufReader<File>> {
+        let file = File::open(&self.path).map_err(|e| ZipError::FileOpen {
+            path: self.path.clone(),
+            source: e,
+        })?;
+        let mut reader = BufReader::new(file);
+        reader.seek(SeekFrom::Start(self.central_dir_offset))?;
+        Ok(reader)
+    }
+
+    /// 从当前位置读取一条完整的中央目录记录
+    fn read_raw_central_record<R: Read>(reader: &mut R) -> Result<Vec<u8>> {
+        let mut record = vec![0u8; 46];
+        reader.read_exact(&mut record).map_err(|e| {
+            ZipError::generic(&format!("Failed to read central directory header: {:?}", e))
+        })?;
+        let name_len = u16::from_le_bytes(record[28..30].try_into().unwrap()) as usize;
+        let extra_len = u16::from_le_bytes(record[30..32].try_into().unwrap()) as usize;
+        let comment_len = u16::from_le_bytes(record[32..34].try_into().unwrap()) as usize;
+        record.resize(46 + name_len + extra_len + comment_len, 0);
+        reader.read_exact(&mut record[46..]).map_err(|e| {
+            ZipError::generic(&format!("Failed to read central directory record: {:?}", e))
+        })?;
+        Ok(record)
+    }
+}
+
+/// 找出按目录处理但带有数据的记录，这些数据在解压时被忽略
+fn directory_warnings(path: &Path, entries: &[ZipEntryInfo]) -> Vec<ReadWarning> {
+    let mut warnings = Vec::new();
+    for entry in entries.iter().filter(|entry| entry.uncompressed_size > 0) {
+        let name = entry.name.clone();
+        let size = entry.uncompressed_size;
+        if entry.name.ends_with('/') {
+            log::warn!("{}: directory entry {} has {} bytes of data", path.display(), name, size);
+            warnings.push(ReadWarning::DirectoryWithData { name, size });
+        } else if entry.external_attr & 0x10 != 0 {
+            log::warn!("{}: {} has the directory attribute and {} bytes of data, treating it as a directory", path.display(), name, size);
+            warnings.push(ReadWarning::DirectoryAttributeWithData { name, size });
+        }
+    }
+    warnings
 }
 
 #[cfg(test)]
@@ -416,4 +1265,477 @@ mod tests {
         let reader = reader.unwrap();
         assert_eq!(reader.entries.len(), 0);
     }
+
+    #[test]
+    fn test_truncated_eocd_accepted_with_warning() {
+        let tmp_dir = TempDir::new().unwrap();
+        let zip_path = tmp_dir.path().join("empty.zip");
+        // 空归档的 EOCD 去掉最后两个字节的注释长度
+        let mut eocd = vec![0x50, 0x4b, 0x05, 0x06];
+        eocd.extend_from_slice(&[0u8; 16]);
+        fs::write(&zip_path, &eocd).unwrap();
+
+        let reader = ZipReader::open(&zip_path).unwrap();
+        assert!(reader.entries().is_empty());
+        assert_eq!(reader.warnings(), &[ReadWarning::TruncatedEndOfCentralDirectory]);
+    }
+
+    #[test]
+    fn test_fake_eocd_in_comment_ignored() {
+        use crate::zip::ZipBuilder;
+
+        let tmp_dir = TempDir::new().unwrap();
+        fs::write(tmp_dir.path().join("a.txt"), b"alpha\n").unwrap();
+        let zip_path = tmp_dir.path().join("test.zip");
+        ZipBuilder::new(&zip_path)
+            .unwrap()
+            .root(tmp_dir.path())
+            .files(&["a.txt"])
+            .unwrap()
+            .build()
+            .unwrap();
+
+        // 注释中嵌入一条声称 0 个条目的伪造 EOCD，其注释长度到不了文件末尾
+        let mut comment = b"see ".to_vec();
+        comment.extend_from_slice(&[0x50, 0x4b, 0x05, 0x06]);
+        comment.extend_from_slice(&[0u8; 18]);
+        comment.extend_from_slice(b" for details");
+
+        let mut bytes = fs::read(&zip_path).unwrap();
+        let real_eocd = bytes.len() - 22;
+        bytes[real_eocd + 20..real_eocd + 22].copy_from_slice(&(comment.len() as u16).to_le_bytes());
+        bytes.extend_from_slice(&comment);
+        fs::write(&zip_path, &bytes).unwrap();
+
+        let reader = ZipReader::open(&zip_path).unwrap();
+        assert_eq!(reader.entries().len(), 1);
+        assert_eq!(reader.entries()[0].name, "a.txt");
+    }
+
+    #[test]
+    fn test_recover_misplaced_central_directory() {
+        use crate::zip::ZipBuilder;
+
+        let tmp_dir = TempDir::new().unwrap();
+        fs::write(tmp_dir.path().join("a.txt"), b"alpha\n").unwrap();
+        fs::write(tmp_dir.path().join("b.txt"), b"bravo\n").unwrap();
+        let zip_path = tmp_dir.path().join("test.zip");
+        ZipBuilder::new(&zip_path)
+            .unwrap()
+            .root(tmp_dir.path())
+            .files(&["a.txt", "b.txt"])
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let original = fs::read(&zip_path).unwrap();
+        let eocd_pos = original.len() - 22;
+        let cd_offset = u32::from_le_bytes(original[eocd_pos + 16..eocd_pos + 20].try_into().unwrap());
+
+        // 偏移少了几个字节，指向最后一个条目的数据内部
+        let mut bytes = original.clone();
+        bytes[eocd_pos + 16..eocd_pos + 20].copy_from_slice(&(cd_offset - 3).to_le_bytes());
+        fs::write(&zip_path, &bytes).unwrap();
+
+        let reader = ZipReader::open(&zip_path).unwrap();
+        assert_eq!(reader.entries().len(), 2);
+        assert_eq!(reader.entries()[1].name, "b.txt");
+        assert_eq!(
+            reader.warnings(),
+            &[ReadWarning::CentralDirectoryRelocated {
+                claimed: (cd_offset - 3) as u64,
+                actual: cd_offset as u64,
+            }]
+        );
+
+        // 中央目录签名也被破坏时无法恢复
+        bytes[cd_offset as usize] = 0;
+        fs::write(&zip_path, &bytes).unwrap();
+        let err = ZipReader::open(&zip_path).err().unwrap();
+        assert!(matches!(err, ZipError::CorruptArchive { .. }), "{:?}", err);
+    }
+
+    #[test]
+    fn test_recover_ignores_nested_central_directory() {
+        use crate::error::CompressionLevel;
+        use crate::zip::ZipBuilder;
+
+        let tmp_dir = TempDir::new().unwrap();
+        let inner_dir = tmp_dir.path().join("inner");
+        fs::create_dir(&inner_dir).unwrap();
+        for name in ["x.txt", "y.txt", "z.txt"] {
+            fs::write(inner_dir.join(name), name).unwrap();
+        }
+        let outer_dir = tmp_dir.path().join("outer");
+        fs::create_dir(&outer_dir).unwrap();
+        ZipBuilder::new(outer_dir.join("nested.zip"))
+            .unwrap()
+            .root(&inner_dir)
+            .files(&["x.txt", "y.txt", "z.txt"])
+            .unwrap()
+            .build()
+            .unwrap();
+        fs::write(outer_dir.join("a.txt"), b"alpha\n").unwrap();
+
+        // 嵌套归档原样存储，其中央目录（3 条记录）出现在外层归档的数据中
+        let zip_path = tmp_dir.path().join("outer.zip");
+        ZipBuilder::new(&zip_path)
+            .unwrap()
+            .compression_level(CompressionLevel::NoCompression)
+            .root(&outer_dir)
+            .files(&["nested.zip", "a.txt"])
+            .unwrap()
+            .build()
+            .unwrap();
+
+        // 偏移和大小都错误，只能靠扫描找到中央目录
+        let mut bytes = fs::read(&zip_path).unwrap();
+        let eocd_pos = bytes.len() - 22;
+        let cd_offset = u32::from_le_bytes(bytes[eocd_pos + 16..eocd_pos + 20].try_into().unwrap());
+        bytes[eocd_pos + 12..eocd_pos + 16].copy_from_slice(&0u32.to_le_bytes());
+        bytes[eocd_pos + 16..eocd_pos + 20].copy_from_slice(&(cd_offset - 3).to_le_bytes());
+        fs::write(&zip_path, &bytes).unwrap();
+
+        let reader = ZipReader::open(&zip_path).unwrap();
+        let names: Vec<&str> = reader.entries().iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, ["nested.zip", "a.txt"]);
+        assert_eq!(reader.central_dir_offset, cd_offset as u64);
+
+        // 外层中央目录损坏时不会退而采用嵌套归档的中央目录
+        bytes[cd_offset as usize] = 0;
+        fs::write(&zip_path, &bytes).unwrap();
+        let err = ZipReader::open(&zip_path).err().unwrap();
+        assert!(matches!(err, ZipError::CorruptArchive { .. }), "{:?}", err);
+    }
+
+    #[test]
+    fn test_error_mode_collect() {
+        use crate::zip::ZipBuilder;
+
+        let tmp_dir = TempDir::new().unwrap();
+        for name in ["a.txt", "b.txt", "c.txt"] {
+            fs::write(tmp_dir.path().join(name), name).unwrap();
+        }
+        let zip_path = tmp_dir.path().join("test.zip");
+        ZipBuilder::new(&zip_path)
+            .unwrap()
+            .root(tmp_dir.path())
+            .files(&["a.txt", "b.txt", "c.txt"])
+            .unwrap()
+            .build()
+            .unwrap();
+
+        // 破坏第二条中央目录记录的签名
+        let mut bytes = fs::read(&zip_path).unwrap();
+        let sig = zip_format::CENTRAL_DIR_HEADER_SIG.to_le_bytes();
+        let second = bytes
+            .windows(4)
+            .enumerate()
+            .filter(|(_, w)| *w == sig)
+            .nth(1)
+            .unwrap()
+            .0;
+        bytes[second + 3] = 0xFF;
+        fs::write(&zip_path, &bytes).unwrap();
+
+        assert!(ZipReader::open(&zip_path).is_err());
+
+        let reader = ZipReader::open_with_error_mode(&zip_path, ErrorMode::Collect).unwrap();
+        let names: Vec<&str> = reader.entries().iter().map(|entry| entry.name.as_str()).collect();
+        assert_eq!(names, ["a.txt", "c.txt"]);
+        match reader.warnings() {
+            [ReadWarning::EntrySkipped { index: 1, offset, reason }] => {
+                assert_eq!(*offset, second as u64);
+                assert!(reason.contains("signature"), "{}", reason);
+            }
+            other => panic!("unexpected warnings: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_name_encoding_policy() {
+        use crate::error::CompressionLevel;
+        use crate::zip::ZipWriter;
+
+        let tmp_dir = TempDir::new().unwrap();
+        let zip_path = tmp_dir.path().join("names.zip");
+        let mut writer = ZipWriter::new(&zip_path, CompressionLevel::default()).unwrap();
+        writer.add_reader("a?b.txt", &mut &b"data"[..]).unwrap();
+        writer.finalize().unwrap();
+        drop(writer);
+
+        // 中央目录中的名字改成非法 UTF-8，并设置 bit 11
+        let mut bytes = fs::read(&zip_path).unwrap();
+        let cd = bytes.windows(4).position(|w| w == b"PK\x01\x02").unwrap();
+        bytes[cd + 9] |= 0x08; // flags 高字节中的 bit 11
+        bytes[cd + 46 + 1] = 0xFF;
+        fs::write(&zip_path, &bytes).unwrap();
+
+        let reader = ZipReader::open(&zip_path).unwrap().name_encoding(NamePolicy::Lossy).unwrap();
+        assert_eq!(reader.entries()[0].name, "a\u{FFFD}b.txt");
+
+        let err = ZipReader::open(&zip_path)
+            .unwrap()
+            .name_encoding(NamePolicy::Strict)
+            .err()
+            .unwrap();
+        assert!(
+            matches!(&err, ZipError::CorruptEntry { name, .. } if name == "a\u{FFFD}b.txt"),
+            "{:?}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_open_with_cd_offset() {
+        use crate::zip::ZipBuilder;
+
+        let tmp_dir = TempDir::new().unwrap();
+        fs::write(tmp_dir.path().join("a.txt"), b"alpha\n").unwrap();
+        fs::write(tmp_dir.path().join("b.txt"), b"bravo\n").unwrap();
+        let zip_path = tmp_dir.path().join("test.zip");
+        ZipBuilder::new(&zip_path)
+            .unwrap()
+            .root(tmp_dir.path())
+            .files(&["a.txt", "b.txt"])
+            .unwrap()
+            .build()
+            .unwrap();
+
+        // 抹掉 EOCD
+        let mut bytes = fs::read(&zip_path).unwrap();
+        let eocd_pos = bytes.len() - 22;
+        let cd_offset = u32::from_le_bytes(bytes[eocd_pos + 16..eocd_pos + 20].try_into().unwrap());
+        bytes[eocd_pos..].fill(0);
+        fs::write(&zip_path, &bytes).unwrap();
+        assert!(ZipReader::open(&zip_path).is_err());
+
+        let reader = ZipReader::open_with_cd_offset(&zip_path, cd_offset as u64, 2).unwrap();
+        let names: Vec<_> = reader.entries().iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, ["a.txt", "b.txt"]);
+        let data = reader.raw_entry_data(&reader.entries()[1]).unwrap();
+        assert_eq!(data.len() as u64, reader.entries()[1].compressed_size);
+
+        let err = ZipReader::open_with_cd_offset(&zip_path, cd_offset as u64 + 1, 2).err().unwrap();
+        assert!(matches!(err, ZipError::CorruptArchive { .. }), "{:?}", err);
+    }
+
+    #[test]
+    fn test_central_directory_and_eocd_bytes() {
+        use crate::zip::ZipBuilder;
+
+        let tmp_dir = TempDir::new().unwrap();
+        fs::write(tmp_dir.path().join("a.txt"), b"alpha\n").unwrap();
+        fs::write(tmp_dir.path().join("b.txt"), b"bravo\n").unwrap();
+        let zip_path = tmp_dir.path().join("test.zip");
+        ZipBuilder::new(&zip_path)
+            .unwrap()
+            .root(tmp_dir.path())
+            .files(&["a.txt", "b.txt"])
+            .unwrap()
+            .build()
+            .unwrap();
+
+        // 加上归档注释，确认 EOCD 字节包含注释
+        let mut bytes = fs::read(&zip_path).unwrap();
+        let len = bytes.len();
+        bytes[len - 2..].copy_from_slice(&7u16.to_le_bytes());
+        bytes.extend_from_slice(b"signed!");
+        fs::write(&zip_path, &bytes).unwrap();
+
+        let reader = ZipReader::open(&zip_path).unwrap();
+        let cd = reader.central_directory_bytes().unwrap();
+        let eocd = reader.eocd_bytes().unwrap();
+        assert_eq!(&cd[..4], b"PK\x01\x02");
+        assert_eq!(&eocd[..4], b"PK\x05\x06");
+        assert!(eocd.ends_with(b"signed!"));
+
+        let cd_offset = reader.get_append_offset() as usize;
+        assert_eq!([cd, eocd].concat(), &bytes[cd_offset..]);
+    }
+
+    #[test]
+    fn test_raw_central_header() {
+        use crate::zip::ZipBuilder;
+
+        let tmp_dir = TempDir::new().unwrap();
+        fs::write(tmp_dir.path().join("a.txt"), b"alpha\n").unwrap();
+        fs::create_dir_all(tmp_dir.path().join("dir")).unwrap();
+        fs::write(tmp_dir.path().join("dir").join("b.txt"), "bravo\n".repeat(50)).unwrap();
+        let zip_path = tmp_dir.path().join("test.zip");
+        ZipBuilder::new(&zip_path)
+            .unwrap()
+            .root(tmp_dir.path())
+            .files(&["a.txt", "dir"])
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let reader = ZipReader::open(&zip_path).unwrap();
+        let mut headers = Vec::new();
+        for (index, entry) in reader.entries().iter().enumerate() {
+            let raw = reader.raw_central_header(index).unwrap();
+            let u16_at = |i: usize| u16::from_le_bytes(raw[i..i + 2].try_into().unwrap());
+            let u32_at = |i: usize| u32::from_le_bytes(raw[i..i + 4].try_into().unwrap());
+            assert_eq!(u32_at(0), zip_format::CENTRAL_DIR_HEADER_SIG);
+            assert_eq!(u16_at(4), entry.version_made_by);
+            assert_eq!(u16_at(8), entry.flags);
+            assert_eq!(u16_at(10), entry.compression_method);
+            assert_eq!((u16_at(12), u16_at(14)), (entry.mtime_dos, entry.mdate_dos));
+            assert_eq!(u32_at(16), entry.crc32);
+            assert_eq!(u32_at(20) as u64, entry.compressed_size);
+            assert_eq!(u32_at(24) as u64, entry.uncompressed_size);
+            assert_eq!(u32_at(38), entry.external_attr);
+            assert_eq!(u32_at(42) as u64, entry.local_header_offset);
+            let name_len = u16_at(28) as usize;
+            assert_eq!(&raw[46..46 + name_len], entry.name.as_bytes());
+            assert_eq!(&raw[46 + name_len..46 + name_len + u16_at(30) as usize], entry.extra.as_slice());
+            headers.push(raw);
+        }
+        assert!(reader.raw_central_header(headers.len()).is_err());
+
+        // 原样写回中央目录记录，得到与原文件相同的字节
+        let bytes = fs::read(&zip_path).unwrap();
+        let cd_offset = reader.get_append_offset() as usize;
+        let rebuilt = [bytes[..cd_offset].to_vec(), headers.concat(), reader.eocd_bytes().unwrap()].concat();
+        assert_eq!(rebuilt, bytes);
+    }
+
+    #[test]
+    fn test_zip64_data_descriptor() {
+        use crate::unzip::ZipArchive;
+        use crate::zip::ZipBuilder;
+
+        let tmp_dir = TempDir::new().unwrap();
+        let content = "streamed zip64 content\n".repeat(40);
+        fs::write(tmp_dir.path().join("s.txt"), &content).unwrap();
+        let zip_path = tmp_dir.path().join("test.zip");
+        ZipBuilder::new(&zip_path)
+            .unwrap()
+            .root(tmp_dir.path())
+            .files(&["s.txt"])
+            .unwrap()
+            .build()
+            .unwrap();
+        let reader = ZipReader::open(&zip_path).unwrap();
+        let entry = reader.entries()[0].clone();
+        let record = reader.raw_central_records().unwrap().remove(0);
+        let bytes = fs::read(&zip_path).unwrap();
+
+        let mut zip64 = vec![0x01, 0x00, 16, 0];
+        zip64.extend_from_slice(&entry.uncompressed_size.to_le_bytes());
+        zip64.extend_from_slice(&entry.compressed_size.to_le_bytes());
+
+        // 改写成 ZIP64 流式输出：本地头 bit 3、CRC 为 0、大小为占位值，带 ZIP64 字段；
+        // 数据之后是带签名、大小为 64 位的数据描述符
+        let extra_len = u16::from_le_bytes([bytes[28], bytes[29]]) as usize;
+        let header_len = 30 + 5 + extra_len;
+        let mut out = bytes[..header_len].to_vec();
+        out[6] |= 0x08;
+        out[14..18].fill(0);
+        out[18..26].fill(0xFF);
+        out[28..30].copy_from_slice(&((extra_len + 20) as u16).to_le_bytes());
+        out.extend_from_slice(&[0x01, 0x00, 16, 0]);
+        out.extend_from_slice(&[0u8; 16]);
+        out.extend_from_slice(&bytes[header_len..header_len + entry.compressed_size as usize]);
+        out.extend_from_slice(&0x08074b50u32.to_le_bytes());
+        out.extend_from_slice(&entry.crc32.to_le_bytes());
+        out.extend_from_slice(&entry.compressed_size.to_le_bytes());
+        out.extend_from_slice(&entry.uncompressed_size.to_le_bytes());
+
+        // 中央目录记录：大小为占位值，实际值在 ZIP64 字段中
+        let cd_offset = out.len();
+        let mut record = record;
+        record[8] |= 0x08;
+        record[20..28].fill(0xFF);
+        let cd_extra_len = u16::from_le_bytes([record[30], record[31]]) as usize;
+        record[30..32].copy_from_slice(&((cd_extra_len + zip64.len()) as u16).to_le_bytes());
+        record.splice(46 + 5 + cd_extra_len..46 + 5 + cd_extra_len, zip64);
+        out.extend_from_slice(&record);
+        let mut eocd = bytes[bytes.len() - 22..].to_vec();
+        eocd[12..16].copy_from_slice(&(record.len() as u32).to_le_bytes());
+        eocd[16..20].copy_from_slice(&(cd_offset as u32).to_le_bytes());
+        out.extend_from_slice(&eocd);
+        fs::write(&zip_path, &out).unwrap();
+
+        let reader = ZipReader::open(&zip_path).unwrap();
+        let parsed = &reader.entries()[0];
+        assert_eq!(parsed.compressed_size, entry.compressed_size);
+        assert_eq!(parsed.uncompressed_size, entry.uncompressed_size);
+        assert_eq!(
+            reader.data_descriptor(parsed).unwrap(),
+            Some(DataDescriptor {
+                crc32: entry.crc32,
+                compressed_size: entry.compressed_size,
+                uncompressed_size: entry.uncompressed_size,
+                len: 24,
+            })
+        );
+        assert_eq!(reader.entries_end_offset().unwrap(), cd_offset as u64);
+        let map = ZipArchive::open(&zip_path).unwrap().extract_to_map().unwrap();
+        assert_eq!(map["s.txt"], content.as_bytes());
+    }
+
+    #[test]
+    fn test_open_with_footer() {
+        use crate::unzip::ZipArchive;
+        use crate::zip::ZipBuilder;
+        use std::io::Cursor;
+
+        /// 记录读取次数的 Cursor，模拟每次读取都是一次范围请求
+        struct RangeReader {
+            inner: Cursor<Vec<u8>>,
+            reads: usize,
+        }
+        impl Read for RangeReader {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                self.reads += 1;
+                self.inner.read(buf)
+            }
+        }
+        impl Seek for RangeReader {
+            fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+                self.inner.seek(pos)
+            }
+        }
+
+        let tmp_dir = TempDir::new().unwrap();
+        fs::create_dir_all(tmp_dir.path().join("dir")).unwrap();
+        fs::write(tmp_dir.path().join("a.txt"), b"alpha\n").unwrap();
+        fs::write(tmp_dir.path().join("dir").join("b.txt"), "bravo ".repeat(500)).unwrap();
+        let zip_path = tmp_dir.path().join("test.zip");
+        ZipBuilder::new(&zip_path)
+            .unwrap()
+            .root(tmp_dir.path())
+            .files(&["a.txt", "dir"])
+            .unwrap()
+            .build()
+            .unwrap();
+        let bytes = fs::read(&zip_path).unwrap();
+        let expected = ZipArchive::open(&zip_path).unwrap().extract_to_map().unwrap();
+        let cd_offset = ZipReader::open(&zip_path).unwrap().get_append_offset() as usize;
+
+        let mut range = RangeReader {
+            inner: Cursor::new(bytes.clone()),
+            reads: 0,
+        };
+
+        // footer 覆盖中央目录：打开时不读取 reader
+        let reader = ZipReader::open_with_footer(&mut range, &bytes[cd_offset - 10..]).unwrap();
+        assert_eq!(range.reads, 0);
+        assert_eq!(reader.entries().len(), 3);
+        for entry in reader.entries().iter().filter(|entry| !entry.is_dir) {
+            let data = reader.read_entry_from(&mut range, entry).unwrap();
+            assert_eq!(data, expected[&entry.name]);
+        }
+        assert_eq!(range.reads, 4);
+
+        // footer 只有 EOCD：补读一次中央目录
+        range.reads = 0;
+        let reader = ZipReader::open_with_footer(&mut range, &bytes[bytes.len() - 22..]).unwrap();
+        assert_eq!(range.reads, 1);
+        assert_eq!(reader.entries().len(), 3);
+
+        assert!(ZipReader::open_with_footer(&mut range, &bytes[..10]).is_err());
+    }
 }
@@ -0,0 +1,221 @@
+//! 原子替换文件
+//!
+//! 先把新内容写入临时文件 `.{文件名}.{pid}.{序号}.tmp`，同步到磁盘后重命名为目标，
+//! 目标在任何时刻（包括崩溃或断电之后）都是旧文件或完整的新文件。
+
+use crate::error::{Result, ZipError};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// 临时文件名中的序号，每次生成路径时递增
+static TEMP_SEQUENCE: AtomicU64 = AtomicU64::new(0);
+
+/// `target` 对应的临时文件路径：位于 `dir`，未指定时与 `target` 同目录
+///
+/// 文件名带有进程号和进程内递增的序号，同一进程中并发替换同一目标，
+/// 或在共享的 `dir` 中替换同名目标时，各自得到不同的临时文件。
+pub(crate) fn temp_path(target: &Path, dir: Option<&Path>) -> PathBuf {
+    let file_name = target
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let sequence = TEMP_SEQUENCE.fetch_add(1, Ordering::Relaxed);
+    let temp_name = format!(".{}.{}.{}.tmp", file_name, std::process::id(), sequence);
+    match dir {
+        Some(dir) => dir.join(temp_name),
+        None => target.with_file_name(temp_name),
+    }
+}
+
+/// 以 `create_new` 创建空的临时文件并返回其路径，不会截断已有的文件
+///
+/// 名字已被占用（例如崩溃的进程留下的同名文件）时换下一个序号重试。
+fn create_temp(target: &Path, dir: Option<&Path>) -> std::io::Result<PathBuf> {
+    loop {
+        let path = temp_path(target, dir);
+        match fs::OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(_) => return Ok(path),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// 调用 `write` 写出临时文件，成功后用它替换 `target`
+///
+/// 临时文件位于 `temp_dir`，未指定时与目标同目录。任何一步失败都删除临时文件，目标保持不变。
+/// 重命名前对临时文件调用 `sync_all`，重命名后在 Unix 上同步目标所在目录，
+/// 否则崩溃后目标可能指向尚未落盘的空文件或截断的文件。
+pub(crate) fn replace_file<T>(
+    target: &Path,
+    temp_dir: Option<&Path>,
+    write: impl FnOnce(&Path) -> Result<T>,
+) -> Result<T> {
+    let temp = create_temp(target, temp_dir).map_err(|e| ZipError::OpenWriteFailed {
+        path: target.to_path_buf(),
+        source: e,
+    })?;
+    let result = write(&temp).and_then(|written| {
+        sync_file(&temp)
+            .and_then(|()| move_into_place(&temp, target, |from, to| fs::rename(from, to)))
+            .and_then(|()| sync_parent_dir(target))
+            .map_err(|e| ZipError::OpenWriteFailed {
+                path: target.to_path_buf(),
+                source: e,
+            })
+            .map(|()| written)
+    });
+    // 重命名成功时临时文件已不存在；失败时删除
+    let _ = fs::remove_file(&temp);
+    result
+}
+
+/// 把写好的临时文件 `temp` 重命名为 `target`
+///
+/// 临时文件与目标不在同一文件系统（`ErrorKind::CrossesDevices`）时，先复制到目标同目录下的
+/// 另一个临时文件再重命名，目标始终是旧文件或完整的新文件；其他错误直接返回。
+fn move_into_place(
+    temp: &Path,
+    target: &Path,
+    rename: impl Fn(&Path, &Path) -> std::io::Result<()>,
+) -> std::io::Result<()> {
+    match rename(temp, target) {
+        Err(e) if e.kind() == std::io::ErrorKind::CrossesDevices => {
+            let sibling = create_temp(target, None)?;
+            let result = fs::copy(temp, &sibling)
+                .and_then(|_| sync_file(&sibling))
+                .and_then(|()| rename(&sibling, target));
+            let _ = fs::remove_file(&sibling);
+            result
+        }
+        result => result,
+    }
+}
+
+/// 把文件内容和元数据同步到磁盘
+fn sync_file(path: &Path) -> std::io::Result<()> {
+    fs::OpenOptions::new().write(true).open(path)?.sync_all()
+}
+
+/// 同步 `path` 所在的目录，使其中的重命名在崩溃后依然可见
+#[cfg(unix)]
+fn sync_parent_dir(path: &Path) -> std::io::Result<()> {
+    let dir = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    fs::File::open(dir)?.sync_all()
+}
+
+/// 非 Unix 平台无法打开目录进行同步，重命名的持久性由文件系统保证
+#[cfg(not(unix))]
+fn sync_parent_dir(_path: &Path) -> std::io::Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_move_into_place_across_devices() {
+        use std::cell::Cell;
+        use std::io::{Error, ErrorKind};
+
+        let temp = TempDir::new().unwrap();
+        let staged = temp.path().join("staged.zip");
+        let target = temp.path().join("out").join("out.zip");
+        fs::create_dir_all(target.parent().unwrap()).unwrap();
+        fs::write(&staged, b"new").unwrap();
+        fs::write(&target, b"old").unwrap();
+
+        // 只有从 temp_dir 直接重命名会跨设备失败；目标目录内的重命名照常进行
+        let calls = Cell::new(0);
+        let rename = |from: &Path, to: &Path| {
+            calls.set(calls.get() + 1);
+            if from == staged {
+                return Err(Error::from(ErrorKind::CrossesDevices));
+            }
+            assert_eq!(from.parent(), to.parent());
+            assert_eq!(fs::read(to).unwrap(), b"old");
+            fs::rename(from, to)
+        };
+        move_into_place(&staged, &target, rename).unwrap();
+        assert_eq!(calls.get(), 2);
+        assert_eq!(fs::read(&target).unwrap(), b"new");
+        assert_eq!(fs::read_dir(target.parent().unwrap()).unwrap().count(), 1);
+
+        // 其他错误不退回到复制，目标保持不变
+        let err = move_into_place(&staged, &target, |_, _| Err(Error::from(ErrorKind::PermissionDenied))).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::PermissionDenied);
+        assert_eq!(fs::read(&target).unwrap(), b"new");
+    }
+
+    #[test]
+    fn test_replace_file_keeps_target_on_error() {
+        let temp = TempDir::new().unwrap();
+        let target = temp.path().join("out.zip");
+        fs::write(&target, b"old").unwrap();
+
+        let err = replace_file(&target, None, |path| {
+            fs::write(path, b"partial").unwrap();
+            Err::<(), _>(ZipError::generic("interrupted"))
+        })
+        .unwrap_err();
+        assert!(err.to_string().contains("interrupted"), "{}", err);
+        assert_eq!(fs::read(&target).unwrap(), b"old");
+        assert_eq!(fs::read_dir(temp.path()).unwrap().count(), 1);
+
+        let len = replace_file(&target, None, |path| {
+            assert_eq!(path.parent(), Some(temp.path()));
+            assert!(path.file_name().unwrap().to_string_lossy().starts_with(".out.zip."));
+            fs::write(path, b"new").unwrap();
+            Ok(3)
+        })
+        .unwrap();
+        assert_eq!(len, 3);
+        assert_eq!(fs::read(&target).unwrap(), b"new");
+        assert_eq!(fs::read_dir(temp.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_concurrent_replacements_use_distinct_temp_files() {
+        use std::sync::Barrier;
+
+        let temp = TempDir::new().unwrap();
+        let scratch = temp.path().join("scratch");
+        fs::create_dir_all(&scratch).unwrap();
+        let targets = [temp.path().join("a").join("out.zip"), temp.path().join("b").join("out.zip")];
+        for target in &targets {
+            fs::create_dir_all(target.parent().unwrap()).unwrap();
+        }
+
+        // 同一目标和共享 temp_dir 中的同名目标：两个写入同时进行，互不截断
+        let barrier = Barrier::new(2);
+        for (first, second) in [(&targets[0], &targets[0]), (&targets[0], &targets[1])] {
+            std::thread::scope(|scope| {
+                for (target, content) in [(first, b"first ".repeat(1000)), (second, b"second".repeat(1000))] {
+                    let barrier = &barrier;
+                    let scratch = &scratch;
+                    scope.spawn(move || {
+                        replace_file(target, Some(scratch), |path| {
+                            let mut file = fs::OpenOptions::new().write(true).open(path).unwrap();
+                            std::io::Write::write_all(&mut file, &content[..content.len() / 2]).unwrap();
+                            barrier.wait();
+                            std::io::Write::write_all(&mut file, &content[content.len() / 2..]).unwrap();
+                            Ok(())
+                        })
+                        .unwrap();
+                    });
+                }
+            });
+            for target in [first, second] {
+                let data = fs::read(target).unwrap();
+                assert!(data == b"first ".repeat(1000) || data == b"second".repeat(1000));
+            }
+        }
+        assert_eq!(fs::read_dir(&scratch).unwrap().count(), 0);
+    }
+}
@@ -1,23 +1,46 @@
 //! 纯 Rust ZIP Writer 实现
 //! 完全复刻 C 版本 zip.c 和 miniz.c 的行为
 
-use crate::error::{CompressionLevel, Result, ZipError};
-use crate::miniz::deflate::compress_raw;
+use crate::cancel::CancelToken;
+use crate::error::{Compat, CompressionLevel, Encoding, Encryption, PreserveSet, Result, ZipError};
+use crate::miniz::deflate::{
+    compress_raw_segment_with, compress_raw_with, level_from_i32, stored_blocks, DeflateEncoder,
+    DeflateError, DeflateImpl, DeflateOptions,
+};
 use crate::miniz::crc32::crc32;
-use crate::zip::reader::ZipReader;
+use crate::unzip::normalize_entry_name;
+#[cfg(feature = "aes")]
+use crate::zip::aes;
+use crate::zip::cp437;
+use crate::zip::crypto;
+use crate::zip::extra;
+use crate::zip::reader::{ZipEntryInfo, ZipReader};
+use std::collections::HashMap;
 use std::fs::{File, Metadata, OpenOptions};
-use std::io::{BufWriter, Seek, SeekFrom, Write};
+use std::io::{BufWriter, Read, Seek, SeekFrom, Sink, Write};
 use std::path::{Path, PathBuf};
 use std::time::SystemTime;
 
 #[cfg(unix)]
 use std::os::unix::fs::PermissionsExt;
 
+/// 读取源文件时的块大小
+const READ_CHUNK_SIZE: usize = 1024 * 1024;
+
+/// 输出 `BufWriter` 的默认容量，与标准库默认值相同
+pub(crate) const DEFAULT_WRITE_BUFFER_SIZE: usize = 8 * 1024;
+
 /// ZIP 文件条目信息（对应中央目录）
 #[derive(Debug, Clone)]
 struct ZipEntry {
     /// 文件名（在 ZIP 内）
     name: String,
+    /// 按输出编码编码后的文件名字节
+    name_bytes: Vec<u8>,
+    /// 通用标志位
+    flags: u16,
+    /// 压缩方法
+    method: u16,
     /// 压缩前大小
     uncompressed_size: u64,
     /// 压缩后大小
@@ -26,29 +49,93 @@ struct ZipEntry {
     crc32: u32,
     /// 本地文件头偏移量
     local_header_offset: u64,
-    /// 是否为目录
-    is_dir: bool,
     /// 修改时间（DOS 时间格式）
     mtime_dos: u16,
     /// 修改日期（DOS 日期格式）
     mdate_dos: u16,
     /// Unix 权限（如果适用）
     external_attr: u32,
+    /// extra field（本地头与中央目录共用）
+    extra: Vec<u8>,
+}
+
+/// 已压缩好的条目：数据按 `method` 原样写入，不再压缩或校验
+///
+/// 用于缓存压缩结果的场景，调用方完全控制条目的元数据。
+#[derive(Debug, Clone)]
+pub struct PrecompressedEntry {
+    /// 条目名；以 `/` 结尾时作为目录条目
+    pub name: String,
+    /// 压缩方法（0=store, 8=deflate）
+    pub method: u16,
+    /// 压缩后的数据
+    pub data: Vec<u8>,
+    /// 压缩前大小
+    pub uncompressed_size: u64,
+    /// 压缩前数据的 CRC32
+    pub crc32: u32,
+    /// 修改时间，按本地时间换算为 DOS 时间
+    pub mtime: SystemTime,
+    /// Unix 模式，写入 external_attr 的高 16 位（如 `0o644`，可带文件类型位）
+    pub mode: u32,
+    /// extra field，本地头与中央目录共用；为空时不写
+    pub extra: Vec<u8>,
+}
+
+/// 已写入条目的摘要，与中央目录记录一致
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntrySummary {
+    /// 条目名，目录以 `/` 结尾
+    pub name: String,
+    /// 压缩方法（0=store, 8=deflate, 99=AES）
+    pub method: u16,
+    /// 压缩后大小（加密条目含加密头）
+    pub compressed: u64,
+    /// 压缩前大小
+    pub uncompressed: u64,
+    /// 压缩前数据的 CRC32
+    pub crc32: u32,
 }
 
 /// 纯 Rust ZIP Writer
 /// 对应 C 版本的 mz_zip_archive + zip_zip() 逻辑
-pub struct ZipWriter {
+pub struct ZipWriter<W: Write = File> {
     /// ZIP 文件路径
     path: PathBuf,
     /// BufWriter 用于高效写入
-    writer: BufWriter<File>,
+    writer: BufWriter<W>,
+    /// 当前写入位置（归档内的绝对偏移），写出时累加，不依赖输出可定位
+    position: u64,
+    /// 输出不可定位：文件条目边读边压缩，大小和 CRC32 写在数据描述符中
+    streaming: bool,
     /// 所有已添加的条目（用于写入中央目录）
     entries: Vec<ZipEntry>,
     /// 是否已 finalized
     finalized: bool,
     /// 压缩级别
     compression_level: CompressionLevel,
+    /// 取消令牌（读取大文件时按块检查）
+    cancel: Option<CancelToken>,
+    /// 是否写入高精度时间（0x000a NTFS + 0x5455 扩展时间戳）
+    high_res_time: bool,
+    /// 文件名编码
+    filename_encoding: Encoding,
+    /// DEFLATE 压缩实现
+    algorithm: DeflateImpl,
+    /// 内容已是压缩格式（按魔数识别）时直接存储
+    smart_store: bool,
+    /// 模仿的参考工具的头部约定
+    compat: Compat,
+    /// 文件条目的加密方式
+    encryption: Option<Encryption>,
+    /// DEFLATE 压缩器，首次压缩时创建并在条目之间复用
+    encoder: Option<DeflateEncoder>,
+    /// 启用内容去重时，按 (CRC32, 大小) 记录已写出条目的内容和下标
+    dedup: Option<HashMap<(u32, u64), Vec<(Vec<u8>, usize)>>>,
+    /// 归档总大小需要对齐到的倍数，用 EOCD 注释中的零字节填充
+    pad_to: Option<u64>,
+    /// 要保存的文件元数据；`None` 时沿用由 `compat` 决定的原有输出
+    preserve: Option<PreserveSet>,
 }
 
 /// ZIP 文件格式常量（对应 miniz.c:3061-3149）
@@ -65,86 +152,98 @@ mod zip_format {
 
     // 版本
     pub const VERSION_NEEDED: u16 = 20; // 2.0（兼容大多数工具）
+    pub const VERSION_NEEDED_STORE: u16 = 10; // 1.0：存储条目和目录
+    pub const VERSION_NEEDED_AES: u16 = 51; // 5.1：WinZip AES 加密
     pub const VERSION_MADE_BY: u16 = 0x0317; // Unix (3) + 2.3 (23)
+    pub const VERSION_MADE_BY_INFOZIP: u16 = 0x031E; // Unix (3) + 3.0 (30)
+    pub const VERSION_MADE_BY_PKZIP: u16 = 0x0014; // MS-DOS (0) + 2.0 (20)
 
     // 压缩方法
     pub const METHOD_STORE: u16 = 0; // 无压缩
     pub const METHOD_DEFLATE: u16 = 8; // DEFLATE 压缩
+    pub const METHOD_AES: u16 = 99; // WinZip AES 加密，实际方法在 0x9901 字段中
 
     // DOS 目录属性标志
     pub const DOS_DIR_ATTR: u32 = 0x10;
+
+    // 通用标志位：条目已加密
+    pub const FLAG_ENCRYPTED: u16 = 0x0001;
+    // 通用标志位：文件名和注释为 UTF-8
+    pub const FLAG_UTF8: u16 = 0x0800;
+    // 通用标志位：CRC 和大小写在数据之后的数据描述符中
+    pub const FLAG_DATA_DESCRIPTOR: u16 = 0x0008;
+    // 数据描述符签名
+    pub const DATA_DESCRIPTOR_SIG: u32 = 0x08074b50;
 }
 
-/// 从文件 metadata 计算 external_attr
+/// 数据开头是否为常见压缩格式的魔数
+fn is_already_compressed(data: &[u8]) -> bool {
+    const MAGICS: [&[u8]; 4] = [
+        &[0x1f, 0x8b],                         // gzip
+        b"PK\x03\x04",                          // zip
+        &[0x28, 0xb5, 0x2f, 0xfd],             // zstd
+        &[0xfd, b'7', b'z', b'X', b'Z', 0x00], // xz
+    ];
+    // bzip2："BZh" 加上 1-9 的块大小
+    let bzip2 = data.len() >= 4 && data.starts_with(b"BZh") && (b'1'..=b'9').contains(&data[3]);
+    bzip2 || MAGICS.iter().any(|magic| data.starts_with(magic))
+}
+
+/// 没有文件元数据时文件条目的 external_attr：0644
+const DEFAULT_FILE_ATTR: u32 = 0o644 << 16;
+
+/// 没有文件元数据时目录条目的 external_attr：0755 + DOS 目录属性
+const DEFAULT_DIR_ATTR: u32 = (0o755 << 16) | zip_format::DOS_DIR_ATTR;
+
+/// 符号链接条目的 external_attr：`S_IFLNK | 0777`
+const SYMLINK_ATTR: u32 = 0o120777 << 16;
+
+/// 把 Unix 模式转换为 external_attr
 /// 对应 C 版本 zip.c:93-94 的权限处理
 ///
 /// C 版本逻辑：
 ///   external_attr &= 0x0000FFFF;
 ///   external_attr |= (st.st_mode & 0777) << 16;
 ///
-/// Unix 权限存储在 external_attr 的高16位（bit 16-31）
-/// 低16位保留给 DOS 属性
-fn compute_external_attr(metadata: &Metadata, is_dir: bool) -> u32 {
-    // 默认值（Windows 或无法获取权限时）
-    let default_attr = if is_dir {
-        zip_format::DOS_DIR_ATTR
+/// Unix 权限存储在 external_attr 的高16位（bit 16-31），只保留 0777 权限位（setuid 等特殊位不写入）；
+/// 低16位保留给 DOS 属性，目录设置 `0x10`。与 [`external_attr_to_mode`](crate::unzip::external_attr_to_mode) 互逆。
+pub fn mode_to_external_attr(mode: u32, is_dir: bool) -> u32 {
+    let external_attr = (mode & 0o777) << 16;
+    if is_dir {
+        external_attr | zip_format::DOS_DIR_ATTR
     } else {
-        0
-    };
+        external_attr
+    }
+}
 
+/// 从文件 metadata 计算 external_attr
+fn compute_external_attr(metadata: &Metadata, is_dir: bool) -> u32 {
     #[cfg(unix)]
     {
-        // 提取 Unix 权限（st.st_mode & 0777）
-        let mode = metadata.permissions().mode() & 0o777;
-
-        // 高16位存储 Unix 权限，低16位保留 DOS 属性
-        let mut external_attr: u32 = (mode as u32) << 16;
-
-        // 设置 DOS 目录属性（如果需要）
-        if is_dir {
-            external_attr |= zip_format::DOS_DIR_ATTR;
-        }
-
-        external_attr
+        mode_to_external_attr(metadata.permissions().mode(), is_dir)
     }
 
     #[cfg(not(unix))]
     {
-        // 非 Unix 系统使用默认值
-        default_attr
+        // 非 Unix 系统没有权限位，只保留 DOS 目录属性
+        let _ = metadata;
+        mode_to_external_attr(0, is_dir)
     }
 }
 
-impl ZipWriter {
-    /// 验证ZIP文件名
-    /// 对应 C 版本的 mz_zip_writer_validate_archive_name()
-    ///
-    /// 规则：
-    /// 1. 不能以 '/' 开头（绝对路径）
-    /// 2. 不能包含反斜杠 '\'（DOS风格路径分隔符）
-    fn validate_archive_name(name: &str) -> Result<()> {
-        // 规则1: 不能以 '/' 开头
-        if name.starts_with('/') {
-            return Err(ZipError::generic(&format!(
-                "Invalid filename: cannot start with '/': '{}'",
-                name
-            )));
-        }
-
-        // 规则2: 不能包含反斜杠
-        if name.contains('\\') {
-            return Err(ZipError::generic(&format!(
-                "Invalid filename: cannot contain backslash: '{}'",
-                name
-            )));
-        }
-
-        Ok(())
-    }
-
+impl ZipWriter<File> {
     /// 创建新的 ZIP writer
     /// 对应 C 版本的 mz_zip_writer_init_cfile()
     pub fn new(path: impl AsRef<Path>, compression_level: CompressionLevel) -> Result<Self> {
+        Self::create_buffered(path, compression_level, DEFAULT_WRITE_BUFFER_SIZE)
+    }
+
+    /// 与 `new` 相同，`BufWriter` 的容量为 `buffer_size` 字节
+    pub(crate) fn create_buffered(
+        path: impl AsRef<Path>,
+        compression_level: CompressionLevel,
+        buffer_size: usize,
+    ) -> Result<Self> {
         let path = path.as_ref().to_path_buf();
 
         // 打开文件进行写入
@@ -153,13 +252,13 @@ impl ZipWriter {
             source: e,
         })?;
 
-        Ok(Self {
+        Ok(Self::from_parts(
             path,
-            writer: BufWriter::new(file),
-            entries: Vec::new(),
-            finalized: false,
+            BufWriter::with_capacity(buffer_size, file),
+            0,
+            Vec::new(),
             compression_level,
-        })
+        ))
     }
 
     /// 创建追加模式的 ZIP writer
@@ -171,6 +270,15 @@ impl ZipWriter {
     pub fn new_with_append(
         path: impl AsRef<Path>,
         compression_level: CompressionLevel,
+    ) -> Result<Self> {
+        Self::append_buffered(path, compression_level, DEFAULT_WRITE_BUFFER_SIZE)
+    }
+
+    /// 与 `new_with_append` 相同，`BufWriter` 的容量为 `buffer_size` 字节
+    pub(crate) fn append_buffered(
+        path: impl AsRef<Path>,
+        compression_level: CompressionLevel,
+        buffer_size: usize,
     ) -> Result<Self> {
         let path = path.as_ref().to_path_buf();
 
@@ -183,20 +291,21 @@ impl ZipWriter {
 
         // 2. 获取追加位置（中央目录之前的偏移）
         // 对应 C 版本：writer 从已有数据之后继续
+        // 条目数据与中央目录之间的空隙会被保留（新条目写在空隙之后），
+        // 但 APK 签名块在追加后必然失效，因此直接拒绝
         let append_offset = reader.get_append_offset();
+        if reader.has_apk_signing_block()? {
+            return Err(ZipError::OpenAppendFailed {
+                path: path.clone(),
+                source: std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    "archive contains an APK signing block; appending would invalidate the signature",
+                ),
+            });
+        }
 
         // 3. 转换 ZipEntryInfo 到内部 ZipEntry 格式
-        let existing_entries: Vec<ZipEntry> = reader.entries().iter().map(|info| ZipEntry {
-            name: info.name.clone(),
-            uncompressed_size: info.uncompressed_size,
-            compressed_size: info.compressed_size,
-            crc32: info.crc32,
-            local_header_offset: info.local_header_offset,
-            is_dir: info.is_dir,
-            mtime_dos: 0, // 时间信息不保存，重新读取时为 0
-            mdate_dos: 0,
-            external_attr: info.external_attr,
-        }).collect();
+        let existing_entries = existing_entries(&reader);
 
         // 4. 打开文件进行追加（不截断）
         // 对应 C 版本：以追加模式打开文件
@@ -208,7 +317,7 @@ impl ZipWriter {
                 source: e,
             })?;
 
-        let mut writer = BufWriter::new(file);
+        let mut writer = BufWriter::with_capacity(buffer_size, file);
 
         // 5. 定位到追加位置（中央目录之前）
         // 对应 C 版本：覆盖旧的中央目录和 EOCD
@@ -216,13 +325,325 @@ impl ZipWriter {
             ZipError::generic(&format!("Failed to seek to append position: {:?}", e))
         })?;
 
-        Ok(Self {
+        // 保留已有条目
+        Ok(Self::from_parts(path, writer, append_offset, existing_entries, compression_level))
+    }
+}
+
+impl<W: Write> ZipWriter<W> {
+    /// 创建写到不可定位输出（如标准输出、管道、套接字）的 ZIP writer
+    ///
+    /// 文件条目按块读取、压缩并立即写出，内存占用与条目大小无关：本地文件头设置
+    /// 通用标志位 bit 3，CRC32 和大小写 0，实际值写在数据之后的数据描述符中；
+    /// `finalize` 在当前位置依次写出中央目录和 EOCD，整个过程只向前写，不回退定位。
+    ///
+    /// 加密条目和启用去重时仍先读入整个条目（需要完整内容），但同样只向前写。
+    pub fn streaming(output: W, compression_level: CompressionLevel) -> Self {
+        let mut writer = Self::from_parts(
+            PathBuf::from("-"),
+            BufWriter::new(output),
+            0,
+            Vec::new(),
+            compression_level,
+        );
+        writer.streaming = true;
+        writer
+    }
+}
+
+impl ZipWriter<Sink> {
+    /// 创建只统计输出大小、不写任何文件的 writer，用于估算归档大小
+    /// `append` 时从已有归档的追加位置开始，并保留已有条目
+    pub(crate) fn counting(
+        path: impl AsRef<Path>,
+        compression_level: CompressionLevel,
+        append: bool,
+    ) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let mut position = 0;
+        let mut entries = Vec::new();
+        if append {
+            let reader = ZipReader::open(&path)?;
+            position = reader.get_append_offset();
+            entries = existing_entries(&reader);
+        }
+        Ok(Self::from_parts(path, BufWriter::new(std::io::sink()), position, entries, compression_level))
+    }
+
+    /// 目前为止输出的总字节数；finalize 之后即归档大小
+    pub(crate) fn written_len(&self) -> u64 {
+        self.position
+    }
+}
+
+impl<W: Write> ZipWriter<W> {
+    /// 用给定的输出、起始写入位置和已有条目构造 writer，其余设置取默认值
+    fn from_parts(
+        path: PathBuf,
+        writer: BufWriter<W>,
+        position: u64,
+        entries: Vec<ZipEntry>,
+        compression_level: CompressionLevel,
+    ) -> Self {
+        Self {
             path,
             writer,
-            entries: existing_entries,  // ✅ 保留已有条目
+            position,
+            streaming: false,
+            entries,
             finalized: false,
             compression_level,
-        })
+            cancel: None,
+            high_res_time: false,
+            filename_encoding: Encoding::Utf8,
+            algorithm: DeflateImpl::Fast,
+            smart_store: false,
+            compat: Compat::Miniz,
+            encryption: None,
+            encoder: None,
+            dedup: None,
+            pad_to: None,
+            preserve: None,
+        }
+    }
+
+    /// 验证ZIP文件名
+    /// 对应 C 版本的 mz_zip_writer_validate_archive_name()
+    ///
+    /// 规则：
+    /// 1. 不能以 '/' 开头（绝对路径）
+    /// 2. 不能包含反斜杠 '\'（DOS风格路径分隔符）
+    fn validate_archive_name(name: &str) -> Result<()> {
+        // 规则1: 不能以 '/' 开头
+        if name.starts_with('/') {
+            return Err(ZipError::generic(&format!(
+                "Invalid filename: cannot start with '/': '{}'",
+                name
+            )));
+        }
+
+        // 规则2: 不能包含反斜杠
+        if name.contains('\\') {
+            return Err(ZipError::generic(&format!(
+                "Invalid filename: cannot contain backslash: '{}'",
+                name
+            )));
+        }
+
+        Ok(())
+    }
+
+
+    /// 设置取消令牌
+    /// 读取大文件时在每个块之间检查，被取消时返回 `ZipError::Cancelled`
+    pub fn set_cancel_token(&mut self, cancel: Option<CancelToken>) {
+        self.cancel = cancel;
+    }
+
+    /// 设置是否写入高精度时间
+    /// 启用后为每个条目写入 `0x000a` NTFS 时间（100 ns 精度）和 `0x5455` 扩展时间戳
+    pub fn set_high_res_time(&mut self, high_res_time: bool) {
+        self.high_res_time = high_res_time;
+    }
+
+    /// 设置文件名编码
+    pub fn set_filename_encoding(&mut self, encoding: Encoding) {
+        self.filename_encoding = encoding;
+    }
+
+    /// 设置 DEFLATE 压缩实现，对之后添加的条目生效
+    pub fn set_algorithm(&mut self, algorithm: DeflateImpl) {
+        if self.algorithm != algorithm {
+            self.algorithm = algorithm;
+            self.encoder = None;
+        }
+    }
+
+    /// 设置是否对已压缩的内容（gzip、zip、zstd、xz、bzip2）直接存储
+    /// 按文件开头的魔数识别，命中时使用 STORE 而不是 DEFLATE
+    pub fn set_smart_store(&mut self, smart_store: bool) {
+        self.smart_store = smart_store;
+    }
+
+    /// 设置是否对内容完全相同的文件条目去重
+    ///
+    /// 启用后按 CRC32 + 大小找候选、再逐字节比较，内容相同的条目只写一份数据，
+    /// 多个中央目录记录指向同一个本地文件头。这种布局不常见但符合规范；
+    /// 本地头中的文件名是第一个条目的名字。加密条目不去重。
+    /// 需要在内存中保留已写出文件的内容
+    pub fn set_dedup(&mut self, dedup: bool) {
+        self.dedup = dedup.then(HashMap::new);
+    }
+
+    /// 设置模仿的参考工具，影响 version made by / version needed、extra field 和外部属性
+    /// 对之后添加的条目生效
+    pub fn set_compat(&mut self, compat: Compat) {
+        self.compat = compat;
+    }
+
+    /// 设置归档总大小对齐的倍数，`None`、0 或 1 表示不填充
+    ///
+    /// finalize 时在 EOCD 注释中写入零字节，使文件大小为 `multiple` 的整数倍；
+    /// 零字节中不会出现 EOCD 签名，不影响读取方定位 EOCD。
+    /// 注释最长 65535 字节，需要更多填充时 finalize 返回错误。
+    pub fn set_pad_to(&mut self, multiple: Option<u64>) {
+        self.pad_to = multiple.filter(|&multiple| multiple > 1);
+    }
+
+    /// 设置要保存的文件元数据，对之后添加的条目生效
+    ///
+    /// `None`（默认）保持原有输出：权限和修改时间总是写入，`UT`/`ux` 等 extra field
+    /// 由 `compat` 和 `high_res_time` 决定，符号链接被跟随。设置后只写入所选的部分：
+    /// 未选 `PERMISSIONS` 时权限取 0644/0755，未选 `MTIME` 时 DOS 时间为最小值且不写时间字段，
+    /// 选了 `OWNERSHIP` 时写入 `ux`，选了 `SYMLINKS` 时文件符号链接按链接本身存储。
+    /// 启用 `xattr` feature 时，选了 `XATTR` 会把文件的扩展属性写入中央目录中
+    /// 本库私有的 `0x5a52` 字段（非标准扩展，其他工具不会还原）。
+    /// `Compat::Pkzip` 下仍然不写任何 extra field。
+    pub fn set_preserve(&mut self, preserve: Option<PreserveSet>) {
+        self.preserve = preserve;
+    }
+
+    /// 是否保存 `item` 对应的元数据；未设置 `preserve` 时权限和修改时间总是保存
+    fn preserves(&self, item: PreserveSet) -> bool {
+        self.preserve.map_or(true, |set| set.contains(item))
+    }
+
+    /// 文件条目的 external_attr：未保存权限或没有元数据时为 0644
+    fn file_attr(&self, metadata: Option<&Metadata>) -> u32 {
+        metadata
+            .filter(|_| self.preserves(PreserveSet::PERMISSIONS))
+            .map(|metadata| compute_external_attr(metadata, false))
+            .unwrap_or(DEFAULT_FILE_ATTR)
+    }
+
+    /// 设置文件条目的加密方式，对之后添加的条目生效；目录条目不加密
+    pub fn set_encryption(&mut self, encryption: Option<Encryption>) {
+        self.encryption = encryption;
+    }
+
+    /// 中央目录中的 version made by
+    fn version_made_by(&self) -> u16 {
+        match self.compat {
+            Compat::Miniz => zip_format::VERSION_MADE_BY,
+            Compat::InfoZip => zip_format::VERSION_MADE_BY_INFOZIP,
+            Compat::Pkzip => zip_format::VERSION_MADE_BY_PKZIP,
+        }
+    }
+
+    /// 条目的 version needed to extract
+    fn version_needed(&self, entry: &ZipEntry) -> u16 {
+        match self.compat {
+            // AES 加密需要 5.1
+            _ if entry.method == zip_format::METHOD_AES => zip_format::VERSION_NEEDED_AES,
+            Compat::Miniz => zip_format::VERSION_NEEDED,
+            // 传统加密需要 2.0
+            _ if entry.flags & zip_format::FLAG_ENCRYPTED != 0 => zip_format::VERSION_NEEDED,
+            Compat::InfoZip | Compat::Pkzip if entry.method == zip_format::METHOD_STORE => {
+                zip_format::VERSION_NEEDED_STORE
+            }
+            Compat::InfoZip | Compat::Pkzip => zip_format::VERSION_NEEDED,
+        }
+    }
+
+    /// 中央目录中的外部属性；PKZIP 只保留低 16 位的 DOS 属性
+    fn central_external_attr(&self, entry: &ZipEntry) -> u32 {
+        match self.compat {
+            Compat::Pkzip => entry.external_attr & 0xFFFF,
+            Compat::Miniz | Compat::InfoZip => entry.external_attr,
+        }
+    }
+
+    /// 按输出编码编码文件名，返回 (文件名字节, 通用标志位)
+    /// UTF-8 下非 ASCII 文件名设置 bit 11；CP437 下 bit 11 始终清零
+    fn encode_name(&self, name: &str) -> Result<(Vec<u8>, u16)> {
+        match self.filename_encoding {
+            Encoding::Utf8 => {
+                let flags = if name.is_ascii() { 0 } else { zip_format::FLAG_UTF8 };
+                Ok((name.as_bytes().to_vec(), flags))
+            }
+            Encoding::Cp437 => cp437::encode(name)
+                .map(|bytes| (bytes, 0))
+                .ok_or_else(|| ZipError::InvalidPath {
+                    path: name.to_string(),
+                    reason: "file name cannot be represented in CP437".to_string(),
+                }),
+        }
+    }
+
+    /// 复用的压缩器，首次使用时按当前级别和算法创建
+    fn encoder(&mut self) -> Result<&mut DeflateEncoder> {
+        if self.encoder.is_none() {
+            let options = DeflateOptions {
+                level: level_from_i32(self.compression_level.as_u8() as i32)
+                    .map_err(compression_failed)?,
+                window_bits: 15,
+                algorithm: self.algorithm,
+                ..Default::default()
+            };
+            self.encoder = Some(DeflateEncoder::new(options).map_err(compression_failed)?);
+        }
+        Ok(self.encoder.as_mut().unwrap())
+    }
+
+    /// 使用复用的压缩器做原始 DEFLATE 压缩
+    fn deflate(&mut self, data: &[u8]) -> Result<Vec<u8>> {
+        compress_raw_with(self.encoder()?, data).map_err(compression_failed)
+    }
+
+    /// 根据元数据构造 extra field
+    ///
+    /// 默认只在启用高精度时间时写入 NTFS + 扩展时间戳；`Compat::InfoZip` 总是先写
+    /// `UT` 和 `ux`（与 Info-ZIP 的顺序一致），`Compat::Pkzip` 不写任何 extra field。
+    /// 设置了 `preserve` 时改为按所选的元数据写入。
+    fn time_extra(&self, metadata: Option<&Metadata>) -> Vec<u8> {
+        let mut extra = Vec::new();
+        if self.compat == Compat::Pkzip {
+            return extra;
+        }
+        if let Some(preserve) = self.preserve {
+            if let Some(mtime) = metadata
+                .filter(|_| preserve.contains(PreserveSet::MTIME))
+                .and_then(|m| m.modified().ok())
+            {
+                if self.high_res_time {
+                    let atime = metadata.and_then(|m| m.accessed().ok()).unwrap_or(mtime);
+                    let ctime = metadata.and_then(|m| m.created().ok()).unwrap_or(mtime);
+                    extra::push_ntfs_times(&mut extra, mtime, atime, ctime);
+                }
+                extra::push_extended_timestamp(&mut extra, mtime);
+            }
+            #[cfg(unix)]
+            if let Some(metadata) = metadata.filter(|_| preserve.contains(PreserveSet::OWNERSHIP)) {
+                use std::os::unix::fs::MetadataExt;
+                extra::push_unix_owner(&mut extra, metadata.uid(), metadata.gid());
+            }
+            return extra;
+        }
+        let Some(mtime) = metadata.and_then(|m| m.modified().ok()) else {
+            return extra;
+        };
+        if self.compat == Compat::InfoZip {
+            extra::push_extended_timestamp(&mut extra, mtime);
+            #[cfg(unix)]
+            if let Some(metadata) = metadata {
+                use std::os::unix::fs::MetadataExt;
+                extra::push_unix_owner(&mut extra, metadata.uid(), metadata.gid());
+            }
+            if self.high_res_time {
+                let atime = metadata.and_then(|m| m.accessed().ok()).unwrap_or(mtime);
+                let ctime = metadata.and_then(|m| m.created().ok()).unwrap_or(mtime);
+                extra::push_ntfs_times(&mut extra, mtime, atime, ctime);
+            }
+            return extra;
+        }
+        if !self.high_res_time {
+            return extra;
+        }
+        let atime = metadata.and_then(|m| m.accessed().ok()).unwrap_or(mtime);
+        let ctime = metadata.and_then(|m| m.created().ok()).unwrap_or(mtime);
+        extra::push_ntfs_times(&mut extra, mtime, atime, ctime);
+        extra::push_extended_timestamp(&mut extra, mtime);
+        extra
     }
 
     /// 添加一个文件到 ZIP
@@ -233,11 +654,27 @@ impl ZipWriter {
     /// 2. zip_file_size() - 获取文件大小
     /// 3. mz_zip_writer_add_cfile() - 添加到 ZIP
     /// 4. fclose() - 关闭源文件
+    /// 添加文件条目，条目名先经 [`normalize_entry_name`] 规范化
     pub fn add_file(&mut self, name: &str, source_path: &Path) -> Result<()> {
+        let name = normalize_entry_name(name)?;
+        self.add_file_as_is(&name, source_path)
+    }
+
+    /// 添加文件条目，条目名按 C 版本的规则只校验、不规范化
+    /// `ZipBuilder` 使用这个入口，以保留 `../` 等与 C 版本一致的相对路径
+    pub(crate) fn add_file_as_is(&mut self, name: &str, source_path: &Path) -> Result<()> {
         // 对应 C 版本：mz_zip_writer_validate_archive_name() (miniz.c:6349)
         // 验证文件名：不能以/开头，不能包含反斜杠
         Self::validate_archive_name(name)?;
 
+        if self.preserve.is_some_and(|set| set.contains(PreserveSet::SYMLINKS)) {
+            if let Ok(link) = std::fs::symlink_metadata(source_path) {
+                if link.file_type().is_symlink() {
+                    return self.add_symlink(name, source_path, &link);
+                }
+            }
+        }
+
         // 对应 C 版本：FILE* fh = zip_open_utf8(filename, ZIP__READ, ...)
         // 完全复刻 C 版本的错误检测：在实际打开文件时检测
         let mut source_file = File::open(source_path).map_err(|e| {
@@ -252,27 +689,237 @@ impl ZipWriter {
         let metadata = source_file.metadata().map_err(|e| ZipError::FileSizeFailed {
             path: source_path.to_path_buf(),
         })?;
-        let uncompressed_size = metadata.len();
 
-        // 读取文件内容
-        let mut buffer = Vec::with_capacity(uncompressed_size as usize);
-        std::io::copy(&mut source_file, &mut buffer).map_err(|e| ZipError::generic(&format!(
-            "Failed to read file {}: {:?}",
-            source_path.display(),
-            e
-        )))?;
+        let what = source_path.display().to_string();
+        if self.streams_entries() {
+            self.add_stream(name, &mut source_file, Some(&metadata), &what)?;
+        } else {
+            let buffer = self.read_source(&mut source_file, metadata.len() as usize, &what)?;
+            self.add_buffer(name, buffer, Some(&metadata))?;
+        }
+        #[cfg(all(feature = "xattr", any(target_os = "linux", target_os = "android", target_os = "macos")))]
+        self.add_xattrs(source_path)?;
+        Ok(())
+    }
+
+    /// 把源文件的扩展属性追加到刚添加的条目；只写入中央目录，本地头不变
+    #[cfg(all(feature = "xattr", any(target_os = "linux", target_os = "android", target_os = "macos")))]
+    fn add_xattrs(&mut self, source_path: &Path) -> Result<()> {
+        if self.compat == Compat::Pkzip || !self.preserve.is_some_and(|set| set.contains(PreserveSet::XATTR)) {
+            return Ok(());
+        }
+        let attrs = crate::platform::read_xattrs(source_path).map_err(|e| ZipError::file_read(source_path, e))?;
+        if let Some(entry) = self.entries.last_mut() {
+            for name in extra::push_xattrs(&mut entry.extra, &attrs) {
+                log::warn!("{}: xattr {} is too large to store, skipped", source_path.display(), name);
+            }
+        }
+        Ok(())
+    }
+
+    /// 把符号链接本身添加为条目：内容是链接目标，模式为 `S_IFLNK | 0777`
+    fn add_symlink(&mut self, name: &str, source_path: &Path, link: &Metadata) -> Result<()> {
+        let target = std::fs::read_link(source_path).map_err(|e| ZipError::FileOpen {
+            path: source_path.to_path_buf(),
+            source: e,
+        })?;
+        #[cfg(unix)]
+        let target = {
+            use std::os::unix::ffi::OsStrExt;
+            target.as_os_str().as_bytes().to_vec()
+        };
+        #[cfg(not(unix))]
+        let target = target.to_string_lossy().replace('\\', "/").into_bytes();
+
+        self.add_buffer(name, target, Some(link))?;
+        // 本地头中没有 external_attr，写出后改中央目录中的值即可
+        if let Some(entry) = self.entries.last_mut() {
+            entry.external_attr = SYMLINK_ATTR;
+        }
+        Ok(())
+    }
+
+    /// 从任意 reader 读取全部内容并作为文件条目添加
+    /// 没有文件元数据：修改时间取当前时间，权限为 0644
+    pub fn add_reader(&mut self, name: &str, reader: &mut impl Read) -> Result<()> {
+        Self::validate_archive_name(name)?;
+        if self.streams_entries() {
+            return self.add_stream(name, reader, None, name);
+        }
+        let buffer = self.read_source(reader, 0, name)?;
+        self.add_buffer(name, buffer, None)
+    }
+
+    /// 分块读取全部内容，每块之间检查取消
+    fn read_source(&self, reader: &mut impl Read, capacity: usize, what: &str) -> Result<Vec<u8>> {
+        let mut buffer = Vec::with_capacity(capacity);
+        let mut chunk = vec![0u8; READ_CHUNK_SIZE];
+        loop {
+            let n = self.read_chunk(reader, &mut chunk, what)?;
+            buffer.extend_from_slice(&chunk[..n]);
+            if n < chunk.len() {
+                break;
+            }
+        }
+        Ok(buffer)
+    }
+
+    /// 读满 `chunk` 或读到末尾，返回读到的字节数；每次读取前检查取消
+    fn read_chunk(&self, reader: &mut impl Read, chunk: &mut [u8], what: &str) -> Result<usize> {
+        let mut filled = 0;
+        while filled < chunk.len() {
+            if let Some(cancel) = &self.cancel {
+                cancel.check()?;
+            }
+            let n = match reader.read(&mut chunk[filled..]) {
+                Ok(n) => n,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(e) => {
+                    return Err(ZipError::generic(format!("Failed to read file {}: {:?}", what, e)))
+                }
+            };
+            if n == 0 {
+                break;
+            }
+            filled += n;
+        }
+        Ok(filled)
+    }
+
+    /// 文件条目是否边读边写：只用于不可定位的输出，且条目不需要完整内容（加密、去重）
+    fn streams_entries(&self) -> bool {
+        self.streaming && self.encryption.is_none() && self.dedup.is_none()
+    }
+
+    /// 边读边压缩写出一个文件条目，本地头之后是压缩数据和数据描述符
+    ///
+    /// 本地头设置 bit 3，CRC32 和大小写 0；每块压缩为一个 DEFLATE 片段后立即写出，
+    /// 压缩后没有变小的块改为存储块，与 `add_buffer` 的整体回退规则相同。
+    /// 实际的 CRC32 和大小写在数据描述符和中央目录中。
+    fn add_stream(
+        &mut self,
+        name: &str,
+        reader: &mut impl Read,
+        metadata: Option<&Metadata>,
+        what: &str,
+    ) -> Result<()> {
+        let mtime = match metadata {
+            Some(metadata) => metadata.modified().ok().filter(|_| self.preserves(PreserveSet::MTIME)),
+            None => Some(SystemTime::now()),
+        };
+        let (mtime_dos, mdate_dos) = system_time_to_dos(mtime);
+
+        // 压缩方法写在本地头中，由第一块内容决定
+        let mut chunk = vec![0u8; READ_CHUNK_SIZE];
+        let mut n = self.read_chunk(reader, &mut chunk, what)?;
+        let store = self.compression_level == CompressionLevel::NoCompression
+            || (self.smart_store && is_already_compressed(&chunk[..n]));
+        let method = if store { zip_format::METHOD_STORE } else { zip_format::METHOD_DEFLATE };
+
+        let (name_bytes, flags) = self.encode_name(name)?;
+        let mut entry = ZipEntry {
+            name: name.to_string(),
+            name_bytes,
+            flags: flags | zip_format::FLAG_DATA_DESCRIPTOR,
+            method,
+            uncompressed_size: 0,
+            compressed_size: 0,
+            crc32: 0,
+            local_header_offset: self.position,
+            mtime_dos,
+            mdate_dos,
+            external_attr: self.file_attr(metadata),
+            extra: self.time_extra(metadata),
+        };
+        self.write_local_file_header(&entry)?;
+
+        let mut crc = 0;
+        loop {
+            // 没有读满说明已到末尾，这一块就是最后一个片段
+            let data = &chunk[..n];
+            let is_final = n < chunk.len();
+            crc = crc32(crc, data);
+            entry.uncompressed_size += n as u64;
+            if store {
+                self.write_all(data)?;
+                entry.compressed_size += n as u64;
+            } else {
+                let segment = compress_raw_segment_with(self.encoder()?, data, is_final)
+                    .map_err(compression_failed)?;
+                let segment = if segment.len() >= data.len() {
+                    stored_blocks(data, is_final)
+                } else {
+                    segment
+                };
+                self.write_all(&segment)?;
+                entry.compressed_size += segment.len() as u64;
+            }
+            if is_final {
+                break;
+            }
+            n = self.read_chunk(reader, &mut chunk, what)?;
+        }
+        entry.crc32 = crc;
+
+        self.write_data_descriptor(&entry)?;
+
+        log::debug!(
+            "adding {} streamed ({} -> {} bytes, method {})",
+            entry.name,
+            entry.uncompressed_size,
+            entry.compressed_size,
+            entry.method
+        );
+        self.entries.push(entry);
+        Ok(())
+    }
+
+    /// 压缩并写出一个文件条目；`metadata` 为 `None` 时使用当前时间和 0644 权限
+    fn add_buffer(&mut self, name: &str, buffer: Vec<u8>, metadata: Option<&Metadata>) -> Result<()> {
+        let uncompressed_size = buffer.len() as u64;
 
         // 计算 CRC32（初始值为 0）
         let crc = crc32(0, &buffer);
 
         // 获取修改时间
-        let mtime = metadata.modified().ok();
+        let mtime = match metadata {
+            Some(metadata) => metadata.modified().ok().filter(|_| self.preserves(PreserveSet::MTIME)),
+            None => Some(SystemTime::now()),
+        };
         let (mtime_dos, mdate_dos) = system_time_to_dos(mtime);
 
+        if self.encryption.is_none() {
+            if let Some(original) = self.find_duplicate(crc, &buffer) {
+                let (name_bytes, utf8_flag) = self.encode_name(name)?;
+                let original = &self.entries[original];
+                let entry = ZipEntry {
+                    name: name.to_string(),
+                    name_bytes,
+                    flags: (original.flags & !zip_format::FLAG_UTF8) | utf8_flag,
+                    method: original.method,
+                    uncompressed_size,
+                    compressed_size: original.compressed_size,
+                    crc32: original.crc32,
+                    local_header_offset: original.local_header_offset,
+                    mtime_dos,
+                    mdate_dos,
+                    external_attr: self.file_attr(metadata),
+                    extra: self.time_extra(metadata),
+                };
+                log::debug!("adding {} (duplicate of {})", entry.name, original.name);
+                self.entries.push(entry);
+                return Ok(());
+            }
+        }
+
         // 压缩数据（如果需要）
         // 对应 C 版本：mz_zip_writer_add_cfile() 内部的压缩逻辑
         // 注意：C 版本中 compression_level = 0 表示无压缩（STORE）
         let (compressed_data, method) = match self.compression_level {
+            // 已压缩的内容再 DEFLATE 只会变大并浪费 CPU
+            _ if self.smart_store && is_already_compressed(&buffer) => {
+                (buffer.clone(), zip_format::METHOD_STORE)
+            }
             CompressionLevel::NoCompression => {
                 // NoCompression = 0: 直接存储，不压缩（对应 C 版本的 STORE 模式）
                 (buffer.clone(), zip_format::METHOD_STORE)  // compression_method = 0
@@ -280,9 +927,7 @@ impl ZipWriter {
             CompressionLevel::Level1 => {
                 // 使用纯 DEFLATE 压缩（不带 ZLIB 头，ZIP 格式要求）
                 // 对应 miniz.c 的 tdefl_compress()
-                let compressed = compress_raw(&buffer, 1).map_err(|e| {
-                    ZipError::generic(&format!("Compression failed: {:?}", e))
-                })?;
+                let compressed = self.deflate(&buffer)?;
                 // 始终使用 DEFLATE 方法（compression_method=8）
                 // 如果压缩后没有变小，使用 uncompressed block（BTYPE=00）
                 // 这对应 C 版本 miniz 的行为
@@ -304,9 +949,7 @@ impl ZipWriter {
                 (final_data, zip_format::METHOD_DEFLATE)  // compression_method = 8
             }
             _ => {
-                let compressed = compress_raw(&buffer, self.compression_level.as_u8() as i32).map_err(|e| {
-                    ZipError::generic(&format!("Compression failed: {:?}", e))
-                })?;
+                let compressed = self.deflate(&buffer)?;
                 // 同样的逻辑：如果压缩无效，使用 uncompressed block
                 let final_data = if compressed.len() >= buffer.len() {
                     let mut uncompressed_block = Vec::with_capacity(5 + buffer.len());
@@ -325,44 +968,192 @@ impl ZipWriter {
             }
         };
 
-        let compressed_size = compressed_data.len() as u64;
+        let (name_bytes, mut flags) = self.encode_name(name)?;
 
-        // 记录当前偏移量（用于中央目录）
-        let local_header_offset = self.stream_position()?;
+        // 加密压缩后的数据，返回 (条目数据, 压缩方法, 头部中的 CRC32, extra field)
+        let (compressed_data, method, stored_crc, extra) = match &self.encryption {
+            // CRC 已知，校验字节取 CRC 的最高字节
+            Some(Encryption::ZipCrypto { password }) => {
+                flags |= zip_format::FLAG_ENCRYPTED;
+                let data =
+                    crypto::encrypt_entry(password.as_bytes(), (crc >> 24) as u8, &compressed_data);
+                (data, method, crc, self.time_extra(metadata))
+            }
+            // AE-2：方法写 99、CRC 写 0，实际压缩方法记录在 0x9901 字段中
+            #[cfg(feature = "aes")]
+            Some(Encryption::Aes { password, strength }) => {
+                flags |= zip_format::FLAG_ENCRYPTED;
+                let data = aes::encrypt_entry(password.as_bytes(), *strength, &compressed_data)
+                    .map_err(|e| {
+                        ZipError::generic(&format!("Failed to generate AES salt: {}", e))
+                    })?;
+                let mut extra = self.time_extra(metadata);
+                extra::push_aes(&mut extra, strength.to_u8(), method);
+                (data, zip_format::METHOD_AES, 0, extra)
+            }
+            None => (compressed_data, method, crc, self.time_extra(metadata)),
+        };
 
-        // 写入本地文件头
-        self.write_local_file_header(
-            name,
-            uncompressed_size,
-            compressed_data.len() as u64,
-            crc,
+        // 保存条目信息（用于中央目录）
+        // 对应 C 版本 zip.c:93-94 的权限处理
+        // external_attr 高16位存储 Unix 权限 (st.st_mode & 0777) << 16
+        let entry = ZipEntry {
+            name: name.to_string(),
+            name_bytes,
+            flags,
             method,
+            uncompressed_size,
+            compressed_size: compressed_data.len() as u64,
+            crc32: stored_crc,
+            // 记录当前偏移量（用于中央目录）
+            local_header_offset: self.position,
             mtime_dos,
             mdate_dos,
-        )?;
+            external_attr: self.file_attr(metadata),
+            extra,
+        };
 
-        // 写入文件名
-        self.write_all(name.as_bytes())?;
+        log::debug!(
+            "adding {} ({} -> {} bytes, method {})",
+            entry.name,
+            entry.uncompressed_size,
+            entry.compressed_size,
+            entry.method
+        );
+
+        // 写入本地文件头（含文件名和 extra field）
+        self.write_local_file_header(&entry)?;
 
         // 写入压缩/原始数据
         self.write_all(&compressed_data)?;
 
-        // 保存条目信息（用于中央目录）
-        // 对应 C 版本 zip.c:93-94 的权限处理
-        // external_attr 高16位存储 Unix 权限 (st.st_mode & 0777) << 16
-        let external_attr = compute_external_attr(&metadata, false);
+        if entry.flags & zip_format::FLAG_ENCRYPTED == 0 {
+            if let Some(dedup) = &mut self.dedup {
+                dedup
+                    .entry((crc, uncompressed_size))
+                    .or_default()
+                    .push((buffer, self.entries.len()));
+            }
+        }
+        self.entries.push(entry);
 
-        self.entries.push(ZipEntry {
-            name: name.to_string(),
-            uncompressed_size,
-            compressed_size: compressed_data.len() as u64,
-            crc32: crc,
-            local_header_offset,
-            is_dir: false,
+        Ok(())
+    }
+
+    /// 启用去重时，查找内容与 `buffer` 完全相同的已写出条目
+    fn find_duplicate(&self, crc: u32, buffer: &[u8]) -> Option<usize> {
+        self.dedup
+            .as_ref()?
+            .get(&(crc, buffer.len() as u64))?
+            .iter()
+            .find(|(content, _)| content.as_slice() == buffer)
+            .map(|&(_, index)| index)
+    }
+
+    /// 写入一个已压缩好的条目，数据和元数据都按 `entry` 原样写出
+    pub fn add_precompressed(&mut self, entry: PrecompressedEntry) -> Result<()> {
+        Self::validate_archive_name(&entry.name)?;
+
+        let is_dir = entry.name.ends_with('/');
+        let (name_bytes, flags) = self.encode_name(&entry.name)?;
+        let (mtime_dos, mdate_dos) = system_time_to_dos(Some(entry.mtime));
+        let dos_attr = if is_dir { zip_format::DOS_DIR_ATTR } else { 0 };
+
+        let record = ZipEntry {
+            name: entry.name,
+            name_bytes,
+            flags,
+            method: entry.method,
+            uncompressed_size: entry.uncompressed_size,
+            compressed_size: entry.data.len() as u64,
+            crc32: entry.crc32,
+            local_header_offset: self.position,
             mtime_dos,
             mdate_dos,
-            external_attr,
-        });
+            external_attr: (entry.mode << 16) | dos_attr,
+            extra: entry.extra,
+        };
+        self.push_raw(record, &entry.data)
+    }
+
+    /// 从另一个归档原样复制一个条目（不解压、不重新压缩），可以改名
+    /// 对应 C 版本的 mz_zip_writer_add_from_zip_reader()
+    pub(crate) fn copy_raw_entry(
+        &mut self,
+        source: &ZipReader,
+        info: &ZipEntryInfo,
+        name: &str,
+    ) -> Result<()> {
+        Self::validate_archive_name(name)?;
+
+        let data = source.raw_entry_data(info)?;
+        self.push_with_info(info, name, info.compression_method, &data)
+    }
+
+    /// 按当前压缩级别重新压缩条目的解压数据，其余元数据沿用 `info`
+    /// 压缩后没有变小（或级别为 0）时改为 STORE
+    pub(crate) fn add_recompressed(&mut self, info: &ZipEntryInfo, data: &[u8]) -> Result<()> {
+        let compressed = match self.compression_level {
+            CompressionLevel::NoCompression => None,
+            _ => Some(self.deflate(data)?).filter(|compressed| compressed.len() < data.len()),
+        };
+        match compressed {
+            Some(compressed) => {
+                self.push_with_info(info, &info.name, zip_format::METHOD_DEFLATE, &compressed)
+            }
+            None => self.push_with_info(info, &info.name, zip_format::METHOD_STORE, data),
+        }
+    }
+
+    /// 以 `info` 的元数据写出条目，`data` 是按 `method` 压缩好的数据
+    fn push_with_info(&mut self, info: &ZipEntryInfo, name: &str, method: u16, data: &[u8]) -> Result<()> {
+        let (name_bytes, utf8_flag) = if name == info.name {
+            (info.raw_name().to_vec(), info.flags & zip_format::FLAG_UTF8)
+        } else {
+            self.encode_name(name)?
+        };
+
+        // 本地头中直接写入真实的 CRC 和大小，因此清除数据描述符标志（bit 3）。
+        // 例外是 ZipCrypto 条目：bit 3 时加密头的校验字节取自修改时间而不是 CRC，
+        // 清除后解密会拿错校验字节，因此保留 bit 3 并照样写出数据描述符
+        let zipcrypto = info.flags & zip_format::FLAG_ENCRYPTED != 0 && method != zip_format::METHOD_AES;
+        let descriptor = if zipcrypto { info.flags & zip_format::FLAG_DATA_DESCRIPTOR } else { 0 };
+        let flags = (info.flags & !(zip_format::FLAG_UTF8 | zip_format::FLAG_DATA_DESCRIPTOR))
+            | utf8_flag
+            | descriptor;
+
+        let entry = ZipEntry {
+            name: name.to_string(),
+            name_bytes,
+            flags,
+            method,
+            uncompressed_size: info.uncompressed_size,
+            compressed_size: data.len() as u64,
+            crc32: info.crc32,
+            local_header_offset: self.position,
+            mtime_dos: info.mtime_dos,
+            mdate_dos: info.mdate_dos,
+            external_attr: info.external_attr,
+            extra: info.extra.clone(),
+        };
+        self.push_raw(entry, data)
+    }
+
+    /// 写出本地文件头和原样的条目数据，并记录到中央目录
+    fn push_raw(&mut self, entry: ZipEntry, data: &[u8]) -> Result<()> {
+        log::debug!(
+            "adding {} raw ({} -> {} bytes, method {})",
+            entry.name,
+            entry.uncompressed_size,
+            entry.compressed_size,
+            entry.method
+        );
+        self.write_local_file_header(&entry)?;
+        self.write_all(data)?;
+        if entry.flags & zip_format::FLAG_DATA_DESCRIPTOR != 0 {
+            self.write_data_descriptor(&entry)?;
+        }
+        self.entries.push(entry);
 
         Ok(())
     }
@@ -386,54 +1177,105 @@ impl ZipWriter {
         let metadata = std::fs::metadata(dir_path).or_else(|_| std::fs::metadata("."));
 
         // 获取修改时间（如果元数据可用）
-        let mtime = metadata.as_ref().ok().and_then(|m| m.modified().ok());
-        let (mtime_dos, mdate_dos) = system_time_to_dos(mtime);
+        let mtime = metadata
+            .as_ref()
+            .ok()
+            .and_then(|m| m.modified().ok())
+            .filter(|_| self.preserves(PreserveSet::MTIME));
 
         // 计算 external_attr（如果元数据可用，使用默认值）
-        let external_attr = if let Ok(meta) = metadata {
-            compute_external_attr(&meta, true)
+        let external_attr = if !self.preserves(PreserveSet::PERMISSIONS) {
+            DEFAULT_DIR_ATTR
+        } else if let Ok(meta) = &metadata {
+            compute_external_attr(meta, true)
         } else {
             // 无法读取元数据时使用默认值
             zip_format::DOS_DIR_ATTR
         };
 
-        // 记录偏移量
-        let local_header_offset = self.stream_position()?;
+        self.push_directory(dir_name, mtime, external_attr, metadata.as_ref().ok())
+    }
 
-        // 写入本地文件头（目录无数据）
-        self.write_local_file_header(
-            &dir_name,
-            0, // uncompressed_size
-            0, // compressed_size
-            0, // crc32
-            zip_format::METHOD_STORE,
-            mtime_dos,
-            mdate_dos,
-        )?;
+    /// 添加一个不对应磁盘目录的目录条目
+    /// 修改时间取当前时间，权限为 0755
+    pub fn add_directory_entry(&mut self, name: &str) -> Result<()> {
+        Self::validate_archive_name(name)?;
+        let dir_name = if name.ends_with('/') {
+            name.to_string()
+        } else {
+            format!("{}/", name)
+        };
+        self.push_directory(dir_name, Some(SystemTime::now()), DEFAULT_DIR_ATTR, None)
+    }
+
+    /// 写出目录条目的本地文件头并记录到中央目录
+    fn push_directory(
+        &mut self,
+        dir_name: String,
+        mtime: Option<SystemTime>,
+        external_attr: u32,
+        metadata: Option<&Metadata>,
+    ) -> Result<()> {
+        let (mtime_dos, mdate_dos) = system_time_to_dos(mtime);
 
-        // 写入文件名
-        self.write_all(dir_name.as_bytes())?;
+        let (name_bytes, flags) = self.encode_name(&dir_name)?;
 
         // 保存条目 - 使用 compute_external_attr 读取实际权限
         // 对应 C 版本 zip.c:93-94 的权限处理
         // external_attr 高16位存储 Unix 权限 (st.st_mode & 0777) << 16
-        self.entries.push(ZipEntry {
+        let entry = ZipEntry {
             name: dir_name,
+            name_bytes,
+            flags,
+            method: zip_format::METHOD_STORE,
             uncompressed_size: 0,
             compressed_size: 0,
             crc32: 0,
-            local_header_offset,
-            is_dir: true,
+            // 记录偏移量
+            local_header_offset: self.position,
             mtime_dos,
             mdate_dos,
             external_attr,
-        });
+            extra: self.time_extra(metadata),
+        };
+
+        // 写入本地文件头（目录无数据）
+        self.write_local_file_header(&entry)?;
+
+        self.entries.push(entry);
 
         Ok(())
     }
 
+    /// 从中央目录中去掉名字满足 `matches` 的条目，返回去掉的个数
+    ///
+    /// 条目的数据仍留在文件中，不再被任何条目引用。
+    pub(crate) fn remove_entries(&mut self, matches: impl Fn(&str) -> bool) -> usize {
+        let before = self.entries.len();
+        self.entries.retain(|entry| !matches(&entry.name));
+        before - self.entries.len()
+    }
+
+    /// 目前为止添加的所有条目的摘要，顺序与中央目录一致
+    /// 追加模式下包含原归档中的条目
+    pub fn entry_summaries(&self) -> Vec<EntrySummary> {
+        self.entries
+            .iter()
+            .map(|entry| EntrySummary {
+                name: entry.name.clone(),
+                method: entry.method,
+                compressed: entry.compressed_size,
+                uncompressed: entry.uncompressed_size,
+                crc32: entry.crc32,
+            })
+            .collect()
+    }
+
     /// 完成 ZIP 文件写入
     /// 对应 C 版本 zip.c:413-424: mz_zip_writer_finalize_archive() + mz_zip_writer_end()
+    ///
+    /// 中央目录和 EOCD 由已记录的条目信息生成，追加在当前位置之后，不回退定位，
+    /// 因此也适用于 [`ZipWriter::streaming`] 的不可定位输出。
     pub fn finalize(&mut self) -> Result<()> {
         if self.finalized {
             return Ok(());
@@ -441,11 +1283,11 @@ impl ZipWriter {
 
         // 对应 C 版本：mz_zip_writer_finalize_archive()
         // 写入中央目录
-        let central_dir_offset = self.stream_position()?;
+        let central_dir_offset = self.position;
         self.write_central_directory()?;
 
         // 对应 C 版本：写入 EOCD
-        let central_dir_size = self.stream_position()? - central_dir_offset;
+        let central_dir_size = self.position - central_dir_offset;
         self.write_end_of_central_directory(central_dir_offset, central_dir_size)?;
 
         // 刷新缓冲区
@@ -455,22 +1297,14 @@ impl ZipWriter {
         )))?;
 
         self.finalized = true;
+        log::debug!("finalized {} with {} entries", self.path.display(), self.entries.len());
         Ok(())
     }
 
-    /// 写入本地文件头
+    /// 写入本地文件头（含文件名和 extra field）
     /// 对应 miniz.c 的本地文件头格式
-    fn write_local_file_header(
-        &mut self,
-        name: &str,
-        uncompressed_size: u64,
-        compressed_size: u64,
-        crc32: u32,
-        method: u16,
-        mtime_dos: u16,
-        mdate_dos: u16,
-    ) -> Result<()> {
-        let name_len = name.len() as u16;
+    fn write_local_file_header(&mut self, entry: &ZipEntry) -> Result<()> {
+        let name_len = entry.name_bytes.len() as u16;
 
         // 构建本地文件头（30 字节）
         // 对应 miniz.c:3101-3113
@@ -480,38 +1314,48 @@ impl ZipWriter {
         header[0..4].copy_from_slice(&zip_format::LOCAL_DIR_HEADER_SIG.to_le_bytes());
 
         // 版本需要
-        header[4..6].copy_from_slice(&zip_format::VERSION_NEEDED.to_le_bytes());
+        header[4..6].copy_from_slice(&self.version_needed(entry).to_le_bytes());
 
         // 位标志
-        header[6..8].copy_from_slice(&0u16.to_le_bytes());
+        header[6..8].copy_from_slice(&entry.flags.to_le_bytes());
 
         // 压缩方法
-        header[8..10].copy_from_slice(&method.to_le_bytes());
+        header[8..10].copy_from_slice(&entry.method.to_le_bytes());
 
         // 文件时间/日期 (DOS 格式)
-        header[10..12].copy_from_slice(&mtime_dos.to_le_bytes());
-        header[12..14].copy_from_slice(&mdate_dos.to_le_bytes());
-
-        // CRC32
-        header[14..18].copy_from_slice(&crc32.to_le_bytes());
-
-        // 压缩后大小
-        header[18..22].copy_from_slice(&(compressed_size as u32).to_le_bytes());
-
-        // 压缩前大小
-        header[22..26].copy_from_slice(&(uncompressed_size as u32).to_le_bytes());
+        header[10..12].copy_from_slice(&entry.mtime_dos.to_le_bytes());
+        header[12..14].copy_from_slice(&entry.mdate_dos.to_le_bytes());
+
+        // CRC32 和两个大小；bit 3 时它们写在数据描述符中，本地头里保持 0
+        if entry.flags & zip_format::FLAG_DATA_DESCRIPTOR == 0 {
+            header[14..18].copy_from_slice(&entry.crc32.to_le_bytes());
+            header[18..22].copy_from_slice(&(entry.compressed_size as u32).to_le_bytes());
+            header[22..26].copy_from_slice(&(entry.uncompressed_size as u32).to_le_bytes());
+        }
 
         // 文件名长度
         header[26..28].copy_from_slice(&name_len.to_le_bytes());
 
         // Extra field 长度
-        header[28..30].copy_from_slice(&0u16.to_le_bytes());
+        header[28..30].copy_from_slice(&(entry.extra.len() as u16).to_le_bytes());
 
         self.write_all(&header)?;
+        self.write_all(&entry.name_bytes)?;
+        self.write_all(&entry.extra)?;
 
         Ok(())
     }
 
+    /// 写出带签名的数据描述符：CRC32、压缩后大小、压缩前大小
+    fn write_data_descriptor(&mut self, entry: &ZipEntry) -> Result<()> {
+        let mut descriptor = [0u8; 16];
+        descriptor[0..4].copy_from_slice(&zip_format::DATA_DESCRIPTOR_SIG.to_le_bytes());
+        descriptor[4..8].copy_from_slice(&entry.crc32.to_le_bytes());
+        descriptor[8..12].copy_from_slice(&(entry.compressed_size as u32).to_le_bytes());
+        descriptor[12..16].copy_from_slice(&(entry.uncompressed_size as u32).to_le_bytes());
+        self.write_all(&descriptor)
+    }
+
     /// 写入中央目录
     /// 对应 miniz.c:3083-3100
     fn write_central_directory(&mut self) -> Result<()> {
@@ -526,21 +1370,16 @@ impl ZipWriter {
             header[0..4].copy_from_slice(&zip_format::CENTRAL_DIR_HEADER_SIG.to_le_bytes());
 
             // Version made by
-            header[4..6].copy_from_slice(&zip_format::VERSION_MADE_BY.to_le_bytes());
+            header[4..6].copy_from_slice(&self.version_made_by().to_le_bytes());
 
             // Version needed
-            header[6..8].copy_from_slice(&zip_format::VERSION_NEEDED.to_le_bytes());
+            header[6..8].copy_from_slice(&self.version_needed(entry).to_le_bytes());
 
             // Bit flag
-            header[8..10].copy_from_slice(&0u16.to_le_bytes());
+            header[8..10].copy_from_slice(&entry.flags.to_le_bytes());
 
             // Compression method
-            let method = if entry.is_dir || entry.compressed_size == entry.uncompressed_size {
-                zip_format::METHOD_STORE
-            } else {
-                zip_format::METHOD_DEFLATE
-            };
-            header[10..12].copy_from_slice(&method.to_le_bytes());
+            header[10..12].copy_from_slice(&entry.method.to_le_bytes());
 
             // File time/date
             header[12..14].copy_from_slice(&entry.mtime_dos.to_le_bytes());
@@ -556,11 +1395,11 @@ impl ZipWriter {
             header[24..28].copy_from_slice(&(entry.uncompressed_size as u32).to_le_bytes());
 
             // Filename length
-            let name_len = entry.name.len() as u16;
+            let name_len = entry.name_bytes.len() as u16;
             header[28..30].copy_from_slice(&name_len.to_le_bytes());
 
             // Extra field length
-            header[30..32].copy_from_slice(&0u16.to_le_bytes());
+            header[30..32].copy_from_slice(&(entry.extra.len() as u16).to_le_bytes());
 
             // File comment length
             header[32..34].copy_from_slice(&0u16.to_le_bytes());
@@ -572,13 +1411,14 @@ impl ZipWriter {
             header[36..38].copy_from_slice(&0u16.to_le_bytes());
 
             // External attributes
-            header[38..42].copy_from_slice(&entry.external_attr.to_le_bytes());
+            header[38..42].copy_from_slice(&self.central_external_attr(entry).to_le_bytes());
 
             // Local header offset
             header[42..46].copy_from_slice(&(entry.local_header_offset as u32).to_le_bytes());
 
             central_dir_data.extend_from_slice(&header);
-            central_dir_data.extend_from_slice(entry.name.as_bytes());
+            central_dir_data.extend_from_slice(&entry.name_bytes);
+            central_dir_data.extend_from_slice(&entry.extra);
         }
 
         // 一次性写入所有中央目录数据
@@ -595,6 +1435,7 @@ impl ZipWriter {
         central_dir_size: u64,
     ) -> Result<()> {
         let num_entries = self.entries.len() as u16;
+        let padding = self.eocd_padding()?;
 
         let mut eocd = [0u8; 22];
 
@@ -620,28 +1461,77 @@ impl ZipWriter {
         eocd[16..20].copy_from_slice(&(central_dir_offset as u32).to_le_bytes());
 
         // Comment length
-        eocd[20..22].copy_from_slice(&0u16.to_le_bytes());
+        eocd[20..22].copy_from_slice(&padding.to_le_bytes());
 
         self.write_all(&eocd)?;
+        self.write_all(&vec![0u8; padding as usize])?;
 
         Ok(())
     }
 
-    /// 获取当前写入位置
-    fn stream_position(&mut self) -> Result<u64> {
-        self.writer.stream_position().map_err(|e| {
-            ZipError::generic(&format!("Failed to get stream position: {:?}", e))
+    /// `pad_to` 要求的 EOCD 注释长度：写入 22 字节 EOCD 后距离下一个整数倍还差的字节数
+    fn eocd_padding(&mut self) -> Result<u16> {
+        let Some(multiple) = self.pad_to else {
+            return Ok(0);
+        };
+        let unpadded = self.position + zip_format::END_OF_CENTRAL_DIR_SIZE as u64;
+        let padding = (multiple - unpadded % multiple) % multiple;
+        u16::try_from(padding).map_err(|_| {
+            ZipError::generic(&format!(
+                "Cannot pad archive to a multiple of {}: {} bytes of padding exceed the EOCD comment limit",
+                multiple, padding
+            ))
         })
     }
 
+    /// 在归档开头写入自解压程序的可执行存根，只能在写入任何条目之前调用
+    ///
+    /// 之后写入的本地头偏移和中央目录偏移都包含存根的长度（绝对偏移），
+    /// 生成的文件可以作为存根运行，也能被任意 ZIP 工具直接读取。
+    pub(crate) fn write_sfx_prefix(&mut self, stub: &[u8]) -> Result<()> {
+        if !self.entries.is_empty() || self.position != 0 {
+            return Err(ZipError::generic("sfx prefix must be written before any entry"));
+        }
+        self.write_all(stub)
+    }
+
     /// 写入字节
     fn write_all(&mut self, data: &[u8]) -> Result<()> {
         self.writer.write_all(data).map_err(|e| {
             ZipError::generic(&format!("Failed to write to ZIP file: {:?}", e))
-        })
+        })?;
+        self.position += data.len() as u64;
+        Ok(())
     }
 }
 
+/// 将已有归档的中央目录记录转换为内部条目，追加时写回中央目录
+fn existing_entries(reader: &ZipReader) -> Vec<ZipEntry> {
+    reader
+        .entries()
+        .iter()
+        .map(|info| ZipEntry {
+            name: info.name.clone(),
+            name_bytes: info.raw_name().to_vec(),
+            flags: info.flags,
+            method: info.compression_method,
+            uncompressed_size: info.uncompressed_size,
+            compressed_size: info.compressed_size,
+            crc32: info.crc32,
+            local_header_offset: info.local_header_offset,
+            mtime_dos: info.mtime_dos,
+            mdate_dos: info.mdate_dos,
+            external_attr: info.external_attr,
+            extra: info.extra.clone(),
+        })
+        .collect()
+}
+
+/// 压缩失败时的错误
+fn compression_failed(e: DeflateError) -> ZipError {
+    ZipError::generic(&format!("Compression failed: {:?}", e))
+}
+
 /// 转换 SystemTime 到 DOS 时间/日期格式
 /// 对应 C 版本的 mz_zip_time_t_to_dos_time() (miniz.c:3278-3292)
 ///
@@ -693,7 +1583,7 @@ fn system_time_to_dos(time: Option<SystemTime>) -> (u16, u16) {
     (dos_time, dos_date)
 }
 
-impl Drop for ZipWriter {
+impl<W: Write> Drop for ZipWriter<W> {
     fn drop(&mut self) {
         if !self.finalized {
             // 尝试 finalize，但不 panic
@@ -705,6 +1595,7 @@ impl Drop for ZipWriter {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::unzip::ZipArchive;
     use std::fs;
     use tempfile::TempDir;
 
@@ -728,4 +1619,252 @@ mod tests {
         assert_eq!(month, 1);
         assert_eq!(day, 1);
     }
+
+    /// 在中央目录之前插入 `block`，并修正 EOCD 中的中央目录偏移
+    fn insert_before_central_dir(zip_path: &Path, block: &[u8]) {
+        let bytes = fs::read(zip_path).unwrap();
+        let eocd_pos = bytes.len() - 22;
+        let cd_offset = u32::from_le_bytes(bytes[eocd_pos + 16..eocd_pos + 20].try_into().unwrap());
+
+        let mut out = bytes[..cd_offset as usize].to_vec();
+        out.extend_from_slice(block);
+        out.extend_from_slice(&bytes[cd_offset as usize..]);
+        let eocd_pos = out.len() - 22;
+        out[eocd_pos + 16..eocd_pos + 20]
+            .copy_from_slice(&(cd_offset + block.len() as u32).to_le_bytes());
+        fs::write(zip_path, out).unwrap();
+    }
+
+    fn make_zip(dir: &Path) -> PathBuf {
+        fs::write(dir.join("a.txt"), b"alpha\n").unwrap();
+        fs::write(dir.join("b.txt"), b"bravo\n").unwrap();
+        let zip_path = dir.join("test.zip");
+        let mut writer = ZipWriter::new(&zip_path, CompressionLevel::Level6).unwrap();
+        writer.add_file("a.txt", &dir.join("a.txt")).unwrap();
+        writer.finalize().unwrap();
+        zip_path
+    }
+
+    #[test]
+    fn test_mode_external_attr_round_trip() {
+        use crate::unzip::external_attr_to_mode;
+
+        let unix = zip_format::VERSION_MADE_BY;
+        for mode in [0o644, 0o755, 0o400] {
+            let attr = mode_to_external_attr(mode, false);
+            assert_eq!(attr, mode << 16);
+            assert_eq!(external_attr_to_mode(attr, unix, false), mode);
+        }
+        // setuid 等特殊位不写入，与 C 版本的 `st_mode & 0777` 一致
+        assert_eq!(external_attr_to_mode(mode_to_external_attr(0o4755, false), unix, false), 0o755);
+
+        let dir = mode_to_external_attr(0o755, true);
+        assert_eq!(dir & 0xFFFF, zip_format::DOS_DIR_ATTR);
+        assert_eq!(external_attr_to_mode(dir, unix, true), 0o755);
+
+        // 非 Unix 创建的条目或没有权限位时使用默认权限
+        assert_eq!(external_attr_to_mode(0o755 << 16, zip_format::VERSION_MADE_BY_PKZIP, false), 0o600);
+        assert_eq!(external_attr_to_mode(0o755 << 16, zip_format::VERSION_MADE_BY_PKZIP, true), 0o700);
+        assert_eq!(external_attr_to_mode(mode_to_external_attr(0, true), unix, true), 0o700);
+        assert_eq!(external_attr_to_mode(mode_to_external_attr(0, false), unix, false), 0o600);
+    }
+
+    /// 只实现 `Write` 的输出，模拟标准输出或管道
+    struct PipeSink(Vec<u8>);
+
+    impl Write for PipeSink {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_streaming_to_non_seekable_sink() {
+        use crate::unzip::StreamingZipReader;
+
+        let temp = TempDir::new().unwrap();
+        // 跨越多个读取块，以及恰好一个读取块（最后一个片段为空）
+        let big: Vec<u8> = (0..READ_CHUNK_SIZE as u32 * 5 / 2).map(|i| (i % 251) as u8 ^ (i / 4093) as u8).collect();
+        let exact = vec![b'x'; READ_CHUNK_SIZE];
+        let mut sink = PipeSink(Vec::new());
+        {
+            let mut writer = ZipWriter::streaming(&mut sink, CompressionLevel::Level6);
+            writer.add_reader("big.bin", &mut &big[..]).unwrap();
+            writer.add_reader("exact.txt", &mut &exact[..]).unwrap();
+            writer.add_directory_entry("dir/").unwrap();
+            writer.add_reader("dir/small.txt", &mut &b"small\n"[..]).unwrap();
+            writer.add_reader("dir/empty.txt", &mut &b""[..]).unwrap();
+            writer.finalize().unwrap();
+        }
+
+        let zip_path = temp.path().join("streamed.zip");
+        fs::write(&zip_path, &sink.0).unwrap();
+        let reader = ZipReader::open(&zip_path).unwrap();
+        for entry in reader.entries() {
+            let offset = entry.local_header_offset as usize;
+            let local = &sink.0[offset..offset + 30];
+            if entry.name.ends_with('/') {
+                assert_eq!(entry.flags & zip_format::FLAG_DATA_DESCRIPTOR, 0);
+                continue;
+            }
+            // 本地头中 CRC32 和大小为 0，数据描述符与中央目录一致
+            assert_ne!(entry.flags & zip_format::FLAG_DATA_DESCRIPTOR, 0, "{}", entry.name);
+            assert_eq!(local[14..26], [0u8; 12]);
+            let data_start = offset + 30 + u16::from_le_bytes([local[26], local[27]]) as usize
+                + u16::from_le_bytes([local[28], local[29]]) as usize;
+            let descriptor = &sink.0[data_start + entry.compressed_size as usize..][..16];
+            assert_eq!(descriptor[0..4], zip_format::DATA_DESCRIPTOR_SIG.to_le_bytes());
+            assert_eq!(descriptor[4..8], entry.crc32.to_le_bytes());
+            assert_eq!(descriptor[8..12], (entry.compressed_size as u32).to_le_bytes());
+            assert_eq!(descriptor[12..16], (entry.uncompressed_size as u32).to_le_bytes());
+        }
+        let archive = ZipArchive::open(&zip_path).unwrap();
+        assert_eq!(archive.read_entry("big.bin").unwrap(), big);
+        assert_eq!(archive.read_entry("exact.txt").unwrap(), exact);
+        assert_eq!(archive.read_entry("dir/small.txt").unwrap(), b"small\n");
+        assert!(archive.read_entry("dir/empty.txt").unwrap().is_empty());
+        assert_eq!(archive.entries().unwrap().len(), 5);
+
+        // 只向前读取的读取器依靠数据描述符得到同样的内容
+        let streamed: Vec<(String, Vec<u8>)> = StreamingZipReader::new(&sink.0[..])
+            .map(|item| item.map(|(entry, data)| (entry.filename, data)))
+            .collect::<Result<_>>()
+            .unwrap();
+        let names: Vec<&str> = streamed.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, ["big.bin", "exact.txt", "dir/", "dir/small.txt", "dir/empty.txt"]);
+        assert_eq!(streamed[0].1, big);
+        assert_eq!(streamed[1].1, exact);
+
+        // 与先读入整个条目的可定位输出内容相同，压缩率只因分段略有下降
+        let seekable_path = temp.path().join("seekable.zip");
+        let mut writer = ZipWriter::new(&seekable_path, CompressionLevel::Level6).unwrap();
+        writer.add_reader("big.bin", &mut &big[..]).unwrap();
+        writer.finalize().unwrap();
+        let seekable = ZipReader::open(&seekable_path).unwrap();
+        assert_eq!(seekable.entries()[0].crc32, reader.entries()[0].crc32);
+        assert!(reader.entries()[0].compressed_size < big.len() as u64 / 2);
+    }
+
+    #[test]
+    fn test_add_precompressed_from_cache() {
+        let temp = TempDir::new().unwrap();
+        let source = make_zip(temp.path());
+
+        // 从已有归档取出压缩好的数据作为缓存
+        let reader = ZipReader::open(&source).unwrap();
+        let info = &reader.entries()[0];
+        let cached = reader.raw_entry_data(info).unwrap();
+        assert_eq!(info.compression_method, zip_format::METHOD_DEFLATE);
+
+        let mtime = SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_600_000_000);
+        let zip_path = temp.path().join("cached.zip");
+        let mut writer = ZipWriter::new(&zip_path, CompressionLevel::Level6).unwrap();
+        for (name, mode) in [("one.txt", 0o644), ("bin/two.txt", 0o755)] {
+            writer
+                .add_precompressed(PrecompressedEntry {
+                    name: name.to_string(),
+                    method: info.compression_method,
+                    data: cached.clone(),
+                    uncompressed_size: info.uncompressed_size,
+                    crc32: info.crc32,
+                    mtime,
+                    mode,
+                    extra: Vec::new(),
+                })
+                .unwrap();
+        }
+        writer.finalize().unwrap();
+        drop(writer);
+
+        let archive = ZipArchive::open(&zip_path).unwrap();
+        let map = archive.extract_to_map().unwrap();
+        assert_eq!(map["one.txt"], b"alpha\n");
+        assert_eq!(map["bin/two.txt"], b"alpha\n");
+
+        let entries = archive.entries().unwrap();
+        let expected_dos = system_time_to_dos(Some(mtime));
+        for (entry, mode) in entries.iter().zip([0o644, 0o755]) {
+            assert_eq!(entry.permissions & 0o777, mode);
+            assert_eq!(entry.crc32, info.crc32);
+            assert_eq!(entry.uncompressed_size, info.uncompressed_size);
+            assert_eq!(entry.compressed_size, cached.len() as u64);
+            assert_eq!((entry.dos_time, entry.dos_date), expected_dos);
+        }
+    }
+
+    #[test]
+    fn test_append_preserves_gap_before_central_dir() {
+        let temp = TempDir::new().unwrap();
+        let zip_path = make_zip(temp.path());
+        let gap = b"opaque vendor block";
+        insert_before_central_dir(&zip_path, gap);
+
+        let mut writer = ZipWriter::new_with_append(&zip_path, CompressionLevel::Level6).unwrap();
+        writer.add_file("b.txt", &temp.path().join("b.txt")).unwrap();
+        writer.finalize().unwrap();
+        drop(writer);
+
+        let bytes = fs::read(&zip_path).unwrap();
+        assert!(bytes.windows(gap.len()).any(|w| w == gap));
+        let map = ZipArchive::open(&zip_path).unwrap().extract_to_map().unwrap();
+        assert_eq!(map["a.txt"], b"alpha\n");
+        assert_eq!(map["b.txt"], b"bravo\n");
+    }
+
+    #[test]
+    fn test_append_keeps_existing_flags_and_raw_names() {
+        let temp = TempDir::new().unwrap();
+        fs::write(temp.path().join("a.txt"), b"alpha\n").unwrap();
+        fs::write(temp.path().join("b.txt"), b"bravo\n").unwrap();
+        let zip_path = temp.path().join("test.zip");
+        let mut writer = ZipWriter::new(&zip_path, CompressionLevel::Level6).unwrap();
+        writer.set_encryption(Some(Encryption::ZipCrypto {
+            password: "s3cret".to_string(),
+        }));
+        writer.add_file("a.txt", &temp.path().join("a.txt")).unwrap();
+        writer.set_filename_encoding(Encoding::Cp437);
+        writer.add_file("╔═╗.txt", &temp.path().join("b.txt")).unwrap();
+        writer.finalize().unwrap();
+        drop(writer);
+        let before = ZipReader::open(&zip_path).unwrap().entries().to_vec();
+
+        let mut writer = ZipWriter::new_with_append(&zip_path, CompressionLevel::Level6).unwrap();
+        writer.add_file("b.txt", &temp.path().join("b.txt")).unwrap();
+        writer.finalize().unwrap();
+        drop(writer);
+
+        let reader = ZipReader::open(&zip_path).unwrap();
+        let after = reader.entries();
+        assert_eq!(after.len(), 3);
+        for (old, new) in before.iter().zip(after) {
+            assert_eq!(new.flags, old.flags);
+            assert_eq!(new.raw_name(), old.raw_name());
+            assert_eq!((new.mtime_dos, new.mdate_dos), (old.mtime_dos, old.mdate_dos));
+        }
+        assert_ne!(after[0].flags & zip_format::FLAG_ENCRYPTED, 0);
+        assert_eq!(after[1].raw_name(), b"\xc9\xcd\xbb.txt");
+    }
+
+    #[test]
+    fn test_append_refuses_apk_signing_block() {
+        let temp = TempDir::new().unwrap();
+        let zip_path = make_zip(temp.path());
+
+        // size_of_block(u64) + (id-value pairs) + size_of_block(u64) + magic
+        let mut block = Vec::new();
+        block.extend_from_slice(&32u64.to_le_bytes());
+        block.extend_from_slice(&[0u8; 8]);
+        block.extend_from_slice(&32u64.to_le_bytes());
+        block.extend_from_slice(b"APK Sig Block 42");
+        insert_before_central_dir(&zip_path, &block);
+
+        let err = ZipWriter::new_with_append(&zip_path, CompressionLevel::Level6).err().unwrap();
+        assert!(matches!(err, ZipError::OpenAppendFailed { .. }), "{:?}", err);
+        // 原归档仍然可读
+        assert_eq!(ZipArchive::list(&zip_path).unwrap().len(), 1);
+    }
 }
@@ -0,0 +1,64 @@
+// 复用 DEFLATE 压缩器的分配次数测试
+//
+// 计数分配器通过 #[global_allocator] 替换整个测试二进制的分配器，
+// 因此单独放在这个集成测试里，不影响库的单元测试。
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use zip_rs::miniz::deflate::{compress_raw, compress_raw_with, CompressionLevel, DeflateEncoder, DeflateOptions};
+
+/// 按线程统计分配次数的分配器，用于比较复用压缩器前后的分配量
+struct CountingAlloc;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|c| c.set(c.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+fn allocations() -> usize {
+    ALLOCATIONS.with(|c| c.get())
+}
+
+#[test]
+fn test_encoder_reuse_reduces_allocations() {
+    let entries: Vec<Vec<u8>> = (0..50u8)
+        .map(|i| format!("entry {} payload", i).into_bytes())
+        .collect();
+
+    let before = allocations();
+    let fresh: Vec<Vec<u8>> = entries.iter().map(|e| compress_raw(e, 1).unwrap()).collect();
+    let fresh_allocs = allocations() - before;
+
+    let options = DeflateOptions {
+        level: CompressionLevel::Fastest,
+        ..Default::default()
+    };
+    let mut encoder = DeflateEncoder::new(options).unwrap();
+    let before = allocations();
+    let reused: Vec<Vec<u8>> = entries
+        .iter()
+        .map(|e| compress_raw_with(&mut encoder, e).unwrap())
+        .collect();
+    let reused_allocs = allocations() - before;
+
+    assert_eq!(fresh, reused);
+    assert!(
+        reused_allocs < fresh_allocs,
+        "reused {} >= fresh {}",
+        reused_allocs,
+        fresh_allocs
+    );
+}